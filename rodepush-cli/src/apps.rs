@@ -0,0 +1,119 @@
+//! Application management
+//!
+//! Creating and listing applications requires the server admin key; showing
+//! and deleting one works with its own API key as well.
+
+use rodepush_core::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::client::ApiClient;
+
+/// An application as reported by the server
+#[derive(Debug, Clone, Deserialize)]
+pub struct Application {
+    /// Application ID
+    pub id: String,
+    /// Application name
+    pub name: String,
+    /// Application description
+    pub description: Option<String>,
+    /// Application owner
+    pub owner: Option<String>,
+    /// Application settings
+    #[serde(default)]
+    pub settings: HashMap<String, serde_json::Value>,
+    /// Creation timestamp
+    pub created_at: String,
+    /// Last update timestamp
+    pub updated_at: String,
+}
+
+/// A newly created application with its API key
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedApplication {
+    /// API key of the application; the server does not show it again
+    pub api_key: String,
+    /// The created application
+    #[serde(flatten)]
+    pub application: Application,
+}
+
+/// One page of applications
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApplicationPage {
+    /// Applications on the page
+    pub items: Vec<Application>,
+}
+
+/// Request body for creating an application
+#[derive(Debug, Clone, Serialize)]
+struct CreateApplication<'a> {
+    name: &'a str,
+    description: Option<&'a str>,
+    owner: Option<&'a str>,
+}
+
+/// Client for the server's application API
+pub struct AppClient {
+    client: ApiClient,
+}
+
+impl AppClient {
+    /// Create a client on top of an authenticated API client
+    pub fn new(client: ApiClient) -> Self {
+        Self { client }
+    }
+
+    /// Create an application
+    pub async fn create(
+        &self,
+        name: &str,
+        description: Option<&str>,
+        owner: Option<&str>,
+    ) -> Result<CreatedApplication> {
+        let body = CreateApplication {
+            name,
+            description,
+            owner,
+        };
+        // Not retried: a lost response would otherwise create the application twice
+        self.client
+            .send(
+                self.client
+                    .http()
+                    .post(self.client.url("/apps"))
+                    .json(&body),
+            )
+            .await
+    }
+
+    /// List applications
+    pub async fn list(&self, limit: u32, offset: u32) -> Result<ApplicationPage> {
+        let url = self.client.url("/apps");
+        self.client
+            .send_with_retry(|| {
+                self.client
+                    .http()
+                    .get(&url)
+                    .query(&[("limit", limit), ("offset", offset)])
+            })
+            .await
+    }
+
+    /// Get a single application
+    pub async fn show(&self, app_id: &str) -> Result<Application> {
+        let url = self.client.url(&format!("/apps/{}", app_id));
+        self.client
+            .send_with_retry(|| self.client.http().get(&url))
+            .await
+    }
+
+    /// Delete an application with all its bundles and deployments
+    pub async fn delete(&self, app_id: &str) -> Result<()> {
+        let url = self.client.url(&format!("/apps/{}", app_id));
+        self.client
+            .send_empty(self.client.http().delete(&url))
+            .await
+    }
+}
@@ -6,7 +6,7 @@ mod tests {
     use std::path::PathBuf;
     
     // Import the CLI structures from main.rs
-    use crate::{Cli, Commands, AssetActions};
+    use crate::{Cli, Commands, AssetActions, AppActions, EnvActions};
     
     #[test]
     fn test_cli_parsing_no_args() {
@@ -51,17 +51,19 @@ mod tests {
             "build", 
             "--project-dir", "/path/to/project",
             "--assets-dir", "/path/to/assets",
-            "--platform", "android"
+            "--platform", "android",
+            "--deterministic"
         ];
         let cli = Cli::try_parse_from(args);
         assert!(cli.is_ok());
         
         let cli = cli.unwrap();
         match cli.command {
-            Some(Commands::Build { project_dir, assets_dir, platform, .. }) => {
+            Some(Commands::Build { project_dir, assets_dir, platform, deterministic, .. }) => {
                 assert_eq!(project_dir, Some(PathBuf::from("/path/to/project")));
                 assert_eq!(assets_dir, Some(PathBuf::from("/path/to/assets")));
                 assert_eq!(platform, "android");
+                assert!(deterministic);
             }
             _ => panic!("Expected Build command"),
         }
@@ -90,6 +92,40 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_cli_parsing_resumable_upload_options() {
+        let args = vec![
+            "rodepush",
+            "upload",
+            "--bundle-path", "/path/to/index.android.bundle",
+            "--app-id", "6f1c2a3e-0000-4000-8000-000000000001",
+            "--platform", "android",
+            "--bundle-version", "1.4.2",
+            "--chunk-size", "1048576",
+            "--retries", "5"
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Some(Commands::Upload { app_id, platform, bundle_version, chunk_size, retries, .. }) => {
+                assert_eq!(app_id, Some("6f1c2a3e-0000-4000-8000-000000000001".to_string()));
+                assert_eq!(platform, Some("android".to_string()));
+                assert_eq!(bundle_version, Some("1.4.2".to_string()));
+                assert_eq!(chunk_size, 1048576);
+                assert_eq!(retries, 5);
+            }
+            _ => panic!("Expected Upload command"),
+        }
+        
+        let cli = Cli::try_parse_from(vec!["rodepush", "upload"]).unwrap();
+        match cli.command {
+            Some(Commands::Upload { chunk_size, retries, .. }) => {
+                assert_eq!(chunk_size, crate::upload::DEFAULT_CHUNK_SIZE);
+                assert_eq!(retries, 3);
+            }
+            _ => panic!("Expected Upload command"),
+        }
+    }
+    
     #[test]
     fn test_cli_parsing_deploy_command() {
         let args = vec![
@@ -118,7 +154,14 @@ mod tests {
             "assets", 
             "create", 
             "/path/to/assets",
-            "--output", "/path/to/output.json"
+            "--output", "/path/to/output.json",
+            "--platform", "android",
+            "--layout", "metro-android",
+            "--ignore", "*.psd",
+            "--include", "images/**",
+            "--symlinks", "error",
+            "--max-file-size", "1048576",
+            "--deterministic"
         ];
         let cli = Cli::try_parse_from(args);
         assert!(cli.is_ok());
@@ -127,9 +170,17 @@ mod tests {
         match cli.command {
             Some(Commands::Assets { action }) => {
                 match action {
-                    AssetActions::Create { assets_dir, output } => {
+                    AssetActions::Create { assets_dir, output, platform, layout, asset_filters, deterministic } => {
                         assert_eq!(assets_dir, PathBuf::from("/path/to/assets"));
                         assert_eq!(output, Some(PathBuf::from("/path/to/output.json")));
+                        assert_eq!(platform, Some("android".to_string()));
+                        assert_eq!(layout, "metro-android");
+                        assert_eq!(asset_filters.ignore_patterns, vec!["*.psd".to_string()]);
+                        assert_eq!(asset_filters.include_patterns, vec!["images/**".to_string()]);
+                        assert_eq!(asset_filters.symlinks, "error");
+                        assert_eq!(asset_filters.max_file_size, Some(1048576));
+                        assert!(asset_filters.to_scan_options().is_ok());
+                        assert!(deterministic);
                     }
                     _ => panic!("Expected Create action"),
                 }
@@ -174,6 +225,7 @@ mod tests {
             "assets", 
             "compress", 
             "/path/to/collection.json",
+            "/path/to/assets",
             "--output", "/path/to/compressed.bin"
         ];
         let cli = Cli::try_parse_from(args);
@@ -183,8 +235,9 @@ mod tests {
         match cli.command {
             Some(Commands::Assets { action }) => {
                 match action {
-                    AssetActions::Compress { collection, output } => {
+                    AssetActions::Compress { collection, assets_dir, output } => {
                         assert_eq!(collection, PathBuf::from("/path/to/collection.json"));
+                        assert_eq!(assets_dir, PathBuf::from("/path/to/assets"));
                         assert_eq!(output, Some(PathBuf::from("/path/to/compressed.bin")));
                     }
                     _ => panic!("Expected Compress action"),
@@ -194,6 +247,71 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_cli_parsing_assets_optimize_command() {
+        let args = vec![
+            "rodepush", 
+            "assets", 
+            "optimize", 
+            "/path/to/collection.json",
+            "/path/to/assets",
+            "--output-dir", "/path/to/optimized",
+            "--cache-dir", "/path/to/cache"
+        ];
+        let cli = Cli::try_parse_from(args);
+        assert!(cli.is_ok());
+        
+        let cli = cli.unwrap();
+        match cli.command {
+            Some(Commands::Assets { action }) => {
+                match action {
+                    AssetActions::Optimize { collection, assets_dir, output_dir, cache_dir, output } => {
+                        assert_eq!(collection, PathBuf::from("/path/to/collection.json"));
+                        assert_eq!(assets_dir, PathBuf::from("/path/to/assets"));
+                        assert_eq!(output_dir, PathBuf::from("/path/to/optimized"));
+                        assert_eq!(cache_dir, Some(PathBuf::from("/path/to/cache")));
+                        assert_eq!(output, None);
+                    }
+                    _ => panic!("Expected Optimize action"),
+                }
+            }
+            _ => panic!("Expected Assets command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_assets_validate_command() {
+        let args = vec![
+            "rodepush", 
+            "assets", 
+            "validate", 
+            "/path/to/collection.json",
+            "/path/to/assets",
+            "--fix-mime-types",
+            "--report", "/path/to/report.json"
+        ];
+        let cli = Cli::try_parse_from(args);
+        assert!(cli.is_ok());
+        
+        let cli = cli.unwrap();
+        match cli.command {
+            Some(Commands::Assets { action }) => {
+                match action {
+                    AssetActions::Validate { collection, assets_dir, fix_mime_types, strict, report, output } => {
+                        assert_eq!(collection, PathBuf::from("/path/to/collection.json"));
+                        assert_eq!(assets_dir, PathBuf::from("/path/to/assets"));
+                        assert!(fix_mime_types);
+                        assert!(!strict);
+                        assert_eq!(report, Some(PathBuf::from("/path/to/report.json")));
+                        assert_eq!(output, None);
+                    }
+                    _ => panic!("Expected Validate action"),
+                }
+            }
+            _ => panic!("Expected Assets command"),
+        }
+    }
+    
     #[test]
     fn test_cli_parsing_assets_decompress_command() {
         let args = vec![
@@ -201,6 +319,7 @@ mod tests {
             "assets", 
             "decompress", 
             "/path/to/compressed.bin",
+            "/path/to/collection.json",
             "--output-dir", "/path/to/output"
         ];
         let cli = Cli::try_parse_from(args);
@@ -210,9 +329,10 @@ mod tests {
         match cli.command {
             Some(Commands::Assets { action }) => {
                 match action {
-                    AssetActions::Decompress { compressed_collection, output_dir } => {
+                    AssetActions::Decompress { compressed_collection, collection, output_dir } => {
                         assert_eq!(compressed_collection, PathBuf::from("/path/to/compressed.bin"));
-                        assert_eq!(output_dir, Some(PathBuf::from("/path/to/output")));
+                        assert_eq!(collection, PathBuf::from("/path/to/collection.json"));
+                        assert_eq!(output_dir, PathBuf::from("/path/to/output"));
                     }
                     _ => panic!("Expected Decompress action"),
                 }
@@ -220,4 +340,177 @@ mod tests {
             _ => panic!("Expected Assets command"),
         }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_cli_parsing_app_create_command() {
+        let args = vec![
+            "rodepush",
+            "app",
+            "create",
+            "My App",
+            "--description",
+            "Mobile storefront",
+            "--server-url",
+            "https://push.example.com",
+            "--api-key",
+            "admin-key",
+        ];
+        let cli = Cli::try_parse_from(args);
+        assert!(cli.is_ok());
+
+        let cli = cli.unwrap();
+        match cli.command {
+            Some(Commands::App {
+                server_url,
+                api_key,
+                action,
+            }) => {
+                assert_eq!(server_url, Some("https://push.example.com".to_string()));
+                assert_eq!(api_key, Some("admin-key".to_string()));
+                match action {
+                    AppActions::Create {
+                        name,
+                        description,
+                        owner,
+                    } => {
+                        assert_eq!(name, "My App");
+                        assert_eq!(description, Some("Mobile storefront".to_string()));
+                        assert_eq!(owner, None);
+                    }
+                    _ => panic!("Expected App Create action"),
+                }
+            }
+            _ => panic!("Expected App command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_app_list_show_delete_commands() {
+        let cli = Cli::try_parse_from(vec!["rodepush", "app", "list", "--limit", "5"]).unwrap();
+        match cli.command {
+            Some(Commands::App {
+                action: AppActions::List { limit, offset },
+                ..
+            }) => {
+                assert_eq!(limit, 5);
+                assert_eq!(offset, 0);
+            }
+            _ => panic!("Expected App List action"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["rodepush", "app", "show", "app-123"]).unwrap();
+        match cli.command {
+            Some(Commands::App {
+                action: AppActions::Show { app_id },
+                ..
+            }) => {
+                assert_eq!(app_id, "app-123");
+            }
+            _ => panic!("Expected App Show action"),
+        }
+
+        let cli =
+            Cli::try_parse_from(vec!["rodepush", "app", "delete", "app-123", "--yes"]).unwrap();
+        match cli.command {
+            Some(Commands::App {
+                action: AppActions::Delete { app_id, yes },
+                ..
+            }) => {
+                assert_eq!(app_id, "app-123");
+                assert!(yes);
+            }
+            _ => panic!("Expected App Delete action"),
+        }
+
+        // Showing an application needs its ID
+        assert!(Cli::try_parse_from(vec!["rodepush", "app", "show"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_promote_command() {
+        let cli = Cli::try_parse_from(vec![
+            "rodepush",
+            "promote",
+            "--app-id",
+            "app-123",
+            "--from",
+            "staging",
+            "--to",
+            "production",
+            "--rollout",
+            "25",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Promote {
+                app_id,
+                from_environment,
+                to_environment,
+                deployment_id,
+                rollout,
+                description,
+                mandatory,
+                ..
+            }) => {
+                assert_eq!(app_id, "app-123");
+                assert_eq!(from_environment, "staging");
+                assert_eq!(to_environment, "production");
+                assert_eq!(deployment_id, None);
+                assert_eq!(rollout, Some(25));
+                assert_eq!(description, None);
+                assert!(!mandatory);
+            }
+            _ => panic!("Expected Promote command"),
+        }
+
+        // Both environments are required
+        assert!(
+            Cli::try_parse_from(vec![
+                "rodepush", "promote", "--app-id", "app-123", "--from", "staging"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_env_create() {
+        let cli = Cli::try_parse_from(vec![
+            "rodepush",
+            "env",
+            "create",
+            "beta",
+            "--app-id",
+            "app-123",
+            "--rollout",
+            "10",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Env {
+                app_id,
+                action:
+                    EnvActions::Create {
+                        name,
+                        description,
+                        rollout,
+                    },
+                ..
+            }) => {
+                assert_eq!(app_id.as_deref(), Some("app-123"));
+                assert_eq!(name, "beta");
+                assert_eq!(description, None);
+                assert_eq!(rollout, Some(10));
+            }
+            _ => panic!("Expected Env Create command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["rodepush", "env", "--app-id", "app-123", "list"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Env {
+                action: EnvActions::List,
+                ..
+            })
+        ));
+    }
+}
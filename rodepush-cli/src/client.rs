@@ -0,0 +1,202 @@
+//! HTTP client for the RodePush server API
+//!
+//! Every server response wraps its payload in an envelope carrying either
+//! `data` or an `error` message. [`ApiClient`] unwraps it, turns failures into
+//! [`NetworkError`]s and retries transient ones with exponential backoff; the
+//! clients of the individual resources build their requests on top of it.
+
+use rodepush_core::{NetworkError, Result, RodePushError};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tracing::warn;
+
+/// Server response envelope
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    data: Option<T>,
+    error: Option<String>,
+}
+
+/// Authenticated client for the versioned server API
+#[derive(Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    api_url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl ApiClient {
+    /// Create a client for the server at `server_url`
+    pub fn new(server_url: &str, timeout: Duration) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| RodePushError::Config {
+                message: format!("Failed to create HTTP client: {}", e),
+            })?;
+        Ok(Self {
+            http,
+            api_url: format!("{}/api/v1", server_url.trim_end_matches('/')),
+            api_key: None,
+            timeout,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+        })
+    }
+
+    /// Authenticate requests with an API key
+    pub fn with_api_key(mut self, api_key: String) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    /// Set how often a failed request is retried and the delay before the first retry
+    ///
+    /// The delay doubles with every further attempt.
+    pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Underlying HTTP client, for building requests
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Absolute URL of an API path such as `/apps`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.api_url, path)
+    }
+
+    /// Send a request, retrying transient failures with exponential backoff
+    pub async fn send_with_retry<T, F>(&self, request: F) -> Result<T>
+    where
+        T: DeserializeOwned,
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            match self.send(request()).await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.max_retries && is_transient(&e) => {
+                    attempt += 1;
+                    warn!(
+                        "Request failed ({}), retrying in {:?} ({}/{})",
+                        e, delay, attempt, self.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Send a request and unwrap the data of the response envelope
+    pub async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let (status, body) = self.execute(request).await?;
+        let envelope: Option<ApiResponse<T>> = serde_json::from_slice(&body).ok();
+        envelope.and_then(|envelope| envelope.data).ok_or_else(|| {
+            NetworkError::HttpRequest {
+                status_code: status.as_u16(),
+                message: "Unexpected response from server".to_string(),
+            }
+            .into()
+        })
+    }
+
+    /// Send a request answered without a body, such as a deletion
+    pub async fn send_empty(&self, request: reqwest::RequestBuilder) -> Result<()> {
+        self.execute(request).await.map(|_| ())
+    }
+
+    /// Send a request, failing unless the server reports success
+    async fn execute(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<(reqwest::StatusCode, Vec<u8>)> {
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await.map_err(|e| self.network_error(e))?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(|e| self.network_error(e))?;
+
+        if !status.is_success() {
+            let message = serde_json::from_slice::<ApiResponse<serde_json::Value>>(&body)
+                .ok()
+                .and_then(|envelope| envelope.error)
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+            return Err(NetworkError::http_request(status.as_u16(), message).into());
+        }
+        Ok((status, body.to_vec()))
+    }
+
+    /// Map a transport failure onto a network error
+    fn network_error(&self, error: reqwest::Error) -> RodePushError {
+        if error.is_timeout() {
+            return NetworkError::Timeout {
+                timeout_ms: self.timeout.as_millis() as u64,
+            }
+            .into();
+        }
+        if error.is_connect()
+            && let Some(url) = error.url()
+        {
+            return NetworkError::ConnectionRefused {
+                host: url.host_str().unwrap_or_default().to_string(),
+                port: url.port_or_known_default().unwrap_or_default(),
+            }
+            .into();
+        }
+        NetworkError::UploadFailed {
+            reason: error.to_string(),
+        }
+        .into()
+    }
+}
+
+/// Whether a failed request is worth retrying
+pub fn is_transient(error: &RodePushError) -> bool {
+    match error {
+        RodePushError::Network(NetworkError::HttpRequest { status_code, .. }) => {
+            *status_code == 429 || *status_code >= 500
+        }
+        RodePushError::Network(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_errors() {
+        let server_error: RodePushError = NetworkError::http_request(503, "unavailable").into();
+        let rejected: RodePushError = NetworkError::http_request(422, "bad checksum").into();
+        let dropped: RodePushError = NetworkError::UploadFailed {
+            reason: "connection reset".to_string(),
+        }
+        .into();
+
+        assert!(is_transient(&server_error));
+        assert!(is_transient(&dropped));
+        assert!(!is_transient(&rejected));
+        assert!(!is_transient(&RodePushError::Validation {
+            message: "invalid".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_api_urls() {
+        let client = ApiClient::new("https://push.example.com/", Duration::from_secs(5)).unwrap();
+        assert_eq!(client.url("/apps"), "https://push.example.com/api/v1/apps");
+    }
+}
@@ -125,6 +125,32 @@ fn default_api_key_file() -> String {
     "~/.rodepush/api_key".to_string()
 }
 
+impl AuthConfig {
+    /// Resolve the API key: the configured key, or the content of the key file
+    ///
+    /// A missing key file is not an error; `None` is returned instead.
+    pub fn resolve_api_key(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if let Some(api_key) = &self.api_key {
+            return Ok(Some(api_key.clone()));
+        }
+
+        let path = expand_home(&self.api_key_file);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let api_key = fs::read_to_string(&path)?.trim().to_string();
+        Ok((!api_key.is_empty()).then_some(api_key))
+    }
+}
+
+/// Expand a leading `~` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
 impl Config {
     /// Load configuration from file
     pub fn from_file(path: &PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
@@ -288,6 +314,25 @@ api_key_file = "/path/to/api_key"
         Ok(())
     }
 
+    #[test]
+    fn test_resolve_api_key() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = TempDir::new()?;
+        let key_path = temp_dir.path().join("api_key");
+        let mut auth = AuthConfig {
+            api_key_file: key_path.to_string_lossy().to_string(),
+            api_key: None,
+        };
+        assert_eq!(auth.resolve_api_key()?, None);
+
+        std::fs::write(&key_path, "rp_from_file\n")?;
+        assert_eq!(auth.resolve_api_key()?.as_deref(), Some("rp_from_file"));
+
+        auth.api_key = Some("rp_from_env".to_string());
+        assert_eq!(auth.resolve_api_key()?.as_deref(), Some("rp_from_env"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_config_load_with_env() -> Result<(), Box<dyn std::error::Error>> {
         // This test is tricky because we can't easily modify environment variables in a test
@@ -0,0 +1,93 @@
+//! Deployment management
+//!
+//! Promoting deploys the bundle of a deployment, by default the one active
+//! in the source environment, to another environment of the application.
+
+use rodepush_core::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::client::ApiClient;
+
+/// A deployment as reported by the server
+#[derive(Debug, Clone, Deserialize)]
+pub struct Deployment {
+    /// Deployment ID
+    pub id: String,
+    /// Deployed bundle
+    pub bundle_id: String,
+    /// Environment the bundle is deployed to
+    pub environment: String,
+    /// Deployment status
+    pub status: String,
+    /// Percentage of clients receiving the deployment
+    pub rollout_percentage: u32,
+    /// Deployment description
+    pub description: Option<String>,
+}
+
+/// Request body for promoting a deployment
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PromoteRequest {
+    /// Environment to promote from
+    pub from_environment: String,
+    /// Environment to promote to
+    pub to_environment: String,
+    /// Deployment to promote instead of the active one of `from_environment`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment_id: Option<String>,
+    /// Percentage of clients receiving the new deployment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout_percentage: Option<u32>,
+    /// Description of the new deployment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Whether clients must install the new deployment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mandatory: Option<bool>,
+}
+
+/// Client for the server's deployment API
+pub struct DeploymentClient {
+    client: ApiClient,
+}
+
+impl DeploymentClient {
+    /// Create a client on top of an authenticated API client
+    pub fn new(client: ApiClient) -> Self {
+        Self { client }
+    }
+
+    /// Promote a deployment to another environment, returning the new deployment
+    pub async fn promote(&self, app_id: &str, request: &PromoteRequest) -> Result<Deployment> {
+        let url = self
+            .client
+            .url(&format!("/apps/{}/deployments/promote", app_id));
+        // Not retried: a lost response would otherwise deploy the bundle twice
+        self.client
+            .send(self.client.http().post(&url).json(request))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promote_request_omits_defaults() {
+        let request = PromoteRequest {
+            from_environment: "staging".to_string(),
+            to_environment: "production".to_string(),
+            rollout_percentage: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "from_environment": "staging",
+                "to_environment": "production",
+                "rollout_percentage": 10
+            })
+        );
+    }
+}
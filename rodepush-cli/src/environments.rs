@@ -0,0 +1,137 @@
+//! Environment management
+//!
+//! Every environment of an application has a deployment key clients can
+//! check for updates with, and a default rollout applied to deployments that
+//! do not choose their own. Managing environments requires the `admin` scope.
+
+use rodepush_core::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::client::ApiClient;
+
+/// A stage of a rollout plan
+#[derive(Debug, Clone, Deserialize)]
+pub struct RolloutStep {
+    /// Percentage of clients receiving the deployment during the stage
+    pub percentage: u32,
+    /// Seconds to wait before moving to the next stage
+    pub wait_seconds: u64,
+}
+
+/// An environment as reported by the server
+#[derive(Debug, Clone, Deserialize)]
+pub struct Environment {
+    /// Environment name
+    pub name: String,
+    /// Environment description
+    pub description: Option<String>,
+    /// Percentage of clients receiving new deployments
+    pub default_rollout_percentage: u32,
+    /// Stages new deployments are rolled out in
+    pub default_rollout_plan: Option<Vec<RolloutStep>>,
+    /// Key clients check for updates of the environment with
+    pub deployment_key: String,
+    /// Creation timestamp
+    pub created_at: String,
+}
+
+/// Request body for creating an environment
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateEnvironmentRequest {
+    /// Environment name
+    pub name: String,
+    /// Environment description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Percentage of clients receiving new deployments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_rollout_percentage: Option<u32>,
+}
+
+/// Client for the server's environment API
+pub struct EnvironmentClient {
+    client: ApiClient,
+}
+
+impl EnvironmentClient {
+    /// Create a client on top of an authenticated API client
+    pub fn new(client: ApiClient) -> Self {
+        Self { client }
+    }
+
+    /// List the environments of an application
+    pub async fn list(&self, app_id: &str) -> Result<Vec<Environment>> {
+        let url = self.client.url(&format!("/apps/{}/environments", app_id));
+        self.client
+            .send_with_retry(|| self.client.http().get(&url))
+            .await
+    }
+
+    /// Create an environment
+    pub async fn create(
+        &self,
+        app_id: &str,
+        request: &CreateEnvironmentRequest,
+    ) -> Result<Environment> {
+        let url = self.client.url(&format!("/apps/{}/environments", app_id));
+        // Not retried: a lost response would otherwise report a conflict
+        self.client
+            .send(self.client.http().post(&url).json(request))
+            .await
+    }
+
+    /// Get a single environment
+    pub async fn show(&self, app_id: &str, name: &str) -> Result<Environment> {
+        let url = self
+            .client
+            .url(&format!("/apps/{}/environments/{}", app_id, name));
+        self.client
+            .send_with_retry(|| self.client.http().get(&url))
+            .await
+    }
+
+    /// Delete an environment that was never deployed to
+    pub async fn delete(&self, app_id: &str, name: &str) -> Result<()> {
+        let url = self
+            .client
+            .url(&format!("/apps/{}/environments/{}", app_id, name));
+        self.client
+            .send_empty(self.client.http().delete(&url))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_from_server_response() {
+        let environment: Environment = serde_json::from_value(serde_json::json!({
+            "id": "5b0c3f7e-8d52-4b0b-9a53-1c1f0a6d2e11",
+            "application_id": "0f8e4c1a-3b7d-4e6f-9a2b-5c8d7e6f1a2b",
+            "name": "production",
+            "description": null,
+            "default_rollout_percentage": 10,
+            "default_rollout_plan": [
+                {"percentage": 10, "wait_seconds": 3600},
+                {"percentage": 100, "wait_seconds": 0}
+            ],
+            "deployment_key": "rpd_0123456789abcdef",
+            "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-01T00:00:00Z"
+        }))
+        .unwrap();
+        assert_eq!(environment.name, "production");
+        assert_eq!(environment.default_rollout_plan.unwrap().len(), 2);
+
+        let request = CreateEnvironmentRequest {
+            name: "beta".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({"name": "beta"})
+        );
+    }
+}
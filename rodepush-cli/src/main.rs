@@ -1,16 +1,28 @@
-use clap::Parser;
+use clap::{Args, Parser};
 use rodepush_core::{
-    AssetCollection, AssetCompressor, AssetDiffEngine, CompressedAssetCollection, LogConfig,
-    LogContext, LogFormat, Platform, init_logging,
+    AssetCollection, AssetCompressor, AssetDiffEngine, AssetInspector, AssetLayout,
+    AssetScanOptions, CompressedAssetCollection, ImageOptimizer, LogConfig, LogContext, LogFormat,
+    Platform, SemanticVersion, SymlinkPolicy, init_logging, source_date_epoch,
 };
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 use tracing::info;
 
+mod apps;
+mod client;
 mod config;
+mod deployments;
+mod environments;
 mod react_native;
+mod upload;
+use apps::AppClient;
+use client::ApiClient;
 use config::Config;
+use deployments::{DeploymentClient, PromoteRequest};
+use environments::{CreateEnvironmentRequest, EnvironmentClient};
 use react_native::{BuildConfig, ReactNativeBuilder};
+use upload::{DEFAULT_CHUNK_SIZE, UploadClient};
 
 #[cfg(test)]
 mod cli_tests;
@@ -44,6 +56,13 @@ enum Commands {
         #[arg(long)]
         assets_dir: Option<PathBuf>,
 
+        #[command(flatten)]
+        asset_filters: AssetFilterArgs,
+
+        /// Derive bundle and asset collection IDs from content
+        #[arg(long)]
+        deterministic: bool,
+
         /// Platform to build for (ios, android, both)
         #[arg(long, default_value = "both")]
         platform: String,
@@ -73,6 +92,30 @@ enum Commands {
         /// API key for authentication
         #[arg(long)]
         api_key: Option<String>,
+
+        /// Application ID to upload the bundle to
+        #[arg(long)]
+        app_id: Option<String>,
+
+        /// Platform of the bundle (ios, android, both)
+        #[arg(long)]
+        platform: Option<String>,
+
+        /// Version of the bundle
+        #[arg(long)]
+        bundle_version: Option<String>,
+
+        /// Entry file the bundle was built from
+        #[arg(long)]
+        entry_file: Option<String>,
+
+        /// Size of each uploaded chunk in bytes
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+
+        /// How often a failed request is retried before giving up
+        #[arg(long, default_value_t = 3)]
+        retries: u32,
     },
     /// Deploy a bundle to an environment
     Deploy {
@@ -85,11 +128,206 @@ enum Commands {
         environment: Option<String>,
     },
 
+    /// Deploy the bundle of one environment to another (e.g., staging to production)
+    Promote {
+        /// Server URL
+        #[arg(long)]
+        server_url: Option<String>,
+
+        /// API key for authentication
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Application ID
+        #[arg(long)]
+        app_id: String,
+
+        /// Environment to promote from
+        #[arg(long = "from")]
+        from_environment: String,
+
+        /// Environment to promote to
+        #[arg(long = "to")]
+        to_environment: String,
+
+        /// Deployment to promote instead of the active one of the source environment
+        #[arg(long)]
+        deployment_id: Option<String>,
+
+        /// Percentage of clients receiving the new deployment
+        #[arg(long)]
+        rollout: Option<u32>,
+
+        /// Description of the new deployment
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Make clients install the new deployment
+        #[arg(long)]
+        mandatory: bool,
+    },
+
     /// Process assets (create collection, diff, compress)
     Assets {
         #[command(subcommand)]
         action: AssetActions,
     },
+
+    /// Manage applications on the server
+    App {
+        /// Server URL
+        #[arg(long, global = true)]
+        server_url: Option<String>,
+
+        /// API key for authentication; creating and listing applications needs the server admin key
+        #[arg(long, global = true)]
+        api_key: Option<String>,
+
+        #[command(subcommand)]
+        action: AppActions,
+    },
+
+    /// Manage the environments of an application
+    Env {
+        /// Server URL
+        #[arg(long, global = true)]
+        server_url: Option<String>,
+
+        /// API key for authentication; needs the admin scope
+        #[arg(long, global = true)]
+        api_key: Option<String>,
+
+        /// Application ID
+        #[arg(long, global = true)]
+        app_id: Option<String>,
+
+        #[command(subcommand)]
+        action: EnvActions,
+    },
+}
+
+#[derive(Parser)]
+enum EnvActions {
+    /// List environments with their deployment keys
+    List,
+
+    /// Create an environment
+    Create {
+        /// Environment name (lowercase letters, digits, '-' and '_')
+        name: String,
+
+        /// Environment description
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Percentage of clients receiving new deployments by default
+        #[arg(long)]
+        rollout: Option<u32>,
+    },
+
+    /// Show an environment
+    Show {
+        /// Environment name
+        name: String,
+    },
+
+    /// Delete an environment that was never deployed to
+    Delete {
+        /// Environment name
+        name: String,
+    },
+}
+
+#[derive(Parser)]
+enum AppActions {
+    /// Create an application and print its API key
+    Create {
+        /// Application name
+        name: String,
+
+        /// Application description
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Application owner
+        #[arg(long)]
+        owner: Option<String>,
+    },
+
+    /// List applications
+    List {
+        /// Maximum number of applications to list
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+
+        /// Number of applications to skip
+        #[arg(long, default_value_t = 0)]
+        offset: u32,
+    },
+
+    /// Show an application
+    Show {
+        /// Application ID
+        app_id: String,
+    },
+
+    /// Delete an application with all its bundles and deployments
+    Delete {
+        /// Application ID
+        app_id: String,
+
+        /// Confirm the deletion
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+/// Options controlling which files of an assets directory are collected
+#[derive(Args, Debug, Clone)]
+struct AssetFilterArgs {
+    /// Gitignore-style pattern for files to leave out (repeatable)
+    #[arg(long = "ignore")]
+    ignore_patterns: Vec<String>,
+
+    /// Glob a file must match to be collected; without a `/` it matches
+    /// file names in any directory (repeatable)
+    #[arg(long = "include")]
+    include_patterns: Vec<String>,
+
+    /// How symbolic links are treated (follow, skip, error)
+    #[arg(long, default_value = "skip")]
+    symlinks: String,
+
+    /// Fail if any asset file is larger than this many bytes
+    #[arg(long)]
+    max_file_size: Option<u64>,
+}
+
+impl AssetFilterArgs {
+    /// Convert the command line flags into scan options
+    fn to_scan_options(&self) -> rodepush_core::Result<AssetScanOptions> {
+        let mut options =
+            AssetScanOptions::new().with_symlink_policy(SymlinkPolicy::from_str(&self.symlinks)?);
+        for pattern in &self.ignore_patterns {
+            options = options.with_ignore_pattern(pattern.clone());
+        }
+        for pattern in &self.include_patterns {
+            options = options.with_include_pattern(pattern.clone());
+        }
+        if let Some(max_file_size) = self.max_file_size {
+            options = options.with_max_file_size(max_file_size);
+        }
+        Ok(options)
+    }
+}
+
+/// Give a collection its content-based ID and the `SOURCE_DATE_EPOCH` timestamp, if set
+fn reproducible_collection(collection: AssetCollection) -> rodepush_core::Result<AssetCollection> {
+    let collection = collection.with_content_id();
+    Ok(match source_date_epoch()? {
+        Some(created_at) => collection.with_created_at(created_at),
+        None => collection,
+    })
 }
 
 #[derive(Parser)]
@@ -102,6 +340,21 @@ enum AssetActions {
         /// Output file for the asset collection (JSON format)
         #[arg(long)]
         output: Option<PathBuf>,
+
+        /// Only keep the assets a client on this platform needs (ios, android)
+        #[arg(long)]
+        platform: Option<String>,
+
+        /// Layout of the assets directory (source, metro-ios, metro-android)
+        #[arg(long, default_value = "source")]
+        layout: String,
+
+        #[command(flatten)]
+        asset_filters: AssetFilterArgs,
+
+        /// Derive the collection ID from content
+        #[arg(long)]
+        deterministic: bool,
     },
 
     /// Diff two asset collections
@@ -117,24 +370,76 @@ enum AssetActions {
         output: Option<PathBuf>,
     },
 
-    /// Compress an asset collection
+    /// Package the files of an asset collection into a compressed archive
     Compress {
         /// Path to the asset collection JSON file
         collection: PathBuf,
 
+        /// Directory containing the asset files described by the collection
+        assets_dir: PathBuf,
+
         /// Output file for the compressed data
         #[arg(long)]
         output: Option<PathBuf>,
     },
 
-    /// Decompress an asset collection
+    /// Losslessly optimize the images of an asset collection
+    Optimize {
+        /// Path to the asset collection JSON file
+        collection: PathBuf,
+
+        /// Directory containing the asset files described by the collection
+        assets_dir: PathBuf,
+
+        /// Output directory for the optimized assets
+        #[arg(long)]
+        output_dir: PathBuf,
+
+        /// Directory used to cache optimized images between builds
+        #[arg(long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Output file for the optimized asset collection (JSON format)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check asset files for corruption and content that does not match their extension
+    Validate {
+        /// Path to the asset collection JSON file
+        collection: PathBuf,
+
+        /// Directory containing the asset files described by the collection
+        assets_dir: PathBuf,
+
+        /// Replace mismatched MIME types with the detected ones
+        #[arg(long)]
+        fix_mime_types: bool,
+
+        /// Fail on warnings as well as errors
+        #[arg(long)]
+        strict: bool,
+
+        /// Output file for the validation report (JSON format)
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Output file for the asset collection with dimensions recorded (JSON format)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Extract a compressed asset collection and verify its files
     Decompress {
         /// Path to the compressed asset collection file
         compressed_collection: PathBuf,
 
-        /// Output directory for the decompressed assets
+        /// Path to the asset collection JSON file the archive was built from
+        collection: PathBuf,
+
+        /// Output directory for the extracted assets
         #[arg(long)]
-        output_dir: Option<PathBuf>,
+        output_dir: PathBuf,
     },
 }
 
@@ -168,6 +473,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some(Commands::Build {
             project_dir,
             assets_dir,
+            asset_filters,
+            deterministic,
             platform,
             entry_file,
             output_dir,
@@ -202,6 +509,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             build_config.entry_file = effective_entry_file.clone();
             build_config.platform = effective_platform;
             build_config.output_dir = effective_output_dir.clone();
+            build_config.deterministic_ids = *deterministic;
 
             // Create React Native builder
             let builder = ReactNativeBuilder::new(build_config);
@@ -222,7 +530,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     if let Some(assets_path) = assets_dir {
                         if assets_path.exists() && assets_path.is_dir() {
                             context.info(&format!("Processing assets from: {:?}", assets_path));
-                            let asset_collection = AssetCollection::from_directory(assets_path)?;
+                            let scan_options = asset_filters.to_scan_options()?;
+                            let mut asset_collection = AssetCollection::from_directory_with_options(
+                                assets_path,
+                                &scan_options,
+                            )?;
+                            if *deterministic {
+                                asset_collection = reproducible_collection(asset_collection)?;
+                            }
                             println!(
                                 "📦 Created asset collection with {} assets, total size: {} bytes",
                                 asset_collection.len(),
@@ -248,25 +563,81 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             assets_dir,
             server_url,
             api_key,
+            app_id,
+            platform,
+            bundle_version,
+            entry_file,
+            chunk_size,
+            retries,
         }) => {
             context.info("Uploading bundle to server");
 
             // Use config values as defaults, override with command line args
             let effective_server_url = server_url.as_ref().unwrap_or(&config.server.url);
-            // For API key, we would normally load from file or env, but for now we'll just use a placeholder
-            let _effective_api_key = api_key
-                .as_deref()
-                .or_else(|| {
-                    // In a real implementation, we'd load from config.auth.api_key_file
-                    None
-                })
-                .unwrap_or("placeholder-api-key");
+            let effective_api_key = match api_key {
+                Some(api_key) => Some(api_key.clone()),
+                None => config.auth.resolve_api_key()?,
+            };
+            let effective_platform = platform.as_ref().unwrap_or(&config.build.platform);
+            let effective_entry_file = entry_file.as_ref().unwrap_or(&config.build.entry_file);
 
             info!(
                 "Upload command executed with bundle_path: {:?}, assets_dir: {:?}, server_url: {}, api_key: ***",
                 bundle_path, assets_dir, effective_server_url
             );
 
+            if let Some(bundle_path) = bundle_path {
+                let (Some(app_id), Some(bundle_version)) = (app_id, bundle_version) else {
+                    eprintln!("❌ --app-id and --bundle-version are required to upload a bundle");
+                    std::process::exit(1);
+                };
+
+                let data = std::fs::read(bundle_path)?;
+                let bundle = upload::prepare_bundle(
+                    &data,
+                    SemanticVersion::parse(bundle_version)?,
+                    Platform::from_str(effective_platform)?,
+                    effective_entry_file.clone(),
+                    *chunk_size,
+                )?;
+                println!(
+                    "Uploading bundle {} ({} bytes in {} chunks)",
+                    bundle.id(),
+                    bundle.size(),
+                    bundle.chunk_count()
+                );
+
+                let client = UploadClient::new(
+                    effective_server_url,
+                    Duration::from_secs(config.server.timeout_seconds),
+                )?
+                .with_retries(*retries, Duration::from_secs(1));
+                let Some(api_key) = effective_api_key else {
+                    eprintln!(
+                        "❌ An API key is required to upload: pass --api-key, set RODEPUSH_API_KEY or write it to {}",
+                        config.auth.api_key_file
+                    );
+                    std::process::exit(1);
+                };
+                let client = client.with_api_key(api_key);
+
+                match client.upload_bundle(app_id, &bundle).await {
+                    Ok(uploaded) if uploaded.created => println!(
+                        "✅ Uploaded bundle {} ({} bytes, checksum {})",
+                        uploaded.bundle_id, uploaded.size_bytes, uploaded.checksum
+                    ),
+                    Ok(uploaded) => println!(
+                        "✅ Bundle {} was already on the server",
+                        uploaded.bundle_id
+                    ),
+                    Err(e) => {
+                        eprintln!("❌ Upload failed: {}", e);
+                        eprintln!("   Run the same command again to resume the upload");
+                        std::process::exit(1);
+                    }
+                }
+            }
+
             // If assets directory is provided, process it
             if let Some(assets_path) = assets_dir {
                 if assets_path.exists() && assets_path.is_dir() {
@@ -299,15 +670,96 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 effective_app_id, effective_environment
             );
         }
+        Some(Commands::Promote {
+            server_url,
+            api_key,
+            app_id,
+            from_environment,
+            to_environment,
+            deployment_id,
+            rollout,
+            description,
+            mandatory,
+        }) => {
+            context.info(&format!(
+                "Promoting {} to {}",
+                from_environment, to_environment
+            ));
+
+            let effective_server_url = server_url.as_ref().unwrap_or(&config.server.url);
+            let effective_api_key = match api_key {
+                Some(api_key) => Some(api_key.clone()),
+                None => config.auth.resolve_api_key()?,
+            };
+            let Some(effective_api_key) = effective_api_key else {
+                eprintln!(
+                    "❌ An API key is required: pass --api-key or write it to {}",
+                    config.auth.api_key_file
+                );
+                std::process::exit(1);
+            };
+            let client = DeploymentClient::new(
+                ApiClient::new(
+                    effective_server_url,
+                    Duration::from_secs(config.server.timeout_seconds),
+                )?
+                .with_api_key(effective_api_key),
+            );
+
+            let request = PromoteRequest {
+                from_environment: from_environment.clone(),
+                to_environment: to_environment.clone(),
+                deployment_id: deployment_id.clone(),
+                rollout_percentage: *rollout,
+                description: description.clone(),
+                mandatory: mandatory.then_some(true),
+            };
+            match client.promote(app_id, &request).await {
+                Ok(deployment) => {
+                    println!(
+                        "✅ Promoted bundle {} from {} to {}",
+                        deployment.bundle_id, from_environment, deployment.environment
+                    );
+                    println!("   Deployment: {}", deployment.id);
+                    println!("   Status:     {}", deployment.status);
+                    println!("   Rollout:    {}%", deployment.rollout_percentage);
+                    if let Some(description) = &deployment.description {
+                        println!("   Notes:      {}", description);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("❌ Promotion failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         Some(Commands::Assets { action }) => {
             match action {
-                AssetActions::Create { assets_dir, output } => {
+                AssetActions::Create {
+                    assets_dir,
+                    output,
+                    platform,
+                    layout,
+                    asset_filters,
+                    deterministic,
+                } => {
                     context.info(&format!("Creating asset collection from: {:?}", assets_dir));
                     if assets_dir.exists() && assets_dir.is_dir() {
-                        let asset_collection = AssetCollection::from_directory(assets_dir)?;
+                        let layout = AssetLayout::from_str(layout)?;
+                        let scan_options = asset_filters.to_scan_options()?;
+                        let mut asset_collection =
+                            AssetCollection::from_directory_with_options(assets_dir, &scan_options)?;
+                        if let Some(platform) = platform {
+                            let platform = Platform::from_str(platform)?;
+                            asset_collection = asset_collection.for_platform(platform, layout);
+                        }
+                        if *deterministic {
+                            asset_collection = reproducible_collection(asset_collection)?;
+                        }
                         println!(
-                            "Created asset collection with {} assets, total size: {} bytes",
+                            "Created asset collection with {} assets ({} logical), total size: {} bytes",
                             asset_collection.len(),
+                            asset_collection.logical_assets(layout).len(),
                             asset_collection.total_size
                         );
 
@@ -343,6 +795,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("  Added: {}", diff.added.len());
                     println!("  Removed: {}", diff.removed.len());
                     println!("  Renamed: {}", diff.renamed.len());
+                    println!("  Copied: {}", diff.copied.len());
                     println!("  Modified: {}", diff.modified.len());
 
                     if let Some(output_path) = output {
@@ -351,12 +804,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("Asset diff saved to: {:?}", output_path);
                     }
                 }
-                AssetActions::Compress { collection, output } => {
+                AssetActions::Compress {
+                    collection,
+                    assets_dir,
+                    output,
+                } => {
                     context.info("Compressing asset collection");
                     let json = std::fs::read_to_string(collection)?;
                     let asset_collection: AssetCollection = serde_json::from_str(&json)?;
 
-                    let compressed = AssetCompressor::compress_collection(&asset_collection)?;
+                    let compressed =
+                        AssetCompressor::compress_collection(&asset_collection, assets_dir)?;
 
                     println!("Asset collection compressed:");
                     println!(
@@ -376,39 +834,293 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("Compressed asset collection saved to: {:?}", output_path);
                     }
                 }
+                AssetActions::Optimize {
+                    collection,
+                    assets_dir,
+                    output_dir,
+                    cache_dir,
+                    output,
+                } => {
+                    context.info("Optimizing asset collection images");
+                    let json = std::fs::read_to_string(collection)?;
+                    let asset_collection: AssetCollection = serde_json::from_str(&json)?;
+
+                    let mut optimizer = ImageOptimizer::new();
+                    if let Some(cache_dir) = cache_dir {
+                        optimizer = optimizer.with_cache_dir(cache_dir);
+                    }
+                    let (optimized, report) =
+                        optimizer.optimize_collection(&asset_collection, assets_dir, output_dir)?;
+
+                    println!("Asset images optimized:");
+                    println!("  Images processed: {}", report.images_processed);
+                    println!("  Images optimized: {}", report.images_optimized);
+                    println!("  Cache hits: {}", report.cache_hits);
+                    println!("  Bytes saved: {}", report.bytes_saved());
+                    println!("Optimized assets written to: {:?}", output_dir);
+
+                    if let Some(output_path) = output {
+                        let json = serde_json::to_string_pretty(&optimized)?;
+                        std::fs::write(output_path, json)?;
+                        println!("Optimized asset collection saved to: {:?}", output_path);
+                    }
+                }
+                AssetActions::Validate {
+                    collection,
+                    assets_dir,
+                    fix_mime_types,
+                    strict,
+                    report,
+                    output,
+                } => {
+                    context.info("Validating asset collection");
+                    let json = std::fs::read_to_string(collection)?;
+                    let asset_collection: AssetCollection = serde_json::from_str(&json)?;
+
+                    let inspector = AssetInspector::new().with_mime_correction(*fix_mime_types);
+                    let (inspected, validation) =
+                        inspector.inspect_collection(&asset_collection, assets_dir)?;
+
+                    for issue in &validation.issues {
+                        println!("{}", issue);
+                    }
+                    println!("Asset validation:");
+                    println!("  Assets checked: {}", validation.assets_checked);
+                    println!("  Dimensions recorded: {}", validation.dimensions_recorded);
+                    println!("  MIME types corrected: {}", validation.mime_types_corrected);
+                    println!("  Errors: {}", validation.errors().count());
+                    println!("  Warnings: {}", validation.warnings().count());
+
+                    if let Some(report_path) = report {
+                        let json = serde_json::to_string_pretty(&validation)?;
+                        std::fs::write(report_path, json)?;
+                        println!("Validation report saved to: {:?}", report_path);
+                    }
+                    if let Some(output_path) = output {
+                        let json = serde_json::to_string_pretty(&inspected)?;
+                        std::fs::write(output_path, json)?;
+                        println!("Asset collection saved to: {:?}", output_path);
+                    }
+
+                    if validation.has_errors() || (*strict && !validation.is_clean()) {
+                        eprintln!("❌ Asset validation failed");
+                        std::process::exit(1);
+                    }
+                }
                 AssetActions::Decompress {
                     compressed_collection,
+                    collection,
                     output_dir,
                 } => {
                     context.info("Decompressing asset collection");
                     let compressed_data = std::fs::read(compressed_collection)?;
+                    let json = std::fs::read_to_string(collection)?;
+                    let asset_collection: AssetCollection = serde_json::from_str(&json)?;
 
                     let compressed_collection = CompressedAssetCollection {
-                        data: compressed_data,
                         uncompressed_size: 0, // These values aren't used in decompression
-                        compressed_size: 0,   // These values aren't used in decompression
+                        compressed_size: compressed_data.len() as u64,
+                        data: compressed_data,
                         compression_type: rodepush_core::CompressionType::Zstd,
                     };
 
-                    let asset_collection =
-                        AssetCompressor::decompress_collection(&compressed_collection)?;
+                    AssetCompressor::decompress_collection(
+                        &compressed_collection,
+                        &asset_collection,
+                        output_dir,
+                    )?;
 
                     println!(
-                        "Asset collection decompressed with {} assets",
-                        asset_collection.len()
+                        "Extracted and verified {} assets to: {:?}",
+                        asset_collection.len(),
+                        output_dir
                     );
+                }
+            }
+        }
+        Some(Commands::App {
+            server_url,
+            api_key,
+            action,
+        }) => {
+            let effective_server_url = server_url.as_ref().unwrap_or(&config.server.url);
+            // The admin key takes precedence over the configured application API key
+            let effective_api_key = match api_key {
+                Some(api_key) => Some(api_key.clone()),
+                None => match std::env::var("RODEPUSH_ADMIN_KEY") {
+                    Ok(admin_key) if !admin_key.trim().is_empty() => {
+                        Some(admin_key.trim().to_string())
+                    }
+                    _ => config.auth.resolve_api_key()?,
+                },
+            };
+            let Some(effective_api_key) = effective_api_key else {
+                eprintln!(
+                    "❌ An API key is required: pass --api-key, set RODEPUSH_ADMIN_KEY or write it to {}",
+                    config.auth.api_key_file
+                );
+                std::process::exit(1);
+            };
+            let client = AppClient::new(
+                ApiClient::new(
+                    effective_server_url,
+                    Duration::from_secs(config.server.timeout_seconds),
+                )?
+                .with_api_key(effective_api_key),
+            );
 
-                    if let Some(output_path) = output_dir {
-                        // In a real implementation, we would extract the assets to the directory
-                        // For now, we'll just save the collection metadata
-                        let json = serde_json::to_string_pretty(&asset_collection)?;
-                        std::fs::write(output_path.join("assets.json"), json)?;
-                        println!(
-                            "Decompressed asset collection metadata saved to: {:?}",
-                            output_path
+            let result = match action {
+                AppActions::Create {
+                    name,
+                    description,
+                    owner,
+                } => {
+                    context.info(&format!("Creating application {}", name));
+                    client
+                        .create(name, description.as_deref(), owner.as_deref())
+                        .await
+                        .map(|created| {
+                            println!(
+                                "✅ Created application {} ({})",
+                                created.application.name, created.application.id
+                            );
+                            println!("🔑 API key: {}", created.api_key);
+                            println!("   Store it now, it cannot be shown again");
+                        })
+                }
+                AppActions::List { limit, offset } => {
+                    client.list(*limit, *offset).await.map(|page| {
+                        if page.items.is_empty() {
+                            println!("No applications found");
+                        }
+                        for application in &page.items {
+                            println!("{}  {}", application.id, application.name);
+                        }
+                    })
+                }
+                AppActions::Show { app_id } => client.show(app_id).await.map(|application| {
+                    println!("ID:          {}", application.id);
+                    println!("Name:        {}", application.name);
+                    println!(
+                        "Description: {}",
+                        application.description.as_deref().unwrap_or("-")
+                    );
+                    println!(
+                        "Owner:       {}",
+                        application.owner.as_deref().unwrap_or("-")
+                    );
+                    println!("Created:     {}", application.created_at);
+                    println!("Updated:     {}", application.updated_at);
+                    for (key, value) in &application.settings {
+                        println!("Setting:     {} = {}", key, value);
+                    }
+                }),
+                AppActions::Delete { app_id, yes } => {
+                    if !*yes {
+                        eprintln!(
+                            "❌ Deleting application {} removes all its bundles and deployments; pass --yes to confirm",
+                            app_id
                         );
+                        std::process::exit(1);
                     }
+                    client
+                        .delete(app_id)
+                        .await
+                        .map(|()| println!("✅ Deleted application {}", app_id))
                 }
+            };
+            if let Err(e) = result {
+                eprintln!("❌ Request failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Env {
+            server_url,
+            api_key,
+            app_id,
+            action,
+        }) => {
+            let Some(app_id) = app_id else {
+                eprintln!("❌ An application is required: pass --app-id");
+                std::process::exit(1);
+            };
+            let effective_server_url = server_url.as_ref().unwrap_or(&config.server.url);
+            let effective_api_key = match api_key {
+                Some(api_key) => Some(api_key.clone()),
+                None => config.auth.resolve_api_key()?,
+            };
+            let Some(effective_api_key) = effective_api_key else {
+                eprintln!(
+                    "❌ An API key is required: pass --api-key or write it to {}",
+                    config.auth.api_key_file
+                );
+                std::process::exit(1);
+            };
+            let client = EnvironmentClient::new(
+                ApiClient::new(
+                    effective_server_url,
+                    Duration::from_secs(config.server.timeout_seconds),
+                )?
+                .with_api_key(effective_api_key),
+            );
+
+            let print_environment = |environment: &environments::Environment| {
+                println!("Name:           {}", environment.name);
+                println!(
+                    "Description:    {}",
+                    environment.description.as_deref().unwrap_or("-")
+                );
+                println!("Deployment key: {}", environment.deployment_key);
+                match &environment.default_rollout_plan {
+                    Some(steps) => {
+                        let stages: Vec<String> = steps
+                            .iter()
+                            .map(|step| format!("{}% for {}s", step.percentage, step.wait_seconds))
+                            .collect();
+                        println!("Rollout:        {}", stages.join(", then "));
+                    }
+                    None => println!("Rollout:        {}%", environment.default_rollout_percentage),
+                }
+                println!("Created:        {}", environment.created_at);
+            };
+
+            let result = match action {
+                EnvActions::List => client.list(app_id).await.map(|environments| {
+                    if environments.is_empty() {
+                        println!("No environments found");
+                    }
+                    for environment in &environments {
+                        println!("{}  {}", environment.deployment_key, environment.name);
+                    }
+                }),
+                EnvActions::Create {
+                    name,
+                    description,
+                    rollout,
+                } => {
+                    context.info(&format!("Creating environment {}", name));
+                    let request = CreateEnvironmentRequest {
+                        name: name.clone(),
+                        description: description.clone(),
+                        default_rollout_percentage: *rollout,
+                    };
+                    client.create(app_id, &request).await.map(|environment| {
+                        println!("✅ Created environment {}", environment.name);
+                        print_environment(&environment);
+                    })
+                }
+                EnvActions::Show { name } => client
+                    .show(app_id, name)
+                    .await
+                    .map(|environment| print_environment(&environment)),
+                EnvActions::Delete { name } => client
+                    .delete(app_id, name)
+                    .await
+                    .map(|()| println!("✅ Deleted environment {}", name)),
+            };
+            if let Err(e) = result {
+                eprintln!("❌ Request failed: {}", e);
+                std::process::exit(1);
             }
         }
         None => {
@@ -3,7 +3,9 @@
 //! This module provides functionality to build React Native JavaScript bundles
 //! from source code, including platform-specific configurations and optimization.
 
-use rodepush_core::{Bundle, BundleBuilder, BundleError, Platform, Result, SemanticVersion};
+use rodepush_core::{
+    Bundle, BundleBuilder, BundleError, Platform, Result, SemanticVersion, source_date_epoch,
+};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
@@ -28,6 +30,8 @@ pub struct BuildConfig {
     pub metro_options: Vec<String>,
     /// Environment variables for the build process
     pub env_vars: std::collections::HashMap<String, String>,
+    /// Whether bundle IDs are derived from content instead of generated randomly
+    pub deterministic_ids: bool,
 }
 
 impl Default for BuildConfig {
@@ -41,6 +45,7 @@ impl Default for BuildConfig {
             source_maps: false,
             metro_options: Vec::new(),
             env_vars: std::collections::HashMap::new(),
+            deterministic_ids: false,
         }
     }
 }
@@ -256,6 +261,12 @@ impl ReactNativeBuilder {
         // Create bundle metadata
         let version = self.extract_version_from_package_json()?;
         let mut builder = BundleBuilder::new(version, platform, self.config.entry_file.clone());
+        if self.config.deterministic_ids {
+            builder = builder.with_deterministic_id();
+            if let Some(created_at) = source_date_epoch()? {
+                builder = builder.with_created_at(created_at);
+            }
+        }
 
         // Add bundle data as a single chunk
         builder.add_chunk_from_data(&bundle_data, "main".to_string())?;
@@ -410,6 +421,7 @@ mod tests {
         assert_eq!(config.platform, Platform::Both);
         assert!(config.minify);
         assert!(!config.source_maps);
+        assert!(!config.deterministic_ids);
     }
 
     #[test]
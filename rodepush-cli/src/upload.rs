@@ -0,0 +1,200 @@
+//! Resumable bundle uploads
+//!
+//! Bundles are sent through the server's upload sessions: the bundle metadata
+//! opens a session, every chunk the server reports missing is uploaded on its
+//! own, and a final commit assembles the bundle. Bundle ids are derived from
+//! content, so running an interrupted upload again resumes the same session
+//! and only sends the chunks that never arrived.
+
+use rodepush_core::{
+    Bundle, BundleBuilder, BundleError, NetworkError, Platform, Result, RodePushError,
+    SemanticVersion, source_date_epoch,
+};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+
+use crate::client::ApiClient;
+
+/// Default size of an uploaded chunk before compression, in bytes
+pub const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Split bundle data into chunks and build its metadata
+///
+/// The bundle id is derived from the content, and the creation time honours
+/// `SOURCE_DATE_EPOCH`, so the same input always produces the same upload.
+pub fn prepare_bundle(
+    data: &[u8],
+    version: SemanticVersion,
+    platform: Platform,
+    entry_point: String,
+    chunk_size: usize,
+) -> Result<Bundle> {
+    if chunk_size == 0 {
+        return Err(RodePushError::Validation {
+            message: "Chunk size must be greater than zero".to_string(),
+        });
+    }
+    if data.is_empty() {
+        return Err(BundleError::invalid_format("Bundle file is empty").into());
+    }
+
+    let mut builder = BundleBuilder::new(version, platform, entry_point).with_deterministic_id();
+    if let Some(created_at) = source_date_epoch()? {
+        builder = builder.with_created_at(created_at);
+    }
+    for (index, chunk) in data.chunks(chunk_size).enumerate() {
+        builder.add_chunk_from_data(chunk, format!("chunk-{}", index))?;
+    }
+    builder.build()
+}
+
+/// Upload session as reported by the server
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadSession {
+    /// ID of the upload session
+    pub session_id: String,
+    /// Checksums of the chunks the server still needs
+    pub missing_chunks: Vec<String>,
+}
+
+/// Bundle stored by a committed upload
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadedBundle {
+    /// ID of the stored bundle
+    pub bundle_id: String,
+    /// Size of the stored bundle data in bytes
+    pub size_bytes: u64,
+    /// Checksum of the stored bundle data
+    pub checksum: String,
+    /// Whether the upload stored a new bundle, as opposed to matching an existing one
+    pub created: bool,
+}
+
+/// Client for the server's resumable upload API
+pub struct UploadClient {
+    client: ApiClient,
+}
+
+impl UploadClient {
+    /// Create a client for the server at `server_url`
+    pub fn new(server_url: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: ApiClient::new(server_url, timeout)?,
+        })
+    }
+
+    /// Authenticate requests with an API key
+    pub fn with_api_key(mut self, api_key: String) -> Self {
+        self.client = self.client.with_api_key(api_key);
+        self
+    }
+
+    /// Set how often a failed request is retried and the delay before the first retry
+    ///
+    /// The delay doubles with every further attempt.
+    pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.client = self.client.with_retries(max_retries, retry_delay);
+        self
+    }
+
+    /// Upload a bundle, resuming an earlier interrupted upload when possible
+    pub async fn upload_bundle(&self, app_id: &str, bundle: &Bundle) -> Result<UploadedBundle> {
+        let sessions_url = self.client.url(&format!("/apps/{}/uploads", app_id));
+        let metadata = serde_json::to_vec(&bundle.metadata)?;
+        let session: UploadSession = self
+            .client
+            .send_with_retry(|| {
+                self.client
+                    .http()
+                    .post(&sessions_url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(metadata.clone())
+            })
+            .await?;
+
+        let total = bundle.metadata.chunks.len();
+        let missing = session.missing_chunks.len();
+        if missing < total {
+            info!(
+                "Resuming upload session {}: {} of {} chunks already on the server",
+                session.session_id,
+                total - missing,
+                total
+            );
+        }
+
+        let session_url = format!("{}/{}", sessions_url, session.session_id);
+        for (index, checksum) in session.missing_chunks.iter().enumerate() {
+            let chunk = bundle
+                .chunks
+                .iter()
+                .find(|chunk| &chunk.metadata.checksum == checksum)
+                .ok_or_else(|| NetworkError::UploadFailed {
+                    reason: format!("Server asked for unknown chunk {}", checksum),
+                })?;
+            let chunk_url = format!("{}/chunks/{}", session_url, checksum);
+            let _: serde_json::Value = self
+                .client
+                .send_with_retry(|| self.client.http().put(&chunk_url).body(chunk.data.clone()))
+                .await?;
+            info!(
+                "Uploaded chunk {} ({} bytes, {}/{})",
+                chunk.id(),
+                chunk.size(),
+                index + 1,
+                missing
+            );
+        }
+
+        let commit_url = format!("{}/commit", session_url);
+        self.client
+            .send_with_retry(|| self.client.http().post(&commit_url))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_bundle_is_reproducible() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let prepare = || {
+            prepare_bundle(
+                &data,
+                SemanticVersion::new(1, 0, 0),
+                Platform::Android,
+                "index.js".to_string(),
+                4096,
+            )
+            .unwrap()
+        };
+
+        let first = prepare();
+        let second = prepare();
+        assert_eq!(first.chunk_count(), 3);
+        assert_eq!(first.id(), second.id());
+        assert_eq!(first.metadata.checksum, second.metadata.checksum);
+        assert_eq!(first.metadata.chunks, second.metadata.chunks);
+    }
+
+    #[test]
+    fn test_prepare_bundle_rejects_bad_input() {
+        let version = SemanticVersion::new(1, 0, 0);
+        assert!(
+            prepare_bundle(
+                b"",
+                version.clone(),
+                Platform::Ios,
+                "index.js".to_string(),
+                16
+            )
+            .is_err()
+        );
+        assert!(
+            prepare_bundle(b"data", version, Platform::Ios, "index.js".to_string(), 0).is_err()
+        );
+    }
+}
@@ -0,0 +1,962 @@
+//! Content inspection and validation for asset collections.
+//!
+//! [`AssetCollection::from_directory`] derives MIME types from file
+//! extensions alone. The inspector sniffs the real type from the leading
+//! bytes of each file, flags files whose content does not match their
+//! extension, parses image headers for pixel dimensions and checks images,
+//! fonts, JSON and Lottie files for truncation or corruption. Problems are
+//! collected into a report so broken assets are caught before an update is
+//! published.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::asset_optimizer::PNG_SIGNATURE;
+use crate::assets::{AssetCollection, AssetMetadata, ImageDimensions, validate_relative_path};
+use crate::crypto::{ChecksumVerifier, HashAlgorithm};
+use crate::error::Result;
+
+/// Asset content types recognised by their leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    TrueType,
+    OpenType,
+    FontCollection,
+    Woff,
+    Woff2,
+    Json,
+    /// Lottie animation, a JSON document with a known top-level shape
+    Lottie,
+}
+
+impl AssetKind {
+    /// Detect the kind of `data` from its content
+    ///
+    /// JSON is only reported when the whole document parses.
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(PNG_SIGNATURE) {
+            return Some(AssetKind::Png);
+        }
+        if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            return Some(AssetKind::Jpeg);
+        }
+        if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            return Some(AssetKind::Gif);
+        }
+        if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            return Some(AssetKind::WebP);
+        }
+        if data.starts_with(&[0x00, 0x01, 0x00, 0x00]) || data.starts_with(b"true") {
+            return Some(AssetKind::TrueType);
+        }
+        if data.starts_with(b"OTTO") {
+            return Some(AssetKind::OpenType);
+        }
+        if data.starts_with(b"ttcf") {
+            return Some(AssetKind::FontCollection);
+        }
+        if data.starts_with(b"wOFF") {
+            return Some(AssetKind::Woff);
+        }
+        if data.starts_with(b"wOF2") {
+            return Some(AssetKind::Woff2);
+        }
+        match parse_json(data) {
+            Ok(value) if is_lottie(&value) => Some(AssetKind::Lottie),
+            Ok(_) => Some(AssetKind::Json),
+            Err(_) => None,
+        }
+    }
+
+    /// Kind an extension-derived MIME type promises, if it is one we can check
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        match mime_type {
+            "image/png" => Some(AssetKind::Png),
+            "image/jpeg" => Some(AssetKind::Jpeg),
+            "image/gif" => Some(AssetKind::Gif),
+            "image/webp" => Some(AssetKind::WebP),
+            "font/ttf" | "application/x-font-ttf" => Some(AssetKind::TrueType),
+            "font/otf" | "application/x-font-otf" => Some(AssetKind::OpenType),
+            "font/collection" => Some(AssetKind::FontCollection),
+            "font/woff" | "application/font-woff" => Some(AssetKind::Woff),
+            "font/woff2" => Some(AssetKind::Woff2),
+            "application/json" => Some(AssetKind::Json),
+            _ => None,
+        }
+    }
+
+    /// Canonical MIME type of this kind
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AssetKind::Png => "image/png",
+            AssetKind::Jpeg => "image/jpeg",
+            AssetKind::Gif => "image/gif",
+            AssetKind::WebP => "image/webp",
+            AssetKind::TrueType => "font/ttf",
+            AssetKind::OpenType => "font/otf",
+            AssetKind::FontCollection => "font/collection",
+            AssetKind::Woff => "font/woff",
+            AssetKind::Woff2 => "font/woff2",
+            AssetKind::Json | AssetKind::Lottie => "application/json",
+        }
+    }
+
+    /// Check whether this is a font format
+    pub fn is_font(&self) -> bool {
+        matches!(
+            self,
+            AssetKind::TrueType
+                | AssetKind::OpenType
+                | AssetKind::FontCollection
+                | AssetKind::Woff
+                | AssetKind::Woff2
+        )
+    }
+
+    /// Check whether content of this kind is acceptable under `mime_type`
+    ///
+    /// Fonts are interchangeable because platforms load any sfnt flavour from
+    /// either extension; Lottie animations are plain JSON.
+    pub fn matches_mime_type(&self, mime_type: &str) -> bool {
+        match AssetKind::from_mime_type(mime_type) {
+            Some(declared) if self.is_font() => declared.is_font(),
+            Some(_) => self.mime_type() == mime_type,
+            None => false,
+        }
+    }
+
+    /// Parse dimensions and check the structure of `data`, which must be of this kind
+    fn validate(&self, data: &[u8]) -> std::result::Result<Option<ImageDimensions>, String> {
+        match self {
+            AssetKind::Png => png_dimensions(data).map(Some),
+            AssetKind::Jpeg => jpeg_dimensions(data).map(Some),
+            AssetKind::Gif => gif_dimensions(data).map(Some),
+            AssetKind::WebP => webp_dimensions(data).map(Some),
+            AssetKind::TrueType | AssetKind::OpenType => validate_sfnt(data).map(|_| None),
+            AssetKind::FontCollection => validate_font_collection(data).map(|_| None),
+            AssetKind::Woff => validate_woff(data, 44).map(|_| None),
+            AssetKind::Woff2 => validate_woff(data, 48).map(|_| None),
+            AssetKind::Json => Ok(None),
+            AssetKind::Lottie => {
+                let value = parse_json(data).map_err(|e| e.to_string())?;
+                lottie_dimensions(&value).map(Some)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AssetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AssetKind::Png => "PNG",
+            AssetKind::Jpeg => "JPEG",
+            AssetKind::Gif => "GIF",
+            AssetKind::WebP => "WebP",
+            AssetKind::TrueType => "TrueType",
+            AssetKind::OpenType => "OpenType",
+            AssetKind::FontCollection => "font collection",
+            AssetKind::Woff => "WOFF",
+            AssetKind::Woff2 => "WOFF2",
+            AssetKind::Json => "JSON",
+            AssetKind::Lottie => "Lottie",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How serious an [`AssetIssue`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// The asset works but is probably not what was intended
+    Warning,
+    /// The asset is broken and must not be shipped
+    Error,
+}
+
+/// What is wrong with an asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AssetIssueKind {
+    /// The file has no content
+    Empty,
+    /// The file could not be read
+    Unreadable { reason: String },
+    /// The file does not match the size or checksum in the collection
+    ChecksumMismatch,
+    /// The content is a different type than the extension claims
+    MimeMismatch { declared: String, detected: String },
+    /// The content is truncated or malformed
+    Corrupt { reason: String },
+}
+
+/// A problem found while inspecting one asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetIssue {
+    /// Path of the asset relative to the asset root
+    pub path: String,
+    /// How serious the problem is
+    pub severity: IssueSeverity,
+    /// What is wrong
+    pub kind: AssetIssueKind,
+}
+
+impl AssetIssue {
+    fn error(path: &str, kind: AssetIssueKind) -> Self {
+        Self {
+            path: path.to_string(),
+            severity: IssueSeverity::Error,
+            kind,
+        }
+    }
+
+    fn warning(path: &str, kind: AssetIssueKind) -> Self {
+        Self {
+            path: path.to_string(),
+            severity: IssueSeverity::Warning,
+            kind,
+        }
+    }
+}
+
+impl std::fmt::Display for AssetIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            IssueSeverity::Warning => "warning",
+            IssueSeverity::Error => "error",
+        };
+        write!(f, "{}: {}: ", severity, self.path)?;
+        match &self.kind {
+            AssetIssueKind::Empty => write!(f, "file is empty"),
+            AssetIssueKind::Unreadable { reason } => write!(f, "cannot be read: {}", reason),
+            AssetIssueKind::ChecksumMismatch => {
+                write!(f, "content does not match the collection checksum")
+            }
+            AssetIssueKind::MimeMismatch { declared, detected } => {
+                write!(f, "declared as {} but contains {}", declared, detected)
+            }
+            AssetIssueKind::Corrupt { reason } => write!(f, "corrupt: {}", reason),
+        }
+    }
+}
+
+/// Result of inspecting a single asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetInspection {
+    /// Content type detected from the file's bytes
+    pub detected: Option<AssetKind>,
+    /// Pixel dimensions of images and animations
+    pub dimensions: Option<ImageDimensions>,
+    /// Problems found
+    pub issues: Vec<AssetIssue>,
+}
+
+/// Summary of an inspection pass over a collection
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetValidationReport {
+    /// Number of assets inspected
+    pub assets_checked: usize,
+    /// Number of assets whose dimensions were recorded
+    pub dimensions_recorded: usize,
+    /// Number of assets whose MIME type was replaced by the detected one
+    pub mime_types_corrected: usize,
+    /// Problems found, ordered by path
+    pub issues: Vec<AssetIssue>,
+}
+
+impl AssetValidationReport {
+    /// Issues that must block a release
+    pub fn errors(&self) -> impl Iterator<Item = &AssetIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+    }
+
+    /// Issues worth a look that do not break the asset
+    pub fn warnings(&self) -> impl Iterator<Item = &AssetIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == IssueSeverity::Warning)
+    }
+
+    /// Check whether any error was found
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// Check whether no issue of any severity was found
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Sniffs, measures and validates asset files
+#[derive(Debug, Clone, Default)]
+pub struct AssetInspector {
+    correct_mime_types: bool,
+}
+
+impl AssetInspector {
+    /// Create an inspector that reports MIME mismatches without changing metadata
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace mismatched MIME types with the detected ones
+    pub fn with_mime_correction(mut self, correct_mime_types: bool) -> Self {
+        self.correct_mime_types = correct_mime_types;
+        self
+    }
+
+    /// Inspect the content of one asset
+    pub fn inspect(&self, metadata: &AssetMetadata, data: &[u8]) -> AssetInspection {
+        let path = metadata.path.as_str();
+        let mut inspection = AssetInspection {
+            detected: None,
+            dimensions: None,
+            issues: Vec::new(),
+        };
+
+        let expected = AssetKind::from_mime_type(&metadata.mime_type);
+        if data.is_empty() {
+            let issue = match expected {
+                Some(_) => AssetIssue::error(path, AssetIssueKind::Empty),
+                None => AssetIssue::warning(path, AssetIssueKind::Empty),
+            };
+            inspection.issues.push(issue);
+            return inspection;
+        }
+
+        match AssetKind::sniff(data) {
+            Some(detected) => {
+                inspection.detected = Some(detected);
+                if expected.is_some() && !detected.matches_mime_type(&metadata.mime_type) {
+                    inspection.issues.push(AssetIssue::warning(
+                        path,
+                        AssetIssueKind::MimeMismatch {
+                            declared: metadata.mime_type.clone(),
+                            detected: detected.mime_type().to_string(),
+                        },
+                    ));
+                }
+                match detected.validate(data) {
+                    Ok(dimensions) => inspection.dimensions = dimensions,
+                    Err(reason) => inspection.issues.push(AssetIssue::error(
+                        path,
+                        AssetIssueKind::Corrupt {
+                            reason: format!("invalid {} file: {}", detected, reason),
+                        },
+                    )),
+                }
+            }
+            None => {
+                if let Some(expected) = expected {
+                    let reason = match expected {
+                        AssetKind::Json => match parse_json(data) {
+                            Err(e) => format!("invalid JSON: {}", e),
+                            Ok(_) => "invalid JSON".to_string(),
+                        },
+                        kind => format!("not a {} file", kind),
+                    };
+                    inspection
+                        .issues
+                        .push(AssetIssue::error(path, AssetIssueKind::Corrupt { reason }));
+                }
+            }
+        }
+
+        inspection
+    }
+
+    /// Inspect every asset of a collection
+    ///
+    /// Each asset is read from `source_dir` and checked against its size and
+    /// checksum first. The returned collection has dimensions recorded and,
+    /// when MIME correction is enabled, mismatched MIME types replaced.
+    pub fn inspect_collection<P: AsRef<Path>>(
+        &self,
+        collection: &AssetCollection,
+        source_dir: P,
+    ) -> Result<(AssetCollection, AssetValidationReport)> {
+        let source_dir = source_dir.as_ref();
+        let verifier = ChecksumVerifier::new(HashAlgorithm::Sha256);
+
+        let mut inspected = collection.clone();
+        let mut report = AssetValidationReport::default();
+
+        let mut paths: Vec<&String> = collection.assets.keys().collect();
+        paths.sort();
+
+        for path in paths {
+            let metadata = &collection.assets[path];
+            let relative_path = validate_relative_path(Path::new(path))?;
+            report.assets_checked += 1;
+
+            let data = match std::fs::read(source_dir.join(&relative_path)) {
+                Ok(data) => data,
+                Err(e) => {
+                    report.issues.push(AssetIssue::error(
+                        path,
+                        AssetIssueKind::Unreadable {
+                            reason: e.to_string(),
+                        },
+                    ));
+                    continue;
+                }
+            };
+            if data.len() as u64 != metadata.size
+                || verifier.verify(&data, &metadata.checksum).is_err()
+            {
+                report
+                    .issues
+                    .push(AssetIssue::error(path, AssetIssueKind::ChecksumMismatch));
+                continue;
+            }
+
+            let inspection = self.inspect(metadata, &data);
+            let entry = inspected.assets.get_mut(path).unwrap();
+            if inspection.dimensions.is_some() {
+                report.dimensions_recorded += 1;
+            }
+            entry.dimensions = inspection.dimensions;
+            if self.correct_mime_types
+                && let Some(detected) = inspection.detected
+                && inspection
+                    .issues
+                    .iter()
+                    .any(|issue| matches!(issue.kind, AssetIssueKind::MimeMismatch { .. }))
+            {
+                entry.mime_type = detected.mime_type().to_string();
+                report.mime_types_corrected += 1;
+            }
+            report.issues.extend(inspection.issues);
+        }
+
+        Ok((inspected, report))
+    }
+}
+
+fn read_u16_be(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u16_le(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u24_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+fn non_empty(width: u32, height: u32) -> std::result::Result<ImageDimensions, String> {
+    if width == 0 || height == 0 {
+        return Err(format!("zero-sized image {}x{}", width, height));
+    }
+    Ok(ImageDimensions::new(width, height))
+}
+
+/// Parse JSON, tolerating a UTF-8 byte order mark
+fn parse_json(data: &[u8]) -> serde_json::Result<serde_json::Value> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    serde_json::from_slice(data)
+}
+
+/// Lottie documents carry a version, frame timing and a layer list
+fn is_lottie(value: &serde_json::Value) -> bool {
+    let Some(object) = value.as_object() else {
+        return false;
+    };
+    object.contains_key("v")
+        && object.contains_key("ip")
+        && object.contains_key("op")
+        && object.get("layers").is_some_and(|layers| layers.is_array())
+}
+
+fn lottie_dimensions(value: &serde_json::Value) -> std::result::Result<ImageDimensions, String> {
+    let dimension = |key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_f64())
+            .filter(|v| v.is_finite() && *v >= 0.0 && *v <= u32::MAX as f64)
+            .map(|v| v.round() as u32)
+            .ok_or_else(|| format!("missing or invalid \"{}\"", key))
+    };
+    non_empty(dimension("w")?, dimension("h")?)
+}
+
+/// Walk every chunk, checking CRCs and the closing IEND chunk
+fn png_dimensions(data: &[u8]) -> std::result::Result<ImageDimensions, String> {
+    let mut offset = PNG_SIGNATURE.len();
+    let mut dimensions = None;
+    loop {
+        let length = read_u32_be(data, offset).ok_or("truncated before IEND")? as usize;
+        let chunk_type = data
+            .get(offset + 4..offset + 8)
+            .ok_or("truncated before IEND")?;
+        let body_end = (offset + 8)
+            .checked_add(length)
+            .filter(|end| end + 4 <= data.len())
+            .ok_or_else(|| format!("chunk {} is truncated", String::from_utf8_lossy(chunk_type)))?;
+        let body = &data[offset + 8..body_end];
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(chunk_type);
+        hasher.update(body);
+        if read_u32_be(data, body_end) != Some(hasher.finalize()) {
+            return Err(format!(
+                "CRC mismatch in chunk {}",
+                String::from_utf8_lossy(chunk_type)
+            ));
+        }
+
+        match (dimensions, chunk_type) {
+            (None, b"IHDR") if length == 13 => {
+                dimensions = Some(non_empty(
+                    read_u32_be(body, 0).unwrap_or(0),
+                    read_u32_be(body, 4).unwrap_or(0),
+                )?);
+            }
+            (None, _) => return Err("first chunk is not a valid IHDR".to_string()),
+            (Some(dimensions), b"IEND") => return Ok(dimensions),
+            (Some(_), _) => {}
+        }
+        offset = body_end + 4;
+    }
+}
+
+/// Read the frame size from the first SOF segment and require an EOI marker
+fn jpeg_dimensions(data: &[u8]) -> std::result::Result<ImageDimensions, String> {
+    let mut offset = 2;
+    loop {
+        if data.get(offset) != Some(&0xFF) {
+            return Err("missing frame header".to_string());
+        }
+        while data.get(offset) == Some(&0xFF) {
+            offset += 1;
+        }
+        let marker = *data.get(offset).ok_or("missing frame header")?;
+        offset += 1;
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            continue;
+        }
+        if marker == 0xD9 || marker == 0xDA {
+            return Err("missing frame header".to_string());
+        }
+
+        let length = read_u16_be(data, offset).ok_or("truncated segment")? as usize;
+        if length < 2 || offset + length > data.len() {
+            return Err("truncated segment".to_string());
+        }
+        let is_frame_header =
+            matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+        if is_frame_header {
+            let height = read_u16_be(data, offset + 3).ok_or("truncated frame header")?;
+            let width = read_u16_be(data, offset + 5).ok_or("truncated frame header")?;
+            let dimensions = non_empty(width as u32, height as u32)?;
+            if !data[offset + length..]
+                .windows(2)
+                .any(|w| w == [0xFF, 0xD9])
+            {
+                return Err("missing end of image marker".to_string());
+            }
+            return Ok(dimensions);
+        }
+        offset += length;
+    }
+}
+
+/// Read the logical screen size and require the trailer byte
+fn gif_dimensions(data: &[u8]) -> std::result::Result<ImageDimensions, String> {
+    let width = read_u16_le(data, 6).ok_or("truncated header")?;
+    let height = read_u16_le(data, 8).ok_or("truncated header")?;
+    if data.len() < 14 || data.last() != Some(&0x3B) {
+        return Err("missing trailer".to_string());
+    }
+    non_empty(width as u32, height as u32)
+}
+
+/// Read the canvas size from the first chunk of a WebP container
+fn webp_dimensions(data: &[u8]) -> std::result::Result<ImageDimensions, String> {
+    let riff_size = read_u32_le(data, 4).ok_or("truncated header")?;
+    if (riff_size as u64) + 8 > data.len() as u64 {
+        return Err(format!(
+            "RIFF size {} exceeds file size {}",
+            riff_size + 8,
+            data.len()
+        ));
+    }
+    let chunk = data.get(12..16).ok_or("truncated header")?;
+    match chunk {
+        b"VP8 " => {
+            if data.get(23..26) != Some(&[0x9D, 0x01, 0x2A]) {
+                return Err("invalid VP8 start code".to_string());
+            }
+            let width = read_u16_le(data, 26).ok_or("truncated VP8 header")? & 0x3FFF;
+            let height = read_u16_le(data, 28).ok_or("truncated VP8 header")? & 0x3FFF;
+            non_empty(width as u32, height as u32)
+        }
+        b"VP8L" => {
+            if data.get(20) != Some(&0x2F) {
+                return Err("invalid VP8L signature".to_string());
+            }
+            let bits = read_u32_le(data, 21).ok_or("truncated VP8L header")?;
+            non_empty((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1)
+        }
+        b"VP8X" => {
+            let width = read_u24_le(data, 24).ok_or("truncated VP8X header")? + 1;
+            let height = read_u24_le(data, 27).ok_or("truncated VP8X header")? + 1;
+            non_empty(width, height)
+        }
+        other => Err(format!("unknown chunk {}", String::from_utf8_lossy(other))),
+    }
+}
+
+/// Check that the table directory of a TrueType or OpenType font fits the file
+fn validate_sfnt(data: &[u8]) -> std::result::Result<(), String> {
+    let num_tables = read_u16_be(data, 4).ok_or("truncated header")? as usize;
+    if num_tables == 0 {
+        return Err("font has no tables".to_string());
+    }
+    for table in 0..num_tables {
+        let record = 12 + table * 16;
+        let offset = read_u32_be(data, record + 8).ok_or("truncated table directory")?;
+        let length = read_u32_be(data, record + 12).ok_or("truncated table directory")?;
+        if offset as u64 + length as u64 > data.len() as u64 {
+            return Err(format!(
+                "table {} extends past the end of the file",
+                String::from_utf8_lossy(&data[record..record + 4])
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_font_collection(data: &[u8]) -> std::result::Result<(), String> {
+    let num_fonts = read_u32_be(data, 8).ok_or("truncated header")? as usize;
+    if num_fonts == 0 {
+        return Err("collection has no fonts".to_string());
+    }
+    for font in 0..num_fonts {
+        let offset = read_u32_be(data, 12 + font * 4).ok_or("truncated offset table")?;
+        let font_data = data
+            .get(offset as usize..)
+            .ok_or("font offset past the end of the file")?;
+        validate_sfnt(font_data)?;
+    }
+    Ok(())
+}
+
+/// WOFF and WOFF2 headers record the total file length at offset 8
+fn validate_woff(data: &[u8], header_size: usize) -> std::result::Result<(), String> {
+    if data.len() < header_size {
+        return Err("truncated header".to_string());
+    }
+    let length = read_u32_be(data, 8).ok_or("truncated header")?;
+    if length as usize != data.len() {
+        return Err(format!(
+            "header length {} does not match file size {}",
+            length,
+            data.len()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_optimizer::write_png_chunk;
+    use crate::error::{BundleError, RodePushError};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn test_png(width: u32, height: u32) -> Vec<u8> {
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_png_chunk(&mut png, b"IHDR", &ihdr);
+        write_png_chunk(
+            &mut png,
+            b"IDAT",
+            &[0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01],
+        );
+        write_png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    fn test_jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x10]);
+        jpeg.extend_from_slice(b"JFIF\0\x01\x01\x00\x00\x01\x00\x01\x00\x00");
+        jpeg.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x0B, 0x08]);
+        jpeg.extend_from_slice(&height.to_be_bytes());
+        jpeg.extend_from_slice(&width.to_be_bytes());
+        jpeg.extend_from_slice(&[0x01, 0x01, 0x11, 0x00]);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3F, 0x00]);
+        jpeg.extend_from_slice(&[0x12, 0x34, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    fn test_gif(width: u16, height: u16) -> Vec<u8> {
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.extend_from_slice(&[0x00, 0x00, 0x00, 0x3B]);
+        gif
+    }
+
+    fn test_webp_lossless(width: u32, height: u32) -> Vec<u8> {
+        let bits = (width - 1) | ((height - 1) << 14);
+        let mut payload = vec![0x2F];
+        payload.extend_from_slice(&bits.to_le_bytes());
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&((4 + 8 + payload.len()) as u32).to_le_bytes());
+        webp.extend_from_slice(b"WEBPVP8L");
+        webp.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        webp.extend_from_slice(&payload);
+        webp
+    }
+
+    fn test_ttf() -> Vec<u8> {
+        let mut font = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0];
+        font.extend_from_slice(b"head");
+        font.extend_from_slice(&0u32.to_be_bytes());
+        font.extend_from_slice(&28u32.to_be_bytes());
+        font.extend_from_slice(&4u32.to_be_bytes());
+        font.extend_from_slice(&[0, 1, 0, 0]);
+        font
+    }
+
+    fn metadata(path: &str, data: &[u8]) -> AssetMetadata {
+        AssetMetadata {
+            path: path.to_string(),
+            size: data.len() as u64,
+            checksum: ChecksumVerifier::new(HashAlgorithm::Sha256).calculate(data),
+            mime_type: mime_guess::from_path(path)
+                .first_or_octet_stream()
+                .to_string(),
+            original_checksum: None,
+            dimensions: None,
+        }
+    }
+
+    #[test]
+    fn test_sniff_asset_kinds() {
+        assert_eq!(AssetKind::sniff(&test_png(1, 1)), Some(AssetKind::Png));
+        assert_eq!(AssetKind::sniff(&test_jpeg(1, 1)), Some(AssetKind::Jpeg));
+        assert_eq!(AssetKind::sniff(&test_gif(1, 1)), Some(AssetKind::Gif));
+        assert_eq!(
+            AssetKind::sniff(&test_webp_lossless(1, 1)),
+            Some(AssetKind::WebP)
+        );
+        assert_eq!(AssetKind::sniff(&test_ttf()), Some(AssetKind::TrueType));
+        assert_eq!(AssetKind::sniff(b"OTTO\0\x01"), Some(AssetKind::OpenType));
+        assert_eq!(AssetKind::sniff(b"wOFF"), Some(AssetKind::Woff));
+        assert_eq!(AssetKind::sniff(b"wOF2"), Some(AssetKind::Woff2));
+        assert_eq!(
+            AssetKind::sniff(b"\xEF\xBB\xBF {\"a\": 1}"),
+            Some(AssetKind::Json)
+        );
+        assert_eq!(
+            AssetKind::sniff(br#"{"v":"5.7.4","fr":30,"ip":0,"op":60,"w":100,"h":50,"layers":[]}"#),
+            Some(AssetKind::Lottie)
+        );
+        assert_eq!(AssetKind::sniff(b"{\"a\": "), None);
+        assert_eq!(AssetKind::sniff(b"ID3\x03"), None);
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        let inspector = AssetInspector::new();
+        let cases = [
+            ("a.png", test_png(64, 32)),
+            ("a.jpg", test_jpeg(64, 32)),
+            ("a.gif", test_gif(64, 32)),
+            ("a.webp", test_webp_lossless(64, 32)),
+            (
+                "a.json",
+                br#"{"v":"5.7.4","fr":30,"ip":0,"op":60,"w":64,"h":32,"layers":[]}"#.to_vec(),
+            ),
+        ];
+        for (path, data) in cases {
+            let inspection = inspector.inspect(&metadata(path, &data), &data);
+            assert!(
+                inspection.issues.is_empty(),
+                "{}: {:?}",
+                path,
+                inspection.issues
+            );
+            assert_eq!(
+                inspection.dimensions,
+                Some(ImageDimensions::new(64, 32)),
+                "{}",
+                path
+            );
+        }
+
+        let font = test_ttf();
+        let inspection = inspector.inspect(&metadata("a.ttf", &font), &font);
+        assert!(inspection.issues.is_empty());
+        assert_eq!(inspection.dimensions, None);
+    }
+
+    #[test]
+    fn test_detects_mime_mismatch() {
+        let jpeg = test_jpeg(8, 8);
+        let inspection = AssetInspector::new().inspect(&metadata("photo.png", &jpeg), &jpeg);
+        assert_eq!(inspection.detected, Some(AssetKind::Jpeg));
+        assert_eq!(inspection.dimensions, Some(ImageDimensions::new(8, 8)));
+        assert_eq!(
+            inspection.issues,
+            vec![AssetIssue::warning(
+                "photo.png",
+                AssetIssueKind::MimeMismatch {
+                    declared: "image/png".to_string(),
+                    detected: "image/jpeg".to_string(),
+                }
+            )]
+        );
+
+        // Font flavours are interchangeable
+        let font = test_ttf();
+        let inspection = AssetInspector::new().inspect(&metadata("font.otf", &font), &font);
+        assert!(inspection.issues.is_empty());
+    }
+
+    #[test]
+    fn test_detects_corrupt_assets() {
+        let inspector = AssetInspector::new();
+        let is_corrupt = |path: &str, data: &[u8]| {
+            let issues = inspector.inspect(&metadata(path, data), data).issues;
+            issues.len() == 1
+                && issues[0].severity == IssueSeverity::Error
+                && matches!(issues[0].kind, AssetIssueKind::Corrupt { .. })
+        };
+
+        let png = test_png(4, 4);
+        assert!(is_corrupt("truncated.png", &png[..png.len() - 12]));
+        let mut bad_crc = png.clone();
+        bad_crc[20] ^= 0xFF;
+        assert!(is_corrupt("crc.png", &bad_crc));
+        assert!(is_corrupt("zero.png", &test_png(0, 4)));
+
+        let jpeg = test_jpeg(4, 4);
+        assert!(is_corrupt("truncated.jpg", &jpeg[..jpeg.len() - 2]));
+        let gif = test_gif(4, 4);
+        assert!(is_corrupt("truncated.gif", &gif[..gif.len() - 1]));
+        let webp = test_webp_lossless(4, 4);
+        assert!(is_corrupt("truncated.webp", &webp[..webp.len() - 2]));
+        let font = test_ttf();
+        assert!(is_corrupt("truncated.ttf", &font[..font.len() - 2]));
+        assert!(is_corrupt("broken.json", b"{\"key\": "));
+        assert!(is_corrupt("text.png", b"not an image"));
+        assert!(is_corrupt(
+            "anim.json",
+            br#"{"v":"5.7.4","ip":0,"op":60,"layers":[]}"#
+        ));
+
+        let issues = inspector.inspect(&metadata("empty.png", b""), b"").issues;
+        assert_eq!(
+            issues,
+            vec![AssetIssue::error("empty.png", AssetIssueKind::Empty)]
+        );
+        let issues = inspector.inspect(&metadata("empty.txt", b""), b"").issues;
+        assert_eq!(issues[0].severity, IssueSeverity::Warning);
+
+        // Types we cannot check are left alone
+        let issues = inspector
+            .inspect(&metadata("sound.mp3", b"ID3"), b"ID3")
+            .issues;
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_inspect_collection() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("icon.png"), test_png(48, 48))?;
+        fs::write(temp_dir.path().join("photo.png"), test_jpeg(640, 480))?;
+        fs::write(temp_dir.path().join("data.json"), b"{\"broken\": ")?;
+        fs::write(temp_dir.path().join("notes.txt"), b"hello")?;
+        let collection = AssetCollection::from_directory(temp_dir.path())?;
+
+        let (inspected, report) =
+            AssetInspector::new().inspect_collection(&collection, temp_dir.path())?;
+        assert_eq!(report.assets_checked, 4);
+        assert_eq!(report.dimensions_recorded, 2);
+        assert_eq!(report.mime_types_corrected, 0);
+        assert!(report.has_errors());
+        assert_eq!(report.errors().count(), 1);
+        assert_eq!(report.errors().next().unwrap().path, "data.json");
+        assert_eq!(report.warnings().count(), 1);
+        assert_eq!(report.warnings().next().unwrap().path, "photo.png");
+        assert_eq!(
+            inspected.assets["icon.png"].dimensions,
+            Some(ImageDimensions::new(48, 48))
+        );
+        assert_eq!(inspected.assets["photo.png"].mime_type, "image/png");
+
+        let (corrected, report) = AssetInspector::new()
+            .with_mime_correction(true)
+            .inspect_collection(&collection, temp_dir.path())?;
+        assert_eq!(report.mime_types_corrected, 1);
+        assert_eq!(corrected.assets["photo.png"].mime_type, "image/jpeg");
+        assert_eq!(
+            corrected.assets["photo.png"].dimensions,
+            Some(ImageDimensions::new(640, 480))
+        );
+
+        // Dimensions survive serialization and stay optional for old collections
+        let json = serde_json::to_string(&corrected)?;
+        let restored: AssetCollection = serde_json::from_str(&json)?;
+        assert_eq!(restored, corrected);
+        let json = serde_json::to_string(&collection)?;
+        assert!(!json.contains("dimensions"));
+
+        // Files that changed since the collection was created are reported
+        fs::write(temp_dir.path().join("icon.png"), test_png(16, 16))?;
+        fs::remove_file(temp_dir.path().join("notes.txt"))?;
+        let (_, report) = AssetInspector::new().inspect_collection(&collection, temp_dir.path())?;
+        let kinds: Vec<_> = report
+            .errors()
+            .map(|issue| (&issue.path[..], &issue.kind))
+            .collect();
+        assert!(kinds.contains(&("icon.png", &AssetIssueKind::ChecksumMismatch)));
+        assert!(kinds.iter().any(|(path, kind)| *path == "notes.txt"
+            && matches!(kind, AssetIssueKind::Unreadable { .. })));
+
+        // Unsafe paths are rejected outright
+        let mut unsafe_collection = collection.clone();
+        let mut escaping = collection.assets["icon.png"].clone();
+        escaping.path = "../icon.png".to_string();
+        unsafe_collection
+            .assets
+            .insert(escaping.path.clone(), escaping);
+        match AssetInspector::new().inspect_collection(&unsafe_collection, temp_dir.path()) {
+            Err(RodePushError::Bundle(BundleError::InvalidFormat { .. })) => {}
+            other => panic!("Expected InvalidFormat, got {:?}", other.map(|(_, r)| r)),
+        }
+
+        Ok(())
+    }
+}
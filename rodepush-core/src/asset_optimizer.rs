@@ -0,0 +1,663 @@
+//! Lossless image optimization for asset collections.
+//!
+//! The pass runs between [`AssetCollection::from_directory`] and packaging.
+//! PNG files have their image data re-deflated with zopfli and their text and
+//! timestamp chunks removed; JPEG files have their metadata segments removed.
+//! Pixels are never decoded, so the result is bit-for-bit the same image.
+//! Results are cached on disk by the checksum of the source file so unchanged
+//! images are not reprocessed on every build.
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+
+use crate::assets::{AssetCollection, AssetMetadata, validate_relative_path, verify_asset};
+use crate::crypto::{ChecksumVerifier, HashAlgorithm};
+use crate::error::{BundleError, Result};
+
+/// Signature every PNG file starts with
+pub(crate) const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// PNG chunks that only carry metadata and are safe to drop
+const PNG_STRIPPED_CHUNKS: &[&[u8; 4]] = &[b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Largest IDAT chunk written when re-encoding image data
+const PNG_MAX_CHUNK_SIZE: usize = 1 << 30;
+
+/// Zopfli iterations used unless configured otherwise
+const DEFAULT_ZOPFLI_ITERATIONS: u64 = 15;
+
+/// Bumped whenever the optimizer output for a given input changes, so stale
+/// cache entries are not reused
+const OPTIMIZER_VERSION: u32 = 1;
+
+/// Summary of an optimization pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    /// Number of PNG and JPEG assets examined
+    pub images_processed: usize,
+    /// Number of images that got smaller
+    pub images_optimized: usize,
+    /// Number of images answered from the cache
+    pub cache_hits: usize,
+    /// Size of the examined images before optimization
+    pub original_size: u64,
+    /// Size of the examined images after optimization
+    pub optimized_size: u64,
+}
+
+impl OptimizationReport {
+    /// Number of bytes saved by the pass
+    pub fn bytes_saved(&self) -> u64 {
+        self.original_size.saturating_sub(self.optimized_size)
+    }
+}
+
+/// Lossless PNG and JPEG optimizer
+#[derive(Debug, Clone)]
+pub struct ImageOptimizer {
+    cache_dir: Option<PathBuf>,
+    zopfli_iterations: NonZeroU64,
+}
+
+impl ImageOptimizer {
+    /// Create an optimizer without a cache
+    pub fn new() -> Self {
+        Self {
+            cache_dir: None,
+            zopfli_iterations: NonZeroU64::new(DEFAULT_ZOPFLI_ITERATIONS).unwrap(),
+        }
+    }
+
+    /// Cache optimized images in `cache_dir`, keyed by source checksum
+    pub fn with_cache_dir<P: AsRef<Path>>(mut self, cache_dir: P) -> Self {
+        self.cache_dir = Some(cache_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the number of zopfli iterations for PNG data (at least 1)
+    pub fn with_zopfli_iterations(mut self, iterations: u64) -> Self {
+        self.zopfli_iterations = NonZeroU64::new(iterations.max(1)).unwrap();
+        self
+    }
+
+    /// Optimize the images of a collection
+    ///
+    /// Every asset is read from `source_dir`, verified against its metadata
+    /// and written to `output_dir`, optimized if it is an image that got
+    /// smaller. The returned collection describes the files in `output_dir`
+    /// and can be packaged from there.
+    pub fn optimize_collection<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        collection: &AssetCollection,
+        source_dir: P,
+        output_dir: Q,
+    ) -> Result<(AssetCollection, OptimizationReport)> {
+        let source_dir = source_dir.as_ref();
+        let output_dir = output_dir.as_ref();
+        let verifier = ChecksumVerifier::new(HashAlgorithm::Sha256);
+
+        let mut optimized = collection.clone();
+        let mut report = OptimizationReport::default();
+
+        let mut paths: Vec<&String> = collection.assets.keys().collect();
+        paths.sort();
+
+        for path in paths {
+            let metadata = &collection.assets[path];
+            let relative_path = validate_relative_path(Path::new(path))?;
+            let data = std::fs::read(source_dir.join(&relative_path)).map_err(|e| {
+                BundleError::invalid_format(format!("Failed to read asset {}: {}", path, e))
+            })?;
+            verify_asset(&verifier, metadata, &data)?;
+
+            let mut output = None;
+            if Self::is_optimizable(&metadata.mime_type) {
+                report.images_processed += 1;
+                report.original_size += data.len() as u64;
+                output = self.optimize_cached(metadata, &data, &mut report)?;
+                report.optimized_size += output.as_ref().unwrap_or(&data).len() as u64;
+            }
+
+            let output_path = output_dir.join(&relative_path);
+            if let Some(parent) = output_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            match output {
+                Some(bytes) => {
+                    report.images_optimized += 1;
+                    std::fs::write(&output_path, &bytes)?;
+                    optimized.assets.insert(
+                        path.clone(),
+                        AssetMetadata {
+                            size: bytes.len() as u64,
+                            checksum: verifier.calculate(&bytes),
+                            original_checksum: Some(
+                                metadata
+                                    .original_checksum
+                                    .clone()
+                                    .unwrap_or_else(|| metadata.checksum.clone()),
+                            ),
+                            ..metadata.clone()
+                        },
+                    );
+                }
+                None => std::fs::write(&output_path, &data)?,
+            }
+        }
+
+        optimized.total_size = optimized.assets.values().map(|asset| asset.size).sum();
+        Ok((optimized, report))
+    }
+
+    /// Check if assets of this MIME type are handled by the optimizer
+    pub fn is_optimizable(mime_type: &str) -> bool {
+        matches!(mime_type, "image/png" | "image/jpeg")
+    }
+
+    /// Optimize an image, returning `None` if it cannot be made smaller
+    pub fn optimize(&self, mime_type: &str, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        match mime_type {
+            "image/png" => self.optimize_png(data),
+            "image/jpeg" => Self::optimize_jpeg(data),
+            _ => Ok(None),
+        }
+    }
+
+    /// Losslessly recompress a PNG file
+    ///
+    /// All IDAT chunks are inflated and deflated again with zopfli; the
+    /// filtered scanlines are kept as they are. Text and timestamp chunks are
+    /// dropped, every other chunk is copied unchanged.
+    pub fn optimize_png(&self, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        if !data.starts_with(PNG_SIGNATURE) {
+            return Err(BundleError::invalid_format("Not a PNG file").into());
+        }
+
+        // `None` marks where the image data goes
+        let mut chunks: Vec<Option<([u8; 4], &[u8])>> = Vec::new();
+        let mut idat = Vec::new();
+        let mut idat_seen = false;
+        let mut pos = PNG_SIGNATURE.len();
+        loop {
+            let header = data
+                .get(pos..pos + 8)
+                .ok_or_else(|| BundleError::invalid_format("Truncated PNG file"))?;
+            let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            let chunk_type = [header[4], header[5], header[6], header[7]];
+            let body_end = pos + 8 + length;
+            let body = data
+                .get(pos + 8..body_end)
+                .ok_or_else(|| BundleError::invalid_format("Truncated PNG chunk"))?;
+            let crc = data
+                .get(body_end..body_end + 4)
+                .ok_or_else(|| BundleError::invalid_format("Truncated PNG chunk"))?;
+
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&chunk_type);
+            hasher.update(body);
+            if hasher.finalize() != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+                return Err(BundleError::invalid_format("PNG chunk CRC mismatch").into());
+            }
+            pos = body_end + 4;
+
+            if &chunk_type == b"IDAT" {
+                if !idat_seen {
+                    chunks.push(None);
+                    idat_seen = true;
+                }
+                idat.extend_from_slice(body);
+            } else if !PNG_STRIPPED_CHUNKS.contains(&&chunk_type) {
+                chunks.push(Some((chunk_type, body)));
+            }
+
+            if &chunk_type == b"IEND" {
+                break;
+            }
+        }
+        if idat.is_empty() {
+            return Err(BundleError::invalid_format("PNG file has no image data").into());
+        }
+
+        let mut raw = Vec::new();
+        flate2::read::ZlibDecoder::new(idat.as_slice())
+            .read_to_end(&mut raw)
+            .map_err(|e| {
+                BundleError::invalid_format(format!("Failed to inflate PNG data: {}", e))
+            })?;
+
+        let options = zopfli::Options {
+            iteration_count: self.zopfli_iterations,
+            ..Default::default()
+        };
+        let mut recompressed = Vec::new();
+        zopfli::compress(
+            options,
+            zopfli::Format::Zlib,
+            raw.as_slice(),
+            &mut recompressed,
+        )
+        .map_err(|e| BundleError::compression_failed(e.to_string()))?;
+        let image_data = if recompressed.len() < idat.len() {
+            recompressed
+        } else {
+            idat
+        };
+
+        let mut output = Vec::with_capacity(data.len());
+        output.extend_from_slice(PNG_SIGNATURE);
+        for chunk in chunks {
+            match chunk {
+                Some((chunk_type, body)) => write_png_chunk(&mut output, &chunk_type, body),
+                None => {
+                    for part in image_data.chunks(PNG_MAX_CHUNK_SIZE) {
+                        write_png_chunk(&mut output, b"IDAT", part);
+                    }
+                }
+            }
+        }
+
+        Ok((output.len() < data.len()).then_some(output))
+    }
+
+    /// Strip metadata segments from a JPEG file
+    ///
+    /// Comments, XMP, IPTC, thumbnails and other application segments are
+    /// dropped. JFIF, ICC profiles and Adobe color transform segments are kept,
+    /// as is an Exif segment whose orientation is not the default, since
+    /// clients use it to rotate the image. Compressed image data is copied
+    /// unchanged.
+    pub fn optimize_jpeg(data: &[u8]) -> Result<Option<Vec<u8>>> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return Err(BundleError::invalid_format("Not a JPEG file").into());
+        }
+
+        let mut output = Vec::with_capacity(data.len());
+        output.extend_from_slice(&[0xFF, 0xD8]);
+        let mut pos = 2;
+        loop {
+            if data.get(pos) != Some(&0xFF) {
+                return Err(BundleError::invalid_format("Invalid JPEG marker").into());
+            }
+            while data.get(pos) == Some(&0xFF) {
+                pos += 1;
+            }
+            let marker = *data
+                .get(pos)
+                .ok_or_else(|| BundleError::invalid_format("Truncated JPEG file"))?;
+            pos += 1;
+
+            match marker {
+                // End of image before any scan
+                0xD9 => {
+                    output.extend_from_slice(&[0xFF, marker]);
+                    break;
+                }
+                // Markers without a length
+                0x01 | 0xD0..=0xD7 => {
+                    output.extend_from_slice(&[0xFF, marker]);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let length = data
+                .get(pos..pos + 2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+                .ok_or_else(|| BundleError::invalid_format("Truncated JPEG segment"))?;
+            let segment = data
+                .get(pos..pos + length)
+                .filter(|_| length >= 2)
+                .ok_or_else(|| BundleError::invalid_format("Truncated JPEG segment"))?;
+
+            // Start of scan: everything from here on is image data
+            if marker == 0xDA {
+                output.extend_from_slice(&[0xFF, marker]);
+                output.extend_from_slice(&data[pos..]);
+                break;
+            }
+
+            if keep_jpeg_segment(marker, &segment[2..]) {
+                output.extend_from_slice(&[0xFF, marker]);
+                output.extend_from_slice(segment);
+            }
+            pos += length;
+        }
+
+        Ok((output.len() < data.len()).then_some(output))
+    }
+
+    /// Optimize an image through the cache
+    ///
+    /// Images that fail to parse are shipped unchanged with a warning rather
+    /// than failing the build.
+    fn optimize_cached(
+        &self,
+        metadata: &AssetMetadata,
+        data: &[u8],
+        report: &mut OptimizationReport,
+    ) -> Result<Option<Vec<u8>>> {
+        let cache_path = self.cache_dir.as_ref().map(|dir| {
+            dir.join(format!(
+                "{}-v{}-z{}",
+                metadata.checksum, OPTIMIZER_VERSION, self.zopfli_iterations
+            ))
+        });
+
+        // An empty cache entry records that the image could not be improved
+        if let Some(cache_path) = &cache_path
+            && let Ok(cached) = std::fs::read(cache_path)
+        {
+            report.cache_hits += 1;
+            return Ok((!cached.is_empty()).then_some(cached));
+        }
+
+        let result = match self.optimize(&metadata.mime_type, data) {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!("Skipping optimization of {}: {}", metadata.path, e);
+                None
+            }
+        };
+
+        if let Some(cache_path) = &cache_path {
+            let contents = result.as_deref().unwrap_or_default();
+            if let Err(e) = write_cache_entry(cache_path, contents) {
+                tracing::warn!(
+                    "Failed to write image optimization cache {}: {}",
+                    cache_path.display(),
+                    e
+                );
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Default for ImageOptimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Append a PNG chunk with its length and CRC
+pub(crate) fn write_png_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], body: &[u8]) {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(chunk_type);
+    hasher.update(body);
+
+    output.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    output.extend_from_slice(chunk_type);
+    output.extend_from_slice(body);
+    output.extend_from_slice(&hasher.finalize().to_be_bytes());
+}
+
+/// Decide whether a JPEG segment survives metadata stripping
+fn keep_jpeg_segment(marker: u8, payload: &[u8]) -> bool {
+    match marker {
+        // APP0: keep JFIF, drop JFXX thumbnails
+        0xE0 => payload.starts_with(b"JFIF\0"),
+        // APP1: keep Exif only for a non-default orientation, drop XMP
+        0xE1 => {
+            payload.starts_with(b"Exif\0\0")
+                && exif_orientation(&payload[6..]).is_some_and(|orientation| orientation != 1)
+        }
+        // APP2: keep ICC profiles
+        0xE2 => payload.starts_with(b"ICC_PROFILE\0"),
+        // APP14: Adobe color transform affects decoding
+        0xEE => true,
+        // Remaining application segments and comments
+        0xE3..=0xEF | 0xFE => false,
+        _ => true,
+    }
+}
+
+/// Read the orientation tag from the first IFD of a TIFF-encoded Exif block
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let little_endian = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let read_u16 = |at: usize| {
+        tiff.get(at..at + 2).map(|bytes| {
+            let bytes = [bytes[0], bytes[1]];
+            if little_endian {
+                u16::from_le_bytes(bytes)
+            } else {
+                u16::from_be_bytes(bytes)
+            }
+        })
+    };
+    let read_u32 = |at: usize| {
+        tiff.get(at..at + 4).map(|bytes| {
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            if little_endian {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            }
+        })
+    };
+
+    let ifd = read_u32(4)? as usize;
+    let entries = read_u16(ifd)? as usize;
+    (0..entries)
+        .map(|index| ifd + 2 + index * 12)
+        .find(|entry| read_u16(*entry) == Some(0x0112))
+        .and_then(|entry| read_u16(entry + 8))
+}
+
+/// Write a cache entry through a temporary file so readers never see a
+/// partial entry
+fn write_cache_entry(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension(format!("tmp-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Raw scanlines of a 32x32 grayscale image, each row prefixed by filter type 0
+    fn raw_scanlines() -> Vec<u8> {
+        let mut raw = Vec::new();
+        for row in 0..32u8 {
+            raw.push(0);
+            raw.extend((0..32u8).map(|column| (row / 4) ^ (column / 8)));
+        }
+        raw
+    }
+
+    /// PNG with poorly compressed data split over two IDAT chunks and a text chunk
+    fn test_png() -> Vec<u8> {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::none());
+        encoder.write_all(&raw_scanlines()).unwrap();
+        let idat = encoder.finish().unwrap();
+
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&32u32.to_be_bytes());
+        ihdr.extend_from_slice(&32u32.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_png_chunk(&mut png, b"IHDR", &ihdr);
+        write_png_chunk(&mut png, b"tEXt", b"Software\0Image Editor 1.0");
+        let (first, second) = idat.split_at(idat.len() / 2);
+        write_png_chunk(&mut png, b"IDAT", first);
+        write_png_chunk(&mut png, b"IDAT", second);
+        write_png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    fn jpeg_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+        let mut payload = b"Exif\0\0MM\0\x2a\0\0\0\x08".to_vec();
+        payload.extend_from_slice(&1u16.to_be_bytes());
+        payload.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0, 0, 0, 1]);
+        payload.extend_from_slice(&orientation.to_be_bytes());
+        payload.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        payload
+    }
+
+    fn test_jpeg(exif: &[u8]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend(jpeg_segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+        jpeg.extend(jpeg_segment(0xE1, exif));
+        jpeg.extend(jpeg_segment(
+            0xE1,
+            b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>",
+        ));
+        jpeg.extend(jpeg_segment(0xE2, b"ICC_PROFILE\0\x01\x01profile"));
+        jpeg.extend(jpeg_segment(0xFE, b"A comment"));
+        jpeg.extend(jpeg_segment(0xDB, &[0; 65]));
+        jpeg.extend(jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+        jpeg.extend_from_slice(&[0x12, 0x34, 0xFF, 0x00, 0x56, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    fn png_chunk_types(png: &[u8]) -> Vec<[u8; 4]> {
+        let mut types = Vec::new();
+        let mut pos = PNG_SIGNATURE.len();
+        while pos < png.len() {
+            let length = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+            types.push(png[pos + 4..pos + 8].try_into().unwrap());
+            pos += 12 + length;
+        }
+        types
+    }
+
+    #[test]
+    fn test_optimize_png_is_lossless() -> Result<()> {
+        let png = test_png();
+        let optimized = ImageOptimizer::new()
+            .with_zopfli_iterations(1)
+            .optimize_png(&png)?
+            .expect("PNG should shrink");
+
+        assert!(optimized.len() < png.len());
+        assert_eq!(
+            png_chunk_types(&optimized),
+            vec![*b"IHDR", *b"IDAT", *b"IEND"]
+        );
+
+        // The scanlines are unchanged
+        let idat_start = PNG_SIGNATURE.len() + 12 + 13 + 8;
+        let idat_length = u32::from_be_bytes(
+            optimized[idat_start - 8..idat_start - 4]
+                .try_into()
+                .unwrap(),
+        );
+        let mut raw = Vec::new();
+        flate2::read::ZlibDecoder::new(&optimized[idat_start..idat_start + idat_length as usize])
+            .read_to_end(&mut raw)?;
+        assert_eq!(raw, raw_scanlines());
+
+        // The output is a valid PNG with correct CRCs
+        assert!(ImageOptimizer::new().optimize_png(&optimized).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_optimize_png_rejects_corrupt_file() {
+        let mut png = test_png();
+        let last = png.len() - 1;
+        png[last] ^= 0xFF;
+        assert!(ImageOptimizer::new().optimize_png(&png).is_err());
+        assert!(ImageOptimizer::new().optimize_png(b"not a png").is_err());
+    }
+
+    #[test]
+    fn test_optimize_jpeg_strips_metadata() -> Result<()> {
+        let rotated = test_jpeg(&exif_with_orientation(6));
+        let optimized = ImageOptimizer::optimize_jpeg(&rotated)?.expect("JPEG should shrink");
+
+        let contains = |haystack: &[u8], needle: &[u8]| {
+            haystack
+                .windows(needle.len())
+                .any(|window| window == needle)
+        };
+        assert!(contains(&optimized, b"JFIF\0"));
+        assert!(contains(&optimized, b"ICC_PROFILE\0"));
+        assert!(contains(&optimized, b"Exif\0\0"));
+        assert!(!contains(&optimized, b"xmpmeta"));
+        assert!(!contains(&optimized, b"A comment"));
+        assert!(optimized.ends_with(&[0x12, 0x34, 0xFF, 0x00, 0x56, 0xFF, 0xD9]));
+
+        // Exif with the default orientation carries nothing clients need
+        let upright = test_jpeg(&exif_with_orientation(1));
+        let optimized = ImageOptimizer::optimize_jpeg(&upright)?.expect("JPEG should shrink");
+        assert!(!contains(&optimized, b"Exif\0\0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_optimize_collection_with_cache() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        fs::create_dir_all(source_dir.path().join("images"))?;
+        fs::write(source_dir.path().join("images/icon.png"), test_png())?;
+        fs::write(source_dir.path().join("data.json"), b"{}")?;
+        let collection = AssetCollection::from_directory(source_dir.path())?;
+
+        let cache_dir = TempDir::new()?;
+        let optimizer = ImageOptimizer::new()
+            .with_zopfli_iterations(1)
+            .with_cache_dir(cache_dir.path());
+
+        let output_dir = TempDir::new()?;
+        let (optimized, report) =
+            optimizer.optimize_collection(&collection, source_dir.path(), output_dir.path())?;
+
+        assert_eq!(report.images_processed, 1);
+        assert_eq!(report.images_optimized, 1);
+        assert_eq!(report.cache_hits, 0);
+        assert!(report.bytes_saved() > 0);
+
+        let icon = optimized.get_asset("images/icon.png").unwrap();
+        let original = collection.get_asset("images/icon.png").unwrap();
+        assert_eq!(icon.original_checksum.as_ref(), Some(&original.checksum));
+        assert_ne!(icon.checksum, original.checksum);
+        assert_eq!(
+            optimized.get_asset("data.json"),
+            collection.get_asset("data.json")
+        );
+        assert_eq!(
+            optimized.total_size,
+            optimized
+                .assets
+                .values()
+                .map(|asset| asset.size)
+                .sum::<u64>()
+        );
+
+        // The optimized collection describes the output directory exactly
+        crate::AssetCompressor::compress_collection(&optimized, output_dir.path())?;
+
+        // A second build reuses the cached result
+        let second_output = TempDir::new()?;
+        let (cached, report) =
+            optimizer.optimize_collection(&collection, source_dir.path(), second_output.path())?;
+        assert_eq!(report.cache_hits, 1);
+        assert_eq!(cached.assets, optimized.assets);
+
+        Ok(())
+    }
+}
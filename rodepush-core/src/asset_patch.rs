@@ -0,0 +1,532 @@
+//! Asset patches built from an [`AssetDiff`].
+//!
+//! A patch carries only the bytes of added and modified assets together with
+//! a manifest of removals and renames. Applying it turns an on-disk asset
+//! directory for the old collection into the directory for the new one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::assets::{
+    AssetCollection, AssetDiff, AssetMetadata, deterministic_header, validate_relative_path,
+    verify_asset,
+};
+use crate::compression::{Compressor, ZstdCompressor};
+use crate::crypto::{ChecksumVerifier, HashAlgorithm};
+use crate::error::{BundleError, Result};
+
+/// Archive entry holding the serialized [`AssetPatchManifest`]
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Archive directory holding the bytes of added and modified assets
+const FILES_DIR: &str = "files";
+
+/// Description of the changes carried by an [`AssetPatch`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetPatchManifest {
+    /// Paths removed from the old collection, sorted
+    pub removed: Vec<String>,
+    /// Renamed assets (old path -> new path)
+    pub renamed: BTreeMap<String, String>,
+    /// Assets copied from an existing asset (new path -> source path)
+    #[serde(default)]
+    pub copied: BTreeMap<String, String>,
+    /// Metadata of the added and modified assets shipped in the patch, sorted by path
+    pub files: Vec<AssetMetadata>,
+}
+
+/// Shippable asset patch
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetPatch {
+    /// Changes carried by this patch
+    pub manifest: AssetPatchManifest,
+    /// Bytes of the added and modified assets, keyed by path
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl AssetPatch {
+    /// Get the bytes shipped for an asset
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+
+    /// Total size of the asset bytes carried by this patch
+    pub fn payload_size(&self) -> u64 {
+        self.files.values().map(|data| data.len() as u64).sum()
+    }
+
+    /// Check if this patch carries no changes
+    pub fn is_empty(&self) -> bool {
+        self.manifest.removed.is_empty()
+            && self.manifest.renamed.is_empty()
+            && self.manifest.copied.is_empty()
+            && self.manifest.files.is_empty()
+    }
+
+    /// Serialize the patch into a zstd-compressed tar archive
+    ///
+    /// The archive holds `manifest.json` followed by the shipped assets under
+    /// `files/`, written with deterministic headers in path order.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut tar_builder = tar::Builder::new(Vec::new());
+
+        let manifest = serde_json::to_vec(&self.manifest)?;
+        let mut header = deterministic_header(manifest.len() as u64);
+        tar_builder
+            .append_data(&mut header, MANIFEST_ENTRY, manifest.as_slice())
+            .map_err(|e| {
+                BundleError::invalid_format(format!("Failed to add patch manifest: {}", e))
+            })?;
+
+        for (path, data) in &self.files {
+            let entry_path = Path::new(FILES_DIR).join(validate_relative_path(Path::new(path))?);
+            let mut header = deterministic_header(data.len() as u64);
+            tar_builder
+                .append_data(&mut header, &entry_path, data.as_slice())
+                .map_err(|e| {
+                    BundleError::invalid_format(format!(
+                        "Failed to add {} to patch archive: {}",
+                        path, e
+                    ))
+                })?;
+        }
+
+        let tar_data = tar_builder.into_inner().map_err(|e| {
+            BundleError::invalid_format(format!("Failed to finalize patch archive: {}", e))
+        })?;
+
+        let compressor = ZstdCompressor::new();
+        compressor.compress(&tar_data, compressor.default_level())
+    }
+
+    /// Parse a patch produced by [`AssetPatch::to_bytes`]
+    ///
+    /// Every shipped file is verified against the manifest, and files missing
+    /// from or not described by the manifest are rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let compressor = ZstdCompressor::new();
+        let tar_data = compressor.decompress(data)?;
+
+        let mut manifest: Option<AssetPatchManifest> = None;
+        let mut files: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+
+        let mut tar_archive = tar::Archive::new(&tar_data[..]);
+        let entries = tar_archive.entries().map_err(|e| {
+            BundleError::invalid_format(format!("Failed to read patch archive: {}", e))
+        })?;
+        for entry in entries {
+            let mut entry = entry.map_err(|e| {
+                BundleError::invalid_format(format!("Failed to read patch entry: {}", e))
+            })?;
+            if entry.header().entry_type() != tar::EntryType::Regular {
+                return Err(BundleError::invalid_format(
+                    "Asset patch may only contain regular files",
+                )
+                .into());
+            }
+
+            let entry_path = entry.path().map_err(|e| {
+                BundleError::invalid_format(format!("Invalid patch entry path: {}", e))
+            })?;
+            let entry_path = validate_relative_path(&entry_path)?;
+
+            let mut contents = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut contents)?;
+
+            if entry_path == Path::new(MANIFEST_ENTRY) {
+                manifest = Some(serde_json::from_slice(&contents)?);
+            } else if let Ok(asset_path) = entry_path.strip_prefix(FILES_DIR) {
+                let asset_path = asset_path.to_string_lossy().to_string();
+                if files.insert(asset_path.clone(), contents).is_some() {
+                    return Err(BundleError::invalid_format(format!(
+                        "Asset patch contains duplicate file: {}",
+                        asset_path
+                    ))
+                    .into());
+                }
+            } else {
+                return Err(BundleError::invalid_format(format!(
+                    "Unexpected entry in asset patch: {}",
+                    entry_path.display()
+                ))
+                .into());
+            }
+        }
+
+        let manifest =
+            manifest.ok_or_else(|| BundleError::invalid_format("Asset patch has no manifest"))?;
+
+        let verifier = ChecksumVerifier::new(HashAlgorithm::Sha256);
+        for metadata in &manifest.files {
+            let data = files.get(&metadata.path).ok_or_else(|| {
+                BundleError::invalid_format(format!(
+                    "Asset patch is missing file: {}",
+                    metadata.path
+                ))
+            })?;
+            verify_asset(&verifier, metadata, data)?;
+        }
+        if files.len() != manifest.files.len() {
+            return Err(BundleError::invalid_format(
+                "Asset patch contains files not listed in its manifest",
+            )
+            .into());
+        }
+
+        Ok(Self { manifest, files })
+    }
+}
+
+/// Builder for [`AssetPatch`]
+pub struct AssetPatchBuilder<'a> {
+    diff: &'a AssetDiff,
+    source_dir: PathBuf,
+}
+
+impl<'a> AssetPatchBuilder<'a> {
+    /// Create a builder for `diff`, reading new asset bytes from `source_dir`
+    ///
+    /// `source_dir` is the asset directory of the new collection.
+    pub fn new<P: AsRef<Path>>(diff: &'a AssetDiff, source_dir: P) -> Self {
+        Self {
+            diff,
+            source_dir: source_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Build the patch
+    ///
+    /// Only added and modified assets are read; each is verified against its
+    /// new metadata so the patch always matches the diff it was built from.
+    pub fn build(self) -> Result<AssetPatch> {
+        let verifier = ChecksumVerifier::new(HashAlgorithm::Sha256);
+
+        let mut shipped: Vec<&AssetMetadata> = self
+            .diff
+            .added
+            .values()
+            .chain(self.diff.modified.values().map(|(_, new)| new))
+            .collect();
+        shipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut files = BTreeMap::new();
+        for metadata in &shipped {
+            let relative_path = validate_relative_path(Path::new(&metadata.path))?;
+            let data = std::fs::read(self.source_dir.join(relative_path)).map_err(|e| {
+                BundleError::invalid_format(format!(
+                    "Failed to read asset {}: {}",
+                    metadata.path, e
+                ))
+            })?;
+            verify_asset(&verifier, metadata, &data)?;
+            files.insert(metadata.path.clone(), data);
+        }
+
+        let mut removed: Vec<String> = self.diff.removed.iter().cloned().collect();
+        removed.sort();
+
+        let manifest = AssetPatchManifest {
+            removed,
+            renamed: self
+                .diff
+                .renamed
+                .iter()
+                .map(|(old, new)| (old.clone(), new.clone()))
+                .collect(),
+            copied: self
+                .diff
+                .copied
+                .iter()
+                .map(|(new, source)| (new.clone(), source.clone()))
+                .collect(),
+            files: shipped.into_iter().cloned().collect(),
+        };
+
+        Ok(AssetPatch { manifest, files })
+    }
+}
+
+/// Applies an [`AssetPatch`] to an on-disk asset directory
+///
+/// The new directory is assembled in a staging directory next to the asset
+/// directory and verified against the target collection before the two are
+/// swapped, so a failed apply leaves the original directory untouched.
+pub struct AssetPatchApplier<'a> {
+    target: &'a AssetCollection,
+}
+
+impl<'a> AssetPatchApplier<'a> {
+    /// Create an applier producing the `target` collection
+    pub fn new(target: &'a AssetCollection) -> Self {
+        Self { target }
+    }
+
+    /// Apply `patch` to `asset_dir`
+    pub fn apply<P: AsRef<Path>>(&self, patch: &AssetPatch, asset_dir: P) -> Result<()> {
+        let asset_dir = asset_dir.as_ref();
+        if !asset_dir.is_dir() {
+            return Err(BundleError::invalid_format(format!(
+                "Asset directory does not exist: {}",
+                asset_dir.display()
+            ))
+            .into());
+        }
+        self.check_manifest(&patch.manifest)?;
+
+        let staging_dir = sibling_path(asset_dir, "staging")?;
+        if let Err(e) = self.stage(patch, asset_dir, &staging_dir) {
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e);
+        }
+
+        let backup_dir = sibling_path(asset_dir, "backup")?;
+        std::fs::rename(asset_dir, &backup_dir)?;
+        if let Err(e) = std::fs::rename(&staging_dir, asset_dir) {
+            let _ = std::fs::rename(&backup_dir, asset_dir);
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e.into());
+        }
+        if let Err(e) = std::fs::remove_dir_all(&backup_dir) {
+            tracing::warn!(
+                "Failed to remove asset backup directory {}: {}",
+                backup_dir.display(),
+                e
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check that the manifest describes a transition into the target collection
+    fn check_manifest(&self, manifest: &AssetPatchManifest) -> Result<()> {
+        for metadata in &manifest.files {
+            match self.target.get_asset(&metadata.path) {
+                Some(expected) if expected.checksum == metadata.checksum => {}
+                _ => {
+                    return Err(BundleError::invalid_format(format!(
+                        "Patched asset {} does not match the target collection",
+                        metadata.path
+                    ))
+                    .into());
+                }
+            }
+        }
+        for path in &manifest.removed {
+            if self.target.contains_asset(path) {
+                return Err(BundleError::invalid_format(format!(
+                    "Removed asset {} is still in the target collection",
+                    path
+                ))
+                .into());
+            }
+        }
+        for new_path in manifest.renamed.values().chain(manifest.copied.keys()) {
+            if !self.target.contains_asset(new_path) {
+                return Err(BundleError::invalid_format(format!(
+                    "Relocated asset {} is not in the target collection",
+                    new_path
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Write every asset of the target collection into `staging_dir`
+    fn stage(&self, patch: &AssetPatch, asset_dir: &Path, staging_dir: &Path) -> Result<()> {
+        let verifier = ChecksumVerifier::new(HashAlgorithm::Sha256);
+        // Where each relocated asset is read from in the existing directory
+        let sources: HashMap<&str, &str> = patch
+            .manifest
+            .renamed
+            .iter()
+            .map(|(old, new)| (new.as_str(), old.as_str()))
+            .chain(
+                patch
+                    .manifest
+                    .copied
+                    .iter()
+                    .map(|(new, source)| (new.as_str(), source.as_str())),
+            )
+            .collect();
+
+        std::fs::create_dir_all(staging_dir)?;
+
+        let mut paths: Vec<&String> = self.target.assets.keys().collect();
+        paths.sort();
+
+        for path in paths {
+            let metadata = &self.target.assets[path];
+            let relative_path = validate_relative_path(Path::new(path))?;
+
+            let data = match patch.file(path) {
+                Some(data) => data.to_vec(),
+                None => {
+                    let source = sources.get(path.as_str()).copied().unwrap_or(path);
+                    let source_path = asset_dir.join(validate_relative_path(Path::new(source))?);
+                    std::fs::read(&source_path).map_err(|e| {
+                        BundleError::invalid_format(format!(
+                            "Failed to read existing asset {}: {}",
+                            source, e
+                        ))
+                    })?
+                }
+            };
+            verify_asset(&verifier, metadata, &data)?;
+
+            let staged_path = staging_dir.join(relative_path);
+            if let Some(parent) = staged_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&staged_path, data)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Unique hidden path next to `dir`, on the same filesystem so it can be renamed
+fn sibling_path(dir: &Path, purpose: &str) -> Result<PathBuf> {
+    let name = dir.file_name().ok_or_else(|| {
+        BundleError::invalid_format(format!("Invalid asset directory: {}", dir.display()))
+    })?;
+    let sibling_name = format!(".{}.{}-{}", name.to_string_lossy(), purpose, Uuid::new_v4());
+    Ok(dir.with_file_name(sibling_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::AssetDiffEngine;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Old and new asset directories covering every change category
+    fn fixture() -> Result<(TempDir, TempDir)> {
+        let old_dir = TempDir::new()?;
+        fs::create_dir_all(old_dir.path().join("images"))?;
+        fs::write(old_dir.path().join("images/unchanged.png"), b"unchanged")?;
+        fs::write(old_dir.path().join("images/modified.png"), b"old bytes")?;
+        fs::write(old_dir.path().join("removed.ttf"), b"removed font")?;
+        fs::write(old_dir.path().join("old_name.json"), b"renamed content")?;
+
+        let new_dir = TempDir::new()?;
+        fs::create_dir_all(new_dir.path().join("images"))?;
+        fs::write(new_dir.path().join("images/unchanged.png"), b"unchanged")?;
+        fs::write(new_dir.path().join("images/modified.png"), b"new bytes!")?;
+        fs::write(new_dir.path().join("added.mp3"), b"added sound")?;
+        fs::write(new_dir.path().join("new_name.json"), b"renamed content")?;
+        fs::write(new_dir.path().join("images/copy.png"), b"unchanged")?;
+
+        Ok((old_dir, new_dir))
+    }
+
+    fn build_patch(old_dir: &Path, new_dir: &Path) -> Result<(AssetCollection, AssetPatch)> {
+        let old = AssetCollection::from_directory(old_dir)?;
+        let new = AssetCollection::from_directory(new_dir)?;
+        let diff = AssetDiffEngine::new().diff(&old, &new)?;
+        let patch = AssetPatchBuilder::new(&diff, new_dir).build()?;
+        Ok((new, patch))
+    }
+
+    #[test]
+    fn test_patch_ships_only_changed_bytes() -> Result<()> {
+        let (old_dir, new_dir) = fixture()?;
+        let (_, patch) = build_patch(old_dir.path(), new_dir.path())?;
+
+        assert_eq!(patch.file("images/modified.png"), Some(&b"new bytes!"[..]));
+        assert_eq!(patch.file("added.mp3"), Some(&b"added sound"[..]));
+        assert!(patch.file("images/unchanged.png").is_none());
+        assert!(patch.file("new_name.json").is_none());
+        assert!(patch.file("images/copy.png").is_none());
+        assert_eq!(
+            patch.manifest.copied.get("images/copy.png"),
+            Some(&"images/unchanged.png".to_string())
+        );
+        assert_eq!(patch.manifest.removed, vec!["removed.ttf".to_string()]);
+        assert_eq!(
+            patch.manifest.renamed.get("old_name.json"),
+            Some(&"new_name.json".to_string())
+        );
+        assert_eq!(patch.payload_size(), 21);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_bytes_round_trip() -> Result<()> {
+        let (old_dir, new_dir) = fixture()?;
+        let (_, patch) = build_patch(old_dir.path(), new_dir.path())?;
+
+        let bytes = patch.to_bytes()?;
+        assert_eq!(bytes, patch.to_bytes()?);
+        assert_eq!(AssetPatch::from_bytes(&bytes)?, patch);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_patch() -> Result<()> {
+        let (old_dir, new_dir) = fixture()?;
+        let (new, patch) = build_patch(old_dir.path(), new_dir.path())?;
+
+        AssetPatchApplier::new(&new).apply(&patch, old_dir.path())?;
+
+        let patched = AssetCollection::from_directory(old_dir.path())?;
+        assert_eq!(patched.assets, new.assets);
+        assert!(!old_dir.path().join("removed.ttf").exists());
+        assert_eq!(
+            fs::read(old_dir.path().join("new_name.json"))?,
+            b"renamed content"
+        );
+
+        // No staging or backup directories are left behind
+        let parent = old_dir.path().parent().unwrap();
+        let name = old_dir.path().file_name().unwrap().to_string_lossy();
+        let leftovers = fs::read_dir(parent)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!(".{}.", name))
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_to_wrong_base_leaves_directory_untouched() -> Result<()> {
+        let (old_dir, new_dir) = fixture()?;
+        let (new, patch) = build_patch(old_dir.path(), new_dir.path())?;
+
+        // The base no longer matches what the patch was built against
+        fs::write(old_dir.path().join("images/unchanged.png"), b"drifted")?;
+
+        let result = AssetPatchApplier::new(&new).apply(&patch, old_dir.path());
+        assert!(result.is_err());
+        assert_eq!(
+            fs::read(old_dir.path().join("images/modified.png"))?,
+            b"old bytes"
+        );
+        assert!(old_dir.path().join("removed.ttf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_tampered_manifest() -> Result<()> {
+        let (old_dir, new_dir) = fixture()?;
+        let (_, mut patch) = build_patch(old_dir.path(), new_dir.path())?;
+
+        patch.manifest.files[0].checksum = "0".repeat(64);
+        assert!(AssetPatch::from_bytes(&patch.to_bytes()?).is_err());
+
+        Ok(())
+    }
+}
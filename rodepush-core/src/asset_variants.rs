@@ -0,0 +1,476 @@
+//! React Native asset variants.
+//!
+//! React Native ships one logical asset as several files: density variants
+//! (`icon.png`, `icon@2x.png`, `icon@3x.png`) and platform variants
+//! (`icon.ios.png`, `icon.android.png`). Metro's `--assets-dest` output moves
+//! them again: iOS keeps the source directories under `assets/`, while Android
+//! puts images into `drawable-<density>/` and other files into `raw/` under a
+//! flattened resource name. This module maps every file of a collection back
+//! to the logical asset it belongs to.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::assets::AssetCollection;
+use crate::bundle::Platform;
+use crate::error::{BundleError, Result};
+
+/// Android density qualifiers and the scale they stand for
+const ANDROID_DENSITIES: &[(&str, f32)] = &[
+    ("ldpi", 0.75),
+    ("mdpi", 1.0),
+    ("hdpi", 1.5),
+    ("xhdpi", 2.0),
+    ("xxhdpi", 3.0),
+    ("xxxhdpi", 4.0),
+];
+
+/// Directory layout an asset collection was created from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetLayout {
+    /// Project source tree, with scale and platform suffixes in file names
+    #[default]
+    Source,
+    /// Metro `--assets-dest` output for iOS
+    MetroIos,
+    /// Metro `--assets-dest` output for Android
+    MetroAndroid,
+}
+
+impl std::fmt::Display for AssetLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetLayout::Source => write!(f, "source"),
+            AssetLayout::MetroIos => write!(f, "metro-ios"),
+            AssetLayout::MetroAndroid => write!(f, "metro-android"),
+        }
+    }
+}
+
+impl std::str::FromStr for AssetLayout {
+    type Err = crate::RodePushError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "source" => Ok(AssetLayout::Source),
+            "metro-ios" => Ok(AssetLayout::MetroIos),
+            "metro-android" => Ok(AssetLayout::MetroAndroid),
+            _ => Err(BundleError::invalid_format(format!("Unknown asset layout: {}", s)).into()),
+        }
+    }
+}
+
+/// One file of a logical asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetVariant {
+    /// Path of the file in the collection
+    pub path: String,
+    /// Name of the logical asset this file belongs to
+    pub logical_name: String,
+    /// Pixel density the file is meant for (1.0 for `icon.png`, 2.0 for `icon@2x.png`)
+    pub scale: f32,
+    /// Platform the file is restricted to, `None` if it serves every platform
+    pub platform: Option<Platform>,
+}
+
+impl AssetVariant {
+    /// Parse a collection path according to `layout`
+    pub fn parse(path: &str, layout: AssetLayout) -> Self {
+        match layout {
+            AssetLayout::Source => Self::parse_source(path),
+            AssetLayout::MetroIos => {
+                let relative = path.strip_prefix("assets/").unwrap_or(path);
+                Self {
+                    path: path.to_string(),
+                    platform: Some(Platform::Ios),
+                    ..Self::parse_source(relative)
+                }
+            }
+            AssetLayout::MetroAndroid => Self::parse_android(path),
+        }
+    }
+
+    /// Check if this variant should be shipped to `platform`
+    pub fn is_for(&self, platform: Platform) -> bool {
+        match self.platform {
+            None => true,
+            Some(variant_platform) => variant_platform.is_compatible_with(platform),
+        }
+    }
+
+    /// Parse `dir/name@2x.ios.png` into its logical name, scale and platform
+    fn parse_source(path: &str) -> Self {
+        let (dir, file_name) = match path.rfind('/') {
+            Some(index) => (&path[..=index], &path[index + 1..]),
+            None => ("", path),
+        };
+        let (mut stem, extension) = match file_name.rfind('.') {
+            Some(index) if index > 0 => (&file_name[..index], &file_name[index..]),
+            _ => (file_name, ""),
+        };
+
+        let mut platform = None;
+        for (suffix, suffix_platform) in [
+            (".ios", Some(Platform::Ios)),
+            (".android", Some(Platform::Android)),
+            (".native", None),
+        ] {
+            if let Some(rest) = stem.strip_suffix(suffix) {
+                stem = rest;
+                platform = suffix_platform;
+                break;
+            }
+        }
+
+        let mut scale = 1.0;
+        if let Some((name, suffix)) = stem.rsplit_once('@')
+            && let Some(value) = suffix.strip_suffix('x')
+            && let Ok(value) = value.parse::<f32>()
+            && value > 0.0
+        {
+            stem = name;
+            scale = value;
+        }
+
+        Self {
+            path: path.to_string(),
+            logical_name: format!("{}{}{}", dir, stem, extension),
+            scale,
+            platform,
+        }
+    }
+
+    /// Parse `drawable-xhdpi/src_images_icon.png` or `raw/src_data.json`
+    fn parse_android(path: &str) -> Self {
+        let (dir, file_name) = match path.split_once('/') {
+            Some((dir, file_name)) if !file_name.contains('/') => (dir, file_name),
+            _ => ("", path),
+        };
+
+        let scale = if dir.starts_with("drawable") {
+            dir.split('-')
+                .find_map(|qualifier| {
+                    ANDROID_DENSITIES
+                        .iter()
+                        .find(|(name, _)| *name == qualifier)
+                        .map(|(_, scale)| *scale)
+                })
+                .unwrap_or(1.0)
+        } else {
+            1.0
+        };
+
+        let logical_name = if dir.starts_with("drawable") || dir == "raw" {
+            file_name.to_string()
+        } else {
+            path.to_string()
+        };
+
+        Self {
+            path: path.to_string(),
+            logical_name,
+            scale,
+            platform: Some(Platform::Android),
+        }
+    }
+}
+
+/// A logical asset and all of its files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogicalAsset {
+    /// Logical name, as referenced from JavaScript
+    pub name: String,
+    /// Files of this asset, sorted by path
+    pub variants: Vec<AssetVariant>,
+}
+
+impl LogicalAsset {
+    /// Distinct scales available for this asset, in ascending order
+    pub fn scales(&self) -> Vec<f32> {
+        let mut scales: Vec<f32> = self.variants.iter().map(|variant| variant.scale).collect();
+        scales.sort_by(f32::total_cmp);
+        scales.dedup();
+        scales
+    }
+
+    /// Variants that should be shipped to `platform`
+    ///
+    /// As in Metro's resolver, a platform-specific file replaces the generic
+    /// file of the same scale.
+    pub fn variants_for(&self, platform: Platform) -> Vec<&AssetVariant> {
+        let mut by_scale: BTreeMap<u32, &AssetVariant> = BTreeMap::new();
+        let mut shipped = Vec::new();
+
+        for variant in self
+            .variants
+            .iter()
+            .filter(|variant| variant.is_for(platform))
+        {
+            if platform == Platform::Both {
+                shipped.push(variant);
+                continue;
+            }
+            let current = by_scale.entry(variant.scale.to_bits()).or_insert(variant);
+            if current.platform.is_none() && variant.platform.is_some() {
+                *current = variant;
+            }
+        }
+
+        shipped.extend(by_scale.into_values());
+        shipped.sort_by(|a, b| a.path.cmp(&b.path));
+        shipped
+    }
+}
+
+impl AssetCollection {
+    /// Group the files of this collection into logical assets
+    pub fn logical_assets(&self, layout: AssetLayout) -> BTreeMap<String, LogicalAsset> {
+        let mut logical: BTreeMap<String, LogicalAsset> = BTreeMap::new();
+
+        for path in self.assets.keys() {
+            let variant = AssetVariant::parse(path, layout);
+            logical
+                .entry(variant.logical_name.clone())
+                .or_insert_with(|| LogicalAsset {
+                    name: variant.logical_name.clone(),
+                    variants: Vec::new(),
+                })
+                .variants
+                .push(variant);
+        }
+
+        for asset in logical.values_mut() {
+            asset.variants.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        logical
+    }
+
+    /// Build the collection a client on `platform` needs
+    ///
+    /// Files restricted to another platform are dropped, and generic files are
+    /// dropped where a platform-specific file of the same scale exists.
+    pub fn for_platform(&self, platform: Platform, layout: AssetLayout) -> AssetCollection {
+        let mut collection = AssetCollection::new();
+
+        for asset in self.logical_assets(layout).values() {
+            for variant in asset.variants_for(platform) {
+                if let Some(metadata) = self.assets.get(&variant.path) {
+                    collection
+                        .assets
+                        .insert(variant.path.clone(), metadata.clone());
+                }
+            }
+        }
+
+        collection.total_size = collection.assets.values().map(|asset| asset.size).sum();
+        collection
+    }
+}
+
+/// Android resource name Metro gives a source asset
+///
+/// Mirrors Metro's `getAndroidResourceIdentifier`: the directory and file
+/// stem are lowercased, joined with `_` and stripped of anything outside
+/// `[a-z0-9_]`. Combined with [`AssetLayout::MetroAndroid`] this maps
+/// `src/images/icon.png` and `drawable-xhdpi/src_images_icon.png` to the same
+/// name. The mapping is lossy; [`source_asset_name`] inverts it against the
+/// names of a source collection.
+pub fn android_resource_name(logical_name: &str) -> String {
+    let (stem, extension) = match logical_name.rfind('.') {
+        Some(index) if index > 0 && !logical_name[index..].contains('/') => {
+            (&logical_name[..index], &logical_name[index..])
+        }
+        _ => (logical_name, ""),
+    };
+
+    let identifier: String = format!("assets/{}", stem)
+        .to_lowercase()
+        .replace('/', "_")
+        .chars()
+        .filter(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '_')
+        .collect();
+    let identifier = identifier
+        .strip_prefix("assets_")
+        .unwrap_or(&identifier)
+        .to_string();
+
+    format!("{}{}", identifier, extension)
+}
+
+/// Source asset an Android resource name was generated from
+///
+/// Inverse of [`android_resource_name`]: as the mapping is lossy, the
+/// resource name is looked up among `source_names`, the logical names of a
+/// [`AssetLayout::Source`] or [`AssetLayout::MetroIos`] collection. Returns
+/// `None` when no source asset maps to the resource, or when several do.
+pub fn source_asset_name<'a>(
+    resource_name: &str,
+    source_names: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let mut matches = source_names
+        .into_iter()
+        .filter(|name| android_resource_name(name) == resource_name);
+    let source_name = matches.next()?;
+    match matches.all(|name| name == source_name) {
+        true => Some(source_name),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::AssetMetadata;
+
+    fn collection_of(paths: &[&str]) -> AssetCollection {
+        let mut collection = AssetCollection::new();
+        for path in paths {
+            collection.assets.insert(
+                path.to_string(),
+                AssetMetadata {
+                    path: path.to_string(),
+                    size: 10,
+                    checksum: format!("checksum-{}", path),
+                    mime_type: "image/png".to_string(),
+                    original_checksum: None,
+                    dimensions: None,
+                },
+            );
+        }
+        collection.total_size = 10 * paths.len() as u64;
+        collection
+    }
+
+    #[test]
+    fn test_parse_source_variants() {
+        let variant = AssetVariant::parse("images/icon@2x.ios.png", AssetLayout::Source);
+        assert_eq!(variant.logical_name, "images/icon.png");
+        assert_eq!(variant.scale, 2.0);
+        assert_eq!(variant.platform, Some(Platform::Ios));
+
+        let variant = AssetVariant::parse("images/icon@1.5x.png", AssetLayout::Source);
+        assert_eq!(variant.logical_name, "images/icon.png");
+        assert_eq!(variant.scale, 1.5);
+        assert_eq!(variant.platform, None);
+
+        let variant = AssetVariant::parse("fonts/Inter.native.ttf", AssetLayout::Source);
+        assert_eq!(variant.logical_name, "fonts/Inter.ttf");
+        assert_eq!(variant.platform, None);
+
+        // An `@` that is not a scale suffix is part of the name
+        let variant = AssetVariant::parse("user@home.png", AssetLayout::Source);
+        assert_eq!(variant.logical_name, "user@home.png");
+        assert_eq!(variant.scale, 1.0);
+    }
+
+    #[test]
+    fn test_parse_metro_layouts() {
+        let variant = AssetVariant::parse("assets/src/images/icon@3x.png", AssetLayout::MetroIos);
+        assert_eq!(variant.path, "assets/src/images/icon@3x.png");
+        assert_eq!(variant.logical_name, "src/images/icon.png");
+        assert_eq!(variant.scale, 3.0);
+        assert_eq!(variant.platform, Some(Platform::Ios));
+
+        let variant = AssetVariant::parse(
+            "drawable-xxhdpi/src_images_icon.png",
+            AssetLayout::MetroAndroid,
+        );
+        assert_eq!(variant.logical_name, "src_images_icon.png");
+        assert_eq!(variant.scale, 3.0);
+        assert_eq!(variant.platform, Some(Platform::Android));
+
+        let variant = AssetVariant::parse("raw/src_data.json", AssetLayout::MetroAndroid);
+        assert_eq!(variant.logical_name, "src_data.json");
+        assert_eq!(variant.scale, 1.0);
+    }
+
+    #[test]
+    fn test_android_resource_name() {
+        assert_eq!(
+            android_resource_name("src/images/Icon-Large.png"),
+            "src_images_iconlarge.png"
+        );
+        assert_eq!(android_resource_name("icon.png"), "icon.png");
+
+        let ios = AssetVariant::parse("assets/src/images/icon@2x.png", AssetLayout::MetroIos);
+        let android = AssetVariant::parse(
+            "drawable-xhdpi/src_images_icon.png",
+            AssetLayout::MetroAndroid,
+        );
+        assert_eq!(
+            android_resource_name(&ios.logical_name),
+            android.logical_name
+        );
+    }
+
+    #[test]
+    fn test_source_asset_name() {
+        let collection = collection_of(&[
+            "src/images/Icon-Large.png",
+            "src/images/Icon-Large@2x.png",
+            "src/data.json",
+        ]);
+        let logical = collection.logical_assets(AssetLayout::Source);
+        let names = || logical.keys().map(String::as_str);
+
+        assert_eq!(
+            source_asset_name("src_images_iconlarge.png", names()),
+            Some("src/images/Icon-Large.png")
+        );
+        assert_eq!(
+            source_asset_name("src_data.json", names()),
+            Some("src/data.json")
+        );
+        assert_eq!(source_asset_name("src_missing.png", names()), None);
+
+        // Names that collapse onto the same resource cannot be told apart
+        let names = ["src/icon-a.png", "src/icona.png"];
+        assert_eq!(source_asset_name("src_icona.png", names), None);
+    }
+
+    #[test]
+    fn test_logical_assets() {
+        let collection = collection_of(&[
+            "images/icon.png",
+            "images/icon@2x.png",
+            "images/icon@3x.ios.png",
+            "images/logo.png",
+        ]);
+
+        let logical = collection.logical_assets(AssetLayout::Source);
+        assert_eq!(logical.len(), 2);
+
+        let icon = &logical["images/icon.png"];
+        assert_eq!(icon.variants.len(), 3);
+        assert_eq!(icon.scales(), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_for_platform() {
+        let collection = collection_of(&[
+            "icon.png",
+            "icon@2x.png",
+            "icon@2x.ios.png",
+            "icon.android.png",
+            "splash.ios.png",
+        ]);
+
+        let ios = collection.for_platform(Platform::Ios, AssetLayout::Source);
+        let mut ios_paths: Vec<&String> = ios.assets.keys().collect();
+        ios_paths.sort();
+        assert_eq!(
+            ios_paths,
+            vec!["icon.png", "icon@2x.ios.png", "splash.ios.png"]
+        );
+        assert_eq!(ios.total_size, 30);
+
+        let android = collection.for_platform(Platform::Android, AssetLayout::Source);
+        let mut android_paths: Vec<&String> = android.assets.keys().collect();
+        android_paths.sort();
+        assert_eq!(android_paths, vec!["icon.android.png", "icon@2x.png"]);
+
+        let both = collection.for_platform(Platform::Both, AssetLayout::Source);
+        assert_eq!(both.len(), collection.len());
+    }
+}
@@ -5,10 +5,13 @@
 //! diffed and compressed together.
 
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use uuid::Uuid;
-use crate::crypto::{HashAlgorithm, generate_file_checksum};
+use crate::crypto::{ChecksumVerifier, HashAlgorithm, content_uuid, generate_file_checksum};
 use crate::compression::{Compressor, ZstdCompressor};
 use crate::error::{Result, RodePushError, BundleError};
 use crate::CompressionType; // Import CompressionType correctly
@@ -27,6 +30,11 @@ impl AssetCollectionId {
     pub fn from_string(id: String) -> Self {
         Self(id)
     }
+    
+    /// Derive an AssetCollectionId from content, so identical content yields the same ID
+    pub fn from_content(data: &[u8]) -> Self {
+        Self(content_uuid(data).to_string())
+    }
 }
 
 impl Default for AssetCollectionId {
@@ -35,6 +43,197 @@ impl Default for AssetCollectionId {
     }
 }
 
+/// Name of the ignore file read from the root of an asset directory
+pub const ASSET_IGNORE_FILE: &str = ".rodepushignore";
+
+/// Patterns excluded from every asset scan unless disabled
+///
+/// Covers OS metadata files, editor swap and backup files, and VCS folders.
+pub const DEFAULT_ASSET_IGNORE_PATTERNS: &[&str] = &[
+    ".DS_Store",
+    "._*",
+    "Thumbs.db",
+    "desktop.ini",
+    "*.swp",
+    "*.swo",
+    "*~",
+    ".#*",
+    "#*#",
+    ".git/",
+    ".svn/",
+    ".hg/",
+    ASSET_IGNORE_FILE,
+];
+
+/// How symbolic links are treated when scanning an asset directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Follow links to files and directories
+    Follow,
+    /// Leave links out of the collection
+    #[default]
+    Skip,
+    /// Fail the scan when a link is found
+    Error,
+}
+
+impl std::fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymlinkPolicy::Follow => write!(f, "follow"),
+            SymlinkPolicy::Skip => write!(f, "skip"),
+            SymlinkPolicy::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = RodePushError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "follow" => Ok(SymlinkPolicy::Follow),
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "error" => Ok(SymlinkPolicy::Error),
+            _ => Err(BundleError::invalid_format(format!("Unknown symlink policy: {}", s)).into()),
+        }
+    }
+}
+
+/// Options controlling which files [`AssetCollection::from_directory_with_options`] picks up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetScanOptions {
+    /// Gitignore-style patterns for files to leave out
+    pub ignore_patterns: Vec<String>,
+    /// Glob patterns a file must match to be included; empty includes everything
+    ///
+    /// Patterns without a `/` match file names in any directory (`*.png`);
+    /// patterns with one match paths from the directory root (`images/*.png`).
+    pub include_patterns: Vec<String>,
+    /// Whether [`DEFAULT_ASSET_IGNORE_PATTERNS`] are applied
+    pub default_ignores: bool,
+    /// Whether `.rodepushignore` in the directory root is read
+    pub use_ignore_file: bool,
+    /// How symbolic links are treated
+    pub symlink_policy: SymlinkPolicy,
+    /// Largest allowed file size in bytes
+    pub max_file_size: Option<u64>,
+}
+
+impl AssetScanOptions {
+    /// Create options with the default ignore rules
+    pub fn new() -> Self {
+        Self {
+            ignore_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            default_ignores: true,
+            use_ignore_file: true,
+            symlink_policy: SymlinkPolicy::default(),
+            max_file_size: None,
+        }
+    }
+
+    /// Add a gitignore-style exclude pattern
+    pub fn with_ignore_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore_patterns.push(pattern.into());
+        self
+    }
+
+    /// Add an include glob
+    pub fn with_include_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.include_patterns.push(pattern.into());
+        self
+    }
+
+    /// Enable or disable the built-in ignore patterns
+    pub fn with_default_ignores(mut self, enabled: bool) -> Self {
+        self.default_ignores = enabled;
+        self
+    }
+
+    /// Enable or disable reading `.rodepushignore`
+    pub fn with_ignore_file(mut self, enabled: bool) -> Self {
+        self.use_ignore_file = enabled;
+        self
+    }
+
+    /// Set the symlink policy
+    pub fn with_symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlink_policy = policy;
+        self
+    }
+
+    /// Set the largest allowed file size in bytes
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Build the exclude matcher for `root`
+    fn ignore_matcher(&self, root: &Path) -> Result<Gitignore> {
+        let mut builder = GitignoreBuilder::new(root);
+        if self.default_ignores {
+            for pattern in DEFAULT_ASSET_IGNORE_PATTERNS {
+                builder.add_line(None, pattern).map_err(ignore_error)?;
+            }
+        }
+        let ignore_file = root.join(ASSET_IGNORE_FILE);
+        if self.use_ignore_file
+            && ignore_file.is_file()
+            && let Some(e) = builder.add(&ignore_file)
+        {
+            return Err(ignore_error(e));
+        }
+        for pattern in &self.ignore_patterns {
+            builder.add_line(None, pattern).map_err(ignore_error)?;
+        }
+        builder.build().map_err(ignore_error)
+    }
+
+    /// Build the include matcher, `None` if every file is included
+    ///
+    /// Like ignore rules, a pattern without a `/` matches file names at any
+    /// depth, while a pattern with one is anchored to the directory root.
+    fn include_matcher(&self) -> Result<Option<GlobSet>> {
+        if self.include_patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.include_patterns {
+            let anchored = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if pattern.contains('/') => pattern.clone(),
+                None => format!("**/{}", pattern),
+            };
+            let glob = GlobBuilder::new(&anchored)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    BundleError::invalid_format(format!(
+                        "Invalid include pattern {}: {}",
+                        pattern, e
+                    ))
+                })?;
+            builder.add(glob);
+        }
+        let set = builder
+            .build()
+            .map_err(|e| BundleError::invalid_format(format!("Invalid include patterns: {}", e)))?;
+        Ok(Some(set))
+    }
+}
+
+impl Default for AssetScanOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn ignore_error(error: ignore::Error) -> RodePushError {
+    BundleError::invalid_format(format!("Invalid ignore pattern: {}", error)).into()
+}
+
 /// Metadata for an individual asset file
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AssetMetadata {
@@ -46,6 +245,36 @@ pub struct AssetMetadata {
     pub checksum: String,
     /// MIME type of the asset
     pub mime_type: String,
+    /// SHA-256 checksum of the source file when the asset was optimized
+    ///
+    /// `checksum` and `size` always describe the bytes that are shipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_checksum: Option<String>,
+    /// Pixel dimensions of image and animation assets, once inspected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<ImageDimensions>,
+}
+
+/// Width and height of an image asset in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ImageDimensions {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl ImageDimensions {
+    /// Create new dimensions
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+}
+
+impl std::fmt::Display for ImageDimensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
 }
 
 /// Collection of assets with metadata
@@ -73,7 +302,15 @@ impl AssetCollection {
     }
     
     /// Create an asset collection from a directory of files
+    ///
+    /// Uses the default [`AssetScanOptions`]: built-in ignore patterns and
+    /// `.rodepushignore` are applied and symbolic links are skipped.
     pub fn from_directory<P: AsRef<Path>>(dir_path: P) -> Result<Self> {
+        Self::from_directory_with_options(dir_path, &AssetScanOptions::default())
+    }
+
+    /// Create an asset collection from a directory of files using `options`
+    pub fn from_directory_with_options<P: AsRef<Path>>(dir_path: P, options: &AssetScanOptions) -> Result<Self> {
         let mut collection = Self::new();
         let dir_path = dir_path.as_ref();
         
@@ -89,48 +326,127 @@ impl AssetCollection {
             }));
         }
         
+        let ignore = options.ignore_matcher(dir_path)?;
+        let include = options.include_matcher()?;
         let mut total_size = 0u64;
         
-        // Walk the directory and collect asset metadata
-        for entry in walkdir::WalkDir::new(dir_path) {
+        // Walk the directory in a stable order and collect asset metadata
+        let mut walker = walkdir::WalkDir::new(dir_path)
+            .follow_links(options.symlink_policy == SymlinkPolicy::Follow)
+            .sort_by_file_name()
+            .into_iter();
+        while let Some(entry) = walker.next() {
             let entry = entry.map_err(|e| RodePushError::Bundle(BundleError::InvalidFormat { 
                 reason: format!("Failed to walk directory: {}", e) 
             }))?;
+            if entry.depth() == 0 {
+                continue;
+            }
+
             let path = entry.path();
+            let relative_path = path.strip_prefix(dir_path)
+                .map_err(|_| RodePushError::Bundle(BundleError::InvalidFormat {
+                    reason: "Failed to create relative path".to_string()
+                }))?;
+            let relative_str = relative_path.to_string_lossy().to_string();
+            let is_dir = entry.file_type().is_dir();
+
+            if entry.path_is_symlink() {
+                match options.symlink_policy {
+                    SymlinkPolicy::Follow => {}
+                    SymlinkPolicy::Skip => {
+                        tracing::debug!("Skipping symbolic link in assets: {}", relative_str);
+                        continue;
+                    }
+                    SymlinkPolicy::Error => {
+                        return Err(BundleError::symlink_not_allowed(relative_str).into());
+                    }
+                }
+            }
             
-            if path.is_file() {
-                let relative_path = path.strip_prefix(dir_path)
-                    .map_err(|_| RodePushError::Bundle(BundleError::InvalidFormat { 
-                        reason: "Failed to create relative path".to_string() 
-                    }))?;
-                
-                let metadata = std::fs::metadata(path)
-                    .map_err(|_| RodePushError::Bundle(BundleError::InvalidFormat { 
-                        reason: "Failed to read file metadata".to_string() 
-                    }))?;
-                
-                let size = metadata.len();
-                let checksum = generate_file_checksum(path, HashAlgorithm::Sha256)?;
-                
-                // Determine MIME type based on file extension
-                let mime_type = mime_guess::from_path(path).first_or_octet_stream().to_string();
-                
-                let asset_metadata = AssetMetadata {
-                    path: relative_path.to_string_lossy().to_string(),
-                    size,
-                    checksum,
-                    mime_type,
-                };
-                
-                collection.assets.insert(asset_metadata.path.clone(), asset_metadata);
-                total_size += size;
+            if ignore.matched(relative_path, is_dir).is_ignore() {
+                if is_dir {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+
+            if !entry.file_type().is_file() {
+                continue;
             }
+            if let Some(include) = &include && !include.is_match(relative_path) {
+                continue;
+            }
+
+            let metadata = entry.metadata()
+                .map_err(|_| RodePushError::Bundle(BundleError::InvalidFormat {
+                    reason: "Failed to read file metadata".to_string()
+                }))?;
+
+            let size = metadata.len();
+            if let Some(max_size) = options.max_file_size && size > max_size {
+                return Err(BundleError::asset_too_large(relative_str, size, max_size).into());
+            }
+            let checksum = generate_file_checksum(path, HashAlgorithm::Sha256)?;
+
+            // Determine MIME type based on file extension
+            let mime_type = mime_guess::from_path(path).first_or_octet_stream().to_string();
+
+            let asset_metadata = AssetMetadata {
+                path: relative_str,
+                size,
+                checksum,
+                mime_type,
+                original_checksum: None,
+                dimensions: None,
+            };
+
+            collection.assets.insert(asset_metadata.path.clone(), asset_metadata);
+            total_size += size;
         }
         
         collection.total_size = total_size;
         Ok(collection)
     }
     
+    /// Derive the content-based ID of this collection
+    ///
+    /// Only asset paths, sizes and checksums contribute; `id` and `created_at`
+    /// do not, so scanning the same files twice yields the same ID.
+    pub fn content_id(&self) -> AssetCollectionId {
+        let mut paths: Vec<&String> = self.assets.keys().collect();
+        paths.sort();
+        
+        let mut identity = Vec::new();
+        for path in paths {
+            let asset = &self.assets[path];
+            identity.extend_from_slice(path.as_bytes());
+            identity.push(0);
+            identity.extend_from_slice(asset.size.to_string().as_bytes());
+            identity.push(0);
+            identity.extend_from_slice(asset.checksum.as_bytes());
+            identity.push(b'\n');
+        }
+        AssetCollectionId::from_content(&identity)
+    }
+    
+    /// Replace the random ID with the content-based ID
+    pub fn with_content_id(mut self) -> Self {
+        self.id = self.content_id();
+        self
+    }
+    
+    /// Set the creation timestamp
+    pub fn with_created_at(mut self, created_at: chrono::DateTime<chrono::Utc>) -> Self {
+        self.created_at = created_at;
+        self
+    }
+    
+    /// Check whether two collections hold the same assets, ignoring IDs and timestamps
+    pub fn same_content(&self, other: &AssetCollection) -> bool {
+        self.content_id() == other.content_id()
+    }
+    
     /// Get the number of assets in this collection
     pub fn len(&self) -> usize {
         self.assets.len()
@@ -185,6 +501,13 @@ pub struct AssetDiff {
     pub removed: HashSet<String>,
     /// Assets that were renamed (old path -> new path)
     pub renamed: HashMap<String, String>,
+    /// Assets whose content duplicates an existing asset (new path -> source path)
+    ///
+    /// The source is a path in the old collection and is read before any other
+    /// change of the same diff is applied, so it may itself be renamed,
+    /// modified or removed.
+    #[serde(default)]
+    pub copied: HashMap<String, String>,
     /// Assets that were modified (path -> (old metadata, new metadata))
     pub modified: HashMap<String, (AssetMetadata, AssetMetadata)>,
 }
@@ -196,6 +519,7 @@ impl AssetDiff {
             added: HashMap::new(),
             removed: HashSet::new(),
             renamed: HashMap::new(),
+            copied: HashMap::new(),
             modified: HashMap::new(),
         }
     }
@@ -205,12 +529,17 @@ impl AssetDiff {
         self.added.is_empty() && 
         self.removed.is_empty() && 
         self.renamed.is_empty() && 
+        self.copied.is_empty() && 
         self.modified.is_empty()
     }
     
     /// Get the total number of changes in this diff
     pub fn len(&self) -> usize {
-        self.added.len() + self.removed.len() + self.renamed.len() + self.modified.len()
+        self.added.len()
+            + self.removed.len()
+            + self.renamed.len()
+            + self.copied.len()
+            + self.modified.len()
     }
 }
 
@@ -224,26 +553,37 @@ impl Default for AssetDiff {
 impl AssetDiff {
     /// Apply this diff to an asset collection
     pub fn apply(&self, collection: &mut AssetCollection) -> Result<()> {
-        // Remove assets
-        for path in &self.removed {
+        // Renames and copies read from the collection as it was before the diff
+        let original = collection.assets.clone();
+        let relocated = |source: &str, path: &str| -> Result<AssetMetadata> {
+            let metadata = original.get(source).ok_or_else(|| {
+                BundleError::invalid_format(format!("Source asset not found: {}", source))
+            })?;
+            Ok(AssetMetadata {
+                path: path.to_string(),
+                mime_type: mime_guess::from_path(path).first_or_octet_stream().to_string(),
+                ..metadata.clone()
+            })
+        };
+        
+        // Remove assets, including the old paths of renamed assets
+        for path in self.removed.iter().chain(self.renamed.keys()) {
             collection.assets.remove(path);
         }
         
+        // Handle renames and copies
+        for (old_path, new_path) in &self.renamed {
+            collection.assets.insert(new_path.clone(), relocated(old_path, new_path)?);
+        }
+        for (new_path, source_path) in &self.copied {
+            collection.assets.insert(new_path.clone(), relocated(source_path, new_path)?);
+        }
+        
         // Add new assets
         for (path, metadata) in &self.added {
             collection.assets.insert(path.clone(), metadata.clone());
         }
         
-        // Handle renames
-        for (old_path, new_path) in &self.renamed {
-            if let Some(metadata) = collection.assets.remove(old_path) {
-                // Update the path in the metadata
-                let mut updated_metadata = metadata.clone();
-                updated_metadata.path = new_path.clone();
-                collection.assets.insert(new_path.clone(), updated_metadata);
-            }
-        }
-        
         // Update modified assets
         for (path, (_, new_metadata)) in &self.modified {
             collection.assets.insert(path.clone(), new_metadata.clone());
@@ -257,42 +597,25 @@ impl AssetDiff {
     
     /// Verify that this diff can be applied to the given collection
     pub fn verify_applicable(&self, collection: &AssetCollection) -> bool {
-        // Check that all removed assets exist in the collection
-        for path in &self.removed {
-            if !collection.assets.contains_key(path) {
-                return false;
-            }
-        }
-        
-        // Check that all renamed assets exist in the collection
-        for old_path in self.renamed.keys() {
-            if !collection.assets.contains_key(old_path) {
-                return false;
-            }
-        }
-        
-        // Check that all modified assets exist in the collection
-        for path in self.modified.keys() {
+        // Every path the diff reads from or replaces must exist
+        let sources = self.removed.iter()
+            .chain(self.renamed.keys())
+            .chain(self.copied.values())
+            .chain(self.modified.keys());
+        for path in sources {
             if !collection.assets.contains_key(path) {
                 return false;
             }
         }
         
-        // Check that no added assets already exist
-        for path in self.added.keys() {
-            if collection.assets.contains_key(path) {
-                return false;
-            }
-        }
-        
-        // Check that renamed assets don't conflict with existing assets
-        for new_path in self.renamed.values() {
-            // Skip if it's renaming to itself (not really a rename)
-            if self.renamed.contains_key(new_path) {
-                continue;
-            }
-            
-            if collection.assets.contains_key(new_path) {
+        // Every path the diff creates must be free once removals and renames
+        // have vacated their old paths
+        let created = self.added.keys()
+            .chain(self.renamed.values())
+            .chain(self.copied.keys());
+        for path in created {
+            let vacated = self.removed.contains(path) || self.renamed.contains_key(path);
+            if collection.assets.contains_key(path) && !vacated {
                 return false;
             }
         }
@@ -311,69 +634,68 @@ impl AssetDiffEngine {
     }
     
     /// Compute the difference between two asset collections
+    ///
+    /// Renames and copies are detected through a checksum index of the old
+    /// collection. A new path whose content matches an old path that
+    /// disappeared is a rename; once those are used up, further matches are
+    /// copies of an existing asset. Candidates are taken in path order so the
+    /// result is deterministic.
     pub fn diff(&self, old: &AssetCollection, new: &AssetCollection) -> Result<AssetDiff> {
         let mut diff = AssetDiff::new();
         
-        // Create maps for efficient lookups
-        let old_assets: HashMap<&str, &AssetMetadata> = old.assets.iter()
-            .map(|(k, v)| (k.as_str(), v))
+        // Index old paths by checksum
+        let mut by_checksum: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (path, metadata) in &old.assets {
+            by_checksum.entry(metadata.checksum.as_str()).or_default().push(path.as_str());
+        }
+        for paths in by_checksum.values_mut() {
+            paths.sort_unstable();
+        }
+        
+        // Old paths missing from the new collection are rename candidates
+        let mut rename_candidates: HashMap<&str, VecDeque<&str>> = by_checksum.iter()
+            .map(|(checksum, paths)| {
+                let gone = paths.iter()
+                    .copied()
+                    .filter(|path| !new.assets.contains_key(*path))
+                    .collect();
+                (*checksum, gone)
+            })
             .collect();
         
-        let new_assets: HashMap<&str, &AssetMetadata> = new.assets.iter()
-            .map(|(k, v)| (k.as_str(), v))
+        // Classify paths that only exist in the new collection
+        let mut new_paths: Vec<&String> = new.assets.keys()
+            .filter(|path| !old.assets.contains_key(path.as_str()))
             .collect();
+        new_paths.sort_unstable();
         
-        // Find added assets (in new but not in old)
-        for (path, metadata) in &new.assets {
-            if !old_assets.contains_key(path.as_str()) {
-                // Check if this might be a renamed asset
-                let mut is_renamed = false;
-                for (old_path, old_metadata) in &old.assets {
-                    // If checksum matches but path doesn't, it's likely a rename
-                    if old_metadata.checksum == metadata.checksum && old_path != path {
-                        diff.renamed.insert(old_path.clone(), path.clone());
-                        is_renamed = true;
-                        break;
-                    }
-                }
-                
-                // If not renamed, it's a new addition
-                if !is_renamed {
-                    diff.added.insert(path.clone(), metadata.clone());
-                }
+        for path in new_paths {
+            let metadata = &new.assets[path];
+            let checksum = metadata.checksum.as_str();
+            
+            if let Some(old_path) = rename_candidates.get_mut(checksum).and_then(VecDeque::pop_front) {
+                diff.renamed.insert(old_path.to_string(), path.clone());
+            } else if let Some(source) = by_checksum.get(checksum).and_then(|paths| paths.first()) {
+                diff.copied.insert(path.clone(), source.to_string());
+            } else {
+                diff.added.insert(path.clone(), metadata.clone());
             }
         }
         
-        // Find removed assets (in old but not in new)
-        for (path, metadata) in &old.assets {
-            if !new_assets.contains_key(path.as_str()) {
-                // Check if this was renamed (exists in new with same checksum but different path)
-                let mut is_renamed = false;
-                for (new_path, new_metadata) in &new.assets {
-                    if new_metadata.checksum == metadata.checksum && new_path != path {
-                        // This is handled in the added section above
-                        is_renamed = true;
-                        break;
-                    }
-                }
-                
-                // If not renamed, it was removed
-                if !is_renamed {
-                    diff.removed.insert(path.clone());
-                }
-            }
+        // Rename candidates that were not matched were removed
+        for paths in rename_candidates.into_values() {
+            diff.removed.extend(paths.into_iter().map(str::to_string));
         }
         
         // Find modified assets (same path but different content)
         for (path, new_metadata) in &new.assets {
-            if let Some(old_metadata) = old_assets.get(path.as_str()) {
-                // Same path, check if content changed
-                if old_metadata.checksum != new_metadata.checksum {
-                    diff.modified.insert(
-                        path.clone(), 
-                        ((*old_metadata).clone(), (*new_metadata).clone())
-                    );
-                }
+            if let Some(old_metadata) = old.assets.get(path)
+                && old_metadata.checksum != new_metadata.checksum
+            {
+                diff.modified.insert(
+                    path.clone(), 
+                    (old_metadata.clone(), new_metadata.clone())
+                );
             }
         }
         
@@ -382,12 +704,18 @@ impl AssetDiffEngine {
     
     /// Verify the integrity of a diff by checking if applying it transforms 
     /// the old collection into the new collection
+    ///
+    /// Only the asset maps are compared; collection ids and creation times
+    /// are ignored.
     pub fn verify_diff(&self, old: &AssetCollection, new: &AssetCollection, diff: &AssetDiff) -> Result<bool> {
+        if !diff.verify_applicable(old) {
+            return Ok(false);
+        }
+        
         let mut test_collection = old.clone();
         diff.apply(&mut test_collection)?;
         
-        // Check if the transformed collection matches the new collection
-        Ok(test_collection == *new)
+        Ok(test_collection.assets == new.assets)
     }
 }
 
@@ -411,83 +739,226 @@ pub struct CompressedAssetCollection {
 }
 
 /// Asset compression utilities
+///
+/// Packages are zstd-compressed tar archives of the real asset files. The
+/// archive is deterministic: entries are sorted by path and every header uses
+/// a fixed mtime, uid/gid and mode, so the same files always produce the same
+/// bytes.
 pub struct AssetCompressor;
 
 impl AssetCompressor {
-    /// Compress an entire asset collection into a single compressed blob
-    pub fn compress_collection(collection: &AssetCollection) -> Result<CompressedAssetCollection> {
-        // Create a temporary directory to store assets in a structured way
-        let temp_dir = tempfile::tempdir().map_err(|e| 
-            RodePushError::Bundle(BundleError::InvalidFormat { 
-                reason: format!("Failed to create temp directory: {}", e) 
-            }))?;
-        let temp_path = temp_dir.path();
-        
-        // Write all assets to the temporary directory maintaining their paths
-        for (asset_path, metadata) in &collection.assets {
-            let full_path = temp_path.join(asset_path);
-            
-            // Create parent directories if they don't exist
-            if let Some(parent) = full_path.parent() {
-                std::fs::create_dir_all(parent).map_err(|e| 
-                    RodePushError::Bundle(BundleError::InvalidFormat { 
-                        reason: format!("Failed to create directory: {}", e) 
-                    }))?;
-            }
-            
-            // Create an empty file (in a real implementation, we would copy the actual asset file)
-            // For now, we'll just create a placeholder with the asset's metadata
-            std::fs::write(&full_path, format!("{}:{}", metadata.checksum, metadata.size)).map_err(|e| 
-                RodePushError::Bundle(BundleError::InvalidFormat { 
-                    reason: format!("Failed to write file: {}", e) 
-                }))?;
+    /// Compress the files of an asset collection into a single compressed blob
+    ///
+    /// Each asset is read from `source_dir` and verified against its metadata
+    /// checksum before it is added, so the package always matches the
+    /// collection that describes it.
+    pub fn compress_collection<P: AsRef<Path>>(
+        collection: &AssetCollection,
+        source_dir: P,
+    ) -> Result<CompressedAssetCollection> {
+        let source_dir = source_dir.as_ref();
+        let verifier = ChecksumVerifier::new(HashAlgorithm::Sha256);
+
+        let mut paths: Vec<&String> = collection.assets.keys().collect();
+        paths.sort();
+
+        let mut tar_builder = tar::Builder::new(Vec::new());
+        for asset_path in paths {
+            let metadata = &collection.assets[asset_path];
+            let relative_path = validate_relative_path(Path::new(asset_path))?;
+
+            let data = std::fs::read(source_dir.join(&relative_path)).map_err(|e| {
+                BundleError::invalid_format(format!(
+                    "Failed to read asset {}: {}",
+                    asset_path, e
+                ))
+            })?;
+            verify_asset(&verifier, metadata, &data)?;
+
+            let mut header = deterministic_header(data.len() as u64);
+            tar_builder
+                .append_data(&mut header, &relative_path, data.as_slice())
+                .map_err(|e| {
+                    BundleError::invalid_format(format!(
+                        "Failed to add {} to tar archive: {}",
+                        asset_path, e
+                    ))
+                })?;
         }
-        
-        // Create a tar archive of all assets
-        let tar_data = {
-            let mut tar_builder = tar::Builder::new(Vec::new());
-            tar_builder.append_dir_all(".", temp_path).map_err(|e| 
-                RodePushError::Bundle(BundleError::InvalidFormat { 
-                    reason: format!("Failed to create tar archive: {}", e) 
-                }))?;
-            tar_builder.into_inner().map_err(|e| 
-                RodePushError::Bundle(BundleError::InvalidFormat { 
-                    reason: format!("Failed to finalize tar archive: {}", e) 
-                }))?
-        };
-        
+
+        let tar_data = tar_builder.into_inner().map_err(|e| {
+            BundleError::invalid_format(format!("Failed to finalize tar archive: {}", e))
+        })?;
+
         // Compress the tar archive
-        let compressor = ZstdCompressor::new(); // Use default compression level
+        let compressor = ZstdCompressor::new();
         let compressed_data = compressor.compress(&tar_data, compressor.default_level())?;
-        
+
         Ok(CompressedAssetCollection {
-            data: compressed_data.clone(),
             uncompressed_size: tar_data.len() as u64,
             compressed_size: compressed_data.len() as u64,
-            compression_type: CompressionType::Zstd, // Use the correct path
+            data: compressed_data,
+            compression_type: CompressionType::Zstd,
         })
     }
-    
-    /// Decompress a compressed asset collection
-    pub fn decompress_collection(compressed: &CompressedAssetCollection) -> Result<AssetCollection> {
+
+    /// Extract a compressed asset collection into `output_dir`
+    ///
+    /// Every file in the package must be described by `collection` and match
+    /// its size and checksum, and every asset in `collection` must be present.
+    /// The whole package is verified before anything is written.
+    pub fn decompress_collection<P: AsRef<Path>>(
+        compressed: &CompressedAssetCollection,
+        collection: &AssetCollection,
+        output_dir: P,
+    ) -> Result<()> {
+        let output_dir = output_dir.as_ref();
+        let files = Self::read_verified(compressed, collection)?;
+
+        for (relative_path, data) in files {
+            let full_path = output_dir.join(relative_path);
+            if let Some(parent) = full_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&full_path, data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that a compressed asset collection holds exactly the files of
+    /// `collection`, without extracting it
+    pub fn verify_collection(
+        compressed: &CompressedAssetCollection,
+        collection: &AssetCollection,
+    ) -> Result<()> {
+        Self::read_verified(compressed, collection).map(|_| ())
+    }
+
+    /// Read every file of a package, verified against `collection`
+    fn read_verified(
+        compressed: &CompressedAssetCollection,
+        collection: &AssetCollection,
+    ) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let verifier = ChecksumVerifier::new(HashAlgorithm::Sha256);
+
         // Decompress the data
         let compressor = ZstdCompressor::new();
         let tar_data = compressor.decompress(&compressed.data)?;
-        
-        // Extract the tar archive to a temporary directory
-        let temp_dir = tempfile::tempdir()?;
-        let temp_path = temp_dir.path();
-        
-        {
-            let mut tar_archive = tar::Archive::new(&tar_data[..]);
-            tar_archive.unpack(temp_path)?;
+
+        let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::with_capacity(collection.len());
+        let mut seen: HashSet<String> = HashSet::with_capacity(collection.len());
+
+        let mut tar_archive = tar::Archive::new(&tar_data[..]);
+        let entries = tar_archive.entries().map_err(|e| {
+            BundleError::invalid_format(format!("Failed to read tar archive: {}", e))
+        })?;
+        for entry in entries {
+            let mut entry = entry.map_err(|e| {
+                BundleError::invalid_format(format!("Failed to read tar entry: {}", e))
+            })?;
+            if entry.header().entry_type() != tar::EntryType::Regular {
+                return Err(BundleError::invalid_format(
+                    "Asset package may only contain regular files",
+                )
+                .into());
+            }
+
+            let entry_path = entry.path().map_err(|e| {
+                BundleError::invalid_format(format!("Invalid tar entry path: {}", e))
+            })?;
+            let relative_path = validate_relative_path(&entry_path)?;
+            let asset_path = relative_path.to_string_lossy().to_string();
+
+            let metadata = collection.get_asset(&asset_path).ok_or_else(|| {
+                BundleError::invalid_format(format!(
+                    "Asset package contains unexpected file: {}",
+                    asset_path
+                ))
+            })?;
+            if !seen.insert(asset_path.clone()) {
+                return Err(BundleError::invalid_format(format!(
+                    "Asset package contains duplicate file: {}",
+                    asset_path
+                ))
+                .into());
+            }
+
+            let mut data = Vec::with_capacity(metadata.size as usize);
+            entry.read_to_end(&mut data).map_err(|e| {
+                BundleError::invalid_format(format!(
+                    "Failed to read {} from tar archive: {}",
+                    asset_path, e
+                ))
+            })?;
+            verify_asset(&verifier, metadata, &data)?;
+
+            files.push((relative_path, data));
+        }
+
+        if let Some(missing) = collection.assets.keys().find(|path| !seen.contains(*path)) {
+            return Err(BundleError::invalid_format(format!(
+                "Asset package is missing file: {}",
+                missing
+            ))
+            .into());
+        }
+
+        Ok(files)
+    }
+}
+
+/// Tar header for a regular file with fixed mtime, uid/gid and mode
+pub(crate) fn deterministic_header(size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+    header
+}
+
+/// Check that an asset's bytes match its metadata
+pub(crate) fn verify_asset(
+    verifier: &ChecksumVerifier,
+    metadata: &AssetMetadata,
+    data: &[u8],
+) -> Result<()> {
+    if data.len() as u64 != metadata.size {
+        return Err(BundleError::invalid_format(format!(
+            "Size mismatch for asset {}: expected {} bytes, got {}",
+            metadata.path,
+            metadata.size,
+            data.len()
+        ))
+        .into());
+    }
+    verifier.verify(data, &metadata.checksum)
+}
+
+/// Reject absolute paths and `..` components so packages cannot write
+/// outside the output directory
+pub(crate) fn validate_relative_path(path: &Path) -> Result<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(BundleError::invalid_format(format!(
+                    "Invalid asset path: {}",
+                    path.display()
+                ))
+                .into());
+            }
         }
-        
-        // Reconstruct the asset collection from the extracted files
-        let collection = AssetCollection::from_directory(temp_path)?;
-        
-        Ok(collection)
     }
+    if relative.as_os_str().is_empty() {
+        return Err(BundleError::invalid_format("Empty asset path").into());
+    }
+    Ok(relative)
 }
 
 #[cfg(test)]
@@ -522,6 +993,147 @@ mod tests {
         Ok(())
     }
     
+    #[test]
+    fn test_from_directory_default_ignores() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir_all(temp_dir.path().join("images"))?;
+        fs::create_dir_all(temp_dir.path().join(".git/objects"))?;
+        fs::write(temp_dir.path().join("images/icon.png"), "png")?;
+        fs::write(temp_dir.path().join("images/.DS_Store"), "finder")?;
+        fs::write(temp_dir.path().join("images/.icon.png.swp"), "swap")?;
+        fs::write(temp_dir.path().join("images/icon.png~"), "backup")?;
+        fs::write(temp_dir.path().join(".git/objects/abc"), "object")?;
+        
+        let collection = AssetCollection::from_directory(temp_dir.path())?;
+        assert_eq!(collection.len(), 1);
+        assert!(collection.contains_asset("images/icon.png"));
+
+        let options = AssetScanOptions::new().with_default_ignores(false);
+        let collection = AssetCollection::from_directory_with_options(temp_dir.path(), &options)?;
+        assert_eq!(collection.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_directory_ignore_file_and_includes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir_all(temp_dir.path().join("images/raw"))?;
+        fs::write(temp_dir.path().join("images/icon.png"), "png")?;
+        fs::write(temp_dir.path().join("images/raw/icon.psd"), "psd")?;
+        fs::write(temp_dir.path().join("images/raw/keep.png"), "png")?;
+        fs::write(temp_dir.path().join("notes.md"), "notes")?;
+        fs::write(temp_dir.path().join("sound.mp3"), "mp3")?;
+        fs::write(temp_dir.path().join(".rodepushignore"), "*.md\nimages/raw/*\n!images/raw/keep.png\n")?;
+        
+        let collection = AssetCollection::from_directory(temp_dir.path())?;
+        let mut paths: Vec<&String> = collection.assets.keys().collect();
+        paths.sort();
+        assert_eq!(paths, vec!["images/icon.png", "images/raw/keep.png", "sound.mp3"]);
+
+        let options = AssetScanOptions::new()
+            .with_include_pattern("**/*.png")
+            .with_ignore_pattern("keep.png");
+        let collection = AssetCollection::from_directory_with_options(temp_dir.path(), &options)?;
+        let paths: Vec<&String> = collection.assets.keys().collect();
+        assert_eq!(paths, vec!["images/icon.png"]);
+
+        // Patterns without a separator match at any depth, like ignore rules
+        let options = AssetScanOptions::new().with_include_pattern("*.png");
+        let collection = AssetCollection::from_directory_with_options(temp_dir.path(), &options)?;
+        let mut paths: Vec<&String> = collection.assets.keys().collect();
+        paths.sort();
+        assert_eq!(paths, vec!["images/icon.png", "images/raw/keep.png"]);
+
+        // Patterns with one are anchored to the root
+        let options = AssetScanOptions::new().with_include_pattern("images/*.png");
+        let collection = AssetCollection::from_directory_with_options(temp_dir.path(), &options)?;
+        let paths: Vec<&String> = collection.assets.keys().collect();
+        assert_eq!(paths, vec!["images/icon.png"]);
+        let options = AssetScanOptions::new().with_include_pattern("/*.mp3");
+        let collection = AssetCollection::from_directory_with_options(temp_dir.path(), &options)?;
+        let paths: Vec<&String> = collection.assets.keys().collect();
+        assert_eq!(paths, vec!["sound.mp3"]);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_directory_symlink_policy() -> Result<()> {
+        let outside = TempDir::new()?;
+        fs::write(outside.path().join("shared.png"), "shared")?;
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("icon.png"), "png")?;
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("linked"))?;
+        
+        let collection = AssetCollection::from_directory(temp_dir.path())?;
+        assert_eq!(collection.len(), 1);
+
+        let follow = AssetScanOptions::new().with_symlink_policy(SymlinkPolicy::Follow);
+        let collection = AssetCollection::from_directory_with_options(temp_dir.path(), &follow)?;
+        assert!(collection.contains_asset("linked/shared.png"));
+
+        let error = AssetScanOptions::new().with_symlink_policy(SymlinkPolicy::Error);
+        let result = AssetCollection::from_directory_with_options(temp_dir.path(), &error);
+        assert!(matches!(
+            result,
+            Err(RodePushError::Bundle(BundleError::SymlinkNotAllowed { .. }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_directory_max_file_size() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("small.png"), vec![0u8; 10])?;
+        fs::write(temp_dir.path().join("large.png"), vec![0u8; 100])?;
+
+        let options = AssetScanOptions::new().with_max_file_size(50);
+        let result = AssetCollection::from_directory_with_options(temp_dir.path(), &options);
+        match result {
+            Err(RodePushError::Bundle(BundleError::AssetTooLarge { path, size, max_size })) => {
+                assert_eq!(path, "large.png");
+                assert_eq!(size, 100);
+                assert_eq!(max_size, 50);
+            }
+            other => panic!("Expected AssetTooLarge, got {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_id_is_reproducible() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir(temp_dir.path().join("images"))?;
+        fs::write(temp_dir.path().join("images/logo.png"), b"logo")?;
+        fs::write(temp_dir.path().join("font.ttf"), b"font")?;
+        
+        let first = AssetCollection::from_directory(temp_dir.path())?;
+        let second = AssetCollection::from_directory(temp_dir.path())?;
+        assert_ne!(first.id, second.id);
+        assert_ne!(first, second);
+        assert!(first.same_content(&second));
+        
+        let timestamp = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let first = first.with_content_id().with_created_at(timestamp);
+        let second = second.with_content_id().with_created_at(timestamp);
+        assert_eq!(first.id, second.id);
+        assert_eq!(first, second);
+        
+        // Renaming a file changes the identity even though the bytes match
+        fs::rename(temp_dir.path().join("font.ttf"), temp_dir.path().join("font2.ttf"))?;
+        let renamed = AssetCollection::from_directory(temp_dir.path())?.with_content_id();
+        assert_ne!(renamed.id, first.id);
+        
+        // An empty collection still has a stable identity
+        assert_eq!(AssetCollection::new().content_id(), AssetCollection::new().content_id());
+        
+        Ok(())
+    }
+    
     #[test]
     fn test_asset_diff_empty() {
         let diff = AssetDiff::new();
@@ -540,6 +1152,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         old_collection.assets.insert("image2.png".to_string(), AssetMetadata {
@@ -547,6 +1161,8 @@ mod tests {
             size: 200,
             checksum: "def456".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // Add some assets to the new collection
@@ -556,6 +1172,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // image2.png is modified (different checksum)
@@ -564,6 +1182,8 @@ mod tests {
             size: 250,
             checksum: "ghi789".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // image3.png is added
@@ -572,6 +1192,8 @@ mod tests {
             size: 300,
             checksum: "jkl012".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         let engine = AssetDiffEngine::new();
@@ -600,6 +1222,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // Rename the asset in the new collection (same checksum, different name)
@@ -608,6 +1232,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         let engine = AssetDiffEngine::new();
@@ -634,6 +1260,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         old_collection.assets.insert("image2.png".to_string(), AssetMetadata {
@@ -641,6 +1269,8 @@ mod tests {
             size: 200,
             checksum: "def456".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // Set the total size correctly
@@ -654,6 +1284,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // Add the modified asset
@@ -662,6 +1294,8 @@ mod tests {
             size: 250,
             checksum: "ghi789".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // Add a new asset
@@ -670,6 +1304,8 @@ mod tests {
             size: 300,
             checksum: "jkl012".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // Set the total size correctly
@@ -693,8 +1329,108 @@ mod tests {
             assert_eq!(actual_metadata, expected_metadata);
         }
         
-        // Note: We're not testing verify_diff here because it requires exact equality
-        // which is not guaranteed due to HashMap ordering
+        // Collections are created independently, so only their assets are compared
+        assert!(engine.verify_diff(&old_collection, &new_collection, &diff)?);
+        
+        Ok(())
+    }
+    
+    fn asset(path: &str, checksum: &str) -> AssetMetadata {
+        AssetMetadata {
+            path: path.to_string(),
+            size: 100,
+            checksum: checksum.to_string(),
+            mime_type: mime_guess::from_path(path).first_or_octet_stream().to_string(),
+            original_checksum: None,
+            dimensions: None,
+        }
+    }
+    
+    fn collection_of(assets: &[AssetMetadata]) -> AssetCollection {
+        let mut collection = AssetCollection::new();
+        for metadata in assets {
+            collection.assets.insert(metadata.path.clone(), metadata.clone());
+        }
+        collection.total_size = collection.assets.values().map(|asset| asset.size).sum();
+        collection
+    }
+    
+    #[test]
+    fn test_asset_diff_duplicate_checksums() -> Result<()> {
+        // One old asset whose content appears at three new paths
+        let old_collection = collection_of(&[asset("a.png", "same")]);
+        let new_collection = collection_of(&[
+            asset("b.png", "same"),
+            asset("c.png", "same"),
+            asset("d.jpg", "same"),
+        ]);
+        
+        let engine = AssetDiffEngine::new();
+        let diff = engine.diff(&old_collection, &new_collection)?;
+        
+        assert_eq!(diff.renamed.len(), 1);
+        assert_eq!(diff.renamed.get("a.png"), Some(&"b.png".to_string()));
+        assert_eq!(diff.copied.len(), 2);
+        assert_eq!(diff.copied.get("c.png"), Some(&"a.png".to_string()));
+        assert_eq!(diff.copied.get("d.jpg"), Some(&"a.png".to_string()));
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert!(engine.verify_diff(&old_collection, &new_collection, &diff)?);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_asset_diff_copy_of_kept_asset() -> Result<()> {
+        // Two removed assets share content; the kept asset is copied
+        let old_collection = collection_of(&[
+            asset("kept.png", "k"),
+            asset("gone1.png", "g"),
+            asset("gone2.png", "g"),
+            asset("changed.png", "x"),
+        ]);
+        let new_collection = collection_of(&[
+            asset("kept.png", "k"),
+            asset("kept_copy.png", "k"),
+            asset("moved.png", "g"),
+            asset("changed.png", "y"),
+            asset("fresh.png", "f"),
+        ]);
+        
+        let engine = AssetDiffEngine::new();
+        let diff = engine.diff(&old_collection, &new_collection)?;
+        
+        assert_eq!(diff.copied.get("kept_copy.png"), Some(&"kept.png".to_string()));
+        assert_eq!(diff.renamed.get("gone1.png"), Some(&"moved.png".to_string()));
+        assert_eq!(diff.removed, HashSet::from(["gone2.png".to_string()]));
+        assert!(diff.modified.contains_key("changed.png"));
+        assert!(diff.added.contains_key("fresh.png"));
+        assert_eq!(diff.len(), 5);
+        assert!(engine.verify_diff(&old_collection, &new_collection, &diff)?);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_verify_diff_independent_collections() -> Result<()> {
+        let old_dir = TempDir::new()?;
+        fs::write(old_dir.path().join("a.png"), "a")?;
+        fs::write(old_dir.path().join("b.png"), "b")?;
+        let new_dir = TempDir::new()?;
+        fs::write(new_dir.path().join("a.png"), "a2")?;
+        fs::write(new_dir.path().join("renamed.png"), "b")?;
+        fs::write(new_dir.path().join("copy.png"), "a2")?;
+        
+        let old_collection = AssetCollection::from_directory(old_dir.path())?;
+        let new_collection = AssetCollection::from_directory(new_dir.path())?;
+        
+        let engine = AssetDiffEngine::new();
+        let diff = engine.diff(&old_collection, &new_collection)?;
+        assert!(engine.verify_diff(&old_collection, &new_collection, &diff)?);
+        
+        // A diff computed against another collection does not verify
+        let unrelated = engine.diff(&new_collection, &old_collection)?;
+        assert!(!engine.verify_diff(&old_collection, &new_collection, &unrelated)?);
         
         Ok(())
     }
@@ -707,6 +1443,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         let mut collection2 = AssetCollection::new();
@@ -715,6 +1453,8 @@ mod tests {
             size: 200,
             checksum: "def456".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         // Also add an asset with the same path but different content to test overwrite
@@ -723,6 +1463,8 @@ mod tests {
             size: 150,
             checksum: "xyz789".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         
         collection1.merge(&collection2)?;
@@ -739,4 +1481,112 @@ mod tests {
         
         Ok(())
     }
+
+    fn write_test_assets(dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir.join("images"))?;
+        fs::write(dir.join("images/logo.png"), b"\x89PNG real image bytes")?;
+        fs::write(dir.join("font.ttf"), vec![7u8; 4096])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_and_extract_round_trip() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        write_test_assets(source_dir.path())?;
+        let collection = AssetCollection::from_directory(source_dir.path())?;
+
+        let compressed = AssetCompressor::compress_collection(&collection, source_dir.path())?;
+        assert_eq!(compressed.compressed_size, compressed.data.len() as u64);
+
+        let output_dir = TempDir::new()?;
+        AssetCompressor::decompress_collection(&compressed, &collection, output_dir.path())?;
+
+        assert_eq!(
+            fs::read(output_dir.path().join("images/logo.png"))?,
+            fs::read(source_dir.path().join("images/logo.png"))?
+        );
+        assert_eq!(fs::read(output_dir.path().join("font.ttf"))?, vec![7u8; 4096]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_is_deterministic() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        write_test_assets(source_dir.path())?;
+        let collection = AssetCollection::from_directory(source_dir.path())?;
+
+        let first = AssetCompressor::compress_collection(&collection, source_dir.path())?;
+
+        // Touching a file changes its mtime but must not change the package
+        fs::write(source_dir.path().join("font.ttf"), vec![7u8; 4096])?;
+        let second = AssetCompressor::compress_collection(&collection, source_dir.path())?;
+
+        assert_eq!(first.data, second.data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_rejects_changed_source() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        write_test_assets(source_dir.path())?;
+        let collection = AssetCollection::from_directory(source_dir.path())?;
+
+        fs::write(source_dir.path().join("font.ttf"), vec![8u8; 4096])?;
+        let result = AssetCompressor::compress_collection(&collection, source_dir.path());
+
+        assert!(matches!(
+            result,
+            Err(RodePushError::Bundle(BundleError::ChecksumMismatch { .. }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_verifies_checksums() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        write_test_assets(source_dir.path())?;
+        let collection = AssetCollection::from_directory(source_dir.path())?;
+        let compressed = AssetCompressor::compress_collection(&collection, source_dir.path())?;
+
+        // A collection whose checksum does not match the packaged bytes
+        let mut tampered = collection.clone();
+        tampered.assets.get_mut("font.ttf").unwrap().checksum = "0".repeat(64);
+
+        let output_dir = TempDir::new()?;
+        let result =
+            AssetCompressor::decompress_collection(&compressed, &tampered, output_dir.path());
+        assert!(result.is_err());
+        // Nothing is written when verification fails
+        assert!(!output_dir.path().join("images/logo.png").exists());
+
+        // A collection that expects a file the package does not contain
+        let mut extra = collection.clone();
+        extra.assets.insert("missing.png".to_string(), AssetMetadata {
+            path: "missing.png".to_string(),
+            size: 1,
+            checksum: "0".repeat(64),
+            mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
+        });
+        let result = AssetCompressor::decompress_collection(&compressed, &extra, output_dir.path());
+        assert!(result.is_err());
+        assert!(AssetCompressor::verify_collection(&compressed, &extra).is_err());
+        assert!(AssetCompressor::verify_collection(&compressed, &collection).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_relative_path_rejects_traversal() {
+        assert!(validate_relative_path(Path::new("../escape.png")).is_err());
+        assert!(validate_relative_path(Path::new("/etc/passwd")).is_err());
+        assert_eq!(
+            validate_relative_path(Path::new("./images/a.png")).unwrap(),
+            PathBuf::from("images/a.png")
+        );
+    }
 }
\ No newline at end of file
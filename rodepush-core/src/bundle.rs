@@ -1,9 +1,11 @@
 use crate::{BundleError, Result, crypto};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
-use std::sync::Mutex;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 #[allow(dead_code)]
@@ -11,6 +13,29 @@ const BUNDLE_MAGIC: &[u8] = b"RDPUSHB";
 #[allow(dead_code)]
 const BUNDLE_FORMAT_VERSION: u16 = 1;
 
+/// Environment variable holding the reproducible build timestamp
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// Read the reproducible build timestamp from `SOURCE_DATE_EPOCH`
+///
+/// Returns `None` when the variable is unset, and an error when it is not a
+/// Unix timestamp in seconds.
+pub fn source_date_epoch() -> Result<Option<DateTime<Utc>>> {
+    let value = match std::env::var(SOURCE_DATE_EPOCH) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+    let seconds: i64 = value.trim().parse().map_err(|_| {
+        BundleError::invalid_format(format!("Invalid {}: {}", SOURCE_DATE_EPOCH, value))
+    })?;
+    DateTime::from_timestamp(seconds, 0)
+        .map(Some)
+        .ok_or_else(|| {
+            BundleError::invalid_format(format!("{} out of range: {}", SOURCE_DATE_EPOCH, value))
+                .into()
+        })
+}
+
 /// Unique identifier for a bundle
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BundleId(Uuid);
@@ -21,6 +46,11 @@ impl BundleId {
         Self(Uuid::new_v4())
     }
 
+    /// Derive an ID from content, so identical content yields the same ID
+    pub fn from_content(data: &[u8]) -> Self {
+        Self(crypto::content_uuid(data))
+    }
+
     /// Create from existing UUID
     pub fn from_uuid(uuid: Uuid) -> Self {
         Self(uuid)
@@ -289,6 +319,22 @@ impl Default for CompressionType {
     }
 }
 
+/// Canonical view of [`BundleMetadata`] hashed by [`BundleMetadata::content_id`]
+#[derive(Serialize)]
+struct BundleIdentity<'a> {
+    version: &'a SemanticVersion,
+    platform: Platform,
+    size_bytes: u64,
+    checksum: &'a str,
+    dependencies: &'a [Dependency],
+    chunks: &'a [ChunkMetadata],
+    entry_point: &'a str,
+    format_version: &'a str,
+    custom_metadata: BTreeMap<&'a String, &'a serde_json::Value>,
+    compression_type: Option<CompressionType>,
+    hash_algorithm: Option<crypto::HashAlgorithm>,
+}
+
 /// Bundle dependency information
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Dependency {
@@ -427,6 +473,27 @@ impl BundleMetadata {
         }
     }
 
+    /// Derive the content-based ID of this bundle
+    ///
+    /// Everything except `id` and `created_at` contributes, so rebuilding the
+    /// same source with the same settings yields the same ID.
+    pub fn content_id(&self) -> Result<BundleId> {
+        let identity = BundleIdentity {
+            version: &self.version,
+            platform: self.platform,
+            size_bytes: self.size_bytes,
+            checksum: &self.checksum,
+            dependencies: &self.dependencies,
+            chunks: &self.chunks,
+            entry_point: &self.entry_point,
+            format_version: &self.format_version,
+            custom_metadata: self.custom_metadata.iter().collect(),
+            compression_type: self.compression_type,
+            hash_algorithm: self.hash_algorithm,
+        };
+        Ok(BundleId::from_content(&serde_json::to_vec(&identity)?))
+    }
+
     /// Add a dependency
     pub fn add_dependency(&mut self, dependency: Dependency) {
         self.dependencies.push(dependency);
@@ -554,6 +621,7 @@ pub struct BundleBuilder {
     chunks: Vec<BundleChunk>,
     compression_type: CompressionType,
     hash_algorithm: crypto::HashAlgorithm,
+    deterministic_id: bool,
 }
 
 impl BundleBuilder {
@@ -565,9 +633,22 @@ impl BundleBuilder {
             chunks: Vec::new(),
             compression_type: CompressionType::default(),
             hash_algorithm: crypto::HashAlgorithm::Sha256,
+            deterministic_id: false,
         }
     }
 
+    /// Derive the bundle ID from its content instead of generating a random one
+    pub fn with_deterministic_id(mut self) -> Self {
+        self.deterministic_id = true;
+        self
+    }
+
+    /// Set the creation timestamp recorded in the bundle metadata
+    pub fn with_created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.metadata.created_at = created_at;
+        self
+    }
+
     /// Set compression type for the bundle
     pub fn with_compression(mut self, compression: CompressionType) -> Self {
         self.compression_type = compression;
@@ -625,10 +706,13 @@ impl BundleBuilder {
         let hasher = crypto::BulkHasher::new(self.hash_algorithm);
         let checksum = hasher.hash_data(&compressed_data);
 
+        // Chunks are laid out back to back in the order they are added
+        let offset = self.chunks.iter().map(|chunk| chunk.metadata.size).sum();
+
         // Create chunk metadata
         let chunk_metadata = ChunkMetadata::new(
             chunk_id,
-            self.metadata.size_bytes, // offset
+            offset,
             compressed_data.len() as u64,
             checksum,
             self.compression_type,
@@ -664,6 +748,9 @@ impl BundleBuilder {
         self.metadata.checksum = hasher.hash_data(&all_data);
         self.metadata.compression_type = Some(self.compression_type);
         self.metadata.hash_algorithm = Some(self.hash_algorithm);
+        if self.deterministic_id {
+            self.metadata.id = self.metadata.content_id()?;
+        }
 
         let bundle = Bundle {
             metadata: self.metadata,
@@ -794,6 +881,76 @@ mod tests {
         assert_eq!(id1, id3);
     }
 
+    #[test]
+    fn test_bundle_id_from_content() {
+        let id1 = BundleId::from_content(b"bundle");
+        let id2 = BundleId::from_content(b"bundle");
+        let id3 = BundleId::from_content(b"other");
+        assert_eq!(id1, id2);
+        assert_ne!(id1, id3);
+        assert_eq!(id1.as_uuid().get_version_num(), 8);
+        assert_eq!(BundleId::from_string(&id1.as_str()).unwrap(), id1);
+    }
+
+    #[test]
+    fn test_deterministic_bundle_builds() {
+        let build = |data: &[u8]| {
+            let mut builder = BundleBuilder::new(
+                SemanticVersion::new(1, 0, 0),
+                Platform::Ios,
+                "index.js".to_string(),
+            )
+            .with_compression(CompressionType::None)
+            .with_deterministic_id();
+            builder
+                .add_chunk_from_data(data, "main".to_string())
+                .unwrap();
+            builder.build().unwrap()
+        };
+
+        let first = build(b"console.log('hello');");
+        std::thread::sleep(Duration::from_millis(5));
+        let second = build(b"console.log('hello');");
+        let changed = build(b"console.log('world');");
+
+        assert_eq!(first.id(), second.id());
+        assert_ne!(first.metadata.created_at, second.metadata.created_at);
+        assert_ne!(first.id(), changed.id());
+        assert_eq!(first.metadata.content_id().unwrap(), *first.id());
+
+        // Random IDs stay the default
+        let mut builder = BundleBuilder::new(
+            SemanticVersion::new(1, 0, 0),
+            Platform::Ios,
+            "index.js".to_string(),
+        )
+        .with_compression(CompressionType::None);
+        builder
+            .add_chunk_from_data(b"console.log('hello');", "main".to_string())
+            .unwrap();
+        assert_ne!(builder.build().unwrap().id(), first.id());
+    }
+
+    #[test]
+    fn test_bundle_content_id_ignores_metadata_order() {
+        let version = SemanticVersion::new(1, 0, 0);
+        let mut first = BundleMetadata::new(version.clone(), Platform::Ios, "index.js".to_string());
+        let mut second = BundleMetadata::new(version, Platform::Ios, "index.js".to_string());
+        for (key, value) in [("a", 1), ("b", 2), ("c", 3)] {
+            first.custom_metadata.insert(key.to_string(), value.into());
+        }
+        for (key, value) in [("c", 3), ("b", 2), ("a", 1)] {
+            second.custom_metadata.insert(key.to_string(), value.into());
+        }
+        second.created_at = DateTime::from_timestamp(0, 0).unwrap();
+
+        assert_ne!(first.id, second.id);
+        assert_eq!(first.content_id().unwrap(), second.content_id().unwrap());
+
+        second.custom_metadata.insert("d".to_string(), 4.into());
+        assert_ne!(first.content_id().unwrap(), second.content_id().unwrap());
+    }
+
     #[test]
     fn test_semantic_version() {
         let version = SemanticVersion::new(1, 2, 3);
@@ -895,6 +1052,27 @@ mod tests {
         assert_eq!(bundle.platform(), Platform::Android);
     }
 
+    #[test]
+    fn test_builder_chunk_offsets() {
+        let mut builder = BundleBuilder::new(
+            SemanticVersion::new(1, 0, 0),
+            Platform::Ios,
+            "main.jsbundle".to_string(),
+        )
+        .with_compression(CompressionType::None);
+        builder
+            .add_chunk_from_data(b"first", "a".to_string())
+            .unwrap();
+        builder
+            .add_chunk_from_data(b"second", "b".to_string())
+            .unwrap();
+        let bundle = builder.build().unwrap();
+
+        let offsets: Vec<u64> = bundle.metadata.chunks.iter().map(|c| c.offset).collect();
+        assert_eq!(offsets, vec![0, 5]);
+        assert_eq!(bundle.metadata.size_bytes, 11);
+    }
+
     #[test]
     fn test_bundle_compatibility() {
         let version1 = SemanticVersion::new(1, 2, 3);
@@ -924,78 +1102,329 @@ mod tests {
     }
 }
 
+/// Default byte budget for [`BundleCache`] (256 MiB)
+const DEFAULT_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// A single cached bundle with its bookkeeping data
+struct CacheEntry {
+    bundle: Bundle,
+    size_bytes: u64,
+    inserted_at: Instant,
+    /// Value of the cache clock at the last access, used for LRU ordering
+    last_access: AtomicU64,
+    /// Key of the entry in [`CacheState::recency`]
+    indexed_at: u64,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, ttl: Option<Duration>, now: Instant) -> bool {
+        ttl.is_some_and(|ttl| now.duration_since(self.inserted_at) >= ttl)
+    }
+}
+
+/// Mutable cache contents guarded by the cache lock
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<BundleId, CacheEntry>,
+    /// Entries ordered by the access tick they were last indexed at
+    ///
+    /// Lookups only bump the atomic access clock of an entry, so an entry may
+    /// have been used since it was indexed; eviction moves such entries to
+    /// their current tick before picking a victim.
+    recency: BTreeMap<u64, BundleId>,
+    size_bytes: u64,
+}
+
+impl CacheState {
+    fn insert(&mut self, mut entry: CacheEntry) {
+        let id = entry.bundle.id().clone();
+        entry.indexed_at = entry.last_access.load(Ordering::Relaxed);
+        self.recency.insert(entry.indexed_at, id.clone());
+        self.size_bytes += entry.size_bytes;
+        self.entries.insert(id, entry);
+    }
+
+    fn remove(&mut self, id: &BundleId) -> Option<CacheEntry> {
+        let entry = self.entries.remove(id)?;
+        self.recency.remove(&entry.indexed_at);
+        self.size_bytes -= entry.size_bytes;
+        Some(entry)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.size_bytes = 0;
+    }
+
+    /// Find the least recently used entry
+    ///
+    /// Every entry moved to a later tick here was accessed since it was last
+    /// indexed, so eviction stays O(log n) amortized over lookups.
+    fn lru_victim(&mut self) -> Option<BundleId> {
+        loop {
+            let (tick, id) = self.recency.pop_first()?;
+            let entry = self.entries.get_mut(&id)?;
+            let last_access = entry.last_access.load(Ordering::Relaxed);
+            // Keep the entry indexed so that removing it drops the index key
+            self.recency.insert(last_access, id.clone());
+            entry.indexed_at = last_access;
+            if last_access == tick {
+                return Some(id);
+            }
+        }
+    }
+}
+
 /// Bundle cache for in-memory storage of frequently accessed bundles
+///
+/// The cache is bounded by the total [`Bundle::size`] of its entries and
+/// evicts the least recently used bundle when a new one does not fit.
+/// Recency is tracked with an atomic access clock, so lookups only take a
+/// shared read lock and concurrent readers never block each other.
 pub struct BundleCache {
-    cache: Mutex<HashMap<BundleId, Bundle>>,
-    max_size: usize,
+    state: RwLock<CacheState>,
+    max_size_bytes: u64,
+    max_entries: Option<usize>,
+    ttl: Option<Duration>,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
 }
 
 impl BundleCache {
-    /// Create a new bundle cache with the specified maximum size
-    pub fn new(max_size: usize) -> Self {
+    /// Create a new bundle cache holding at most `max_size_bytes` of bundle data
+    pub fn new(max_size_bytes: u64) -> Self {
         Self {
-            cache: Mutex::new(HashMap::new()),
-            max_size,
+            state: RwLock::new(CacheState::default()),
+            max_size_bytes,
+            max_entries: None,
+            ttl: None,
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            expirations: AtomicU64::new(0),
         }
     }
 
+    /// Additionally limit the number of cached bundles
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Expire entries after they have been cached for `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
     /// Get a bundle from cache by ID
     pub fn get(&self, id: &BundleId) -> Option<Bundle> {
-        self.cache.lock().unwrap().get(id).cloned()
+        let now = Instant::now();
+        {
+            let state = self.state.read().unwrap();
+            match state.entries.get(id) {
+                Some(entry) if !entry.is_expired(self.ttl, now) => {
+                    entry.last_access.store(self.tick(), Ordering::Relaxed);
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(entry.bundle.clone());
+                }
+                Some(_) => {}
+                None => {
+                    self.misses.fetch_add(1, Ordering::Relaxed);
+                    return None;
+                }
+            }
+        }
+
+        // The entry has expired; drop it under the write lock unless another
+        // thread already replaced it in the meantime
+        let mut state = self.state.write().unwrap();
+        if state
+            .entries
+            .get(id)
+            .is_some_and(|entry| entry.is_expired(self.ttl, now))
+        {
+            state.remove(id);
+            self.expirations.fetch_add(1, Ordering::Relaxed);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Check whether a live entry exists without affecting recency or statistics
+    pub fn contains(&self, id: &BundleId) -> bool {
+        let now = Instant::now();
+        self.state
+            .read()
+            .unwrap()
+            .entries
+            .get(id)
+            .is_some_and(|entry| !entry.is_expired(self.ttl, now))
     }
 
     /// Store a bundle in cache
+    ///
+    /// Bundles larger than the whole byte budget are not cached. Otherwise
+    /// least recently used entries are evicted until the bundle fits.
     pub fn put(&self, bundle: Bundle) {
-        let mut cache = self.cache.lock().unwrap();
-
-        // If cache size is 0, don't store anything
-        if self.max_size == 0 {
+        let size_bytes = bundle.size();
+        if self.max_size_bytes == 0 || self.max_entries == Some(0) {
+            return;
+        }
+        if size_bytes > self.max_size_bytes {
+            tracing::debug!(
+                "Bundle {} ({} bytes) exceeds cache capacity of {} bytes, not caching",
+                bundle.id(),
+                size_bytes,
+                self.max_size_bytes
+            );
             return;
         }
 
-        // If cache is full, remove oldest entry (simple LRU-like behavior)
-        if cache.len() >= self.max_size {
-            if let Some((oldest_id, _)) = cache.iter().next() {
-                let oldest_id = oldest_id.clone();
-                let _ = cache.remove(&oldest_id);
-            }
+        let now = Instant::now();
+        let mut state = self.state.write().unwrap();
+        state.remove(bundle.id());
+        self.purge_expired_locked(&mut state, now);
+
+        while state.size_bytes + size_bytes > self.max_size_bytes
+            || self
+                .max_entries
+                .is_some_and(|max| state.entries.len() >= max)
+        {
+            let Some(victim) = state.lru_victim() else {
+                break;
+            };
+            state.remove(&victim);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
         }
 
-        cache.insert(bundle.id().clone(), bundle);
+        let entry = CacheEntry {
+            bundle,
+            size_bytes,
+            inserted_at: now,
+            last_access: AtomicU64::new(self.tick()),
+            indexed_at: 0,
+        };
+        state.insert(entry);
     }
 
     /// Remove a bundle from cache
     pub fn remove(&self, id: &BundleId) -> Option<Bundle> {
-        self.cache.lock().unwrap().remove(id)
+        self.state
+            .write()
+            .unwrap()
+            .remove(id)
+            .map(|entry| entry.bundle)
+    }
+
+    /// Remove all expired entries, returning how many were dropped
+    pub fn purge_expired(&self) -> usize {
+        let mut state = self.state.write().unwrap();
+        self.purge_expired_locked(&mut state, Instant::now())
+    }
+
+    fn purge_expired_locked(&self, state: &mut CacheState, now: Instant) -> usize {
+        if self.ttl.is_none() {
+            return 0;
+        }
+
+        let expired: Vec<BundleId> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.is_expired(self.ttl, now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            state.remove(id);
+        }
+        self.expirations
+            .fetch_add(expired.len() as u64, Ordering::Relaxed);
+        expired.len()
     }
 
     /// Clear all entries from cache
     pub fn clear(&self) {
-        self.cache.lock().unwrap().clear();
+        self.state.write().unwrap().clear();
+    }
+
+    /// Get the number of cached bundles
+    pub fn len(&self) -> usize {
+        self.state.read().unwrap().entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// Get cache statistics
     pub fn stats(&self) -> BundleCacheStats {
-        let cache = self.cache.lock().unwrap();
+        let state = self.state.read().unwrap();
         BundleCacheStats {
-            size: cache.len(),
-            max_size: self.max_size,
-            utilization: cache.len() as f64 / self.max_size as f64,
+            entries: state.entries.len(),
+            max_entries: self.max_entries,
+            size_bytes: state.size_bytes,
+            max_size_bytes: self.max_size_bytes,
+            utilization: if self.max_size_bytes == 0 {
+                0.0
+            } else {
+                state.size_bytes as f64 / self.max_size_bytes as f64
+            },
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            expirations: self.expirations.load(Ordering::Relaxed),
         }
     }
 }
 
 /// Statistics for bundle cache
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BundleCacheStats {
-    pub size: usize,
-    pub max_size: usize,
+    /// Number of cached bundles
+    pub entries: usize,
+    /// Configured entry limit, if any
+    pub max_entries: Option<usize>,
+    /// Total size of cached bundles in bytes
+    pub size_bytes: u64,
+    /// Configured byte budget
+    pub max_size_bytes: u64,
+    /// Fraction of the byte budget in use
     pub utilization: f64,
+    /// Lookups that returned a bundle
+    pub hits: u64,
+    /// Lookups that found nothing (including expired entries)
+    pub misses: u64,
+    /// Entries evicted to make room for new bundles
+    pub evictions: u64,
+    /// Entries dropped because their TTL elapsed
+    pub expirations: u64,
+}
+
+impl BundleCacheStats {
+    /// Fraction of lookups that were hits
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
 }
 
 impl Default for BundleCache {
     fn default() -> Self {
-        Self::new(100) // Default cache size of 100 bundles
+        Self::new(DEFAULT_CACHE_MAX_BYTES)
     }
 }
 
@@ -1003,48 +1432,135 @@ impl Default for BundleCache {
 mod cache_tests {
     use super::*;
 
+    fn sized_bundle(patch: u32, size_bytes: u64) -> Bundle {
+        let mut metadata = BundleMetadata::new(
+            SemanticVersion::new(1, 0, patch),
+            Platform::Ios,
+            "index.js".to_string(),
+        );
+        metadata.size_bytes = size_bytes;
+        Bundle::new(metadata)
+    }
+
     #[test]
     fn test_bundle_cache() {
-        let cache = BundleCache::new(2);
+        let cache = BundleCache::new(200);
 
-        // Create test bundles
-        let bundle1 = Bundle::new(BundleMetadata::new(
-            SemanticVersion::new(1, 0, 0),
-            Platform::Ios,
-            "index.js".to_string(),
-        ));
-        let bundle2 = Bundle::new(BundleMetadata::new(
-            SemanticVersion::new(1, 0, 1),
-            Platform::Android,
-            "index.js".to_string(),
-        ));
+        let bundle1 = sized_bundle(0, 100);
+        let bundle2 = sized_bundle(1, 100);
 
         // Test put and get
         cache.put(bundle1.clone());
-        assert_eq!(cache.get(&bundle1.id()), Some(bundle1.clone()));
+        assert_eq!(cache.get(bundle1.id()), Some(bundle1.clone()));
 
         // Test cache stats
         let stats = cache.stats();
-        assert_eq!(stats.size, 1);
-        assert_eq!(stats.max_size, 2);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.size_bytes, 100);
+        assert_eq!(stats.max_size_bytes, 200);
         assert_eq!(stats.utilization, 0.5);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 0);
 
-        // Test cache eviction
+        // Touch bundle1 so bundle2 becomes the least recently used entry
         cache.put(bundle2.clone());
-        cache.put(Bundle::new(BundleMetadata::new(
-            SemanticVersion::new(1, 0, 2),
-            Platform::Both,
-            "index.js".to_string(),
-        )));
+        assert!(cache.get(bundle1.id()).is_some());
+        cache.put(sized_bundle(2, 100));
 
-        // One of the old entries should be evicted (HashMap iteration order is not guaranteed)
-        let bundle1_exists = cache.get(&bundle1.id()).is_some();
-        let bundle2_exists = cache.get(&bundle2.id()).is_some();
-        assert!(bundle1_exists != bundle2_exists); // Exactly one should exist
-        assert_eq!(cache.stats().size, 2); // Cache should be at max size
+        assert!(cache.get(bundle1.id()).is_some());
+        assert!(cache.get(bundle2.id()).is_none());
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.size_bytes, 200);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.misses, 1);
 
         // Test clear
         cache.clear();
-        assert_eq!(cache.stats().size, 0);
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(cache.stats().size_bytes, 0);
+    }
+
+    #[test]
+    fn test_bundle_cache_byte_budget() {
+        let cache = BundleCache::new(250);
+        let small1 = sized_bundle(0, 100);
+        let small2 = sized_bundle(1, 100);
+        cache.put(small1.clone());
+        cache.put(small2.clone());
+
+        // A large bundle must evict both smaller ones to fit
+        let large = sized_bundle(2, 200);
+        cache.put(large.clone());
+        assert!(!cache.contains(small1.id()));
+        assert!(!cache.contains(small2.id()));
+        assert!(cache.contains(large.id()));
+        assert_eq!(cache.stats().evictions, 2);
+
+        // Bundles larger than the whole budget are never cached
+        let oversized = sized_bundle(3, 251);
+        cache.put(oversized.clone());
+        assert!(!cache.contains(oversized.id()));
+        assert!(cache.contains(large.id()));
+
+        // Replacing an entry does not double count its size
+        cache.put(large.clone());
+        assert_eq!(cache.stats().size_bytes, 200);
+    }
+
+    #[test]
+    fn test_bundle_cache_max_entries() {
+        let cache = BundleCache::new(1024).with_max_entries(2);
+        for patch in 0..5 {
+            cache.put(sized_bundle(patch, 0));
+        }
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().evictions, 3);
+    }
+
+    #[test]
+    fn test_bundle_cache_lru_order() {
+        let cache = BundleCache::new(1024).with_max_entries(3);
+        let bundles: Vec<Bundle> = (0..3).map(|patch| sized_bundle(patch, 0)).collect();
+        for bundle in &bundles {
+            cache.put(bundle.clone());
+        }
+
+        // Access in reverse order, several times, so every entry was used
+        // since it was inserted and the oldest insert is the newest access
+        for _ in 0..3 {
+            for bundle in bundles.iter().rev() {
+                assert!(cache.get(bundle.id()).is_some());
+            }
+        }
+
+        cache.put(sized_bundle(3, 0));
+        assert!(!cache.contains(bundles[2].id()));
+        cache.put(sized_bundle(4, 0));
+        assert!(!cache.contains(bundles[1].id()));
+        assert!(cache.contains(bundles[0].id()));
+
+        // Removed entries leave nothing behind in the recency index
+        cache.remove(bundles[0].id());
+        cache.put(sized_bundle(5, 0));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.stats().evictions, 2);
+    }
+
+    #[test]
+    fn test_bundle_cache_ttl() {
+        let cache = BundleCache::new(1024).with_ttl(Duration::from_millis(20));
+        let bundle = sized_bundle(0, 10);
+        cache.put(bundle.clone());
+        assert!(cache.get(bundle.id()).is_some());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(bundle.id()).is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.size_bytes, 0);
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.hit_rate(), 0.5);
     }
 }
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::io::{Read, BufReader};
 use std::path::Path;
+use uuid::{Builder, Uuid};
 
 /// Supported hashing algorithms for bundle integrity
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -37,6 +38,14 @@ impl HashAlgorithm {
         }
     }
 
+    /// Create an incremental hasher for this algorithm
+    pub fn hasher(&self) -> Box<dyn Hasher + Send> {
+        match self {
+            HashAlgorithm::Sha256 => Box::new(Sha256Hasher::new()),
+            HashAlgorithm::Blake3 => Box::new(Blake3Hasher::new()),
+        }
+    }
+
     /// Parse from string
     pub fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
@@ -188,6 +197,17 @@ pub fn secure_compare(a: &str, b: &str) -> bool {
     subtle::ConstantTimeEq::ct_eq(a.as_bytes(), b.as_bytes()).into()
 }
 
+/// Derive a stable UUID from arbitrary content
+///
+/// The first 16 bytes of the SHA-256 digest are used as a version 8 UUID, so
+/// identical input always maps to the same identifier.
+pub(crate) fn content_uuid(data: &[u8]) -> Uuid {
+    let digest = Sha256::digest(data);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    Builder::from_custom_bytes(bytes).into_uuid()
+}
+
 /// Progress callback type for hashing operations
 pub type ProgressCallback = dyn Fn(u64, u64) + Send + Sync;
 
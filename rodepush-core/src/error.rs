@@ -30,6 +30,14 @@ pub enum RodePushError {
     #[error("Validation error: {message}")]
     Validation { message: String },
 
+    /// Operation conflicting with the current state of a resource
+    #[error("Conflict: {message}")]
+    Conflict { message: String },
+
+    /// Resource that does not exist
+    #[error("Not found: {message}")]
+    NotFound { message: String },
+
     /// Configuration errors
     #[error("Configuration error: {message}")]
     Config { message: String },
@@ -97,6 +105,18 @@ pub enum BundleError {
     /// Bundle signature verification failed
     #[error("Bundle signature verification failed: {reason}")]
     SignatureVerificationFailed { reason: String },
+
+    /// Asset file exceeds the configured size limit
+    #[error("Asset too large: {path} is {size} bytes (max: {max_size})")]
+    AssetTooLarge {
+        path: String,
+        size: u64,
+        max_size: u64,
+    },
+
+    /// Asset directory contains a symlink and the policy forbids them
+    #[error("Symbolic link not allowed in asset directory: {path}")]
+    SymlinkNotAllowed { path: String },
 }
 
 /// Network-related errors
@@ -190,6 +210,10 @@ pub enum AuthError {
     #[error("Token expired at {expired_at}")]
     TokenExpired { expired_at: String },
 
+    /// Revoked token
+    #[error("Token revoked at {revoked_at}")]
+    TokenRevoked { revoked_at: String },
+
     /// Missing authentication
     #[error("Authentication required")]
     MissingAuth,
@@ -224,6 +248,20 @@ impl RodePushError {
         }
     }
 
+    /// Create a conflict error
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict {
+            message: message.into(),
+        }
+    }
+
+    /// Create a not found error
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound {
+            message: message.into(),
+        }
+    }
+
     /// Create a configuration error
     pub fn config(message: impl Into<String>) -> Self {
         Self::Config {
@@ -305,6 +343,20 @@ impl BundleError {
         }
     }
 
+    /// Create an asset too large error
+    pub fn asset_too_large(path: impl Into<String>, size: u64, max_size: u64) -> Self {
+        Self::AssetTooLarge {
+            path: path.into(),
+            size,
+            max_size,
+        }
+    }
+
+    /// Create a symlink not allowed error
+    pub fn symlink_not_allowed(path: impl Into<String>) -> Self {
+        Self::SymlinkNotAllowed { path: path.into() }
+    }
+
     /// Create a build failed error
     pub fn build_failed(message: impl Into<String>) -> Self {
         Self::InvalidFormat {
@@ -34,7 +34,7 @@ mod tests {
         bundle.validate()?;
 
         // Test cache functionality
-        let cache = BundleCache::new(10);
+        let cache = BundleCache::new(1024 * 1024);
         cache.put(bundle.clone());
 
         let cached_bundle = cache.get(&bundle.id());
@@ -77,11 +77,13 @@ mod tests {
         let context = LogContext::new("asset_workflow_test", "test");
         context.info("Starting asset collection workflow test");
 
-        // Create an asset collection
-        let collection = AssetCollection::new();
+        // Create an asset collection from real files
+        let source_dir = tempfile::tempdir()?;
+        std::fs::write(source_dir.path().join("icon.png"), b"icon bytes")?;
+        let collection = AssetCollection::from_directory(source_dir.path())?;
 
-        // Test compression workflow with empty collection
-        let compressed = AssetCompressor::compress_collection(&collection)?;
+        // Package the real files
+        let compressed = AssetCompressor::compress_collection(&collection, source_dir.path())?;
 
         context.log_asset_operation(
             "compress",
@@ -90,12 +92,16 @@ mod tests {
             collection.total_size,
         );
 
-        // Decompress and verify
-        let decompressed = AssetCompressor::decompress_collection(&compressed)?;
-        // Note: AssetCollection ID is regenerated during compression/decompression
-        // so we verify the structure instead of the ID
-        assert_eq!(decompressed.len(), collection.len());
-        assert_eq!(decompressed.total_size, collection.total_size);
+        // Extract and verify the files against the collection checksums
+        let output_dir = tempfile::tempdir()?;
+        AssetCompressor::decompress_collection(&compressed, &collection, output_dir.path())?;
+        let extracted = AssetCollection::from_directory(output_dir.path())?;
+        assert_eq!(extracted.len(), collection.len());
+        assert_eq!(extracted.total_size, collection.total_size);
+        assert_eq!(
+            std::fs::read(output_dir.path().join("icon.png"))?,
+            b"icon bytes"
+        );
 
         context.info("Asset collection workflow test completed successfully");
         Ok(())
@@ -132,7 +138,7 @@ mod tests {
 
         cache.put(bundle.clone());
         // With zero size cache, nothing should be stored
-        assert_eq!(cache.stats().size, 0);
+        assert_eq!(cache.stats().entries, 0);
         assert_eq!(cache.get(&bundle.id()), None);
     }
 
@@ -223,7 +229,7 @@ mod tests {
         }
 
         // Verify all bundles were added
-        assert_eq!(cache.stats().size, 10);
+        assert_eq!(cache.stats().entries, 10);
 
         context.info("Concurrent access test completed successfully");
         Ok(())
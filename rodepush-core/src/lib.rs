@@ -1,3 +1,7 @@
+pub mod asset_inspector;
+pub mod asset_optimizer;
+pub mod asset_patch;
+pub mod asset_variants;
 pub mod assets;
 pub mod bundle;
 pub mod compression;
@@ -10,13 +14,24 @@ pub mod storage;
 #[cfg(test)]
 mod integration_tests;
 
+pub use asset_inspector::{
+    AssetInspection, AssetInspector, AssetIssue, AssetIssueKind, AssetKind, AssetValidationReport,
+    IssueSeverity,
+};
+pub use asset_optimizer::{ImageOptimizer, OptimizationReport};
+pub use asset_patch::{AssetPatch, AssetPatchApplier, AssetPatchBuilder, AssetPatchManifest};
+pub use asset_variants::{
+    AssetLayout, AssetVariant, LogicalAsset, android_resource_name, source_asset_name,
+};
 pub use assets::{
-    AssetCollection, AssetCollectionId, AssetCompressor, AssetDiff, AssetDiffEngine, AssetMetadata,
-    CompressedAssetCollection,
+    ASSET_IGNORE_FILE, AssetCollection, AssetCollectionId, AssetCompressor, AssetDiff,
+    AssetDiffEngine, AssetMetadata, AssetScanOptions, CompressedAssetCollection,
+    DEFAULT_ASSET_IGNORE_PATTERNS, ImageDimensions, SymlinkPolicy,
 };
 pub use bundle::{
     Bundle, BundleBuilder, BundleCache, BundleCacheStats, BundleChunk, BundleId, BundleMetadata,
-    ChunkMetadata, CompressionType, Dependency, Platform, SemanticVersion,
+    ChunkMetadata, CompressionType, Dependency, Platform, SOURCE_DATE_EPOCH, SemanticVersion,
+    source_date_epoch,
 };
 pub use compression::{
     CompressionStats, CompressionUtil, Compressor, NoneCompressor, ZstdCompressor,
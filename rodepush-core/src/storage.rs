@@ -1,13 +1,17 @@
 //! Storage abstraction layer for RodePush.
 //!
 //! This module provides a trait for storage operations and a file system implementation.
+//! Besides typed bundles and asset collections, storage holds raw objects that
+//! are written and read as streams so large uploads never sit in memory.
 
 use crate::error::{Result, RodePushError, StorageError};
 use crate::bundle::Bundle;
 use crate::assets::AssetCollection;
 use async_trait::async_trait;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use tokio::fs;
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncSeekExt, AsyncWriteExt, BufWriter};
 
 /// Storage key for identifying stored objects
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -25,6 +29,22 @@ impl StorageKey {
     }
 }
 
+/// Incremental writer for a raw storage object
+///
+/// Nothing is visible under the object's key until [`ObjectWriter::commit`]
+/// succeeds, so readers never observe a partially written object.
+#[async_trait]
+pub trait ObjectWriter: Send {
+    /// Append data to the object
+    async fn write(&mut self, data: &[u8]) -> Result<()>;
+    
+    /// Publish the object under its key and return its size in bytes
+    async fn commit(self: Box<Self>) -> Result<u64>;
+    
+    /// Discard everything written so far
+    async fn abort(self: Box<Self>) -> Result<()>;
+}
+
 /// Trait for storage operations
 #[async_trait]
 pub trait Storage: Send + Sync {
@@ -45,6 +65,22 @@ pub trait Storage: Send + Sync {
     
     /// Check if an object exists
     async fn exists(&self, key: &StorageKey) -> Result<bool>;
+    
+    /// Start writing a raw object, replacing any existing object on commit
+    async fn create_object(&self, key: &StorageKey) -> Result<Box<dyn ObjectWriter>>;
+    
+    /// Open a raw object for streaming reads
+    async fn open_object(&self, key: &StorageKey) -> Result<Box<dyn AsyncRead + Send + Unpin>>;
+    
+    /// Open a raw object for streaming reads starting at byte `offset`
+    async fn open_object_at(
+        &self,
+        key: &StorageKey,
+        offset: u64,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>>;
+    
+    /// Get the size of a raw object in bytes
+    async fn object_size(&self, key: &StorageKey) -> Result<u64>;
 }
 
 /// File system storage implementation
@@ -70,6 +106,84 @@ impl FilesystemStorage {
     fn get_path(&self, key: &StorageKey) -> PathBuf {
         self.base_path.join(&key.0)
     }
+    
+    /// Get the full path for a raw object key, rejecting keys that escape the base path
+    fn object_path(&self, key: &StorageKey) -> Result<PathBuf> {
+        let path = Path::new(key.as_str());
+        let is_safe = !key.as_str().is_empty()
+            && path.components().all(|component| matches!(component, Component::Normal(_)));
+        if !is_safe {
+            return Err(StorageError::InvalidPath { path: key.0.clone() }.into());
+        }
+        Ok(self.base_path.join(path))
+    }
+    
+    /// Open the file backing a raw object
+    async fn open_file(&self, key: &StorageKey) -> Result<fs::File> {
+        let path = self.object_path(key)?;
+        match fs::File::open(&path).await {
+            Ok(file) => Ok(file),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(StorageError::NotFound { path: key.0.clone() }.into())
+            }
+            Err(e) => Err(FilesystemObjectWriter::io_error(e)),
+        }
+    }
+}
+
+/// Writes to a temporary sibling file and renames it into place on commit
+struct FilesystemObjectWriter {
+    file: Option<BufWriter<fs::File>>,
+    temp_path: PathBuf,
+    final_path: PathBuf,
+    size: u64,
+}
+
+impl FilesystemObjectWriter {
+    fn io_error(e: std::io::Error) -> RodePushError {
+        RodePushError::Storage(StorageError::Io { message: e.to_string() })
+    }
+}
+
+#[async_trait]
+impl ObjectWriter for FilesystemObjectWriter {
+    async fn write(&mut self, data: &[u8]) -> Result<()> {
+        let file = self.file.as_mut().ok_or_else(|| StorageError::Io {
+            message: "object writer already finished".to_string(),
+        })?;
+        file.write_all(data).await.map_err(Self::io_error)?;
+        self.size += data.len() as u64;
+        Ok(())
+    }
+    
+    async fn commit(mut self: Box<Self>) -> Result<u64> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await.map_err(Self::io_error)?;
+            file.get_ref().sync_all().await.map_err(Self::io_error)?;
+        }
+        fs::rename(&self.temp_path, &self.final_path)
+            .await
+            .map_err(Self::io_error)?;
+        Ok(self.size)
+    }
+    
+    async fn abort(mut self: Box<Self>) -> Result<()> {
+        self.file.take();
+        match fs::remove_file(&self.temp_path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Self::io_error(e)),
+        }
+    }
+}
+
+impl Drop for FilesystemObjectWriter {
+    fn drop(&mut self) {
+        // Dropped without commit or abort, e.g. when an upload is cancelled
+        if self.file.take().is_some() {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
 }
 
 #[async_trait]
@@ -158,6 +272,57 @@ impl Storage for FilesystemStorage {
         let path = self.get_path(key);
         Ok(path.exists())
     }
+    
+    async fn create_object(&self, key: &StorageKey) -> Result<Box<dyn ObjectWriter>> {
+        let final_path = self.object_path(key)?;
+        if let Some(parent) = final_path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(FilesystemObjectWriter::io_error)?;
+        }
+        
+        let file_name = final_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let temp_path = final_path.with_file_name(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+        let file = fs::File::create(&temp_path)
+            .await
+            .map_err(FilesystemObjectWriter::io_error)?;
+        
+        Ok(Box::new(FilesystemObjectWriter {
+            file: Some(BufWriter::new(file)),
+            temp_path,
+            final_path,
+            size: 0,
+        }))
+    }
+    
+    async fn open_object(&self, key: &StorageKey) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        Ok(Box::new(self.open_file(key).await?))
+    }
+    
+    async fn open_object_at(
+        &self,
+        key: &StorageKey,
+        offset: u64,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let mut file = self.open_file(key).await?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(FilesystemObjectWriter::io_error)?;
+        Ok(Box::new(file))
+    }
+    
+    async fn object_size(&self, key: &StorageKey) -> Result<u64> {
+        let metadata = self
+            .open_file(key)
+            .await?
+            .metadata()
+            .await
+            .map_err(FilesystemObjectWriter::io_error)?;
+        Ok(metadata.len())
+    }
 }
 
 #[cfg(test)]
@@ -167,6 +332,7 @@ mod tests {
     use crate::assets::AssetMetadata;
     use crate::CompressionType;  // Import CompressionType
     use tempfile::TempDir;
+    use tokio::io::AsyncReadExt;
     
     #[tokio::test]
     async fn test_filesystem_storage_bundle() -> Result<()> {
@@ -219,6 +385,8 @@ mod tests {
             size: 100,
             checksum: "abc123".to_string(),
             mime_type: "image/png".to_string(),
+            original_checksum: None,
+            dimensions: None,
         });
         collection.total_size = 100;
         
@@ -241,4 +409,63 @@ mod tests {
         
         Ok(())
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_filesystem_storage_objects() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = FilesystemStorage::new(temp_dir.path())?;
+        let key = StorageKey::new("bundles/app/bundle.bin".to_string());
+        
+        // Nothing is visible until commit
+        let mut writer = storage.create_object(&key).await?;
+        writer.write(b"hello ").await?;
+        writer.write(b"world").await?;
+        assert!(!storage.exists(&key).await?);
+        assert_eq!(writer.commit().await?, 11);
+        assert!(storage.exists(&key).await?);
+        
+        let mut data = Vec::new();
+        storage.open_object(&key).await?.read_to_end(&mut data).await?;
+        assert_eq!(data, b"hello world");
+        assert_eq!(storage.object_size(&key).await?, 11);
+        
+        let mut data = Vec::new();
+        storage.open_object_at(&key, 6).await?.read_to_end(&mut data).await?;
+        assert_eq!(data, b"world");
+        
+        // Aborted and dropped writers leave the committed object and no temp files behind
+        let mut writer = storage.create_object(&key).await?;
+        writer.write(b"partial").await?;
+        writer.abort().await?;
+        let mut writer = storage.create_object(&key).await?;
+        writer.write(b"partial").await?;
+        drop(writer);
+        let entries = std::fs::read_dir(temp_dir.path().join("bundles/app"))?.count();
+        assert_eq!(entries, 1);
+        
+        let mut data = Vec::new();
+        storage.open_object(&key).await?.read_to_end(&mut data).await?;
+        assert_eq!(data, b"hello world");
+        
+        // Keys cannot escape the storage root
+        for bad_key in ["../outside.bin", "/etc/passwd", ""] {
+            let bad_key = StorageKey::new(bad_key.to_string());
+            assert!(matches!(
+                storage.create_object(&bad_key).await,
+                Err(RodePushError::Storage(StorageError::InvalidPath { .. }))
+            ));
+        }
+        
+        let missing = StorageKey::new("bundles/app/missing.bin".to_string());
+        assert!(matches!(
+            storage.open_object(&missing).await,
+            Err(RodePushError::Storage(StorageError::NotFound { .. }))
+        ));
+        assert!(matches!(
+            storage.object_size(&missing).await,
+            Err(RodePushError::Storage(StorageError::NotFound { .. }))
+        ));
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+-- Resumable upload sessions
+-- A session tracks the declared metadata of a bundle while its chunks are uploaded one by one
+
+CREATE TABLE upload_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    application_id UUID REFERENCES applications(id) ON DELETE CASCADE,
+    bundle_id UUID NOT NULL,
+    metadata JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Create indexes for resuming and expiring sessions
+CREATE INDEX idx_upload_sessions_application_bundle ON upload_sessions(application_id, bundle_id);
+CREATE INDEX idx_upload_sessions_expires_at ON upload_sessions(expires_at);
@@ -0,0 +1,7 @@
+-- Hashed API keys
+-- Applications keep only the SHA-256 hash of their API key; existing keys are hashed in place
+
+ALTER TABLE applications RENAME COLUMN api_key TO api_key_hash;
+UPDATE applications SET api_key_hash = encode(sha256(convert_to(api_key_hash, 'UTF8')), 'hex');
+
+ALTER INDEX idx_applications_api_key RENAME TO idx_applications_api_key_hash;
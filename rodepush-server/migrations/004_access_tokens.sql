@@ -0,0 +1,27 @@
+-- Scoped access tokens and application membership
+-- Tokens carry scopes, an optional expiry and a revocation time; only their SHA-256 hash is stored.
+-- Tokens issued to a member are further limited by the member's role.
+
+CREATE TABLE application_members (
+    application_id UUID REFERENCES applications(id) ON DELETE CASCADE,
+    member VARCHAR(255) NOT NULL,
+    role VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (application_id, member)
+);
+
+CREATE TABLE access_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    application_id UUID REFERENCES applications(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes JSONB NOT NULL,
+    member VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Create index for listing the tokens of an application
+CREATE INDEX idx_access_tokens_application_id ON access_tokens(application_id);
@@ -0,0 +1,39 @@
+-- Deployment state machine
+-- Adds the superseded status and records every status change of a deployment.
+-- A live (active or paused) deployment is superseded by a newer one in the same
+-- environment whose bundle overlaps its own: an overlapping platform ('both'
+-- overlapping either) and the same major.minor version. Bundles of different
+-- platforms or release lines stay live side by side.
+
+ALTER TABLE deployments DROP CONSTRAINT chk_status;
+ALTER TABLE deployments ADD CONSTRAINT chk_status CHECK (status IN ('pending', 'active', 'paused', 'rolled_back', 'failed', 'superseded'));
+
+-- Supersede live deployments that a newer overlapping live deployment replaces
+UPDATE deployments d SET status = 'superseded'
+FROM bundles b
+WHERE b.id = d.bundle_id
+  AND d.status IN ('active', 'paused')
+  AND EXISTS (
+    SELECT 1 FROM deployments n
+    JOIN bundles nb ON nb.id = n.bundle_id
+    WHERE n.application_id = d.application_id
+      AND n.environment = d.environment
+      AND n.status IN ('active', 'paused')
+      AND n.created_at > d.created_at
+      AND (nb.platform = b.platform OR nb.platform = 'both' OR b.platform = 'both')
+      AND split_part(nb.version, '.', 1) = split_part(b.version, '.', 1)
+      AND split_part(nb.version, '.', 2) = split_part(b.version, '.', 2)
+  );
+
+CREATE TABLE deployment_transitions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deployment_id UUID REFERENCES deployments(id) ON DELETE CASCADE,
+    from_status VARCHAR(20) NOT NULL,
+    to_status VARCHAR(20) NOT NULL,
+    actor VARCHAR(255),
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create index for reading the history of a deployment
+CREATE INDEX idx_deployment_transitions_deployment_id ON deployment_transitions(deployment_id, created_at);
@@ -0,0 +1,18 @@
+-- Staged rollout plans
+-- Stores the stages of a deployment's rollout and how far it has progressed,
+-- so the scheduler can raise the rollout percentage over time.
+
+CREATE TABLE rollout_plans (
+    deployment_id UUID PRIMARY KEY REFERENCES deployments(id) ON DELETE CASCADE,
+    steps JSONB NOT NULL,
+    current_step INTEGER NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    next_step_at TIMESTAMP WITH TIME ZONE,
+    halt_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT chk_rollout_plan_status CHECK (status IN ('running', 'halted', 'completed'))
+);
+
+-- Create index for finding plans whose next stage is due
+CREATE INDEX idx_rollout_plans_due ON rollout_plans(status, next_step_at);
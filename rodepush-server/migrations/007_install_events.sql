@@ -0,0 +1,16 @@
+-- Install telemetry
+-- Stores the install outcomes clients report for each deployment, used to
+-- roll back deployments that fail on too many devices.
+
+CREATE TABLE install_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deployment_id UUID NOT NULL REFERENCES deployments(id) ON DELETE CASCADE,
+    client_id VARCHAR(255) NOT NULL,
+    event VARCHAR(32) NOT NULL,
+    message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT chk_install_event CHECK (event IN ('downloaded', 'installed', 'rolled_back', 'crashed_on_launch'))
+);
+
+-- Create index for counting the outcomes of a deployment
+CREATE INDEX idx_install_events_deployment_id ON install_events(deployment_id, event);
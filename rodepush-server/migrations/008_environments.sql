@@ -0,0 +1,28 @@
+-- Deployment environments
+-- Turns the free-form environment names of deployments into entities of
+-- their application, each with rollout defaults and a deployment key clients
+-- use to check for updates.
+
+CREATE TABLE environments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    application_id UUID NOT NULL REFERENCES applications(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    description TEXT,
+    default_rollout_percentage INTEGER NOT NULL DEFAULT 100,
+    default_rollout_plan JSONB,
+    deployment_key VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT uq_environments_application_name UNIQUE (application_id, name),
+    CONSTRAINT uq_environments_deployment_key UNIQUE (deployment_key),
+    CONSTRAINT chk_default_rollout_percentage CHECK (default_rollout_percentage >= 0 AND default_rollout_percentage <= 100)
+);
+
+-- Create an environment for every environment name already deployed to
+INSERT INTO environments (application_id, name, deployment_key)
+SELECT application_id, environment, 'rpd_' || replace(gen_random_uuid()::text, '-', '')
+FROM (SELECT DISTINCT application_id, environment FROM deployments) AS deployed;
+
+-- Deployments can only target existing environments
+ALTER TABLE deployments ADD CONSTRAINT fk_deployments_environment
+    FOREIGN KEY (application_id, environment) REFERENCES environments(application_id, name);
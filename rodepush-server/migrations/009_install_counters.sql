@@ -0,0 +1,44 @@
+-- Install counters
+-- Keeps running counts of the install outcomes of each deployment, so that
+-- evaluating them on every failure report does not scan its install events.
+
+-- Clients already counted in each outcome of a deployment
+CREATE TABLE install_counted_clients (
+    deployment_id UUID NOT NULL REFERENCES deployments(id) ON DELETE CASCADE,
+    outcome VARCHAR(32) NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    PRIMARY KEY (deployment_id, outcome, client_id),
+    CONSTRAINT chk_install_outcome CHECK (outcome IN ('downloaded', 'installed', 'rolled_back', 'crashed_on_launch', 'failed', 'attempted'))
+);
+
+-- Distinct clients counted in each outcome of a deployment
+CREATE TABLE install_counters (
+    deployment_id UUID PRIMARY KEY REFERENCES deployments(id) ON DELETE CASCADE,
+    downloaded BIGINT NOT NULL DEFAULT 0,
+    installed BIGINT NOT NULL DEFAULT 0,
+    rolled_back BIGINT NOT NULL DEFAULT 0,
+    crashed_on_launch BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    attempted BIGINT NOT NULL DEFAULT 0
+);
+
+-- Count the events already reported
+INSERT INTO install_counted_clients (deployment_id, outcome, client_id)
+SELECT deployment_id, event, client_id FROM install_events
+UNION
+SELECT deployment_id, 'failed', client_id FROM install_events
+WHERE event IN ('rolled_back', 'crashed_on_launch')
+UNION
+SELECT deployment_id, 'attempted', client_id FROM install_events
+WHERE event <> 'downloaded';
+
+INSERT INTO install_counters (deployment_id, downloaded, installed, rolled_back, crashed_on_launch, failed, attempted)
+SELECT deployment_id,
+    COUNT(*) FILTER (WHERE outcome = 'downloaded'),
+    COUNT(*) FILTER (WHERE outcome = 'installed'),
+    COUNT(*) FILTER (WHERE outcome = 'rolled_back'),
+    COUNT(*) FILTER (WHERE outcome = 'crashed_on_launch'),
+    COUNT(*) FILTER (WHERE outcome = 'failed'),
+    COUNT(*) FILTER (WHERE outcome = 'attempted')
+FROM install_counted_clients
+GROUP BY deployment_id;
@@ -0,0 +1,103 @@
+//! HTTP API for RodePush Server
+//!
+//! Handlers live in one submodule per resource and share an [`AppState`]
+//! holding the database manager and the storage backend. Every response body
+//! uses the [`ApiResponse`] envelope, and handler failures are reported as
+//! [`ApiError`]s that map onto HTTP status codes.
+
+// Declare submodules
+pub mod access;
+pub mod applications;
+pub mod assets;
+pub mod auth;
+pub mod bundles;
+pub mod deployments;
+pub mod diffs;
+pub mod downloads;
+pub mod environments;
+pub mod error;
+pub mod response;
+pub mod rollouts;
+pub mod state;
+pub mod targeting;
+pub mod telemetry;
+pub mod update_check;
+pub mod uploads;
+
+// Re-export commonly used types for convenience
+pub use access::{AccessTokenInfo, CreateTokenRequest, CreatedTokenResponse, SetMemberRequest};
+pub use applications::{
+    ApplicationInfo, CreateApplicationRequest, CreatedApplicationResponse, UpdateApplicationRequest,
+};
+pub use assets::AssetPackageResponse;
+pub use auth::AuthenticatedApplication;
+pub use bundles::{BundleUploadResponse, ReceivedBundle};
+pub use deployments::{CreateDeploymentRequest, PromoteDeploymentRequest, UpdateRolloutRequest};
+pub use diffs::{DiffGenerator, DiffPackageManifest};
+pub use downloads::{ByteRange, DownloadObject, RangeRequest};
+pub use environments::{CreateEnvironmentRequest, UpdateEnvironmentRequest};
+pub use error::ApiError;
+pub use response::{ApiResponse, Page, Pagination};
+pub use rollouts::SetRolloutPlanRequest;
+pub use state::{AppState, AutoRollbackPolicy, UploadLimits};
+pub use targeting::DryRunResponse;
+pub use telemetry::TelemetryReportRequest;
+pub use update_check::{UpdateCheckRequest, UpdateCheckResponse, UpdateInfo};
+pub use uploads::{ChunkUploadResponse, UploadSessionResponse};
+
+use axum::{Router, middleware};
+
+use crate::database::TokenScope;
+
+/// Build the versioned API router, to be nested under `/api/v1`
+///
+/// Management routes require a credential holding the scope of their group,
+/// and creating or listing applications requires the server admin key;
+/// update checks and telemetry reports authenticate through their request
+/// body, and downloads are public.
+pub fn router(state: AppState) -> Router {
+    let bundle_routes = Router::new()
+        .merge(bundles::routes())
+        .merge(uploads::routes())
+        .merge(assets::routes())
+        .route_layer(middleware::from_fn_with_state(
+            TokenScope::BundlesWrite,
+            auth::require_scope,
+        ));
+    let deployment_routes = Router::new()
+        .merge(deployments::routes())
+        .merge(rollouts::routes())
+        .merge(targeting::routes())
+        .merge(telemetry::routes())
+        .route_layer(middleware::from_fn_with_state(
+            TokenScope::DeploymentsWrite,
+            auth::require_scope,
+        ));
+    let admin_routes = Router::new()
+        .merge(applications::routes())
+        .merge(environments::routes())
+        .merge(access::routes())
+        .route_layer(middleware::from_fn_with_state(
+            TokenScope::Admin,
+            auth::require_scope,
+        ));
+    let authenticated = Router::new()
+        .merge(bundle_routes)
+        .merge(deployment_routes)
+        .merge(admin_routes)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_credentials,
+        ));
+    let server_admin_routes = applications::collection_routes().route_layer(
+        middleware::from_fn_with_state(state.clone(), auth::require_server_admin),
+    );
+
+    Router::new()
+        .merge(authenticated)
+        .merge(server_admin_routes)
+        .merge(downloads::routes())
+        .merge(update_check::routes())
+        .merge(telemetry::report_routes())
+        .with_state(state)
+}
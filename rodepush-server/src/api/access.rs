@@ -0,0 +1,257 @@
+//! Access token and membership endpoints
+//!
+//! Tokens and members of an application are managed under
+//! `/apps/{app_id}/tokens` and `/apps/{app_id}/members`; every route requires
+//! the `admin` scope. A token is only returned in plaintext by the request
+//! creating it.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, put},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::AuthenticatedApplication, error::ApiError, response::ApiResponse, state::AppState,
+};
+use crate::database::{
+    AccessToken, AccessTokenId, ApplicationMember, MemberRole, TokenScope, generate_access_token,
+};
+
+/// Access token and membership routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/apps/{app_id}/tokens", get(list_tokens).post(create_token))
+        .route("/apps/{app_id}/tokens/{token_id}", delete(revoke_token))
+        .route("/apps/{app_id}/members", get(list_members))
+        .route(
+            "/apps/{app_id}/members/{member}",
+            put(set_member).delete(remove_member),
+        )
+}
+
+/// Request body for issuing an access token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTokenRequest {
+    /// Human readable name (e.g., "CI uploads")
+    pub name: String,
+    /// Scopes to grant
+    pub scopes: Vec<TokenScope>,
+    /// Lifetime of the token in seconds; tokens without one do not expire
+    #[serde(default)]
+    pub expires_in_seconds: Option<u64>,
+    /// Member to issue the token to, whose role then caps the token's scopes
+    #[serde(default)]
+    pub member: Option<String>,
+}
+
+/// An access token as listed by the API, without its secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessTokenInfo {
+    /// Access token ID
+    pub id: AccessTokenId,
+    /// Human readable name
+    pub name: String,
+    /// Granted scopes
+    pub scopes: Vec<TokenScope>,
+    /// Member the token was issued to
+    pub member: Option<String>,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Expiry time
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Revocation time
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<&AccessToken> for AccessTokenInfo {
+    fn from(token: &AccessToken) -> Self {
+        Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            member: token.member.clone(),
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            revoked_at: token.revoked_at,
+        }
+    }
+}
+
+/// Response body of issuing an access token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedTokenResponse {
+    /// The token itself; it cannot be retrieved again
+    pub token: String,
+    /// Description of the token
+    #[serde(flatten)]
+    pub info: AccessTokenInfo,
+}
+
+/// Request body for adding a member or changing its role
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetMemberRequest {
+    /// Role to give the member
+    pub role: MemberRole,
+}
+
+impl CreateTokenRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.name.trim().is_empty() {
+            return Err(ApiError::bad_request("Token name must not be empty"));
+        }
+        if self.scopes.is_empty() {
+            return Err(ApiError::bad_request("At least one scope is required"));
+        }
+        if self.expires_in_seconds == Some(0) {
+            return Err(ApiError::bad_request("expires_in_seconds must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Issue an access token
+async fn create_token(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<CreatedTokenResponse>>), ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    request.validate()?;
+
+    let secret = generate_access_token();
+    let mut token = AccessToken::new(
+        application_id.clone(),
+        request.name.trim().to_string(),
+        &secret,
+        request.scopes,
+    );
+    if let Some(seconds) = request.expires_in_seconds {
+        let lifetime = chrono::Duration::seconds(seconds.min(i64::MAX as u64) as i64);
+        let expires_at = Utc::now()
+            .checked_add_signed(lifetime)
+            .ok_or_else(|| ApiError::bad_request("expires_in_seconds is too large"))?;
+        token = token.with_expiry(expires_at);
+    }
+    if let Some(member) = request.member {
+        if state
+            .database
+            .get_member(&application_id, &member)
+            .await?
+            .is_none()
+        {
+            return Err(ApiError::bad_request(format!(
+                "{} is not a member of application {}",
+                member, application_id
+            )));
+        }
+        token = token.with_member(member);
+    }
+
+    state.database.create_access_token(&token).await?;
+    let response = CreatedTokenResponse {
+        token: secret,
+        info: AccessTokenInfo::from(&token),
+    };
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(response))))
+}
+
+/// List the access tokens of an application
+async fn list_tokens(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Vec<AccessTokenInfo>>>, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let tokens = state.database.list_access_tokens(&application_id).await?;
+    Ok(Json(ApiResponse::success(
+        tokens.iter().map(AccessTokenInfo::from).collect(),
+    )))
+}
+
+/// Revoke an access token; revoking it again has no effect
+async fn revoke_token(
+    State(state): State<AppState>,
+    Path((app_id, token_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<StatusCode, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let token_id = AccessTokenId::from_string(&token_id)?;
+    let mut token = state
+        .database
+        .get_access_token(&token_id)
+        .await?
+        .filter(|token| token.application_id == application_id)
+        .ok_or_else(|| ApiError::not_found(format!("Access token {} not found", token_id)))?;
+
+    if !token.is_revoked() {
+        token.revoked_at = Some(Utc::now());
+        state.database.revoke_access_token(&token).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the members of an application
+async fn list_members(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Vec<ApplicationMember>>>, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let members = state.database.list_members(&application_id).await?;
+    Ok(Json(ApiResponse::success(members)))
+}
+
+/// Add a member to an application, or change its role
+async fn set_member(
+    State(state): State<AppState>,
+    Path((app_id, member)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<SetMemberRequest>,
+) -> Result<Json<ApiResponse<ApplicationMember>>, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    if member.trim().is_empty() {
+        return Err(ApiError::bad_request("Member must not be empty"));
+    }
+
+    let member = match state.database.get_member(&application_id, &member).await? {
+        Some(mut existing) => {
+            existing.role = request.role;
+            existing.updated_at = Utc::now();
+            existing
+        }
+        None => ApplicationMember::new(application_id, member, request.role),
+    };
+    state.database.upsert_member(&member).await?;
+    Ok(Json(ApiResponse::success(member)))
+}
+
+/// Remove a member; tokens issued to it stop granting anything
+async fn remove_member(
+    State(state): State<AppState>,
+    Path((app_id, member)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<StatusCode, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    if state
+        .database
+        .get_member(&application_id, &member)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::not_found(format!(
+            "{} is not a member of application {}",
+            member, application_id
+        )));
+    }
+    state
+        .database
+        .delete_member(&application_id, &member)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,272 @@
+//! Application management endpoints
+//!
+//! Applications are created and listed under `/apps` with the server admin
+//! key; the API key of a new application is only returned by the request
+//! creating it, and the application starts with the default staging and
+//! production environments. A single application is read, updated and deleted under
+//! `/apps/{app_id}`, which requires the `admin` scope. Deleting an
+//! application deletes its bundles, deployments, environments and access
+//! tokens as well.
+
+use std::collections::HashMap;
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::AuthenticatedApplication,
+    environments::DEFAULT_ENVIRONMENTS,
+    error::ApiError,
+    response::{ApiResponse, Page, Pagination},
+    state::AppState,
+};
+use crate::database::{Application, ApplicationId, Environment, generate_api_key};
+
+/// Routes creating and listing applications, relative to the API root
+pub fn collection_routes() -> Router<AppState> {
+    Router::new().route("/apps", post(create_application).get(list_applications))
+}
+
+/// Routes managing a single application, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new().route(
+        "/apps/{app_id}",
+        get(get_application)
+            .patch(update_application)
+            .delete(delete_application),
+    )
+}
+
+/// An application as returned by the API, without its API key hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationInfo {
+    /// Application ID
+    pub id: ApplicationId,
+    /// Application name
+    pub name: String,
+    /// Application description
+    pub description: Option<String>,
+    /// Application owner
+    pub owner: Option<String>,
+    /// Application settings
+    pub settings: HashMap<String, serde_json::Value>,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Last update timestamp
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&Application> for ApplicationInfo {
+    fn from(application: &Application) -> Self {
+        Self {
+            id: application.id.clone(),
+            name: application.name.clone(),
+            description: application.description.clone(),
+            owner: application.owner.clone(),
+            settings: application.settings.clone(),
+            created_at: application.created_at,
+            updated_at: application.updated_at,
+        }
+    }
+}
+
+/// Response body of creating an application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedApplicationResponse {
+    /// API key of the application; it cannot be retrieved again
+    pub api_key: String,
+    /// The created application
+    #[serde(flatten)]
+    pub application: ApplicationInfo,
+}
+
+/// Request body for creating an application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApplicationRequest {
+    /// Application name
+    pub name: String,
+    /// Application description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Application owner
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Initial application settings
+    #[serde(default)]
+    pub settings: HashMap<String, serde_json::Value>,
+}
+
+impl CreateApplicationRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        validate_name(&self.name)
+    }
+
+    /// Build the application described by the request, authenticated by `api_key`
+    pub fn into_application(self, api_key: String) -> Application {
+        let mut application = Application::new(self.name.trim().to_string(), api_key);
+        application.description = non_empty(self.description);
+        application.owner = non_empty(self.owner);
+        application.settings = self.settings;
+        application
+    }
+}
+
+/// Request body for updating an application
+///
+/// Omitted fields are left unchanged; an empty description or owner clears
+/// it. Settings are merged into the existing ones, and a `null` value removes
+/// its key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateApplicationRequest {
+    /// New application name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// New application description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// New application owner
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Settings to set or, when `null`, remove
+    #[serde(default)]
+    pub settings: HashMap<String, serde_json::Value>,
+}
+
+impl UpdateApplicationRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        match &self.name {
+            Some(name) => validate_name(name),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply the update to `application`
+    pub fn apply(self, application: &mut Application) {
+        if let Some(name) = self.name {
+            application.name = name.trim().to_string();
+        }
+        if let Some(description) = self.description {
+            application.description = non_empty(Some(description));
+        }
+        if let Some(owner) = self.owner {
+            application.owner = non_empty(Some(owner));
+        }
+        for (key, value) in self.settings {
+            if value.is_null() {
+                application.settings.remove(&key);
+            } else {
+                application.settings.insert(key, value);
+            }
+        }
+        application.updated_at = Utc::now();
+    }
+}
+
+/// Reject empty application names
+fn validate_name(name: &str) -> Result<(), ApiError> {
+    if name.trim().is_empty() {
+        return Err(ApiError::bad_request("Application name must not be empty"));
+    }
+    Ok(())
+}
+
+/// Trim `value`, mapping blank strings to `None`
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Create an application with a freshly generated API key
+async fn create_application(
+    State(state): State<AppState>,
+    Json(request): Json<CreateApplicationRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<CreatedApplicationResponse>>), ApiError> {
+    request.validate()?;
+
+    let api_key = generate_api_key();
+    let application = request.into_application(api_key.clone());
+    let environments: Vec<Environment> = DEFAULT_ENVIRONMENTS
+        .iter()
+        .map(|name| Environment::new(application.id.clone(), name.to_string()))
+        .collect();
+    state
+        .database
+        .create_application_with_environments(&application, &environments)
+        .await?;
+    tracing::info!(
+        "Created application {} ({})",
+        application.name,
+        application.id
+    );
+
+    let response = CreatedApplicationResponse {
+        api_key,
+        application: ApplicationInfo::from(&application),
+    };
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(response))))
+}
+
+/// List all applications
+async fn list_applications(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ApiResponse<Page<ApplicationInfo>>>, ApiError> {
+    let applications = state
+        .database
+        .list_applications(pagination.limit(), pagination.offset())
+        .await?;
+    Ok(Json(ApiResponse::success(Page::new(
+        applications.iter().map(ApplicationInfo::from).collect(),
+        &pagination,
+    ))))
+}
+
+/// Get the authenticated application
+async fn get_application(
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<ApplicationInfo>>, ApiError> {
+    auth.authorize(&app_id)?;
+    Ok(Json(ApiResponse::success(ApplicationInfo::from(
+        &auth.application,
+    ))))
+}
+
+/// Update the description, owner or settings of the authenticated application
+async fn update_application(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<UpdateApplicationRequest>,
+) -> Result<Json<ApiResponse<ApplicationInfo>>, ApiError> {
+    auth.authorize(&app_id)?;
+    request.validate()?;
+
+    let mut application = auth.application;
+    request.apply(&mut application);
+    state.database.update_application(&application).await?;
+    Ok(Json(ApiResponse::success(ApplicationInfo::from(
+        &application,
+    ))))
+}
+
+/// Delete the authenticated application and everything belonging to it
+async fn delete_application(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+) -> Result<StatusCode, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    state.database.delete_application(&application_id).await?;
+    tracing::info!("Deleted application {}", application_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,189 @@
+//! Asset package upload endpoint
+//!
+//! `PUT /assets/compressed/{collection_id}` takes a `multipart/form-data`
+//! body. The first part, `metadata`, holds the JSON [`AssetCollection`]; it
+//! is followed by a `package` part holding the zstd-compressed tar archive
+//! built by [`AssetCompressor::compress_collection`]. Collection ids are
+//! derived from the collection content, so the metadata must hash to the id
+//! in the path and the package must hold exactly the files it describes.
+//! The package is then served by the asset package download endpoint.
+
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    http::StatusCode,
+    routing::put,
+};
+use rodepush_core::storage::{Storage, StorageKey};
+use rodepush_core::{
+    AssetCollection, AssetCollectionId, AssetCompressor, CompressedAssetCollection, CompressionType,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    bundles::METADATA_PART,
+    downloads::asset_package_storage_key,
+    error::ApiError,
+    response::ApiResponse,
+    state::{AppState, UploadLimits},
+};
+
+/// Name of the multipart part carrying the asset package
+pub const PACKAGE_PART: &str = "package";
+
+/// Asset routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new().route(
+        "/assets/compressed/{collection_id}",
+        // Limits are enforced while reading, against the upload limits
+        put(upload_asset_package).layer(DefaultBodyLimit::disable()),
+    )
+}
+
+/// Response body of an asset package upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetPackageResponse {
+    /// Content-derived ID of the collection
+    pub collection_id: String,
+    /// Number of assets in the collection
+    pub assets_count: usize,
+    /// Total size of the assets in bytes
+    pub total_size: u64,
+    /// Size of the stored package in bytes
+    pub package_size: u64,
+    /// Whether this upload stored a new package, as opposed to matching an existing one
+    pub created: bool,
+}
+
+/// Upload the package of an asset collection
+async fn upload_asset_package(
+    State(state): State<AppState>,
+    Path(collection_id): Path<String>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<ApiResponse<AssetPackageResponse>>), ApiError> {
+    let collection_id = uuid::Uuid::parse_str(&collection_id)
+        .map(|uuid| AssetCollectionId::from_string(uuid.to_string()))
+        .map_err(|e| ApiError::bad_request(format!("Invalid asset collection id: {}", e)))?;
+    let limits = &state.upload_limits;
+
+    let metadata = read_part(&mut multipart, METADATA_PART, limits.max_metadata_size).await?;
+    let mut collection: AssetCollection = serde_json::from_slice(&metadata)
+        .map_err(|e| ApiError::bad_request(format!("Invalid asset collection: {}", e)))?;
+    if collection.content_id() != collection_id {
+        return Err(ApiError::unprocessable(format!(
+            "Asset collection metadata does not match collection {}",
+            collection_id.0
+        )));
+    }
+    collection.id = collection_id.clone();
+    collection.total_size = collection.assets.values().map(|asset| asset.size).sum();
+
+    // Packages are content-addressed, so a stored one is always identical
+    let key = asset_package_storage_key(&collection_id);
+    if let Ok(package_size) = state.storage.object_size(&key).await {
+        let response = AssetPackageResponse::new(&collection, package_size, false);
+        return Ok((StatusCode::OK, Json(ApiResponse::success(response))));
+    }
+
+    let package = read_part(&mut multipart, PACKAGE_PART, package_limit(limits)).await?;
+    let package = verify_package(package, collection.clone()).await?;
+
+    let package_size = store_package(state.storage.as_ref(), &key, &package.data).await?;
+    state.storage.store_asset_collection(&collection).await?;
+    tracing::info!(
+        "Stored asset package {} ({} assets, {} bytes)",
+        collection_id.0,
+        collection.len(),
+        package_size
+    );
+
+    let response = AssetPackageResponse::new(&collection, package_size, true);
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(response))))
+}
+
+impl AssetPackageResponse {
+    fn new(collection: &AssetCollection, package_size: u64, created: bool) -> Self {
+        Self {
+            collection_id: collection.id.0.clone(),
+            assets_count: collection.len(),
+            total_size: collection.total_size,
+            package_size,
+            created,
+        }
+    }
+}
+
+/// Largest accepted asset package, the same as the largest bundle
+pub fn package_limit(limits: &UploadLimits) -> usize {
+    usize::try_from(limits.max_bundle_size).unwrap_or(usize::MAX)
+}
+
+/// Read the next multipart part, which must be called `name`
+async fn read_part(
+    multipart: &mut Multipart,
+    name: &str,
+    limit: usize,
+) -> Result<Vec<u8>, ApiError> {
+    let mut field = multipart
+        .next_field()
+        .await?
+        .ok_or_else(|| ApiError::bad_request(format!("Missing {} part", name)))?;
+    if field.name() != Some(name) {
+        return Err(ApiError::bad_request(format!(
+            "Expected the `{}` part",
+            name
+        )));
+    }
+
+    let mut data = Vec::new();
+    while let Some(bytes) = field.chunk().await? {
+        if data.len() + bytes.len() > limit {
+            return Err(ApiError::payload_too_large(format!(
+                "The {} part exceeds {} bytes",
+                name, limit
+            )));
+        }
+        data.extend_from_slice(&bytes);
+    }
+    Ok(data)
+}
+
+/// Check that a package holds exactly the files of `collection`
+///
+/// Decompression is CPU-bound, so it runs on the blocking thread pool.
+pub async fn verify_package(
+    data: Vec<u8>,
+    collection: AssetCollection,
+) -> Result<CompressedAssetCollection, ApiError> {
+    tokio::task::spawn_blocking(move || {
+        let package = CompressedAssetCollection {
+            uncompressed_size: collection.total_size,
+            compressed_size: data.len() as u64,
+            data,
+            compression_type: CompressionType::Zstd,
+        };
+        AssetCompressor::verify_collection(&package, &collection)?;
+        Ok::<_, ApiError>(package)
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Asset package verification panicked: {}", e);
+        ApiError::internal("Internal server error")
+    })?
+}
+
+/// Write a verified package to storage, returning its size
+async fn store_package(
+    storage: &dyn Storage,
+    key: &StorageKey,
+    data: &[u8],
+) -> Result<u64, ApiError> {
+    let mut writer = storage.create_object(key).await?;
+    if let Err(e) = writer.write(data).await {
+        if let Err(abort_error) = writer.abort().await {
+            tracing::warn!("Failed to discard asset package: {}", abort_error);
+        }
+        return Err(e.into());
+    }
+    Ok(writer.commit().await?)
+}
@@ -0,0 +1,351 @@
+//! API key and access token authentication
+//!
+//! Management routes require a credential, sent either as a bearer token in
+//! `Authorization` or in the `X-API-Key` header. Three kinds of credentials
+//! are accepted: the API key of an application, which grants every scope;
+//! scoped [`AccessToken`]s, whose scopes are further limited by the role of
+//! the member they were issued to; and the server admin key, which acts as
+//! the API key of every application and alone may create and list
+//! applications (see [`require_server_admin`]). The [`require_credentials`] middleware
+//! resolves the credential and attaches it to the request as an
+//! [`AuthenticatedApplication`]; [`require_scope`] then rejects requests
+//! lacking the scope a route group needs, and handlers check that they only
+//! act on the authenticated application.
+
+use axum::{
+    extract::{FromRequestParts, RawPathParams, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rodepush_core::{AuthError, secure_compare};
+
+use crate::api::{error::ApiError, state::AppState};
+use crate::database::{
+    ACCESS_TOKEN_PREFIX, AccessToken, AccessTokenId, Application, ApplicationId, MemberRole,
+    TokenScope, hash_api_key,
+};
+
+/// Header carrying an API key as an alternative to `Authorization: Bearer`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Application a request authenticated as, with the scopes it may exercise
+#[derive(Debug, Clone)]
+pub struct AuthenticatedApplication {
+    /// Application the credential belongs to
+    pub application: Application,
+    /// Scopes granted to the request
+    pub scopes: Vec<TokenScope>,
+    /// Access token used, or `None` for the application API key
+    pub token_id: Option<AccessTokenId>,
+}
+
+impl AuthenticatedApplication {
+    /// Authenticated by the API key of the application or the server admin
+    /// key, which grant every scope
+    pub fn with_api_key(application: Application) -> Self {
+        Self {
+            application,
+            scopes: vec![TokenScope::Admin],
+            token_id: None,
+        }
+    }
+
+    /// Authenticated by an access token
+    ///
+    /// `role` is the current role of the member the token was issued to; it is
+    /// ignored for tokens not issued to a member.
+    pub fn with_token(
+        application: Application,
+        token: &AccessToken,
+        role: Option<MemberRole>,
+    ) -> Self {
+        Self {
+            application,
+            scopes: effective_scopes(token, role),
+            token_id: Some(token.id.clone()),
+        }
+    }
+
+    /// Who made the request, as recorded in audit trails
+    pub fn actor(&self) -> String {
+        match &self.token_id {
+            Some(token_id) => format!("token:{}", token_id),
+            None => "api_key".to_string(),
+        }
+    }
+
+    /// Check whether the request may perform operations requiring `scope`
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.iter().any(|granted| granted.grants(scope))
+    }
+
+    /// Fail with 403 unless the request holds `scope`
+    pub fn require_scope(&self, scope: TokenScope) -> Result<(), ApiError> {
+        if !self.has_scope(scope) {
+            return Err(AuthError::InsufficientPermissions {
+                operation: scope.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Check that the request may act on the application `app_id`
+    pub fn authorize(&self, app_id: &str) -> Result<ApplicationId, ApiError> {
+        let application_id = ApplicationId::from_string(app_id)?;
+        if application_id != self.application.id {
+            return Err(AuthError::InsufficientPermissions {
+                operation: format!("access application {}", application_id),
+            }
+            .into());
+        }
+        Ok(application_id)
+    }
+}
+
+impl<S> FromRequestParts<S> for AuthenticatedApplication
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Only present on routes behind `require_credentials`
+        parts
+            .extensions
+            .get::<Self>()
+            .cloned()
+            .ok_or_else(|| AuthError::MissingAuth.into())
+    }
+}
+
+/// Scopes an access token grants, capped by the role of the member it was issued to
+///
+/// A token issued to someone who is no longer a member grants nothing.
+pub fn effective_scopes(token: &AccessToken, role: Option<MemberRole>) -> Vec<TokenScope> {
+    TokenScope::ALL
+        .into_iter()
+        .filter(|scope| token.grants(*scope))
+        .filter(|scope| match (&token.member, role) {
+            (None, _) => true,
+            (Some(_), Some(role)) => role.grants(*scope),
+            (Some(_), None) => false,
+        })
+        .collect()
+}
+
+/// Reject revoked and expired access tokens
+pub fn check_token(token: &AccessToken) -> Result<(), AuthError> {
+    if let Some(revoked_at) = token.revoked_at {
+        return Err(AuthError::TokenRevoked {
+            revoked_at: revoked_at.to_rfc3339(),
+        });
+    }
+    match token.expires_at {
+        Some(expires_at) if token.is_expired() => Err(AuthError::TokenExpired {
+            expired_at: expires_at.to_rfc3339(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Extract the API key of a request
+///
+/// A bearer token in `Authorization` takes precedence over `X-API-Key`. Other
+/// authorization schemes and empty keys are rejected.
+pub fn extract_api_key(headers: &HeaderMap) -> Result<&str, AuthError> {
+    let key = if let Some(value) = headers.get(header::AUTHORIZATION) {
+        let value = value.to_str().map_err(|_| AuthError::InvalidApiKey)?;
+        let (scheme, token) = value
+            .trim()
+            .split_once(' ')
+            .ok_or(AuthError::InvalidApiKey)?;
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            return Err(AuthError::InvalidApiKey);
+        }
+        token
+    } else if let Some(value) = headers.get(API_KEY_HEADER) {
+        value.to_str().map_err(|_| AuthError::InvalidApiKey)?
+    } else {
+        return Err(AuthError::MissingAuth);
+    };
+
+    match key.trim() {
+        "" => Err(AuthError::InvalidApiKey),
+        key => Ok(key),
+    }
+}
+
+/// Resolve an application API key or access token
+pub async fn authenticate(
+    state: &AppState,
+    credential: &str,
+) -> Result<AuthenticatedApplication, ApiError> {
+    if !credential.starts_with(ACCESS_TOKEN_PREFIX) {
+        let application = state
+            .database
+            .get_application_by_api_key(credential)
+            .await?
+            .ok_or(AuthError::InvalidApiKey)?;
+        return Ok(AuthenticatedApplication::with_api_key(application));
+    }
+
+    let token = state
+        .database
+        .get_access_token_by_token(credential)
+        .await?
+        .ok_or(AuthError::InvalidApiKey)?;
+    check_token(&token)?;
+
+    let application = state
+        .database
+        .get_application(&token.application_id)
+        .await?
+        .ok_or(AuthError::InvalidApiKey)?;
+    let role = match &token.member {
+        Some(member) => state
+            .database
+            .get_member(&application.id, member)
+            .await?
+            .map(|member| member.role),
+        None => None,
+    };
+    Ok(AuthenticatedApplication::with_token(
+        application,
+        &token,
+        role,
+    ))
+}
+
+/// Resolve the application of a mobile client
+///
+/// Clients identify themselves by the deployment key of the environment
+/// they follow or, without one, by an app key with the `updates:read` scope.
+/// Returns the application and, for a deployment key, the environment it
+/// belongs to.
+pub async fn authenticate_client(
+    state: &AppState,
+    deployment_key: Option<&str>,
+    app_key: &str,
+) -> Result<(ApplicationId, Option<String>), ApiError> {
+    match deployment_key.map(str::trim).filter(|key| !key.is_empty()) {
+        Some(deployment_key) => {
+            let environment = state
+                .database
+                .get_environment_by_deployment_key(deployment_key)
+                .await?
+                .ok_or(AuthError::InvalidApiKey)?;
+            Ok((environment.application_id, Some(environment.name)))
+        }
+        None => {
+            let authenticated = authenticate(state, app_key).await?;
+            authenticated.require_scope(TokenScope::UpdatesRead)?;
+            Ok((authenticated.application.id, None))
+        }
+    }
+}
+
+/// Check whether `credential` is the server admin key
+pub fn is_admin_key(state: &AppState, credential: &str) -> bool {
+    state
+        .admin_key_hash
+        .as_deref()
+        .is_some_and(|admin_key_hash| secure_compare(&hash_api_key(credential), admin_key_hash))
+}
+
+/// Act with the server admin key on the application named by the `app_id` path parameter
+async fn authenticate_admin(
+    state: &AppState,
+    path_params: &RawPathParams,
+) -> Result<AuthenticatedApplication, ApiError> {
+    let app_id = path_params
+        .iter()
+        .find_map(|(name, value)| (name == "app_id").then_some(value))
+        .ok_or(AuthError::InvalidApiKey)?;
+    let application_id = ApplicationId::from_string(app_id)?;
+    let application = state
+        .database
+        .get_application(&application_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Application {} not found", application_id)))?;
+    Ok(AuthenticatedApplication::with_api_key(application))
+}
+
+/// Middleware authenticating requests by API key or access token
+pub async fn require_credentials(
+    State(state): State<AppState>,
+    path_params: RawPathParams,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let authenticated = match extract_api_key(request.headers()) {
+        Ok(credential) if is_admin_key(&state, credential) => {
+            authenticate_admin(&state, &path_params).await
+        }
+        Ok(credential) => authenticate(&state, credential).await,
+        Err(error) => Err(error.into()),
+    };
+    let authenticated = match authenticated {
+        Ok(authenticated) => authenticated,
+        Err(error) => return unauthorized(error),
+    };
+
+    tracing::debug!(
+        "Request authenticated as application {}",
+        authenticated.application.id
+    );
+    request.extensions_mut().insert(authenticated);
+    next.run(request).await
+}
+
+/// Middleware rejecting authenticated requests that lack a scope
+///
+/// Must run inside [`require_credentials`].
+pub async fn require_scope(
+    State(scope): State<TokenScope>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let granted = match request.extensions().get::<AuthenticatedApplication>() {
+        Some(authenticated) => authenticated.require_scope(scope),
+        None => Err(AuthError::MissingAuth.into()),
+    };
+    match granted {
+        Ok(()) => next.run(request).await,
+        Err(error) => unauthorized(error),
+    }
+}
+
+/// Middleware admitting only requests made with the server admin key
+///
+/// Fails with 403 when the server has no admin key configured.
+pub async fn require_server_admin(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let admitted = match extract_api_key(request.headers()) {
+        Ok(_) if state.admin_key_hash.is_none() => Err(AuthError::InsufficientPermissions {
+            operation: "manage applications".to_string(),
+        }),
+        Ok(credential) if is_admin_key(&state, credential) => Ok(()),
+        Ok(_) => Err(AuthError::InvalidApiKey),
+        Err(error) => Err(error),
+    };
+    match admitted {
+        Ok(()) => next.run(request).await,
+        Err(error) => unauthorized(error.into()),
+    }
+}
+
+/// Render an authentication failure, challenging the client on 401
+fn unauthorized(error: ApiError) -> Response {
+    let mut response = error.into_response();
+    if response.status() == StatusCode::UNAUTHORIZED {
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    }
+    response
+}
@@ -0,0 +1,393 @@
+//! Bundle upload endpoint
+//!
+//! `POST /apps/{app_id}/bundles` takes a `multipart/form-data` body. The first
+//! part, `metadata`, holds the JSON [`BundleMetadata`] of the bundle; it is
+//! followed by one `chunk` part per entry of `metadata.chunks`, in order. Chunk
+//! data is streamed straight into a storage object while sizes and checksums
+//! are checked, so an upload is never held in memory as a whole.
+
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    http::StatusCode,
+    routing::post,
+};
+use rodepush_core::storage::{ObjectWriter, Storage, StorageKey};
+use rodepush_core::{
+    BundleError, BundleId, BundleMetadata, HashAlgorithm, secure_compare, validate_hash_format,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::AuthenticatedApplication,
+    error::ApiError,
+    response::ApiResponse,
+    state::{AppState, UploadLimits},
+};
+use crate::database::{ApplicationId, Bundle};
+
+/// Name of the multipart part carrying the bundle metadata
+pub const METADATA_PART: &str = "metadata";
+
+/// Name of the multipart parts carrying chunk data
+pub const CHUNK_PART: &str = "chunk";
+
+/// Key under [`Bundle::metadata`] holding the uploaded [`BundleMetadata`]
+pub const BUNDLE_METADATA_KEY: &str = "bundle_metadata";
+
+/// Bundle routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new().route(
+        "/apps/{app_id}/bundles",
+        // Limits are enforced while streaming, against the declared metadata
+        post(upload_bundle).layer(DefaultBodyLimit::disable()),
+    )
+}
+
+/// Response body of a bundle upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleUploadResponse {
+    /// ID of the stored bundle
+    pub bundle_id: BundleId,
+    /// Storage key of the bundle data
+    pub storage_key: String,
+    /// Size of the bundle data in bytes
+    pub size_bytes: u64,
+    /// Checksum of the bundle data
+    pub checksum: String,
+    /// Whether this upload stored a new bundle, as opposed to matching an existing one
+    pub created: bool,
+}
+
+impl BundleUploadResponse {
+    pub(crate) fn from_bundle(bundle: &Bundle, created: bool) -> Self {
+        Self {
+            bundle_id: bundle.id.clone(),
+            storage_key: bundle.storage_key.clone(),
+            size_bytes: bundle.size_bytes,
+            checksum: bundle.checksum.clone(),
+            created,
+        }
+    }
+}
+
+/// Bundle whose chunks were streamed into storage and verified
+#[derive(Debug, Clone)]
+pub struct ReceivedBundle {
+    /// Metadata sent with the upload
+    pub metadata: BundleMetadata,
+    /// Storage key the chunk data was committed under
+    pub storage_key: StorageKey,
+    /// Number of bytes stored
+    pub size_bytes: u64,
+}
+
+/// Storage key for one upload of a bundle
+///
+/// Each upload gets its own object so that a failed or concurrent upload of
+/// the same bundle never removes data another request committed.
+pub fn bundle_storage_key(application_id: &ApplicationId, bundle_id: &BundleId) -> StorageKey {
+    StorageKey::new(format!(
+        "bundles/{}/{}/{}.bundle",
+        application_id,
+        bundle_id,
+        uuid::Uuid::new_v4()
+    ))
+}
+
+/// Upload a bundle for an application
+async fn upload_bundle(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<ApiResponse<BundleUploadResponse>>), ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let metadata = read_metadata_part(&mut multipart, &state.upload_limits).await?;
+
+    // Re-uploads of an identical bundle are answered with the stored one
+    if let Some(existing) = find_stored_bundle(&state, &application_id, &metadata).await? {
+        tracing::info!("Bundle {} already stored, skipping upload", existing.id);
+        let response = BundleUploadResponse::from_bundle(&existing, false);
+        return Ok((StatusCode::OK, Json(ApiResponse::success(response))));
+    }
+
+    let received = receive_bundle(
+        &mut multipart,
+        &application_id,
+        metadata,
+        state.storage.as_ref(),
+    )
+    .await?;
+    let bundle = record_bundle(&state, application_id, received).await?;
+
+    let response = BundleUploadResponse::from_bundle(&bundle, true);
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(response))))
+}
+
+/// Find an already stored bundle identical to the one described by `metadata`
+///
+/// A stored bundle with the same id but different content is a conflict.
+pub(crate) async fn find_stored_bundle(
+    state: &AppState,
+    application_id: &ApplicationId,
+    metadata: &BundleMetadata,
+) -> Result<Option<Bundle>, ApiError> {
+    match state.database.get_bundle(&metadata.id).await? {
+        Some(existing)
+            if existing.application_id == *application_id
+                && existing.checksum == metadata.checksum =>
+        {
+            Ok(Some(existing))
+        }
+        Some(_) => Err(ApiError::conflict(format!(
+            "Bundle {} already exists with different content",
+            metadata.id
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Record a received bundle in the database
+///
+/// The stored data is removed again if the bundle cannot be recorded.
+pub(crate) async fn record_bundle(
+    state: &AppState,
+    application_id: ApplicationId,
+    received: ReceivedBundle,
+) -> Result<Bundle, ApiError> {
+    let bundle = Bundle::new(
+        application_id,
+        received.metadata.version.to_string(),
+        received.metadata.platform,
+        received.storage_key.as_str().to_string(),
+        received.size_bytes,
+        received.metadata.checksum.clone(),
+    )
+    .with_id(received.metadata.id.clone())
+    .with_metadata(
+        BUNDLE_METADATA_KEY.to_string(),
+        serde_json::to_value(&received.metadata).map_err(rodepush_core::RodePushError::from)?,
+    );
+
+    if let Err(e) = state.database.create_bundle(&bundle).await {
+        if let Err(delete_error) = state.storage.delete(&received.storage_key).await {
+            tracing::warn!(
+                "Failed to remove data of unrecorded bundle {}: {}",
+                bundle.id,
+                delete_error
+            );
+        }
+        return Err(e.into());
+    }
+
+    tracing::info!(
+        "Stored bundle {} ({} bytes) for application {}",
+        bundle.id,
+        bundle.size_bytes,
+        bundle.application_id
+    );
+    Ok(bundle)
+}
+
+/// Read and validate the leading `metadata` part of an upload
+pub async fn read_metadata_part(
+    multipart: &mut Multipart,
+    limits: &UploadLimits,
+) -> Result<BundleMetadata, ApiError> {
+    let mut field = multipart
+        .next_field()
+        .await?
+        .ok_or_else(|| ApiError::bad_request("Missing metadata part"))?;
+    if field.name() != Some(METADATA_PART) {
+        return Err(ApiError::bad_request(format!(
+            "The first part must be `{}`",
+            METADATA_PART
+        )));
+    }
+
+    let mut data = Vec::new();
+    while let Some(bytes) = field.chunk().await? {
+        if data.len() + bytes.len() > limits.max_metadata_size {
+            return Err(ApiError::payload_too_large(format!(
+                "Bundle metadata exceeds {} bytes",
+                limits.max_metadata_size
+            )));
+        }
+        data.extend_from_slice(&bytes);
+    }
+
+    parse_metadata(&data, limits)
+}
+
+/// Parse and validate a bundle metadata document
+pub(crate) fn parse_metadata(
+    data: &[u8],
+    limits: &UploadLimits,
+) -> Result<BundleMetadata, ApiError> {
+    if data.len() > limits.max_metadata_size {
+        return Err(ApiError::payload_too_large(format!(
+            "Bundle metadata exceeds {} bytes",
+            limits.max_metadata_size
+        )));
+    }
+    let metadata: BundleMetadata = serde_json::from_slice(data)
+        .map_err(|e| ApiError::bad_request(format!("Invalid bundle metadata: {}", e)))?;
+    validate_metadata(&metadata, limits)?;
+    Ok(metadata)
+}
+
+/// Check declared sizes and checksums before any chunk data is accepted
+fn validate_metadata(metadata: &BundleMetadata, limits: &UploadLimits) -> Result<(), ApiError> {
+    metadata.validate()?;
+    if metadata.chunks.is_empty() {
+        return Err(ApiError::bad_request("Bundle has no chunks"));
+    }
+    if metadata.chunks.len() > limits.max_chunks {
+        return Err(ApiError::payload_too_large(format!(
+            "Bundle has {} chunks (max: {})",
+            metadata.chunks.len(),
+            limits.max_chunks
+        )));
+    }
+
+    let algorithm = upload_hash_algorithm(metadata);
+    validate_hash_format(&metadata.checksum, algorithm)?;
+
+    let mut offset = 0u64;
+    for chunk in &metadata.chunks {
+        validate_hash_format(&chunk.checksum, algorithm)?;
+        if chunk.size > limits.max_chunk_size {
+            return Err(BundleError::SizeLimitExceeded {
+                actual_size: chunk.size,
+                limit: limits.max_chunk_size,
+            }
+            .into());
+        }
+        if chunk.offset != offset {
+            return Err(ApiError::bad_request(format!(
+                "Chunk {} starts at offset {}, expected {}",
+                chunk.id, chunk.offset, offset
+            )));
+        }
+        offset += chunk.size;
+    }
+
+    if offset != metadata.size_bytes {
+        return Err(ApiError::bad_request(format!(
+            "Bundle size {} does not match its chunks ({} bytes)",
+            metadata.size_bytes, offset
+        )));
+    }
+    if offset > limits.max_bundle_size {
+        return Err(BundleError::TooLarge {
+            size: offset,
+            max_size: limits.max_bundle_size,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Hash algorithm of the uploaded checksums; bundles built without one use SHA-256
+pub(crate) fn upload_hash_algorithm(metadata: &BundleMetadata) -> HashAlgorithm {
+    metadata.hash_algorithm.unwrap_or(HashAlgorithm::Sha256)
+}
+
+/// Stream the `chunk` parts of an upload into storage
+///
+/// `metadata` must come from [`read_metadata_part`], which checked the
+/// declared sizes against the upload limits; no chunk may grow past its
+/// declared size while streaming. The object is only
+/// committed once every chunk and the whole bundle match their declared sizes
+/// and checksums; on any failure it is discarded.
+pub async fn receive_bundle(
+    multipart: &mut Multipart,
+    application_id: &ApplicationId,
+    metadata: BundleMetadata,
+    storage: &dyn Storage,
+) -> Result<ReceivedBundle, ApiError> {
+    let storage_key = bundle_storage_key(application_id, &metadata.id);
+    let mut writer = storage.create_object(&storage_key).await?;
+
+    if let Err(e) = receive_chunks(multipart, &metadata, writer.as_mut()).await {
+        if let Err(abort_error) = writer.abort().await {
+            tracing::warn!("Failed to discard rejected upload: {}", abort_error);
+        }
+        return Err(e);
+    }
+
+    let size_bytes = writer.commit().await?;
+    Ok(ReceivedBundle {
+        metadata,
+        storage_key,
+        size_bytes,
+    })
+}
+
+async fn receive_chunks(
+    multipart: &mut Multipart,
+    metadata: &BundleMetadata,
+    writer: &mut dyn ObjectWriter,
+) -> Result<(), ApiError> {
+    let algorithm = upload_hash_algorithm(metadata);
+    let mut bundle_hasher = algorithm.hasher();
+
+    for chunk in &metadata.chunks {
+        let mut field = multipart
+            .next_field()
+            .await?
+            .ok_or_else(|| ApiError::bad_request(format!("Missing data for chunk {}", chunk.id)))?;
+        if field.name() != Some(CHUNK_PART) {
+            return Err(ApiError::bad_request(format!(
+                "Expected `{}` part for chunk {}",
+                CHUNK_PART, chunk.id
+            )));
+        }
+        if let Some(file_name) = field.file_name()
+            && file_name != chunk.id
+        {
+            return Err(ApiError::bad_request(format!(
+                "Expected data for chunk {}, got {}",
+                chunk.id, file_name
+            )));
+        }
+
+        let mut chunk_hasher = algorithm.hasher();
+        let mut chunk_size = 0u64;
+        while let Some(data) = field.chunk().await? {
+            chunk_size += data.len() as u64;
+            if chunk_size > chunk.size {
+                return Err(ApiError::bad_request(format!(
+                    "Chunk {} is larger than its declared {} bytes",
+                    chunk.id, chunk.size
+                )));
+            }
+            chunk_hasher.update(&data);
+            bundle_hasher.update(&data);
+            writer.write(&data).await?;
+        }
+
+        if chunk_size != chunk.size {
+            return Err(ApiError::bad_request(format!(
+                "Chunk {} is {} bytes, expected {}",
+                chunk.id, chunk_size, chunk.size
+            )));
+        }
+        let actual = chunk_hasher.finalize();
+        if !secure_compare(&actual, &chunk.checksum) {
+            return Err(BundleError::checksum_mismatch(chunk.checksum.clone(), actual).into());
+        }
+    }
+
+    if multipart.next_field().await?.is_some() {
+        return Err(ApiError::bad_request(
+            "Unexpected part after the last chunk",
+        ));
+    }
+    let actual = bundle_hasher.finalize();
+    if !secure_compare(&actual, &metadata.checksum) {
+        return Err(BundleError::checksum_mismatch(metadata.checksum.clone(), actual).into());
+    }
+    Ok(())
+}
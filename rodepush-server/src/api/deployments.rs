@@ -0,0 +1,579 @@
+//! Deployment management endpoints
+//!
+//! Deployments of an application live under `/apps/{app_id}/deployments`.
+//! Creating one makes its bundle available to clients of the environment
+//! right away and supersedes the deployment serving the same clients there
+//! before; pausing stops serving it without discarding it, and rolling back
+//! withdraws it for good. Either puts the deployment it superseded back in
+//! service. Deployments can only target an existing environment, whose default
+//! rollout applies unless the request chooses one. A deployment created with
+//! a rollout plan starts at the plan's first stage and is rolled out further
+//! by the scheduler in [`crate::api::rollouts`]. Until a deployment reaches
+//! 100%, the one it replaces keeps serving the clients outside its rollout;
+//! targeting rules, managed in [`crate::api::targeting`], restrict it to
+//! some clients. Promoting deploys the bundle of a deployment, typically the
+//! one live in staging, to another environment for the same clients,
+//! provided it suits the bundles that environment already serves. Every
+//! status change is recorded in the deployment's history.
+//! Every route requires the `deployments:write` scope. Actions that the
+//! current status of a deployment does not allow are answered with 409
+//! Conflict.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post, put},
+};
+use rodepush_core::{BundleId, SemanticVersion};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::AuthenticatedApplication,
+    environments::load_environment,
+    error::ApiError,
+    response::{ApiResponse, Page, Pagination},
+    state::AppState,
+};
+use crate::database::{
+    ApplicationId, Bundle, Deployment, DeploymentId, DeploymentStatus, DeploymentTransition,
+    Environment, RolloutPlan, RolloutStep, TargetingRules,
+};
+
+/// Deployment routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/apps/{app_id}/deployments",
+            get(list_deployments).post(create_deployment),
+        )
+        .route(
+            "/apps/{app_id}/deployments/promote",
+            post(promote_deployment),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}",
+            get(get_deployment),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/history",
+            get(get_deployment_history),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/pause",
+            post(pause_deployment),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/resume",
+            post(resume_deployment),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/rollout",
+            put(update_rollout),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/rollback",
+            post(rollback_deployment),
+        )
+}
+
+/// Request body for creating a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateDeploymentRequest {
+    /// Bundle to deploy
+    pub bundle_id: BundleId,
+    /// Environment to deploy to (e.g., "production")
+    pub environment: String,
+    /// Deployment description shown to clients
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Percentage of clients receiving the deployment; the environment's default rollout by default
+    #[serde(default)]
+    pub rollout_percentage: Option<u32>,
+    /// Stages to roll the deployment out in; replaces `rollout_percentage`
+    #[serde(default)]
+    pub rollout_plan: Option<Vec<RolloutStep>>,
+    /// Client attributes the deployment is restricted to; every client by default
+    #[serde(default)]
+    pub targeting: Option<TargetingRules>,
+    /// Whether clients must install the deployment
+    #[serde(default)]
+    pub mandatory: bool,
+}
+
+impl CreateDeploymentRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.environment.trim().is_empty() {
+            return Err(ApiError::bad_request("environment must not be empty"));
+        }
+        if let Some(percentage) = self.rollout_percentage {
+            validate_rollout_percentage(percentage)?;
+        }
+        if let Some(steps) = &self.rollout_plan {
+            if self.rollout_percentage.is_some() {
+                return Err(ApiError::bad_request(
+                    "rollout_percentage and rollout_plan cannot be combined",
+                ));
+            }
+            RolloutPlan::validate_steps(steps)?;
+        }
+        if let Some(rules) = &self.targeting {
+            rules.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Use the default rollout of `environment` unless the request sets one
+    pub fn apply_environment_defaults(&mut self, environment: &Environment) {
+        if self.rollout_percentage.is_none() && self.rollout_plan.is_none() {
+            self.rollout_plan = environment.default_rollout_plan.clone();
+            if self.rollout_plan.is_none() {
+                self.rollout_percentage = Some(environment.default_rollout_percentage);
+            }
+        }
+    }
+
+    /// Build the deployment described by the request
+    ///
+    /// A rollout plan makes the deployment start at its first stage.
+    pub fn into_deployment(self, application_id: ApplicationId) -> Deployment {
+        let rollout_percentage = match &self.rollout_plan {
+            Some(steps) => steps.first().map_or(100, |step| step.percentage),
+            None => self.rollout_percentage.unwrap_or(100),
+        };
+        let mut deployment = Deployment::new(
+            application_id,
+            self.bundle_id,
+            self.environment.trim().to_string(),
+        )
+        .with_rollout_percentage(rollout_percentage)
+        .with_mandatory(self.mandatory);
+        if let Some(description) = self.description {
+            deployment = deployment.with_description(description);
+        }
+        if let Some(rules) = self.targeting.filter(|rules| !rules.is_empty()) {
+            deployment = deployment.with_targeting(rules);
+        }
+        deployment
+    }
+}
+
+/// Request body for promoting a deployment to another environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromoteDeploymentRequest {
+    /// Environment to promote from (e.g., "staging")
+    pub from_environment: String,
+    /// Environment to promote to (e.g., "production")
+    pub to_environment: String,
+    /// Deployment of `from_environment` to promote; its active one by default
+    #[serde(default)]
+    pub deployment_id: Option<DeploymentId>,
+    /// Percentage of clients receiving the new deployment; the target
+    /// environment's default rollout by default
+    #[serde(default)]
+    pub rollout_percentage: Option<u32>,
+    /// Stages to roll the new deployment out in; replaces `rollout_percentage`
+    #[serde(default)]
+    pub rollout_plan: Option<Vec<RolloutStep>>,
+    /// Description of the new deployment; that of the promoted one by default
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Whether clients must install the new deployment; like the promoted one by default
+    #[serde(default)]
+    pub mandatory: Option<bool>,
+}
+
+impl PromoteDeploymentRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let from = self.from_environment.trim();
+        let to = self.to_environment.trim();
+        if from.is_empty() || to.is_empty() {
+            return Err(ApiError::bad_request(
+                "from_environment and to_environment must not be empty",
+            ));
+        }
+        if from == to {
+            return Err(ApiError::bad_request(format!(
+                "Cannot promote a deployment of {} to the same environment",
+                from
+            )));
+        }
+        if let Some(percentage) = self.rollout_percentage {
+            validate_rollout_percentage(percentage)?;
+        }
+        if let Some(steps) = &self.rollout_plan {
+            if self.rollout_percentage.is_some() {
+                return Err(ApiError::bad_request(
+                    "rollout_percentage and rollout_plan cannot be combined",
+                ));
+            }
+            RolloutPlan::validate_steps(steps)?;
+        }
+        Ok(())
+    }
+
+    /// Use the default rollout of the target `environment` unless the request sets one
+    pub fn apply_environment_defaults(&mut self, environment: &Environment) {
+        if self.rollout_percentage.is_none() && self.rollout_plan.is_none() {
+            self.rollout_plan = environment.default_rollout_plan.clone();
+            if self.rollout_plan.is_none() {
+                self.rollout_percentage = Some(environment.default_rollout_percentage);
+            }
+        }
+    }
+
+    /// Build the deployment serving the bundle of `source` in the target environment
+    ///
+    /// The deployment keeps the targeting rules of `source`, so it reaches
+    /// the same clients. Its rollout comes from the request, with a rollout
+    /// plan making it start at the plan's first stage, and is 100% when the
+    /// request sets none.
+    pub fn into_deployment(self, source: &Deployment) -> Deployment {
+        let rollout_percentage = match &self.rollout_plan {
+            Some(steps) => steps.first().map_or(100, |step| step.percentage),
+            None => self.rollout_percentage.unwrap_or(100),
+        };
+        let mut deployment = Deployment::new(
+            source.application_id.clone(),
+            source.bundle_id.clone(),
+            self.to_environment.trim().to_string(),
+        )
+        .with_rollout_percentage(rollout_percentage)
+        .with_mandatory(self.mandatory.unwrap_or(source.is_mandatory()))
+        .with_metadata(
+            "promoted_from".to_string(),
+            serde_json::Value::String(source.id.to_string()),
+        );
+        if let Some(description) = self.description.or_else(|| source.description.clone()) {
+            deployment = deployment.with_description(description);
+        }
+        if let Some(rules) = source.targeting() {
+            deployment = deployment.with_targeting(rules);
+        }
+        deployment
+    }
+}
+
+/// Check that `bundle` suits an environment currently serving `live`
+///
+/// The environment serves each platform and binary release line its live
+/// bundles target. A bundle for another platform or release line is served
+/// next to them. A bundle for a release line already served replaces the
+/// live bundle of that line, so it must not be older than it, which would
+/// downgrade the clients that installed it. The bundle must have a valid
+/// version and not be live there already.
+pub fn check_promotion(
+    bundle: &Bundle,
+    live: &[Bundle],
+    environment: &str,
+) -> Result<(), ApiError> {
+    let version = SemanticVersion::parse(&bundle.version).map_err(|_| {
+        ApiError::unprocessable(format!(
+            "Bundle {} has an invalid version {}",
+            bundle.id, bundle.version
+        ))
+    })?;
+
+    for live in live {
+        if live.id == bundle.id {
+            return Err(ApiError::conflict(format!(
+                "Bundle {} is already deployed to {}",
+                bundle.id, environment
+            )));
+        }
+        if bundle.overlaps(live)
+            && let Ok(live_version) = SemanticVersion::parse(&live.version)
+            && live_version.is_newer_than(&version)
+        {
+            return Err(ApiError::unprocessable(format!(
+                "Bundle version {} is older than version {} served to {} clients in {}",
+                bundle.version, live.version, live.platform, environment
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Request body for changing the rollout percentage of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateRolloutRequest {
+    /// New percentage of clients receiving the deployment
+    pub rollout_percentage: u32,
+}
+
+/// Reject rollout percentages above 100
+pub fn validate_rollout_percentage(percentage: u32) -> Result<(), ApiError> {
+    if percentage > 100 {
+        return Err(ApiError::bad_request(format!(
+            "rollout_percentage must be between 0 and 100, got {}",
+            percentage
+        )));
+    }
+    Ok(())
+}
+
+/// Fail with 409 if the deployment can no longer be changed by hand
+///
+/// Superseded deployments only come back when the deployment that
+/// superseded them is withdrawn.
+pub fn ensure_not_final(deployment: &Deployment, action: &str) -> Result<(), ApiError> {
+    if deployment.status.is_final() || deployment.status == DeploymentStatus::Superseded {
+        return Err(ApiError::conflict(format!(
+            "Cannot {} deployment {} while it is {}",
+            action, deployment.id, deployment.status
+        )));
+    }
+    Ok(())
+}
+
+/// Create a deployment and start serving it
+async fn create_deployment(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    Json(mut request): Json<CreateDeploymentRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Deployment>>), ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    request.validate()?;
+    let environment = load_environment(&state, &auth, &app_id, request.environment.trim()).await?;
+    request.apply_environment_defaults(&environment);
+
+    let steps = request.rollout_plan.clone();
+    let deployment = request.into_deployment(application_id);
+    let plan = steps
+        .map(|steps| RolloutPlan::new(deployment.id.clone(), steps))
+        .transpose()?;
+    let deployment = state
+        .database
+        .deploy_bundle(deployment, plan, Some(auth.actor()))
+        .await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(deployment))))
+}
+
+/// Deploy the bundle of a deployment to another environment
+async fn promote_deployment(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    Json(mut request): Json<PromoteDeploymentRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Deployment>>), ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    request.validate()?;
+    let from = request.from_environment.trim().to_string();
+    let from = from.as_str();
+    let target = load_environment(&state, &auth, &app_id, request.to_environment.trim()).await?;
+    let to = target.name.as_str();
+    request.apply_environment_defaults(&target);
+
+    let source = match &request.deployment_id {
+        Some(deployment_id) => state
+            .database
+            .get_deployment(deployment_id)
+            .await?
+            .filter(|deployment| {
+                deployment.application_id == application_id && deployment.environment == from
+            })
+            .ok_or_else(|| {
+                ApiError::not_found(format!(
+                    "Deployment {} not found in {}",
+                    deployment_id, from
+                ))
+            })?,
+        None => state
+            .database
+            .get_active_deployments(&application_id, from)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ApiError::not_found(format!("No active deployment in {}", from)))?,
+    };
+    if matches!(
+        source.status,
+        DeploymentStatus::RolledBack | DeploymentStatus::Failed
+    ) {
+        return Err(ApiError::conflict(format!(
+            "Cannot promote deployment {} while it is {}",
+            source.id, source.status
+        )));
+    }
+
+    let bundle = state
+        .database
+        .get_bundle(&source.bundle_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Bundle {} not found", source.bundle_id)))?;
+    let mut live = Vec::new();
+    for deployment in state
+        .database
+        .get_active_deployments(&application_id, to)
+        .await?
+    {
+        live.extend(state.database.get_bundle(&deployment.bundle_id).await?);
+    }
+    check_promotion(&bundle, &live, to)?;
+
+    let steps = request.rollout_plan.clone();
+    let deployment = request.into_deployment(&source);
+    let plan = steps
+        .map(|steps| RolloutPlan::new(deployment.id.clone(), steps))
+        .transpose()?;
+    let deployment = state
+        .database
+        .deploy_bundle(deployment, plan, Some(auth.actor()))
+        .await?;
+    tracing::info!(
+        "Promoted deployment {} from {} to {} as {}",
+        source.id,
+        source.environment,
+        deployment.environment,
+        deployment.id
+    );
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(deployment))))
+}
+
+/// List the deployments of an application, newest first
+async fn list_deployments(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    Query(pagination): Query<Pagination>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Page<Deployment>>>, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let deployments = state
+        .database
+        .list_deployments_for_application(&application_id, pagination.limit(), pagination.offset())
+        .await?;
+    Ok(Json(ApiResponse::success(Page::new(
+        deployments,
+        &pagination,
+    ))))
+}
+
+/// Get a single deployment
+async fn get_deployment(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Deployment>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    Ok(Json(ApiResponse::success(deployment)))
+}
+
+/// List the status changes of a deployment, oldest first
+async fn get_deployment_history(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Vec<DeploymentTransition>>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    let history = state
+        .database
+        .get_deployment_history(&deployment.id)
+        .await?;
+    Ok(Json(ApiResponse::success(history)))
+}
+
+/// Stop serving an active deployment
+async fn pause_deployment(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Deployment>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    let actor = auth.actor();
+    let deployment = state
+        .database
+        .transition_deployment(&deployment.id, |deployment| {
+            Ok(deployment.pause()?.with_actor(Some(actor)))
+        })
+        .await?;
+    Ok(Json(ApiResponse::success(deployment)))
+}
+
+/// Serve a paused deployment again
+async fn resume_deployment(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Deployment>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    let actor = auth.actor();
+    let deployment = state
+        .database
+        .transition_deployment(&deployment.id, |deployment| {
+            Ok(deployment.resume()?.with_actor(Some(actor)))
+        })
+        .await?;
+    Ok(Json(ApiResponse::success(deployment)))
+}
+
+/// Change the share of clients receiving a deployment, halting its rollout plan
+async fn update_rollout(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<UpdateRolloutRequest>,
+) -> Result<Json<ApiResponse<Deployment>>, ApiError> {
+    validate_rollout_percentage(request.rollout_percentage)?;
+    let mut deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    ensure_not_final(&deployment, "change the rollout of")?;
+
+    // Halt the plan first, so the scheduler cannot overwrite the percentage
+    state
+        .database
+        .halt_rollout(
+            &deployment.id,
+            &format!(
+                "Rollout percentage set manually to {}%",
+                request.rollout_percentage
+            ),
+        )
+        .await?;
+
+    // Only the percentage is written, and only while the deployment is live
+    if !state
+        .database
+        .set_rollout_percentage(&deployment, request.rollout_percentage, Some(auth.actor()))
+        .await?
+    {
+        return Err(ApiError::conflict(format!(
+            "Deployment {} is no longer live",
+            deployment.id
+        )));
+    }
+    deployment.rollout_percentage = request.rollout_percentage;
+    Ok(Json(ApiResponse::success(deployment)))
+}
+
+/// Withdraw a deployment
+async fn rollback_deployment(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Deployment>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    let deployment = state
+        .database
+        .rollback_deployment(&deployment.id, Some(auth.actor()), None)
+        .await?;
+    Ok(Json(ApiResponse::success(deployment)))
+}
+
+/// Load a deployment of the authenticated application, failing with 404
+pub(crate) async fn load_deployment(
+    state: &AppState,
+    auth: &AuthenticatedApplication,
+    app_id: &str,
+    deployment_id: &str,
+) -> Result<Deployment, ApiError> {
+    let application_id = auth.authorize(app_id)?;
+    let deployment_id = DeploymentId::from_string(deployment_id)?;
+    state
+        .database
+        .get_deployment(&deployment_id)
+        .await?
+        .filter(|deployment| deployment.application_id == application_id)
+        .ok_or_else(|| ApiError::not_found(format!("Deployment {} not found", deployment_id)))
+}
@@ -0,0 +1,495 @@
+//! Differential package generation
+//!
+//! A differential package lets a client move from the bundle it runs to a
+//! newer one by downloading only the chunks that changed. Packages are built
+//! with [`DiffEngine`] in the background the first time an update check asks
+//! for a pair of bundles, which is answered with the full bundle meanwhile.
+//! They are stored next to the bundles and recorded as [`DiffPackage`] rows so
+//! that later checks serve them directly. Concurrent requests for the same
+//! pair share one generation job.
+//!
+//! [`pregenerate_for_new_deployments`] builds packages ahead of time from the
+//! most recent bundles of an application to each newly activated deployment.
+//!
+//! A stored package is laid out as a 4-byte big-endian manifest length, the
+//! JSON [`DiffPackageManifest`], and the data of the listed chunks in order.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rodepush_core::storage::{Storage, StorageKey};
+use rodepush_core::{
+    BulkHasher, Bundle as CoreBundle, BundleChunk, BundleError, BundleId, BundleMetadata,
+    DiffEngine, RodePushError, SemanticVersion, StorageError,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+use crate::api::bundles::{BUNDLE_METADATA_KEY, upload_hash_algorithm};
+use crate::database::{Bundle, DatabaseManager, Deployment, DiffPackage};
+
+/// Number of previous bundles diffed against a newly activated deployment
+pub const DEFAULT_PREGENERATED_DIFFS: usize = 5;
+
+/// Number of newly activated deployments handled per pre-generation pass
+const ACTIVE_DEPLOYMENT_BATCH: i64 = 100;
+
+/// Key under [`DiffPackage::metadata`] holding the ids of the chunks in the package
+pub const DIFF_CHUNKS_KEY: &str = "chunks";
+
+/// Key under [`DiffPackage::metadata`] holding the ids of the removed chunks
+pub const DIFF_REMOVED_CHUNKS_KEY: &str = "removed_chunks";
+
+/// Describes the contents of a stored differential package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffPackageManifest {
+    /// Bundle the package applies to
+    pub source_bundle_id: BundleId,
+    /// Complete metadata of the bundle the package produces
+    pub target: BundleMetadata,
+    /// IDs of the chunks whose data follows the manifest, in order
+    pub chunks: Vec<String>,
+    /// IDs of source chunks that are not part of the target
+    pub removed_chunk_ids: Vec<String>,
+}
+
+impl DiffPackageManifest {
+    /// Encode the manifest followed by the data of `chunks`
+    pub fn encode(&self, chunks: &[BundleChunk]) -> rodepush_core::Result<Vec<u8>> {
+        let manifest = serde_json::to_vec(self)?;
+        let data_size: usize = chunks.iter().map(BundleChunk::size).sum();
+        let mut package = Vec::with_capacity(4 + manifest.len() + data_size);
+        package.extend_from_slice(&(manifest.len() as u32).to_be_bytes());
+        package.extend_from_slice(&manifest);
+        for chunk in chunks {
+            package.extend_from_slice(chunk.as_bytes());
+        }
+        Ok(package)
+    }
+
+    /// Decode a stored package into its manifest and chunks
+    pub fn decode(package: &[u8]) -> rodepush_core::Result<(Self, Vec<BundleChunk>)> {
+        let corrupt = |details: &str| {
+            RodePushError::from(StorageError::Corruption {
+                details: format!("Invalid differential package: {}", details),
+            })
+        };
+
+        let (length, rest) = package
+            .split_first_chunk::<4>()
+            .ok_or_else(|| corrupt("missing manifest length"))?;
+        let length = u32::from_be_bytes(*length) as usize;
+        if rest.len() < length {
+            return Err(corrupt("truncated manifest"));
+        }
+        let (manifest, mut data) = rest.split_at(length);
+        let manifest: Self = serde_json::from_slice(manifest)?;
+
+        let mut chunks = Vec::with_capacity(manifest.chunks.len());
+        for id in &manifest.chunks {
+            let metadata = manifest
+                .target
+                .find_chunk(id)
+                .ok_or_else(|| corrupt(&format!("unknown chunk {}", id)))?;
+            let size = metadata.size as usize;
+            if data.len() < size {
+                return Err(corrupt(&format!("truncated chunk {}", id)));
+            }
+            let (chunk, rest) = data.split_at(size);
+            chunks.push(BundleChunk::new(metadata.clone(), chunk.to_vec()));
+            data = rest;
+        }
+        if !data.is_empty() {
+            return Err(corrupt("trailing data"));
+        }
+        Ok((manifest, chunks))
+    }
+}
+
+/// Storage key for a generated differential package
+pub fn diff_storage_key(source: &Bundle, target: &Bundle) -> StorageKey {
+    StorageKey::new(format!(
+        "diffs/{}/{}/{}/{}.diff",
+        target.application_id,
+        source.id,
+        target.id,
+        uuid::Uuid::new_v4()
+    ))
+}
+
+/// Uploaded metadata of a stored bundle
+pub fn stored_bundle_metadata(bundle: &Bundle) -> rodepush_core::Result<BundleMetadata> {
+    let value = bundle.metadata.get(BUNDLE_METADATA_KEY).ok_or_else(|| {
+        BundleError::invalid_format(format!("Bundle {} has no stored metadata", bundle.id))
+    })?;
+    Ok(serde_json::from_value(value.clone())?)
+}
+
+/// Check whether a differential package can be built from `source` to `target`
+///
+/// Only bundles of the same application and platform whose versions are
+/// compatible can be diffed; other pairs are always served as full bundles.
+pub fn can_diff(source: &Bundle, target: &Bundle) -> bool {
+    if source.id == target.id
+        || source.application_id != target.application_id
+        || source.platform != target.platform
+    {
+        return false;
+    }
+    match (
+        SemanticVersion::parse(&source.version),
+        SemanticVersion::parse(&target.version),
+    ) {
+        (Ok(source), Ok(target)) => source.is_compatible_with(&target),
+        _ => false,
+    }
+}
+
+/// Read a stored bundle back into its chunks
+pub async fn load_bundle(
+    bundle: &Bundle,
+    storage: &dyn Storage,
+) -> rodepush_core::Result<CoreBundle> {
+    let metadata = stored_bundle_metadata(bundle)?;
+    let mut reader = storage
+        .open_object(&StorageKey::new(bundle.storage_key.clone()))
+        .await?;
+    let mut data = Vec::with_capacity(bundle.size_bytes as usize);
+    reader
+        .read_to_end(&mut data)
+        .await
+        .map_err(|e| RodePushError::from(StorageError::from(e)))?;
+    if data.len() as u64 != metadata.size_bytes {
+        return Err(StorageError::Corruption {
+            details: format!(
+                "Bundle {} is {} bytes, expected {}",
+                bundle.id,
+                data.len(),
+                metadata.size_bytes
+            ),
+        }
+        .into());
+    }
+
+    let chunks = metadata
+        .chunks
+        .iter()
+        .map(|chunk| {
+            let start = chunk.offset as usize;
+            BundleChunk::new(
+                chunk.clone(),
+                data[start..start + chunk.size as usize].to_vec(),
+            )
+        })
+        .collect();
+    Ok(CoreBundle { metadata, chunks })
+}
+
+/// Build and store the differential package from `source` to `target`
+///
+/// The package is written to storage but not recorded in the database.
+/// Returns `None` when the pair cannot be diffed.
+pub async fn build_diff_package(
+    source: &Bundle,
+    target: &Bundle,
+    storage: &dyn Storage,
+) -> rodepush_core::Result<Option<DiffPackage>> {
+    if !can_diff(source, target) {
+        return Ok(None);
+    }
+    let old_bundle = load_bundle(source, storage).await?;
+    let new_bundle = load_bundle(target, storage).await?;
+
+    let engine = DiffEngine::new();
+    let diff = engine.compare_bundles(&old_bundle, &new_bundle)?;
+    let changed: Vec<BundleChunk> = new_bundle
+        .chunks
+        .iter()
+        .filter(|chunk| {
+            diff.new_or_modified_chunks
+                .iter()
+                .any(|changed| changed.id == chunk.metadata.id)
+        })
+        .cloned()
+        .collect();
+
+    let manifest = DiffPackageManifest {
+        source_bundle_id: source.id.clone(),
+        target: new_bundle.metadata.clone(),
+        chunks: changed.iter().map(|chunk| chunk.id().to_string()).collect(),
+        removed_chunk_ids: diff.removed_chunk_ids.clone(),
+    };
+    let package = manifest.encode(&changed)?;
+    let checksum = BulkHasher::new(upload_hash_algorithm(&new_bundle.metadata)).hash_data(&package);
+
+    let storage_key = diff_storage_key(source, target);
+    let mut writer = storage.create_object(&storage_key).await?;
+    if let Err(e) = writer.write(&package).await {
+        if let Err(abort_error) = writer.abort().await {
+            tracing::warn!("Failed to discard differential package: {}", abort_error);
+        }
+        return Err(e);
+    }
+    let size_bytes = writer.commit().await?;
+
+    let compression_ratio = if target.size_bytes > 0 {
+        size_bytes as f64 / target.size_bytes as f64
+    } else {
+        1.0
+    };
+    let diff_package = DiffPackage::new(
+        source.id.clone(),
+        target.id.clone(),
+        storage_key.as_str().to_string(),
+        size_bytes,
+        compression_ratio,
+        checksum,
+        target.platform,
+    )
+    .with_metadata(
+        DIFF_CHUNKS_KEY.to_string(),
+        serde_json::to_value(&manifest.chunks)?,
+    )
+    .with_metadata(
+        DIFF_REMOVED_CHUNKS_KEY.to_string(),
+        serde_json::to_value(&manifest.removed_chunk_ids)?,
+    );
+    Ok(Some(diff_package))
+}
+
+/// Pair of bundles a differential package is generated for
+type DiffPair = (BundleId, BundleId);
+
+/// Generates differential packages on demand, one job per pair of bundles
+pub struct DiffGenerator {
+    database: Arc<DatabaseManager>,
+    storage: Arc<dyn Storage>,
+    in_flight: Mutex<HashMap<DiffPair, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl DiffGenerator {
+    /// Create a generator storing packages in `storage`
+    pub fn new(database: Arc<DatabaseManager>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            database,
+            storage,
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the differential package from `source_id` to `target`, generating it if needed
+    ///
+    /// Returns `None` when the source bundle is unknown or cannot be diffed
+    /// against `target`. Concurrent calls for the same pair wait for a single
+    /// generation and then share its result.
+    pub async fn get_or_generate(
+        &self,
+        source_id: &BundleId,
+        target: &Bundle,
+    ) -> rodepush_core::Result<Option<DiffPackage>> {
+        if let Some(existing) = self
+            .database
+            .get_diff_package_by_bundles(source_id, &target.id)
+            .await?
+        {
+            return Ok(Some(existing));
+        }
+
+        let pair = (source_id.clone(), target.id.clone());
+        let job = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            in_flight.entry(pair.clone()).or_default().clone()
+        };
+        let _guard = job.lock().await;
+
+        let result = self.generate_locked(source_id, target).await;
+        {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            // Later callers re-check the database, so the entry can go once it is ours alone
+            if in_flight
+                .get(&pair)
+                .is_some_and(|current| Arc::ptr_eq(current, &job) && Arc::strong_count(&job) == 2)
+            {
+                in_flight.remove(&pair);
+            }
+        }
+        result
+    }
+
+    /// Get the stored differential package from `source_id` to `target`,
+    /// queueing its generation in the background if there is none yet
+    ///
+    /// Returns `None` until the package has been generated, so callers serve
+    /// the full bundle instead of waiting for it. A pair already being
+    /// generated is not queued again.
+    pub async fn get_or_queue(
+        self: &Arc<Self>,
+        source_id: &BundleId,
+        target: &Bundle,
+    ) -> rodepush_core::Result<Option<DiffPackage>> {
+        if let Some(existing) = self
+            .database
+            .get_diff_package_by_bundles(source_id, &target.id)
+            .await?
+        {
+            return Ok(Some(existing));
+        }
+
+        let pair = (source_id.clone(), target.id.clone());
+        if self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(&pair)
+        {
+            return Ok(None);
+        }
+        let generator = Arc::clone(self);
+        let target = target.clone();
+        tokio::spawn(async move {
+            let (source_id, target_id) = pair;
+            if let Err(e) = generator.get_or_generate(&source_id, &target).await {
+                tracing::warn!(
+                    "Failed to generate differential package from {} to {}: {}",
+                    source_id,
+                    target_id,
+                    e
+                );
+            }
+        });
+        Ok(None)
+    }
+
+    /// Generate a package while holding the job lock of the pair
+    async fn generate_locked(
+        &self,
+        source_id: &BundleId,
+        target: &Bundle,
+    ) -> rodepush_core::Result<Option<DiffPackage>> {
+        // A job that held the lock before us may already have recorded the package
+        if let Some(existing) = self
+            .database
+            .get_diff_package_by_bundles(source_id, &target.id)
+            .await?
+        {
+            return Ok(Some(existing));
+        }
+        let Some(source) = self.database.get_bundle(source_id).await? else {
+            return Ok(None);
+        };
+        let Some(diff_package) = build_diff_package(&source, target, self.storage.as_ref()).await?
+        else {
+            return Ok(None);
+        };
+
+        if let Err(e) = self.database.create_diff_package(&diff_package).await {
+            let key = StorageKey::new(diff_package.storage_key.clone());
+            if let Err(delete_error) = self.storage.delete(&key).await {
+                tracing::warn!(
+                    "Failed to remove data of unrecorded differential package {}: {}",
+                    diff_package.id,
+                    delete_error
+                );
+            }
+            return Err(e);
+        }
+
+        tracing::info!(
+            "Generated differential package {} from {} to {} ({} bytes)",
+            diff_package.id,
+            source.id,
+            target.id,
+            diff_package.size_bytes
+        );
+        Ok(Some(diff_package))
+    }
+
+    /// Generate packages from the last `count` bundles of its application to a deployment
+    ///
+    /// Returns the number of packages available afterwards. Failures for one
+    /// source bundle are logged and do not stop the others.
+    pub async fn pregenerate_for_deployment(
+        &self,
+        deployment: &Deployment,
+        count: usize,
+    ) -> rodepush_core::Result<usize> {
+        let Some(target) = self.database.get_bundle(&deployment.bundle_id).await? else {
+            return Ok(0);
+        };
+
+        // Newest first; fetch a few extra so the target and foreign platforms can be skipped
+        let candidates = self
+            .database
+            .list_bundles_for_application(&target.application_id, (count * 2 + 1) as i64, 0)
+            .await?;
+        let mut available = 0;
+        for source in candidates
+            .iter()
+            .filter(|source| source.created_at <= target.created_at && can_diff(source, &target))
+            .take(count)
+        {
+            match self.get_or_generate(&source.id, &target).await {
+                Ok(Some(_)) => available += 1,
+                Ok(None) => {}
+                Err(e) => tracing::warn!(
+                    "Failed to pre-generate differential package from {} to {}: {}",
+                    source.id,
+                    target.id,
+                    e
+                ),
+            }
+        }
+        Ok(available)
+    }
+}
+
+/// Pre-generate packages for deployments activated after `since`
+///
+/// Returns the activation time of the newest deployment seen, to be passed as
+/// `since` on the next pass.
+pub async fn pregenerate_for_new_deployments(
+    generator: &DiffGenerator,
+    since: Option<DateTime<Utc>>,
+    count: usize,
+) -> rodepush_core::Result<Option<DateTime<Utc>>> {
+    let mut newest = since;
+    for deployment in generator
+        .database
+        .get_deployments_activated_since(since, ACTIVE_DEPLOYMENT_BATCH)
+        .await?
+    {
+        if let Err(e) = generator
+            .pregenerate_for_deployment(&deployment, count)
+            .await
+        {
+            tracing::warn!(
+                "Failed to pre-generate differential packages for deployment {}: {}",
+                deployment.id,
+                e
+            );
+        }
+        newest = newest.max(deployment.deployed_at);
+    }
+    Ok(newest)
+}
+
+/// Periodically pre-generate differential packages for newly activated deployments
+pub fn spawn_diff_pregeneration(
+    generator: Arc<DiffGenerator>,
+    count: usize,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut since = None;
+        loop {
+            ticker.tick().await;
+            match pregenerate_for_new_deployments(&generator, since, count).await {
+                Ok(newest) => since = newest,
+                Err(e) => tracing::warn!("Differential package pre-generation failed: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,360 @@
+//! Download endpoints
+//!
+//! Full bundles, single bundle chunks, differential packages and asset
+//! packages are streamed from storage. Every response carries an `ETag`
+//! derived from the stored checksum so that clients can revalidate with
+//! `If-None-Match`, and a single `Range` of bytes can be requested to resume an
+//! interrupted download. `If-Range` makes a resumed request fall back to the
+//! whole object when it changed in the meantime.
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use rodepush_core::storage::{Storage, StorageKey};
+use rodepush_core::{AssetCollectionId, BundleId, RodePushError, StorageError};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::api::{diffs::stored_bundle_metadata, error::ApiError, state::AppState};
+use crate::database::{Bundle, DiffPackageId};
+
+/// Content type of bundles, chunks and differential packages
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Content type of asset packages, which are zstd-compressed tar archives
+pub const ASSET_PACKAGE_CONTENT_TYPE: &str = "application/zstd";
+
+/// Buffer size used when streaming an object to the client
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Download routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/bundles/{bundle_id}/download", get(download_bundle))
+        .route(
+            "/bundles/{bundle_id}/chunks/{chunk_id}",
+            get(download_chunk),
+        )
+        .route("/diffs/{diff_id}/download", get(download_diff))
+        .route(
+            "/assets/compressed/{collection_id}",
+            get(download_asset_package),
+        )
+}
+
+/// Storage key of the package of an asset collection
+pub fn asset_package_storage_key(collection_id: &AssetCollectionId) -> StorageKey {
+    StorageKey::new(format!("assets/{}.tar.zst", collection_id.0))
+}
+
+/// A stretch of a storage object served as one download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadObject {
+    /// Storage object holding the data
+    pub key: StorageKey,
+    /// Byte offset of the download within the storage object
+    pub offset: u64,
+    /// Size of the download in bytes
+    pub size: u64,
+    /// Checksum of the downloaded bytes, used as the entity tag
+    pub checksum: String,
+    /// Content type of the download
+    pub content_type: &'static str,
+}
+
+impl DownloadObject {
+    /// A whole storage object
+    pub fn whole(key: StorageKey, size: u64, checksum: String, content_type: &'static str) -> Self {
+        Self {
+            key,
+            offset: 0,
+            size,
+            checksum,
+            content_type,
+        }
+    }
+
+    /// Quoted entity tag of the download
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.checksum)
+    }
+}
+
+/// Inclusive byte range of a download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    /// First byte served
+    pub start: u64,
+    /// Last byte served
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Number of bytes in the range
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+/// What a `Range` header asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The whole object
+    Full,
+    /// A single range of bytes
+    Partial(ByteRange),
+    /// A range lying outside the object
+    Unsatisfiable,
+}
+
+impl RangeRequest {
+    /// Interpret a `Range` header against an object of `size` bytes
+    ///
+    /// Only single `bytes` ranges are honored; headers that are malformed or
+    /// ask for several ranges are ignored and the whole object is served.
+    pub fn parse(header: Option<&str>, size: u64) -> Self {
+        let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+            return Self::Full;
+        };
+        if spec.contains(',') {
+            return Self::Full;
+        }
+        let Some((start, end)) = spec.trim().split_once('-') else {
+            return Self::Full;
+        };
+
+        let range = match (start.trim(), end.trim()) {
+            ("", "") => return Self::Full,
+            // Suffix range: the last `end` bytes
+            ("", suffix) => match suffix.parse::<u64>() {
+                Ok(0) => return Self::Unsatisfiable,
+                Ok(suffix) => ByteRange {
+                    start: size.saturating_sub(suffix),
+                    end: size.saturating_sub(1),
+                },
+                Err(_) => return Self::Full,
+            },
+            (start, end) => {
+                let Ok(start) = start.parse::<u64>() else {
+                    return Self::Full;
+                };
+                let end = match end {
+                    "" => u64::MAX,
+                    end => match end.parse::<u64>() {
+                        Ok(end) if end >= start => end,
+                        _ => return Self::Full,
+                    },
+                };
+                ByteRange {
+                    start,
+                    end: end.min(size.saturating_sub(1)),
+                }
+            }
+        };
+
+        if size == 0 || range.start >= size {
+            Self::Unsatisfiable
+        } else {
+            Self::Partial(range)
+        }
+    }
+}
+
+/// Check an `If-None-Match` header against an entity tag, using weak comparison
+pub fn etag_matches_any(header: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Build the response for a download, honoring conditional and range headers
+pub async fn serve_download(
+    storage: &dyn Storage,
+    object: &DownloadObject,
+    headers: &HeaderMap,
+) -> Result<Response, ApiError> {
+    let etag = object.etag();
+    let etag_value = HeaderValue::from_str(&etag)
+        .map_err(|_| ApiError::internal("Stored checksum is not a valid entity tag"))?;
+
+    if header_str(headers, header::IF_NONE_MATCH)
+        .is_some_and(|value| etag_matches_any(value, &etag))
+    {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag_value)]).into_response());
+    }
+
+    // A resumed download only gets a range while the object is unchanged
+    let range_header = match header_str(headers, header::IF_RANGE) {
+        Some(if_range) if if_range.trim() != etag => None,
+        _ => header_str(headers, header::RANGE),
+    };
+    let (status, range) = match RangeRequest::parse(range_header, object.size) {
+        RangeRequest::Full => (StatusCode::OK, None),
+        RangeRequest::Partial(range) => (StatusCode::PARTIAL_CONTENT, Some(range)),
+        RangeRequest::Unsatisfiable => {
+            let content_range = format!("bytes */{}", object.size);
+            return Ok((
+                [(header::CONTENT_RANGE, content_range)],
+                ApiError::new(
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    "Requested range lies outside the object",
+                ),
+            )
+                .into_response());
+        }
+    };
+    let (start, length) = match range {
+        Some(range) => (range.start, range.size()),
+        None => (0, object.size),
+    };
+
+    let reader = storage
+        .open_object_at(&object.key, object.offset + start)
+        .await?;
+    let mut response = Response::new(stream_body(reader.take(length)));
+    *response.status_mut() = status;
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::ETAG, etag_value);
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(object.content_type),
+    );
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+    if let Some(range) = range {
+        let content_range = format!("bytes {}-{}/{}", range.start, range.end, object.size);
+        response_headers.insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_str(&content_range).map_err(|e| ApiError::internal(e.to_string()))?,
+        );
+    }
+    Ok(response)
+}
+
+/// Stream a reader as a response body
+fn stream_body<R>(reader: R) -> Body
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
+    let stream = futures::stream::try_unfold(reader, |mut reader| async move {
+        let mut buffer = vec![0u8; STREAM_BUFFER_SIZE];
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok::<_, std::io::Error>(None);
+        }
+        buffer.truncate(read);
+        Ok(Some((Bytes::from(buffer), reader)))
+    });
+    Body::from_stream(stream)
+}
+
+/// Look up a bundle, failing with 404 when it does not exist
+async fn require_bundle(state: &AppState, bundle_id: &str) -> Result<Bundle, ApiError> {
+    let bundle_id = BundleId::from_string(bundle_id)?;
+    state
+        .database
+        .get_bundle(&bundle_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Bundle {} not found", bundle_id)))
+}
+
+/// Download a full bundle
+async fn download_bundle(
+    State(state): State<AppState>,
+    Path(bundle_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let bundle = require_bundle(&state, &bundle_id).await?;
+    let object = DownloadObject::whole(
+        StorageKey::new(bundle.storage_key),
+        bundle.size_bytes,
+        bundle.checksum,
+        OCTET_STREAM,
+    );
+    serve_download(state.storage.as_ref(), &object, &headers).await
+}
+
+/// Download a single chunk of a bundle
+async fn download_chunk(
+    State(state): State<AppState>,
+    Path((bundle_id, chunk_id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let bundle = require_bundle(&state, &bundle_id).await?;
+    let metadata = stored_bundle_metadata(&bundle)?;
+    let chunk = metadata.find_chunk(&chunk_id).ok_or_else(|| {
+        ApiError::not_found(format!("Bundle {} has no chunk {}", bundle.id, chunk_id))
+    })?;
+
+    let object = DownloadObject {
+        key: StorageKey::new(bundle.storage_key.clone()),
+        offset: chunk.offset,
+        size: chunk.size,
+        checksum: chunk.checksum.clone(),
+        content_type: OCTET_STREAM,
+    };
+    serve_download(state.storage.as_ref(), &object, &headers).await
+}
+
+/// Download a differential package
+async fn download_diff(
+    State(state): State<AppState>,
+    Path(diff_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let diff_id = DiffPackageId::from_string(&diff_id)?;
+    let diff = state
+        .database
+        .get_diff_package(&diff_id)
+        .await?
+        .ok_or_else(|| {
+            ApiError::not_found(format!("Differential package {} not found", diff_id))
+        })?;
+
+    let object = DownloadObject::whole(
+        StorageKey::new(diff.storage_key),
+        diff.size_bytes,
+        diff.checksum,
+        OCTET_STREAM,
+    );
+    serve_download(state.storage.as_ref(), &object, &headers).await
+}
+
+/// Download the package of an asset collection
+///
+/// Collection ids are derived from the collection content, so the id doubles
+/// as the entity tag.
+async fn download_asset_package(
+    State(state): State<AppState>,
+    Path(collection_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let collection_id = uuid::Uuid::parse_str(&collection_id)
+        .map(|uuid| AssetCollectionId::from_string(uuid.to_string()))
+        .map_err(|e| ApiError::bad_request(format!("Invalid asset collection id: {}", e)))?;
+
+    let key = asset_package_storage_key(&collection_id);
+    let size = match state.storage.object_size(&key).await {
+        Ok(size) => size,
+        Err(RodePushError::Storage(StorageError::NotFound { .. })) => {
+            return Err(ApiError::not_found(format!(
+                "Asset package {} not found",
+                collection_id.0
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let object = DownloadObject::whole(key, size, collection_id.0, ASSET_PACKAGE_CONTENT_TYPE);
+    serve_download(state.storage.as_ref(), &object, &headers).await
+}
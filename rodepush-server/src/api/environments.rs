@@ -0,0 +1,228 @@
+//! Environment management endpoints
+//!
+//! Environments of an application live under `/apps/{app_id}/environments`
+//! and are addressed by name. Deployments can only target an existing
+//! environment, so a mistyped name is rejected instead of silently opening a
+//! new channel. Each environment carries the rollout applied to deployments
+//! that do not choose their own, and a stable deployment key clients can
+//! check for updates with. New applications start with the environments in
+//! [`DEFAULT_ENVIRONMENTS`]. Every route requires the `admin` scope.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::AuthenticatedApplication, deployments::validate_rollout_percentage, error::ApiError,
+    response::ApiResponse, state::AppState,
+};
+use crate::database::{
+    ApplicationId, Environment, RolloutPlan, RolloutStep, validate_environment_name,
+};
+
+/// Environments every new application starts with
+pub const DEFAULT_ENVIRONMENTS: &[&str] = &["staging", "production"];
+
+/// Environment routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/apps/{app_id}/environments",
+            get(list_environments).post(create_environment),
+        )
+        .route(
+            "/apps/{app_id}/environments/{name}",
+            get(get_environment)
+                .patch(update_environment)
+                .delete(delete_environment),
+        )
+}
+
+/// Request body for creating an environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateEnvironmentRequest {
+    /// Name, unique within the application (e.g., "beta")
+    pub name: String,
+    /// Environment description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Percentage of clients receiving new deployments; all of them by default
+    #[serde(default)]
+    pub default_rollout_percentage: Option<u32>,
+    /// Stages new deployments are rolled out in; replaces `default_rollout_percentage`
+    #[serde(default)]
+    pub default_rollout_plan: Option<Vec<RolloutStep>>,
+}
+
+impl CreateEnvironmentRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        validate_environment_name(self.name.trim())?;
+        validate_rollout_defaults(
+            self.default_rollout_percentage,
+            self.default_rollout_plan.as_deref(),
+        )
+    }
+
+    /// Build the environment described by the request
+    pub fn into_environment(self, application_id: ApplicationId) -> Environment {
+        let mut environment = Environment::new(application_id, self.name.trim().to_string());
+        if let Some(description) = self.description.filter(|d| !d.trim().is_empty()) {
+            environment = environment.with_description(description);
+        }
+        if let Some(percentage) = self.default_rollout_percentage {
+            environment = environment.with_default_rollout_percentage(percentage);
+        }
+        if let Some(steps) = self.default_rollout_plan {
+            environment = environment
+                .with_default_rollout_percentage(steps[0].percentage)
+                .with_default_rollout_plan(steps);
+        }
+        environment
+    }
+}
+
+/// Request body for changing an environment; absent fields stay unchanged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateEnvironmentRequest {
+    /// New description; an empty one clears it
+    #[serde(default)]
+    pub description: Option<String>,
+    /// New default rollout percentage; clears the default rollout plan
+    #[serde(default)]
+    pub default_rollout_percentage: Option<u32>,
+    /// New default rollout plan; an empty one clears it
+    #[serde(default)]
+    pub default_rollout_plan: Option<Vec<RolloutStep>>,
+}
+
+impl UpdateEnvironmentRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let plan = self
+            .default_rollout_plan
+            .as_deref()
+            .filter(|steps| !steps.is_empty());
+        validate_rollout_defaults(self.default_rollout_percentage, plan)
+    }
+
+    /// Apply the requested changes to `environment`
+    pub fn apply(self, environment: &mut Environment) {
+        if let Some(description) = self.description {
+            environment.description = Some(description).filter(|d| !d.trim().is_empty());
+        }
+        if let Some(percentage) = self.default_rollout_percentage {
+            environment.default_rollout_percentage = percentage;
+            environment.default_rollout_plan = None;
+        }
+        match self.default_rollout_plan {
+            Some(steps) if steps.is_empty() => environment.default_rollout_plan = None,
+            Some(steps) => {
+                environment.default_rollout_percentage = steps[0].percentage;
+                environment.default_rollout_plan = Some(steps);
+            }
+            None => {}
+        }
+        environment.updated_at = Utc::now();
+    }
+}
+
+/// Check default rollout settings, which are either a percentage or a plan
+fn validate_rollout_defaults(
+    percentage: Option<u32>,
+    plan: Option<&[RolloutStep]>,
+) -> Result<(), ApiError> {
+    if let Some(percentage) = percentage {
+        validate_rollout_percentage(percentage)?;
+    }
+    if let Some(steps) = plan {
+        if percentage.is_some() {
+            return Err(ApiError::bad_request(
+                "default_rollout_percentage and default_rollout_plan cannot be combined",
+            ));
+        }
+        RolloutPlan::validate_steps(steps)?;
+    }
+    Ok(())
+}
+
+/// Create an environment
+async fn create_environment(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<CreateEnvironmentRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Environment>>), ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    request.validate()?;
+
+    let environment = request.into_environment(application_id);
+    state.database.create_environment(&environment).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(environment))))
+}
+
+/// List the environments of an application by name
+async fn list_environments(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Vec<Environment>>>, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let environments = state.database.list_environments(&application_id).await?;
+    Ok(Json(ApiResponse::success(environments)))
+}
+
+/// Get a single environment
+async fn get_environment(
+    State(state): State<AppState>,
+    Path((app_id, name)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<Environment>>, ApiError> {
+    let environment = load_environment(&state, &auth, &app_id, &name).await?;
+    Ok(Json(ApiResponse::success(environment)))
+}
+
+/// Change the description or rollout defaults of an environment
+async fn update_environment(
+    State(state): State<AppState>,
+    Path((app_id, name)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<UpdateEnvironmentRequest>,
+) -> Result<Json<ApiResponse<Environment>>, ApiError> {
+    request.validate()?;
+    let mut environment = load_environment(&state, &auth, &app_id, &name).await?;
+    request.apply(&mut environment);
+    state.database.update_environment(&environment).await?;
+    Ok(Json(ApiResponse::success(environment)))
+}
+
+/// Delete an environment that was never deployed to
+async fn delete_environment(
+    State(state): State<AppState>,
+    Path((app_id, name)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<StatusCode, ApiError> {
+    let environment = load_environment(&state, &auth, &app_id, &name).await?;
+    state.database.delete_environment(&environment).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Load an environment of the authenticated application, failing with 404
+pub(crate) async fn load_environment(
+    state: &AppState,
+    auth: &AuthenticatedApplication,
+    app_id: &str,
+    name: &str,
+) -> Result<Environment, ApiError> {
+    let application_id = auth.authorize(app_id)?;
+    state
+        .database
+        .get_environment(&application_id, name)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Environment {} not found", name)))
+}
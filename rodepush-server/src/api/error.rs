@@ -0,0 +1,143 @@
+//! API error type and its HTTP mapping
+
+use axum::{
+    Json,
+    extract::multipart::MultipartError,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use rodepush_core::{AuthError, BundleError, RodePushError, StorageError};
+
+use crate::api::response::ApiResponse;
+
+/// Error returned by API handlers, rendered as an [`ApiResponse`] envelope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    /// Create an error with an explicit status code
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// Create a 400 Bad Request error
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    /// Create a 404 Not Found error
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    /// Create a 409 Conflict error
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+
+    /// Create a 410 Gone error
+    pub fn gone(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::GONE, message)
+    }
+
+    /// Create a 413 Payload Too Large error
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::PAYLOAD_TOO_LARGE, message)
+    }
+
+    /// Create a 422 Unprocessable Entity error
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+    }
+
+    /// Create a 500 Internal Server Error
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
+    /// HTTP status of this error
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Message returned to the client
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.status, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ApiResponse::<()>::error(self.message);
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<RodePushError> for ApiError {
+    fn from(error: RodePushError) -> Self {
+        match &error {
+            RodePushError::Validation { .. } => Self::bad_request(error.to_string()),
+            RodePushError::Conflict { .. } => Self::conflict(error.to_string()),
+            RodePushError::NotFound { .. } => Self::not_found(error.to_string()),
+            RodePushError::Bundle(
+                BundleError::TooLarge { .. }
+                | BundleError::SizeLimitExceeded { .. }
+                | BundleError::AssetTooLarge { .. },
+            ) => Self::payload_too_large(error.to_string()),
+            RodePushError::Bundle(BundleError::ChecksumMismatch { .. }) => {
+                Self::unprocessable(error.to_string())
+            }
+            RodePushError::Bundle(_) => Self::bad_request(error.to_string()),
+            RodePushError::Auth(AuthError::InsufficientPermissions { .. }) => {
+                Self::new(StatusCode::FORBIDDEN, error.to_string())
+            }
+            RodePushError::Auth(_) => Self::new(StatusCode::UNAUTHORIZED, error.to_string()),
+            RodePushError::Storage(StorageError::NotFound { .. }) => {
+                Self::not_found(error.to_string())
+            }
+            _ => {
+                // Internal details stay in the server log
+                tracing::error!("Request failed: {}", error);
+                Self::internal("Internal server error")
+            }
+        }
+    }
+}
+
+impl From<BundleError> for ApiError {
+    fn from(error: BundleError) -> Self {
+        RodePushError::from(error).into()
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(error: AuthError) -> Self {
+        RodePushError::from(error).into()
+    }
+}
+
+impl From<axum::Error> for ApiError {
+    fn from(error: axum::Error) -> Self {
+        Self::bad_request(format!("Failed to read request body: {}", error))
+    }
+}
+
+impl From<MultipartError> for ApiError {
+    fn from(error: MultipartError) -> Self {
+        Self::new(error.status(), error.body_text())
+    }
+}
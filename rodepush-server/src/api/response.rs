@@ -0,0 +1,91 @@
+//! Standard response envelope
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Standard API response format
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub request_id: String,
+}
+
+impl<T> ApiResponse<T> {
+    /// Wrap a successful result
+    pub fn success(data: T) -> Self {
+        Self {
+            success: true,
+            data: Some(data),
+            error: None,
+            timestamp: Utc::now(),
+            request_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// Wrap an error message
+    pub fn error(error: String) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(error),
+            timestamp: Utc::now(),
+            request_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+}
+
+/// Number of items a list endpoint returns when no limit is given
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// Largest number of items a list endpoint returns at once
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Pagination query parameters of list endpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pagination {
+    /// Number of items to return, capped at [`MAX_PAGE_SIZE`]
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Number of items to skip
+    #[serde(default)]
+    pub offset: Option<u32>,
+}
+
+impl Pagination {
+    /// Effective page size
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE) as i64
+    }
+
+    /// Effective number of skipped items
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0) as i64
+    }
+}
+
+/// One page of a list
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// Items on this page
+    pub items: Vec<T>,
+    /// Page size the items were fetched with
+    pub limit: i64,
+    /// Number of items skipped before this page
+    pub offset: i64,
+}
+
+impl<T> Page<T> {
+    /// Wrap the items fetched for `pagination`
+    pub fn new(items: Vec<T>, pagination: &Pagination) -> Self {
+        Self {
+            items,
+            limit: pagination.limit(),
+            offset: pagination.offset(),
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Staged rollout endpoints and scheduler
+//!
+//! A deployment can carry a rollout plan under
+//! `/apps/{app_id}/deployments/{deployment_id}/rollout-plan` that raises its
+//! rollout percentage stage by stage. The scheduler spawned with
+//! [`spawn_rollout_scheduler`] moves running plans to their next stage when
+//! the current one is over, and halts plans whose deployment stopped being
+//! active. Setting the rollout percentage by hand halts the plan as well.
+//! The deployments a rolled out deployment replaces stay live for the
+//! clients outside its rollout until it reaches 100%, whether by a stage of
+//! its plan or by hand.
+//! Every route requires the `deployments:write` scope.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::{
+    auth::AuthenticatedApplication,
+    deployments::{ensure_not_final, load_deployment},
+    error::ApiError,
+    response::ApiResponse,
+    state::AppState,
+};
+use crate::database::{DatabaseManager, DeploymentStatus, RolloutPlan, RolloutStep};
+
+/// How many due rollout plans one scheduler pass advances at most
+const DUE_ROLLOUT_BATCH: i64 = 100;
+
+/// Actor recorded in the history of deployments changed by the scheduler
+pub const ROLLOUT_SCHEDULER_ACTOR: &str = "rollout_scheduler";
+
+/// Rollout plan routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/rollout-plan",
+            get(get_rollout_plan)
+                .put(set_rollout_plan)
+                .delete(delete_rollout_plan),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/rollout-plan/halt",
+            post(halt_rollout_plan),
+        )
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/rollout-plan/resume",
+            post(resume_rollout_plan),
+        )
+}
+
+/// Request body for setting the rollout plan of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetRolloutPlanRequest {
+    /// Stages of the plan, in order
+    pub steps: Vec<RolloutStep>,
+}
+
+/// Get the rollout plan of a deployment
+async fn get_rollout_plan(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<RolloutPlan>>, ApiError> {
+    let plan = load_rollout_plan(&state, &auth, &app_id, &deployment_id).await?;
+    Ok(Json(ApiResponse::success(plan)))
+}
+
+/// Replace the rollout plan of a deployment, starting at its first stage
+async fn set_rollout_plan(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<SetRolloutPlanRequest>,
+) -> Result<Json<ApiResponse<RolloutPlan>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    ensure_not_final(&deployment, "plan the rollout of")?;
+
+    let plan = RolloutPlan::new(deployment.id.clone(), request.steps)?;
+    state.database.set_rollout_plan(&plan).await?;
+    state
+        .database
+        .set_rollout_percentage(&deployment, plan.current_percentage(), Some(auth.actor()))
+        .await?;
+    Ok(Json(ApiResponse::success(plan)))
+}
+
+/// Remove the rollout plan of a deployment, keeping its current percentage
+async fn delete_rollout_plan(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<StatusCode, ApiError> {
+    let plan = load_rollout_plan(&state, &auth, &app_id, &deployment_id).await?;
+    state
+        .database
+        .delete_rollout_plan(&plan.deployment_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Keep a running rollout plan at its current stage
+async fn halt_rollout_plan(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<RolloutPlan>>, ApiError> {
+    let mut plan = load_rollout_plan(&state, &auth, &app_id, &deployment_id).await?;
+    let step = plan.current_step;
+    plan.halt(format!("Halted manually by {}", auth.actor()))?;
+    if !state.database.update_rollout_progress(&plan, step).await? {
+        return Err(ApiError::conflict(format!(
+            "Rollout of deployment {} changed while halting it",
+            plan.deployment_id
+        )));
+    }
+    Ok(Json(ApiResponse::success(plan)))
+}
+
+/// Continue a halted rollout plan, restarting its current stage
+async fn resume_rollout_plan(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<RolloutPlan>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    if deployment.status != DeploymentStatus::Active {
+        return Err(ApiError::conflict(format!(
+            "Cannot resume the rollout of deployment {} while it is {}",
+            deployment.id, deployment.status
+        )));
+    }
+    let mut plan = load_rollout_plan(&state, &auth, &app_id, &deployment_id).await?;
+    plan.resume()?;
+    state.database.set_rollout_plan(&plan).await?;
+    state
+        .database
+        .set_rollout_percentage(&deployment, plan.current_percentage(), Some(auth.actor()))
+        .await?;
+    Ok(Json(ApiResponse::success(plan)))
+}
+
+/// Load the rollout plan of a deployment of the authenticated application,
+/// failing with 404
+async fn load_rollout_plan(
+    state: &AppState,
+    auth: &AuthenticatedApplication,
+    app_id: &str,
+    deployment_id: &str,
+) -> Result<RolloutPlan, ApiError> {
+    let deployment = load_deployment(state, auth, app_id, deployment_id).await?;
+    state
+        .database
+        .get_rollout_plan(&deployment.id)
+        .await?
+        .ok_or_else(|| {
+            ApiError::not_found(format!("Deployment {} has no rollout plan", deployment.id))
+        })
+}
+
+/// Move a due rollout plan to its next stage
+///
+/// Returns whether the plan advanced. Plans whose deployment is no longer
+/// active are halted instead.
+async fn advance_rollout(
+    database: &DatabaseManager,
+    mut plan: RolloutPlan,
+    now: DateTime<Utc>,
+) -> rodepush_core::Result<bool> {
+    let Some(deployment) = database.get_deployment(&plan.deployment_id).await? else {
+        return Ok(false);
+    };
+    if deployment.status != DeploymentStatus::Active {
+        database
+            .halt_rollout(
+                &deployment.id,
+                &format!("Deployment is {}", deployment.status),
+            )
+            .await?;
+        return Ok(false);
+    }
+
+    let step = plan.current_step;
+    let Some(percentage) = plan.advance(now) else {
+        return Ok(false);
+    };
+    // Another server may have advanced the plan first
+    if !database.update_rollout_progress(&plan, step).await? {
+        return Ok(false);
+    }
+    if !database
+        .set_rollout_percentage(
+            &deployment,
+            percentage,
+            Some(ROLLOUT_SCHEDULER_ACTOR.to_string()),
+        )
+        .await?
+    {
+        database
+            .halt_rollout(&deployment.id, "Deployment is no longer live")
+            .await?;
+        return Ok(false);
+    }
+    tracing::info!(
+        "Advanced rollout of deployment {} to {}%",
+        deployment.id,
+        percentage
+    );
+    Ok(true)
+}
+
+/// Move every running rollout plan whose current stage is over to its next stage
+///
+/// Returns the number of plans advanced. A plan that fails to advance is
+/// left as it is so that a later pass retries it.
+pub async fn advance_due_rollouts(
+    database: &DatabaseManager,
+    now: DateTime<Utc>,
+) -> rodepush_core::Result<usize> {
+    let mut advanced = 0;
+    for plan in database
+        .list_due_rollout_plans(now, DUE_ROLLOUT_BATCH)
+        .await?
+    {
+        let deployment_id = plan.deployment_id.clone();
+        match advance_rollout(database, plan, now).await {
+            Ok(true) => advanced += 1,
+            Ok(false) => {}
+            Err(e) => tracing::warn!(
+                "Failed to advance rollout of deployment {}: {}",
+                deployment_id,
+                e
+            ),
+        }
+    }
+    Ok(advanced)
+}
+
+/// Periodically advance due rollout plans in the background
+pub fn spawn_rollout_scheduler(
+    database: Arc<DatabaseManager>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = advance_due_rollouts(&database, Utc::now()).await {
+                tracing::warn!("Rollout scheduling failed: {}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,143 @@
+//! Shared handler state
+
+use rodepush_core::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::api::diffs::DiffGenerator;
+use crate::database::{DatabaseManager, InstallStats, hash_api_key};
+
+/// Limits enforced while an upload is streamed in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadLimits {
+    /// Largest accepted bundle, summed over all chunks, in bytes
+    pub max_bundle_size: u64,
+    /// Largest accepted single chunk in bytes
+    pub max_chunk_size: u64,
+    /// Largest number of chunks in one bundle
+    pub max_chunks: usize,
+    /// Largest accepted metadata document in bytes
+    pub max_metadata_size: usize,
+    /// How long a resumable upload session is kept without being resumed, in seconds
+    pub session_ttl_seconds: u64,
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        Self {
+            max_bundle_size: 100 * 1024 * 1024,
+            max_chunk_size: 50 * 1024 * 1024,
+            max_chunks: 256,
+            max_metadata_size: 1024 * 1024,
+            session_ttl_seconds: 24 * 60 * 60,
+        }
+    }
+}
+
+impl UploadLimits {
+    /// Lifetime of a resumable upload session
+    pub fn session_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.session_ttl_seconds.min(i64::MAX as u64) as i64)
+    }
+}
+
+/// When failure telemetry makes the server withdraw a deployment on its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoRollbackPolicy {
+    /// Whether deployments are withdrawn automatically at all
+    pub enabled: bool,
+    /// Share of failed installs, between 0 and 1, above which a deployment is withdrawn
+    pub failure_rate_threshold: f64,
+    /// Fewest clients reporting an install outcome before the failure rate counts
+    pub min_installs: u64,
+}
+
+impl Default for AutoRollbackPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_rate_threshold: 0.1,
+            min_installs: 50,
+        }
+    }
+}
+
+impl AutoRollbackPolicy {
+    /// Decide whether install outcomes call for withdrawing a deployment
+    ///
+    /// Returns the reason for the decision when they do.
+    pub fn evaluate(&self, stats: &InstallStats) -> Option<String> {
+        if !self.enabled || stats.attempted < self.min_installs.max(1) {
+            return None;
+        }
+        let failure_rate = stats.failure_rate();
+        if failure_rate <= self.failure_rate_threshold {
+            return None;
+        }
+        Some(format!(
+            "Failure rate {:.1}% over {} installs exceeds the {:.1}% threshold",
+            failure_rate * 100.0,
+            stats.attempted,
+            self.failure_rate_threshold * 100.0
+        ))
+    }
+}
+
+/// State shared by every API handler
+#[derive(Clone)]
+pub struct AppState {
+    /// Database access
+    pub database: Arc<DatabaseManager>,
+    /// Backend holding bundle and asset data
+    pub storage: Arc<dyn Storage>,
+    /// Upload limits
+    pub upload_limits: UploadLimits,
+    /// Externally visible base URL prefixed to download links; empty for relative links
+    pub public_url: String,
+    /// Generator of differential packages requested by update checks
+    pub diffs: Arc<DiffGenerator>,
+    /// Hash of the server admin key, which manages all applications; `None` disables it
+    pub admin_key_hash: Option<String>,
+    /// When deployments failing on clients are withdrawn automatically
+    pub auto_rollback: AutoRollbackPolicy,
+}
+
+impl AppState {
+    /// Create state with the default upload limits
+    pub fn new(database: Arc<DatabaseManager>, storage: Arc<dyn Storage>) -> Self {
+        let diffs = Arc::new(DiffGenerator::new(database.clone(), storage.clone()));
+        Self {
+            database,
+            storage,
+            upload_limits: UploadLimits::default(),
+            public_url: String::new(),
+            diffs,
+            admin_key_hash: None,
+            auto_rollback: AutoRollbackPolicy::default(),
+        }
+    }
+
+    /// Set the externally visible base URL of the server
+    pub fn with_public_url(mut self, public_url: impl Into<String>) -> Self {
+        self.public_url = public_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Enable the server admin key
+    pub fn with_admin_key(mut self, admin_key: &str) -> Self {
+        self.admin_key_hash = Some(hash_api_key(admin_key));
+        self
+    }
+
+    /// Override the automatic rollback policy
+    pub fn with_auto_rollback(mut self, auto_rollback: AutoRollbackPolicy) -> Self {
+        self.auto_rollback = auto_rollback;
+        self
+    }
+
+    /// Override the upload limits
+    pub fn with_upload_limits(mut self, upload_limits: UploadLimits) -> Self {
+        self.upload_limits = upload_limits;
+        self
+    }
+}
@@ -0,0 +1,169 @@
+//! Deployment targeting endpoints
+//!
+//! The targeting rules of a deployment live under
+//! `/apps/{app_id}/deployments/{deployment_id}/targeting` and restrict it to
+//! clients by native app version, OS version, locale, custom tags or client
+//! ID. `POST /apps/{app_id}/update-check/dry-run` takes the body of an update
+//! check and tells which deployment the described client would receive,
+//! falling back from targeted deployments that leave it out to older ones,
+//! and why it would receive nothing otherwise; the credentials in the body
+//! are ignored. Every route requires the `deployments:write` scope.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{post, put},
+};
+use rodepush_core::{BundleId, SemanticVersion};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::AuthenticatedApplication,
+    deployments::{ensure_not_final, load_deployment},
+    environments::load_environment,
+    error::ApiError,
+    response::ApiResponse,
+    state::AppState,
+    update_check::UpdateCheckRequest,
+};
+use crate::cache::{UpdateCheckKey, UpdateTarget};
+use crate::database::{Deployment, TargetingRules};
+
+/// Targeting routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/apps/{app_id}/deployments/{deployment_id}/targeting",
+            put(set_targeting).delete(delete_targeting),
+        )
+        .route("/apps/{app_id}/update-check/dry-run", post(dry_run))
+}
+
+/// What an update check would answer a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunResponse {
+    /// Whether the client would be offered an update
+    pub update_available: bool,
+    /// Deployment serving the client, or else the newest one serving its
+    /// environment, platform and binary version
+    pub deployment: Option<Deployment>,
+    /// Bundle the client would install
+    pub bundle_id: Option<BundleId>,
+    /// Why the client would not be offered an update
+    pub reason: Option<String>,
+}
+
+impl DryRunResponse {
+    /// Evaluate the answer for `request` given the deployments it may
+    /// receive, newest first
+    pub fn new(request: &UpdateCheckRequest, targets: Vec<UpdateTarget>) -> Self {
+        if targets.is_empty() {
+            return Self {
+                update_available: false,
+                deployment: None,
+                bundle_id: None,
+                reason: Some(format!(
+                    "no active deployment in {} serves {} {}",
+                    request.environment.trim(),
+                    request.platform,
+                    request.binary_version
+                )),
+            };
+        }
+
+        let profile = request.profile();
+        let reasons: Vec<String> = targets
+            .iter()
+            .filter_map(|target| {
+                let reason = target.deployment.exclusion_reason(&profile)?;
+                Some(match targets.len() {
+                    1 => reason,
+                    _ => format!("deployment {}: {}", target.deployment.id, reason),
+                })
+            })
+            .collect();
+        let newest = targets[0].deployment.clone();
+        let Some(target) = request.select_target(targets) else {
+            return Self {
+                update_available: false,
+                deployment: Some(newest),
+                bundle_id: None,
+                reason: Some(reasons.join("; ")),
+            };
+        };
+
+        let reason = request.exclusion_reason(&target);
+        Self {
+            update_available: reason.is_none(),
+            bundle_id: reason.is_none().then(|| target.bundle.id.clone()),
+            deployment: Some(target.deployment),
+            reason,
+        }
+    }
+}
+
+/// Replace the targeting rules of a deployment
+async fn set_targeting(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+    Json(rules): Json<TargetingRules>,
+) -> Result<Json<ApiResponse<TargetingRules>>, ApiError> {
+    rules.validate()?;
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    ensure_not_final(&deployment, "target")?;
+
+    let rules = Some(&rules).filter(|rules| !rules.is_empty());
+    if !state
+        .database
+        .set_deployment_targeting(&deployment, rules)
+        .await?
+    {
+        return Err(ApiError::conflict(format!(
+            "Deployment {} changed while targeting it",
+            deployment.id
+        )));
+    }
+    Ok(Json(ApiResponse::success(
+        rules.cloned().unwrap_or_default(),
+    )))
+}
+
+/// Serve a deployment to every client again
+async fn delete_targeting(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<StatusCode, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    ensure_not_final(&deployment, "target")?;
+    state
+        .database
+        .set_deployment_targeting(&deployment, None)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Tell which deployment a client would receive, without serving it
+async fn dry_run(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    Json(request): Json<UpdateCheckRequest>,
+) -> Result<Json<ApiResponse<DryRunResponse>>, ApiError> {
+    request.validate()?;
+    let binary_version = SemanticVersion::parse(&request.binary_version)?;
+    let environment = load_environment(&state, &auth, &app_id, request.environment.trim()).await?;
+
+    let key = UpdateCheckKey::new(
+        environment.application_id,
+        environment.name,
+        request.platform,
+        binary_version,
+    );
+    let targets = state.database.resolve_update_targets(&key).await?;
+    Ok(Json(ApiResponse::success(DryRunResponse::new(
+        &request, targets,
+    ))))
+}
@@ -0,0 +1,213 @@
+//! Install telemetry endpoints
+//!
+//! `POST /telemetry` is called by the mobile SDK to report what happened to
+//! a deployment on the client: downloaded, installed, rolled back on the
+//! device or crashed on launch. Like update checks, it authenticates through
+//! the deployment key or app key in its body. When the share of failed installs of an active
+//! deployment exceeds the [`AutoRollbackPolicy`] threshold over enough
+//! clients, the deployment is paused, which restores the deployments it
+//! superseded, its rollout plan is halted, and the decision is recorded in
+//! its history. Reports are not tied to the clients that were served the
+//! deployment, so the action stays reversible: resuming the deployment and
+//! its plan undoes it, and a deployment resumed after an automatic pause is
+//! not paused automatically again. Outcomes are counted as reports arrive,
+//! so this check costs the same however many clients reported.
+//!
+//! The counted outcomes of a deployment are available under
+//! `/apps/{app_id}/deployments/{deployment_id}/telemetry` with the
+//! `deployments:write` scope.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use rodepush_core::RodePushError;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::{AuthenticatedApplication, authenticate_client},
+    deployments::load_deployment,
+    error::ApiError,
+    response::ApiResponse,
+    state::{AppState, AutoRollbackPolicy},
+};
+use crate::database::{
+    Deployment, DeploymentId, DeploymentStatus, InstallEvent, InstallReport, InstallStats,
+};
+
+/// Actor recorded in the history of deployments paused automatically
+pub const AUTO_ROLLBACK_ACTOR: &str = "telemetry";
+
+/// Longest accepted client ID
+const MAX_CLIENT_ID_LENGTH: usize = 255;
+
+/// Longest accepted report message
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Client reporting route, relative to the API root
+pub fn report_routes() -> Router<AppState> {
+    Router::new().route("/telemetry", post(report_install))
+}
+
+/// Telemetry management routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new().route(
+        "/apps/{app_id}/deployments/{deployment_id}/telemetry",
+        get(get_install_stats),
+    )
+}
+
+/// What a client reports about a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryReportRequest {
+    /// Deployment key of the environment the client follows; replaces
+    /// `app_key`
+    #[serde(default)]
+    pub deployment_key: Option<String>,
+    /// Credential of the application the client belongs to: an access token
+    /// with the `updates:read` scope, or the application API key
+    #[serde(default)]
+    pub app_key: String,
+    /// Deployment the report is about, as received from the update check
+    pub deployment_id: DeploymentId,
+    /// Stable identifier of the client installation
+    pub client_id: String,
+    /// What happened
+    pub event: InstallEvent,
+    /// Details such as a crash message
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl TelemetryReportRequest {
+    /// Check the request for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let client_id = self.client_id.trim();
+        if client_id.is_empty() {
+            return Err(ApiError::bad_request("client_id must not be empty"));
+        }
+        if client_id.len() > MAX_CLIENT_ID_LENGTH {
+            return Err(ApiError::bad_request(format!(
+                "client_id must be at most {} bytes long",
+                MAX_CLIENT_ID_LENGTH
+            )));
+        }
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|message| message.len() > MAX_MESSAGE_LENGTH)
+        {
+            return Err(ApiError::bad_request(format!(
+                "message must be at most {} bytes long",
+                MAX_MESSAGE_LENGTH
+            )));
+        }
+        Ok(())
+    }
+
+    /// Build the report described by the request
+    pub fn into_report(self) -> InstallReport {
+        InstallReport::new(
+            self.deployment_id,
+            self.client_id.trim().to_string(),
+            self.event,
+        )
+        .with_message(self.message)
+    }
+}
+
+/// Record an install outcome and pause the deployment if it fails too often
+async fn report_install(
+    State(state): State<AppState>,
+    Json(request): Json<TelemetryReportRequest>,
+) -> Result<StatusCode, ApiError> {
+    request.validate()?;
+    let (application_id, environment) =
+        authenticate_client(&state, request.deployment_key.as_deref(), &request.app_key).await?;
+
+    // A deployment key only covers the deployments of its environment
+    let deployment = state
+        .database
+        .get_deployment(&request.deployment_id)
+        .await?
+        .filter(|deployment| {
+            deployment.application_id == application_id
+                && environment
+                    .as_ref()
+                    .is_none_or(|environment| &deployment.environment == environment)
+        })
+        .ok_or_else(|| {
+            ApiError::not_found(format!("Deployment {} not found", request.deployment_id))
+        })?;
+
+    let report = request.into_report();
+    let stats = state.database.record_install_report(&report).await?;
+
+    if report.event.is_failure() && deployment.status == DeploymentStatus::Active {
+        apply_auto_rollback(&state, &deployment, &stats, &state.auto_rollback).await?;
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Pause an active deployment whose install outcomes `stats` breach `policy`
+///
+/// Returns the deployment in its paused state when it was paused. A
+/// deployment that was already paused automatically once and resumed since
+/// is left alone.
+pub async fn apply_auto_rollback(
+    state: &AppState,
+    deployment: &Deployment,
+    stats: &InstallStats,
+    policy: &AutoRollbackPolicy,
+) -> Result<Option<Deployment>, ApiError> {
+    let Some(reason) = policy.evaluate(stats) else {
+        return Ok(None);
+    };
+
+    let history = state
+        .database
+        .get_deployment_history(&deployment.id)
+        .await?;
+    if history
+        .iter()
+        .any(|transition| transition.actor.as_deref() == Some(AUTO_ROLLBACK_ACTOR))
+    {
+        return Ok(None);
+    }
+
+    let paused = match state
+        .database
+        .transition_deployment(&deployment.id, |deployment| {
+            Ok(deployment
+                .pause()?
+                .with_actor(Some(AUTO_ROLLBACK_ACTOR.to_string()))
+                .with_reason(Some(reason.clone())))
+        })
+        .await
+    {
+        Ok(paused) => paused,
+        // Another report or an operator withdrew the deployment first
+        Err(RodePushError::Conflict { .. }) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    state.database.halt_rollout(&deployment.id, &reason).await?;
+    tracing::warn!(
+        "Paused deployment {} automatically: {}",
+        deployment.id,
+        reason
+    );
+    Ok(Some(paused))
+}
+
+/// Count the install outcomes of a deployment
+async fn get_install_stats(
+    State(state): State<AppState>,
+    Path((app_id, deployment_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<InstallStats>>, ApiError> {
+    let deployment = load_deployment(&state, &auth, &app_id, &deployment_id).await?;
+    let stats = state.database.get_install_stats(&deployment.id).await?;
+    Ok(Json(ApiResponse::success(stats)))
+}
@@ -0,0 +1,300 @@
+//! Client update-check endpoint
+//!
+//! `POST /update_check` is called by the mobile SDK, typically on app launch.
+//! The client identifies its application and environment either by the
+//! deployment key of the environment, or by app key, usually an access token
+//! limited to the `updates:read` scope, together with the environment name.
+//! It describes what it is running; the answer is either "up to date" or the
+//! bundle it should install, with a link to a differential package from its
+//! current bundle when one is stored and to the full bundle otherwise.
+//! Clients only get a deployment when they match its targeting rules and fall
+//! within its rollout; otherwise they fall back to the next newest live
+//! deployment, typically the general one. Missing packages are generated in
+//! the background after the first check that needs them, so that checks never
+//! wait for one.
+
+use axum::{Json, Router, extract::State, routing::post};
+use rodepush_core::{BundleId, Platform, SemanticVersion};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    auth::authenticate_client, error::ApiError, response::ApiResponse, state::AppState,
+};
+use crate::cache::{UpdateCheckKey, UpdateTarget};
+use crate::database::{
+    ClientProfile, DeploymentId, DiffPackage, DiffPackageId, MAX_TARGETING_VALUES,
+};
+
+/// Update-check routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/update_check", post(update_check))
+}
+
+/// Download path of a full bundle, relative to the server root
+pub fn bundle_download_path(bundle_id: &BundleId) -> String {
+    format!("/api/v1/bundles/{}/download", bundle_id)
+}
+
+/// Download path of a differential package, relative to the server root
+pub fn diff_download_path(diff_id: &DiffPackageId) -> String {
+    format!("/api/v1/diffs/{}/download", diff_id)
+}
+
+/// What a client reports when asking for an update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheckRequest {
+    /// Deployment key of the environment the client follows; replaces
+    /// `app_key` and `environment`
+    #[serde(default)]
+    pub deployment_key: Option<String>,
+    /// Credential of the application the client belongs to: an access token
+    /// with the `updates:read` scope, or the application API key
+    #[serde(default)]
+    pub app_key: String,
+    /// Deployment environment the client follows (e.g., "production")
+    #[serde(default)]
+    pub environment: String,
+    /// Client platform
+    pub platform: Platform,
+    /// Version of the native binary installed on the client
+    pub binary_version: String,
+    /// ID of the bundle the client is running, if it runs a downloaded one
+    #[serde(default)]
+    pub current_bundle_id: Option<BundleId>,
+    /// Checksum of the bundle the client is running
+    #[serde(default)]
+    pub current_bundle_hash: Option<String>,
+    /// Stable identifier of the client installation
+    pub client_id: String,
+    /// Version of the operating system
+    #[serde(default)]
+    pub os_version: Option<String>,
+    /// Device locale (e.g., "en-US")
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Custom tags assigned to the client by the application
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl UpdateCheckRequest {
+    /// Check the client description for obviously invalid values
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.client_id.trim().is_empty() {
+            return Err(ApiError::bad_request("client_id must not be empty"));
+        }
+        if self.tags.len() > MAX_TARGETING_VALUES {
+            return Err(ApiError::bad_request(format!(
+                "tags must have at most {} values",
+                MAX_TARGETING_VALUES
+            )));
+        }
+        Ok(())
+    }
+
+    /// Attributes of the client that targeting rules are evaluated against
+    pub fn profile(&self) -> ClientProfile {
+        ClientProfile {
+            client_id: self.client_id.clone(),
+            binary_version: self.binary_version.clone(),
+            os_version: self.os_version.clone(),
+            locale: self.locale.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+
+    /// Check whether the client already runs the bundle of `target`
+    pub fn is_running(&self, target: &UpdateTarget) -> bool {
+        self.current_bundle_id.as_ref() == Some(&target.bundle.id)
+            || self
+                .current_bundle_hash
+                .as_deref()
+                .is_some_and(|hash| hash.eq_ignore_ascii_case(&target.bundle.checksum))
+    }
+
+    /// Explain why the client should not be offered `target`, if it should not
+    pub fn exclusion_reason(&self, target: &UpdateTarget) -> Option<String> {
+        if self.is_running(target) {
+            return Some(format!("client already runs bundle {}", target.bundle.id));
+        }
+        target.deployment.exclusion_reason(&self.profile())
+    }
+
+    /// Check whether the client should be offered `target`
+    ///
+    /// Clients the deployment does not target are told they are up to date.
+    pub fn should_update_to(&self, target: &UpdateTarget) -> bool {
+        self.exclusion_reason(target).is_none()
+    }
+
+    /// Pick the target serving the client among `targets`, ordered newest first
+    ///
+    /// A client that a deployment's targeting rules or rollout leave out
+    /// falls back to the next deployment, typically the general one.
+    pub fn select_target(&self, targets: Vec<UpdateTarget>) -> Option<UpdateTarget> {
+        let profile = self.profile();
+        targets
+            .into_iter()
+            .find(|target| target.deployment.targets_client(&profile))
+    }
+}
+
+/// Kind of package a client downloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageType {
+    /// The complete bundle
+    Full,
+    /// A differential package from the client's current bundle
+    Diff,
+}
+
+/// Bundle a client should install
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    /// Deployment the bundle is served from
+    pub deployment_id: DeploymentId,
+    /// ID of the bundle to install
+    pub bundle_id: BundleId,
+    /// Version of the bundle to install
+    pub bundle_version: String,
+    /// Checksum of the bundle once installed
+    pub bundle_checksum: String,
+    /// Kind of package behind `download_url`
+    pub package_type: PackageType,
+    /// Where to download the package
+    pub download_url: String,
+    /// Size of the package in bytes
+    pub package_size: u64,
+    /// Checksum of the package
+    pub package_checksum: String,
+    /// Whether the client must install the update
+    pub is_mandatory: bool,
+    /// Release notes of the deployment
+    pub description: Option<String>,
+}
+
+impl UpdateInfo {
+    /// Describe `target`, served as `diff` when a differential package applies
+    pub fn new(target: &UpdateTarget, diff: Option<&DiffPackage>, public_url: &str) -> Self {
+        let (package_type, path, package_size, package_checksum) = match diff {
+            Some(diff) => (
+                PackageType::Diff,
+                diff_download_path(&diff.id),
+                diff.size_bytes,
+                diff.checksum.clone(),
+            ),
+            None => (
+                PackageType::Full,
+                bundle_download_path(&target.bundle.id),
+                target.bundle.size_bytes,
+                target.bundle.checksum.clone(),
+            ),
+        };
+
+        Self {
+            deployment_id: target.deployment.id.clone(),
+            bundle_id: target.bundle.id.clone(),
+            bundle_version: target.bundle.version.clone(),
+            bundle_checksum: target.bundle.checksum.clone(),
+            package_type,
+            download_url: format!("{}{}", public_url, path),
+            package_size,
+            package_checksum,
+            is_mandatory: target.deployment.is_mandatory(),
+            description: target.deployment.description.clone(),
+        }
+    }
+}
+
+/// Answer to an update check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheckResponse {
+    /// Whether an update is available; `false` means the client is up to date
+    pub update_available: bool,
+    /// The update to install, when one is available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update: Option<UpdateInfo>,
+}
+
+impl UpdateCheckResponse {
+    /// The client already runs what it should
+    pub fn up_to_date() -> Self {
+        Self {
+            update_available: false,
+            update: None,
+        }
+    }
+
+    /// The client should install `update`
+    pub fn available(update: UpdateInfo) -> Self {
+        Self {
+            update_available: true,
+            update: Some(update),
+        }
+    }
+}
+
+/// Tell a client which bundle it should be running
+async fn update_check(
+    State(state): State<AppState>,
+    Json(request): Json<UpdateCheckRequest>,
+) -> Result<Json<ApiResponse<UpdateCheckResponse>>, ApiError> {
+    request.validate()?;
+    let binary_version = SemanticVersion::parse(&request.binary_version)?;
+
+    let deployment_key = request.deployment_key.as_deref();
+    if deployment_key.is_none_or(|key| key.trim().is_empty())
+        && request.environment.trim().is_empty()
+    {
+        return Err(ApiError::bad_request("environment must not be empty"));
+    }
+    let (application_id, environment) =
+        authenticate_client(&state, deployment_key, &request.app_key).await?;
+    let environment = environment.unwrap_or_else(|| request.environment.clone());
+
+    let key = UpdateCheckKey::new(
+        application_id,
+        environment,
+        request.platform,
+        binary_version,
+    );
+    let targets = state.database.resolve_update_targets(&key).await?;
+    let target = match request.select_target(targets) {
+        Some(target) if request.should_update_to(&target) => target,
+        _ => {
+            return Ok(Json(
+                ApiResponse::success(UpdateCheckResponse::up_to_date()),
+            ));
+        }
+    };
+
+    // Packages are generated in the background; until one is stored, or if
+    // it cannot be looked up, the client downloads the full bundle
+    let diff = match &request.current_bundle_id {
+        Some(current) => match state.diffs.get_or_queue(current, &target.bundle).await {
+            Ok(diff) => diff,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to look up differential package from {} to {}: {}",
+                    current,
+                    target.bundle.id,
+                    e
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    let update = UpdateInfo::new(&target, diff.as_ref(), &state.public_url);
+    tracing::debug!(
+        "Offering bundle {} ({:?}) to client {}",
+        update.bundle_id,
+        update.package_type,
+        request.client_id
+    );
+    Ok(Json(ApiResponse::success(UpdateCheckResponse::available(
+        update,
+    ))))
+}
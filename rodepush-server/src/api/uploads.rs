@@ -0,0 +1,481 @@
+//! Resumable bundle uploads
+//!
+//! An upload session is created by posting the JSON [`BundleMetadata`] of a
+//! bundle to `POST /apps/{app_id}/uploads`; the answer lists the checksums of
+//! the chunks the server still needs. Each chunk is then sent on its own with
+//! `PUT /apps/{app_id}/uploads/{session_id}/chunks/{checksum}` and verified on
+//! arrival. Once nothing is missing, `POST .../commit` assembles the chunks
+//! into the bundle and records it.
+//!
+//! Creating a session for a bundle that already has an unexpired one resumes
+//! that session, so an interrupted client only sends what never arrived.
+//! Sessions that are not committed expire and are removed together with
+//! their chunks by [`collect_expired_sessions`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    Json, Router,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Path, State},
+    http::StatusCode,
+    routing::{get, post, put},
+};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use rodepush_core::storage::{Storage, StorageKey};
+use rodepush_core::{
+    BundleError, BundleId, RodePushError, StorageError, secure_compare, validate_hash_format,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+use crate::api::{
+    auth::AuthenticatedApplication,
+    bundles::{
+        BundleUploadResponse, ReceivedBundle, bundle_storage_key, find_stored_bundle,
+        parse_metadata, record_bundle, upload_hash_algorithm,
+    },
+    error::ApiError,
+    response::ApiResponse,
+    state::AppState,
+};
+use crate::database::{ApplicationId, DatabaseManager, UploadSession, UploadSessionId};
+
+/// Number of expired sessions removed per collection pass
+const EXPIRED_SESSION_BATCH: i64 = 100;
+
+/// Buffer size used when copying chunks into the assembled bundle
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Upload session routes, relative to the API root
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/apps/{app_id}/uploads", post(create_session))
+        .route(
+            "/apps/{app_id}/uploads/{session_id}",
+            get(get_session).delete(abort_session),
+        )
+        .route(
+            "/apps/{app_id}/uploads/{session_id}/chunks/{checksum}",
+            // Chunk sizes are enforced while streaming, against the session metadata
+            put(upload_chunk).layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/apps/{app_id}/uploads/{session_id}/commit",
+            post(commit_session),
+        )
+}
+
+/// State of an upload session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSessionResponse {
+    /// ID of the upload session
+    pub session_id: UploadSessionId,
+    /// ID of the bundle being uploaded
+    pub bundle_id: BundleId,
+    /// Time after which the session is discarded
+    pub expires_at: DateTime<Utc>,
+    /// Checksums of the chunks still to be uploaded, in upload order
+    pub missing_chunks: Vec<String>,
+}
+
+impl UploadSessionResponse {
+    fn new(session: &UploadSession, missing_chunks: Vec<String>) -> Self {
+        Self {
+            session_id: session.id.clone(),
+            bundle_id: session.bundle_id.clone(),
+            expires_at: session.expires_at,
+            missing_chunks,
+        }
+    }
+}
+
+/// Response body of a chunk upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkUploadResponse {
+    /// Checksum of the stored chunk
+    pub checksum: String,
+    /// Size of the stored chunk in bytes
+    pub size_bytes: u64,
+}
+
+/// Create an upload session, or resume the open one for the same bundle
+async fn create_session(
+    State(state): State<AppState>,
+    Path(app_id): Path<String>,
+    auth: AuthenticatedApplication,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ApiResponse<UploadSessionResponse>>), ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let metadata = parse_metadata(&body, &state.upload_limits)?;
+    let already_stored = find_stored_bundle(&state, &application_id, &metadata)
+        .await?
+        .is_some();
+    let ttl = state.upload_limits.session_ttl();
+
+    let resumable = state
+        .database
+        .get_active_upload_session(&application_id, &metadata.id)
+        .await?
+        .filter(|session| {
+            session.metadata.checksum == metadata.checksum
+                && session.metadata.chunks == metadata.chunks
+        });
+
+    let (status, session) = match resumable {
+        Some(mut session) => {
+            session.extend(ttl);
+            state
+                .database
+                .update_upload_session_expiry(&session)
+                .await?;
+            tracing::info!("Resuming upload session {}", session.id);
+            (StatusCode::OK, session)
+        }
+        None => {
+            let session = UploadSession::new(application_id, metadata, ttl);
+            state.database.create_upload_session(&session).await?;
+            (StatusCode::CREATED, session)
+        }
+    };
+
+    // Nothing needs to be sent for a bundle the server already has
+    let missing = if already_stored {
+        Vec::new()
+    } else {
+        missing_chunks(&session, state.storage.as_ref()).await?
+    };
+    let response = UploadSessionResponse::new(&session, missing);
+    Ok((status, Json(ApiResponse::success(response))))
+}
+
+/// Report the chunks an upload session is still missing
+async fn get_session(
+    State(state): State<AppState>,
+    Path((app_id, session_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<Json<ApiResponse<UploadSessionResponse>>, ApiError> {
+    let session = load_session(&state, &auth, &app_id, &session_id).await?;
+    let missing = missing_chunks(&session, state.storage.as_ref()).await?;
+    Ok(Json(ApiResponse::success(UploadSessionResponse::new(
+        &session, missing,
+    ))))
+}
+
+/// Upload one chunk of a session
+async fn upload_chunk(
+    State(state): State<AppState>,
+    Path((app_id, session_id, checksum)): Path<(String, String, String)>,
+    auth: AuthenticatedApplication,
+    body: Body,
+) -> Result<Json<ApiResponse<ChunkUploadResponse>>, ApiError> {
+    let session = load_session(&state, &auth, &app_id, &session_id).await?;
+    let size_bytes = receive_chunk(
+        &session,
+        &checksum,
+        body.into_data_stream(),
+        state.storage.as_ref(),
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success(ChunkUploadResponse {
+        checksum,
+        size_bytes,
+    })))
+}
+
+/// Assemble the uploaded chunks into the bundle and record it
+async fn commit_session(
+    State(state): State<AppState>,
+    Path((app_id, session_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<(StatusCode, Json<ApiResponse<BundleUploadResponse>>), ApiError> {
+    let session = load_session(&state, &auth, &app_id, &session_id).await?;
+
+    let (status, bundle, created) =
+        match find_stored_bundle(&state, &session.application_id, &session.metadata).await? {
+            Some(existing) => {
+                tracing::info!("Bundle {} already stored, skipping assembly", existing.id);
+                (StatusCode::OK, existing, false)
+            }
+            None => {
+                let received = assemble_bundle(&session, state.storage.as_ref()).await?;
+                let bundle =
+                    record_bundle(&state, session.application_id.clone(), received).await?;
+                (StatusCode::CREATED, bundle, true)
+            }
+        };
+
+    if let Err(e) = remove_session(&state.database, state.storage.as_ref(), &session).await {
+        tracing::warn!("Failed to clean up upload session {}: {}", session.id, e);
+    }
+
+    let response = BundleUploadResponse::from_bundle(&bundle, created);
+    Ok((status, Json(ApiResponse::success(response))))
+}
+
+/// Abandon an upload session and drop its chunks
+async fn abort_session(
+    State(state): State<AppState>,
+    Path((app_id, session_id)): Path<(String, String)>,
+    auth: AuthenticatedApplication,
+) -> Result<StatusCode, ApiError> {
+    let application_id = auth.authorize(&app_id)?;
+    let session = find_session(&state, &application_id, &session_id).await?;
+    remove_session(&state.database, state.storage.as_ref(), &session).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Load a session of an application, failing when it is unknown or expired
+async fn load_session(
+    state: &AppState,
+    auth: &AuthenticatedApplication,
+    app_id: &str,
+    session_id: &str,
+) -> Result<UploadSession, ApiError> {
+    let application_id = auth.authorize(app_id)?;
+    let session = find_session(state, &application_id, session_id).await?;
+    if session.is_expired() {
+        return Err(ApiError::gone(format!(
+            "Upload session {} has expired",
+            session.id
+        )));
+    }
+    Ok(session)
+}
+
+async fn find_session(
+    state: &AppState,
+    application_id: &ApplicationId,
+    session_id: &str,
+) -> Result<UploadSession, ApiError> {
+    let session_id = UploadSessionId::from_string(session_id)?;
+    state
+        .database
+        .get_upload_session(&session_id)
+        .await?
+        .filter(|session| session.application_id == *application_id)
+        .ok_or_else(|| ApiError::not_found(format!("Upload session {} not found", session_id)))
+}
+
+/// Checksums of the chunks of `session` that are not in storage yet
+pub async fn missing_chunks(
+    session: &UploadSession,
+    storage: &dyn Storage,
+) -> Result<Vec<String>, ApiError> {
+    let mut missing = Vec::new();
+    for checksum in session.chunk_checksums() {
+        let key = StorageKey::new(session.chunk_storage_key(checksum));
+        if !storage.exists(&key).await? {
+            missing.push(checksum.to_string());
+        }
+    }
+    Ok(missing)
+}
+
+/// Stream one chunk of an upload session into storage
+///
+/// The chunk is identified by its checksum and only committed if it has the
+/// size and checksum declared in the session metadata. Uploading a chunk that
+/// is already stored replaces it, so retries are harmless.
+pub async fn receive_chunk<S, E>(
+    session: &UploadSession,
+    checksum: &str,
+    mut data: S,
+    storage: &dyn Storage,
+) -> Result<u64, ApiError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    ApiError: From<E>,
+{
+    let algorithm = upload_hash_algorithm(&session.metadata);
+    validate_hash_format(checksum, algorithm)?;
+    let chunk = session
+        .metadata
+        .chunks
+        .iter()
+        .find(|chunk| chunk.checksum == checksum)
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "Upload session {} has no chunk {}",
+                session.id, checksum
+            ))
+        })?;
+
+    let key = StorageKey::new(session.chunk_storage_key(checksum));
+    let mut writer = storage.create_object(&key).await?;
+    let mut hasher = algorithm.hasher();
+    let mut size = 0u64;
+
+    let received: Result<(), ApiError> = async {
+        while let Some(bytes) = data.next().await {
+            let bytes = bytes?;
+            size += bytes.len() as u64;
+            if size > chunk.size {
+                return Err(ApiError::bad_request(format!(
+                    "Chunk {} is larger than its declared {} bytes",
+                    chunk.id, chunk.size
+                )));
+            }
+            hasher.update(&bytes);
+            writer.write(&bytes).await?;
+        }
+        if size != chunk.size {
+            return Err(ApiError::bad_request(format!(
+                "Chunk {} is {} bytes, expected {}",
+                chunk.id, size, chunk.size
+            )));
+        }
+        let actual = hasher.finalize();
+        if !secure_compare(&actual, &chunk.checksum) {
+            return Err(BundleError::checksum_mismatch(chunk.checksum.clone(), actual).into());
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = received {
+        if let Err(abort_error) = writer.abort().await {
+            tracing::warn!("Failed to discard rejected chunk: {}", abort_error);
+        }
+        return Err(e);
+    }
+
+    Ok(writer.commit().await?)
+}
+
+/// Concatenate the uploaded chunks of a session into a bundle object
+///
+/// Every chunk must have been uploaded; the assembled data is checked against
+/// the bundle checksum before it is committed.
+pub async fn assemble_bundle(
+    session: &UploadSession,
+    storage: &dyn Storage,
+) -> Result<ReceivedBundle, ApiError> {
+    let storage_key = bundle_storage_key(&session.application_id, &session.bundle_id);
+    let mut writer = storage.create_object(&storage_key).await?;
+
+    let assembled: Result<(), ApiError> = async {
+        let mut hasher = upload_hash_algorithm(&session.metadata).hasher();
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+        for chunk in &session.metadata.chunks {
+            let key = StorageKey::new(session.chunk_storage_key(&chunk.checksum));
+            let mut reader = match storage.open_object(&key).await {
+                Ok(reader) => reader,
+                Err(RodePushError::Storage(StorageError::NotFound { .. })) => {
+                    return Err(ApiError::conflict(format!(
+                        "Chunk {} has not been uploaded",
+                        chunk.checksum
+                    )));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            loop {
+                let read = reader
+                    .read(&mut buffer)
+                    .await
+                    .map_err(|e| RodePushError::from(StorageError::from(e)))?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+                writer.write(&buffer[..read]).await?;
+            }
+        }
+
+        let actual = hasher.finalize();
+        if !secure_compare(&actual, &session.metadata.checksum) {
+            return Err(
+                BundleError::checksum_mismatch(session.metadata.checksum.clone(), actual).into(),
+            );
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = assembled {
+        if let Err(abort_error) = writer.abort().await {
+            tracing::warn!("Failed to discard assembled bundle: {}", abort_error);
+        }
+        return Err(e);
+    }
+
+    let size_bytes = writer.commit().await?;
+    Ok(ReceivedBundle {
+        metadata: session.metadata.clone(),
+        storage_key,
+        size_bytes,
+    })
+}
+
+/// Delete the stored chunks of an upload session
+pub async fn discard_chunks(
+    session: &UploadSession,
+    storage: &dyn Storage,
+) -> rodepush_core::Result<()> {
+    for checksum in session.chunk_checksums() {
+        let key = StorageKey::new(session.chunk_storage_key(checksum));
+        if storage.exists(&key).await? {
+            storage.delete(&key).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete an upload session along with its chunks
+async fn remove_session(
+    database: &DatabaseManager,
+    storage: &dyn Storage,
+    session: &UploadSession,
+) -> rodepush_core::Result<()> {
+    discard_chunks(session, storage).await?;
+    database.delete_upload_session(&session.id).await
+}
+
+/// Remove expired upload sessions and their chunks
+///
+/// Returns the number of sessions removed. A session whose chunks cannot be
+/// deleted is kept so that a later pass retries it.
+pub async fn collect_expired_sessions(
+    database: &DatabaseManager,
+    storage: &dyn Storage,
+) -> rodepush_core::Result<usize> {
+    let mut removed = 0;
+    for session in database
+        .list_expired_upload_sessions(EXPIRED_SESSION_BATCH)
+        .await?
+    {
+        match remove_session(database, storage, &session).await {
+            Ok(()) => removed += 1,
+            Err(e) => tracing::warn!(
+                "Failed to remove expired upload session {}: {}",
+                session.id,
+                e
+            ),
+        }
+    }
+
+    if removed > 0 {
+        tracing::info!("Removed {} expired upload sessions", removed);
+    }
+    Ok(removed)
+}
+
+/// Periodically remove expired upload sessions in the background
+pub fn spawn_session_collector(
+    database: Arc<DatabaseManager>,
+    storage: Arc<dyn Storage>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = collect_expired_sessions(&database, storage.as_ref()).await {
+                tracing::warn!("Upload session collection failed: {}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,20 @@
+//! Shared cache layer for RodePush Server
+//!
+//! Update checks are the hottest read path of the server: every app launch asks
+//! which bundle it should be running. This module caches the resolved answer and
+//! bundle metadata behind a [`CacheStore`] so that several server replicas can
+//! share them through Redis, with an in-memory store for tests and single-node setups.
+
+// Declare submodules
+pub mod config;
+pub mod memory;
+pub mod redis_store;
+pub mod server_cache;
+pub mod store;
+
+// Re-export commonly used types for convenience
+pub use config::CacheConfig;
+pub use memory::MemoryCacheStore;
+pub use redis_store::RedisCacheStore;
+pub use server_cache::{ServerCache, UpdateCheckKey, UpdateTarget, UpdateTargetsLookup};
+pub use store::CacheStore;
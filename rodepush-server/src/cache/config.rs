@@ -0,0 +1,27 @@
+//! Cache configuration types
+
+use serde::{Deserialize, Serialize};
+
+/// Cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Redis connection URL; an in-memory cache is used when unset
+    pub redis_url: Option<String>,
+    /// Prefix for every key written by this server
+    pub key_prefix: String,
+    /// How long a resolved update-check answer stays cached, in seconds
+    pub update_check_ttl_seconds: u64,
+    /// How long bundle metadata stays cached, in seconds
+    pub bundle_ttl_seconds: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            key_prefix: "rodepush".to_string(),
+            update_check_ttl_seconds: 60,
+            bundle_ttl_seconds: 3600,
+        }
+    }
+}
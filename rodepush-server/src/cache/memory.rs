@@ -0,0 +1,84 @@
+//! In-memory cache store
+
+use async_trait::async_trait;
+use rodepush_core::Result;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use crate::cache::store::CacheStore;
+
+/// Lifetime of counters, which are meant to never expire
+const COUNTER_LIFETIME: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// Process-local cache store
+///
+/// Used in tests and for single-replica deployments without Redis.
+#[derive(Debug, Default)]
+pub struct MemoryCacheStore {
+    entries: RwLock<HashMap<String, (Vec<u8>, Instant)>>,
+}
+
+impl MemoryCacheStore {
+    /// Create a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of live (non-expired) entries
+    pub fn len(&self) -> usize {
+        let now = Instant::now();
+        self.entries
+            .read()
+            .unwrap()
+            .values()
+            .filter(|(_, expires_at)| *expires_at > now)
+            .count()
+    }
+
+    /// Check if the store has no live entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let now = Instant::now();
+        let entries = self.entries.read().unwrap();
+        Ok(entries
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(value, _)| value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()> {
+        let expires_at = Instant::now() + ttl;
+        let mut entries = self.entries.write().unwrap();
+        entries.retain(|_, (_, expires)| *expires > Instant::now());
+        entries.insert(key.to_string(), (value, expires_at));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.entries.write().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn increment(&self, key: &str) -> Result<u64> {
+        let now = Instant::now();
+        let mut entries = self.entries.write().unwrap();
+        let value = entries
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .and_then(|(value, _)| std::str::from_utf8(value).ok()?.parse::<u64>().ok())
+            .unwrap_or(0)
+            + 1;
+        entries.insert(
+            key.to_string(),
+            (value.to_string().into_bytes(), now + COUNTER_LIFETIME),
+        );
+        Ok(value)
+    }
+}
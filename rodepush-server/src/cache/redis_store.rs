@@ -0,0 +1,62 @@
+//! Redis-backed cache store
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use redis::aio::MultiplexedConnection;
+use rodepush_core::{Result, RodePushError, StorageError};
+use std::time::Duration;
+use tracing::info;
+
+use crate::cache::store::CacheStore;
+
+/// Cache store shared between server replicas through Redis
+#[derive(Clone)]
+pub struct RedisCacheStore {
+    connection: MultiplexedConnection,
+}
+
+impl RedisCacheStore {
+    /// Connect to Redis at the given URL
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).map_err(redis_error)?;
+        let connection = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .map_err(redis_error)?;
+
+        info!("Redis cache connection established");
+        Ok(Self { connection })
+    }
+}
+
+#[async_trait]
+impl CacheStore for RedisCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut conn = self.connection.clone();
+        conn.get(key).await.map_err(redis_error)
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()> {
+        let mut conn = self.connection.clone();
+        conn.set_ex(key, value, ttl.as_secs().max(1))
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let mut conn = self.connection.clone();
+        conn.del(key).await.map_err(redis_error)
+    }
+
+    async fn increment(&self, key: &str) -> Result<u64> {
+        let mut conn = self.connection.clone();
+        conn.incr(key, 1u64).await.map_err(redis_error)
+    }
+}
+
+fn redis_error(error: redis::RedisError) -> RodePushError {
+    RodePushError::Storage(StorageError::Backend {
+        backend: "redis".to_string(),
+        message: error.to_string(),
+    })
+}
@@ -0,0 +1,221 @@
+//! Typed cache for update-check answers and bundle metadata
+
+use rodepush_core::{BundleId, Platform, Result, SemanticVersion};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::{
+    config::CacheConfig, memory::MemoryCacheStore, redis_store::RedisCacheStore, store::CacheStore,
+};
+use crate::database::{ApplicationId, Bundle, Deployment};
+
+/// Identifies one update-check question: which deployments may a client
+/// running `binary_version` on `platform` receive in `environment`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateCheckKey {
+    /// Application the client belongs to
+    pub application_id: ApplicationId,
+    /// Deployment environment (e.g., "production", "staging")
+    pub environment: String,
+    /// Client platform
+    pub platform: Platform,
+    /// Native binary version installed on the client
+    pub binary_version: SemanticVersion,
+}
+
+impl UpdateCheckKey {
+    /// Create a new update-check key
+    pub fn new(
+        application_id: ApplicationId,
+        environment: impl Into<String>,
+        platform: Platform,
+        binary_version: SemanticVersion,
+    ) -> Self {
+        Self {
+            application_id,
+            environment: environment.into(),
+            platform,
+            binary_version,
+        }
+    }
+}
+
+/// Resolved update target: the deployment to serve and its bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTarget {
+    /// Deployment that should be served
+    pub deployment: Deployment,
+    /// Bundle referenced by the deployment
+    pub bundle: Bundle,
+}
+
+/// Result of looking up the cached update targets of an update-check question
+#[derive(Debug, Clone)]
+pub struct UpdateTargetsLookup {
+    /// Generation of the environment's cached answers at the time of the lookup
+    pub generation: u64,
+    /// Cached targets, newest first; `None` on a cache miss
+    pub targets: Option<Vec<UpdateTarget>>,
+}
+
+/// Typed cache for the update-check path
+///
+/// Cache errors are reported to the caller, which is expected to fall back to
+/// the database; the cache is never the source of truth.
+#[derive(Clone)]
+pub struct ServerCache {
+    store: Arc<dyn CacheStore>,
+    config: CacheConfig,
+}
+
+impl ServerCache {
+    /// Create a cache on top of an existing store
+    pub fn new(store: Arc<dyn CacheStore>, config: CacheConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// Create a process-local cache with default settings
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(MemoryCacheStore::new()), CacheConfig::default())
+    }
+
+    /// Connect to Redis if `redis_url` is configured, otherwise use memory
+    pub async fn connect(config: CacheConfig) -> Result<Self> {
+        let store: Arc<dyn CacheStore> = match &config.redis_url {
+            Some(url) => Arc::new(RedisCacheStore::connect(url).await?),
+            None => Arc::new(MemoryCacheStore::new()),
+        };
+        Ok(Self::new(store, config))
+    }
+
+    /// Get the cache configuration
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    fn update_check_key(&self, key: &UpdateCheckKey, generation: u64) -> String {
+        format!(
+            "{}:update:{}:{}:{}:{}:{}",
+            self.config.key_prefix,
+            key.application_id,
+            key.environment,
+            generation,
+            key.platform,
+            key.binary_version
+        )
+    }
+
+    fn generation_key(&self, application_id: &ApplicationId, environment: &str) -> String {
+        format!(
+            "{}:generation:{}:{}",
+            self.config.key_prefix, application_id, environment
+        )
+    }
+
+    /// Current generation of the cached answers of an environment
+    ///
+    /// Update-check keys embed the generation, so bumping it on invalidation
+    /// orphans every answer cached before; orphans expire with their TTL.
+    async fn generation(&self, application_id: &ApplicationId, environment: &str) -> Result<u64> {
+        let bytes = self
+            .store
+            .get(&self.generation_key(application_id, environment))
+            .await?;
+        Ok(bytes
+            .and_then(|bytes| String::from_utf8(bytes).ok()?.parse().ok())
+            .unwrap_or(0))
+    }
+
+    fn bundle_key(&self, id: &BundleId) -> String {
+        format!("{}:bundle:{}", self.config.key_prefix, id)
+    }
+
+    /// Get the cached update targets of an update-check question, newest first
+    ///
+    /// The targets are `None` on a cache miss and an empty list when it is
+    /// cached that no deployment matches. The answer to a miss must be cached
+    /// under the generation returned here, read before the database is.
+    pub async fn get_update_targets(&self, key: &UpdateCheckKey) -> Result<UpdateTargetsLookup> {
+        let generation = self
+            .generation(&key.application_id, &key.environment)
+            .await?;
+        let targets = match self
+            .store
+            .get(&self.update_check_key(key, generation))
+            .await?
+        {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        };
+        Ok(UpdateTargetsLookup {
+            generation,
+            targets,
+        })
+    }
+
+    /// Cache the update targets of an update-check question, including none
+    ///
+    /// `generation` is the one the answer was looked up in. If the
+    /// environment was invalidated since, the answer may predate the change
+    /// and lands under a generation that is no longer read.
+    pub async fn set_update_targets(
+        &self,
+        key: &UpdateCheckKey,
+        generation: u64,
+        targets: &[UpdateTarget],
+    ) -> Result<()> {
+        let bytes = serde_json::to_vec(targets)?;
+        self.store
+            .set(
+                &self.update_check_key(key, generation),
+                bytes,
+                Duration::from_secs(self.config.update_check_ttl_seconds),
+            )
+            .await
+    }
+
+    /// Drop every cached update-check answer for an application environment
+    pub async fn invalidate_environment(
+        &self,
+        application_id: &ApplicationId,
+        environment: &str,
+    ) -> Result<()> {
+        let generation = self
+            .store
+            .increment(&self.generation_key(application_id, environment))
+            .await?;
+        tracing::debug!(
+            "Invalidated cached update checks for {}/{} (generation {})",
+            application_id,
+            environment,
+            generation
+        );
+        Ok(())
+    }
+
+    /// Get cached bundle metadata
+    pub async fn get_bundle(&self, id: &BundleId) -> Result<Option<Bundle>> {
+        match self.store.get(&self.bundle_key(id)).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Cache bundle metadata
+    pub async fn set_bundle(&self, bundle: &Bundle) -> Result<()> {
+        let bytes = serde_json::to_vec(bundle)?;
+        self.store
+            .set(
+                &self.bundle_key(&bundle.id),
+                bytes,
+                Duration::from_secs(self.config.bundle_ttl_seconds),
+            )
+            .await
+    }
+
+    /// Drop cached bundle metadata
+    pub async fn invalidate_bundle(&self, id: &BundleId) -> Result<()> {
+        self.store.delete(&self.bundle_key(id)).await
+    }
+}
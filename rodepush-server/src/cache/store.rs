@@ -0,0 +1,26 @@
+//! Key-value store abstraction used by the server cache
+
+use async_trait::async_trait;
+use rodepush_core::Result;
+use std::time::Duration;
+
+/// Trait for cache backends
+///
+/// Values are opaque bytes; serialization is handled by
+/// [`ServerCache`](crate::cache::ServerCache).
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Get a value by key
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store a value that expires after `ttl`
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()>;
+
+    /// Delete a single key
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Increment the counter stored at `key`, returning its new value
+    ///
+    /// A missing counter counts as zero. Counters never expire.
+    async fn increment(&self, key: &str) -> Result<u64>;
+}
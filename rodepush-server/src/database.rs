@@ -5,21 +5,49 @@
 //! Supports PostgreSQL as primary database with extensible architecture for MySQL support.
 
 // Declare submodules
+pub mod access_token;
 pub mod application;
 pub mod bundle;
 pub mod config;
 pub mod connection;
 pub mod deployment;
 pub mod diff_package;
+pub mod environment;
 pub mod error;
 pub mod manager;
+pub mod member;
+pub mod rollout_plan;
+pub mod targeting;
+pub mod telemetry;
+pub mod upload_session;
 
 // Re-export commonly used types for convenience
-pub use application::{Application, ApplicationId, ApplicationService};
-pub use bundle::{Bundle, BundleService, DatabaseBundleId};
+pub use access_token::{
+    ACCESS_TOKEN_PREFIX, AccessToken, AccessTokenId, AccessTokenService, TokenScope,
+    generate_access_token,
+};
+pub use application::{
+    API_KEY_PREFIX, Application, ApplicationId, ApplicationService, generate_api_key, hash_api_key,
+};
+pub use bundle::{Bundle, BundleService, DatabaseBundleId, releases_overlap};
 pub use config::{DatabaseConfig, DatabaseType};
 pub use connection::{DatabaseConnection, DatabasePool};
-pub use deployment::{Deployment, DeploymentId, DeploymentService, DeploymentStatus};
+pub use deployment::{
+    Deployment, DeploymentId, DeploymentService, DeploymentStatus, DeploymentTransition,
+};
 pub use diff_package::{DiffPackage, DiffPackageId, DiffPackageService};
+pub use environment::{
+    DEPLOYMENT_KEY_PREFIX, Environment, EnvironmentId, EnvironmentService, generate_deployment_key,
+    validate_environment_name,
+};
 pub use error::DatabaseError;
 pub use manager::DatabaseManager;
+pub use member::{ApplicationMember, MemberRole, MemberService};
+pub use rollout_plan::{
+    MAX_STEP_WAIT_SECONDS, RolloutPlan, RolloutPlanService, RolloutPlanStatus, RolloutStep,
+};
+pub use targeting::{
+    ClientProfile, MAX_TARGETING_VALUES, TARGETING_METADATA_KEY, TargetingRules, VersionRange,
+};
+pub use telemetry::{InstallEvent, InstallReport, InstallStats, TelemetryService};
+pub use upload_session::{UploadSession, UploadSessionId, UploadSessionService};
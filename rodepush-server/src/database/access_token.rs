@@ -0,0 +1,489 @@
+//! Scoped access tokens and data models
+
+use chrono::{DateTime, Utc};
+use rodepush_core::{Result, RodePushError};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::{
+    application::{ApplicationId, hash_api_key},
+    connection::DatabasePool,
+    error::DatabaseError,
+};
+use sqlx::Row;
+
+/// Prefix of generated access tokens, telling them apart from application API keys
+pub const ACCESS_TOKEN_PREFIX: &str = "rpt_";
+
+/// Generate a new random access token
+pub fn generate_access_token() -> String {
+    format!(
+        "{}{}{}",
+        ACCESS_TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Access token identifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AccessTokenId(Uuid);
+
+impl AccessTokenId {
+    /// Generate a new access token ID
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// Create from existing UUID
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+
+    /// Create from string representation
+    pub fn from_string(s: &str) -> Result<Self> {
+        let uuid = Uuid::parse_str(s).map_err(|e| RodePushError::Validation {
+            message: format!("Invalid UUID: {}", e),
+        })?;
+        Ok(Self(uuid))
+    }
+
+    /// Get the underlying UUID
+    pub fn as_uuid(&self) -> Uuid {
+        self.0
+    }
+
+    /// Get string representation
+    pub fn as_str(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl Default for AccessTokenId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for AccessTokenId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Operation class an access token may perform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TokenScope {
+    /// Upload bundles
+    #[serde(rename = "bundles:write")]
+    BundlesWrite,
+    /// Create, change and roll back deployments
+    #[serde(rename = "deployments:write")]
+    DeploymentsWrite,
+    /// Check for updates
+    #[serde(rename = "updates:read")]
+    UpdatesRead,
+    /// Everything, including managing tokens and members
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl TokenScope {
+    /// All scopes
+    pub const ALL: [TokenScope; 4] = [
+        TokenScope::BundlesWrite,
+        TokenScope::DeploymentsWrite,
+        TokenScope::UpdatesRead,
+        TokenScope::Admin,
+    ];
+
+    /// Check whether holding this scope allows operations requiring `required`
+    pub fn grants(self, required: TokenScope) -> bool {
+        self == TokenScope::Admin || self == required
+    }
+}
+
+impl std::fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenScope::BundlesWrite => write!(f, "bundles:write"),
+            TokenScope::DeploymentsWrite => write!(f, "deployments:write"),
+            TokenScope::UpdatesRead => write!(f, "updates:read"),
+            TokenScope::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for TokenScope {
+    type Err = RodePushError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "bundles:write" => Ok(TokenScope::BundlesWrite),
+            "deployments:write" => Ok(TokenScope::DeploymentsWrite),
+            "updates:read" => Ok(TokenScope::UpdatesRead),
+            "admin" => Ok(TokenScope::Admin),
+            _ => Err(RodePushError::Validation {
+                message: format!("Invalid token scope: {}", s),
+            }),
+        }
+    }
+}
+
+/// Access token model
+///
+/// Tokens are scoped to one application. A token issued to a member of the
+/// application is further limited by the member's role, so that changing or
+/// removing the membership takes effect on tokens already handed out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessToken {
+    /// Access token ID
+    pub id: AccessTokenId,
+    /// Application the token grants access to
+    pub application_id: ApplicationId,
+    /// Human readable name (e.g., "CI uploads")
+    pub name: String,
+    /// SHA-256 hash of the token; the token itself is never stored
+    pub token_hash: String,
+    /// Granted scopes
+    pub scopes: Vec<TokenScope>,
+    /// Member the token was issued to, if any
+    pub member: Option<String>,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Time after which the token is rejected; `None` for tokens that do not expire
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Time the token was revoked
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl AccessToken {
+    /// Create a new access token for `token` with the given scopes
+    pub fn new(
+        application_id: ApplicationId,
+        name: String,
+        token: &str,
+        scopes: Vec<TokenScope>,
+    ) -> Self {
+        Self {
+            id: AccessTokenId::new(),
+            application_id,
+            name,
+            token_hash: hash_api_key(token),
+            scopes,
+            member: None,
+            created_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
+        }
+    }
+
+    /// Set the expiry time
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Issue the token to a member of the application
+    pub fn with_member(mut self, member: String) -> Self {
+        self.member = Some(member);
+        self
+    }
+
+    /// Check whether the token has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+
+    /// Check whether the token has been revoked
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// Check whether the token allows operations requiring `scope`
+    pub fn grants(&self, scope: TokenScope) -> bool {
+        self.scopes.iter().any(|granted| granted.grants(scope))
+    }
+}
+
+/// Access token service for database operations
+pub struct AccessTokenService;
+
+impl AccessTokenService {
+    /// Create a new access token in the database
+    pub async fn create(pool: &DatabasePool, token: &AccessToken) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::create_postgres(pg_pool, token).await,
+            DatabasePool::MySql(mysql_pool) => Self::create_mysql(mysql_pool, token).await,
+        }
+    }
+
+    /// Get access token by ID from the database
+    pub async fn get_by_id(pool: &DatabasePool, id: &AccessTokenId) -> Result<Option<AccessToken>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::get_by_id_postgres(pg_pool, id).await,
+            DatabasePool::MySql(mysql_pool) => Self::get_by_id_mysql(mysql_pool, id).await,
+        }
+    }
+
+    /// Get access token by its plaintext value from the database
+    pub async fn get_by_token(pool: &DatabasePool, token: &str) -> Result<Option<AccessToken>> {
+        let token_hash = hash_api_key(token);
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::get_by_hash_postgres(pg_pool, &token_hash).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::get_by_hash_mysql(mysql_pool, &token_hash).await
+            }
+        }
+    }
+
+    /// List the access tokens of an application, newest first
+    pub async fn list_for_application(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<AccessToken>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::list_for_application_postgres(pg_pool, application_id).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::list_for_application_mysql(mysql_pool, application_id).await
+            }
+        }
+    }
+
+    /// Record the revocation of an access token
+    pub async fn revoke(pool: &DatabasePool, token: &AccessToken) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::revoke_postgres(pg_pool, token).await,
+            DatabasePool::MySql(mysql_pool) => Self::revoke_mysql(mysql_pool, token).await,
+        }
+    }
+
+    // PostgreSQL implementations
+    async fn create_postgres(pool: &sqlx::PgPool, token: &AccessToken) -> Result<()> {
+        let query = r#"
+            INSERT INTO access_tokens (id, application_id, name, token_hash, scopes, member, created_at, expires_at, revoked_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#;
+
+        sqlx::query(query)
+            .bind(token.id.as_uuid())
+            .bind(token.application_id.as_uuid())
+            .bind(&token.name)
+            .bind(&token.token_hash)
+            .bind(serde_json::to_value(&token.scopes)?)
+            .bind(&token.member)
+            .bind(token.created_at)
+            .bind(token.expires_at)
+            .bind(token.revoked_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Created access token: {}", token.id);
+        Ok(())
+    }
+
+    async fn get_by_id_postgres(
+        pool: &sqlx::PgPool,
+        id: &AccessTokenId,
+    ) -> Result<Option<AccessToken>> {
+        let query = "SELECT * FROM access_tokens WHERE id = $1";
+
+        let row = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn get_by_hash_postgres(
+        pool: &sqlx::PgPool,
+        token_hash: &str,
+    ) -> Result<Option<AccessToken>> {
+        let query = "SELECT * FROM access_tokens WHERE token_hash = $1";
+
+        let row = sqlx::query(query)
+            .bind(token_hash)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn list_for_application_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<AccessToken>> {
+        let query = r#"
+            SELECT * FROM access_tokens
+            WHERE application_id = $1
+            ORDER BY created_at DESC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_postgres_row).collect()
+    }
+
+    async fn revoke_postgres(pool: &sqlx::PgPool, token: &AccessToken) -> Result<()> {
+        let query = "UPDATE access_tokens SET revoked_at = $2 WHERE id = $1";
+
+        sqlx::query(query)
+            .bind(token.id.as_uuid())
+            .bind(token.revoked_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Revoked access token: {}", token.id);
+        Ok(())
+    }
+
+    fn from_postgres_row(row: &sqlx::postgres::PgRow) -> Result<AccessToken> {
+        Ok(AccessToken {
+            id: AccessTokenId::from_uuid(row.get("id")),
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            name: row.get("name"),
+            token_hash: row.get("token_hash"),
+            scopes: serde_json::from_value(row.get("scopes"))?,
+            member: row.get("member"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+            revoked_at: row.get("revoked_at"),
+        })
+    }
+
+    // MySQL implementations
+    async fn create_mysql(pool: &sqlx::MySqlPool, token: &AccessToken) -> Result<()> {
+        let query = r#"
+            INSERT INTO access_tokens (id, application_id, name, token_hash, scopes, member, created_at, expires_at, revoked_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(token.id.as_uuid())
+            .bind(token.application_id.as_uuid())
+            .bind(&token.name)
+            .bind(&token.token_hash)
+            .bind(serde_json::to_value(&token.scopes)?)
+            .bind(&token.member)
+            .bind(token.created_at)
+            .bind(token.expires_at)
+            .bind(token.revoked_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Created access token: {}", token.id);
+        Ok(())
+    }
+
+    async fn get_by_id_mysql(
+        pool: &sqlx::MySqlPool,
+        id: &AccessTokenId,
+    ) -> Result<Option<AccessToken>> {
+        let query = "SELECT * FROM access_tokens WHERE id = ?";
+
+        let row = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn get_by_hash_mysql(
+        pool: &sqlx::MySqlPool,
+        token_hash: &str,
+    ) -> Result<Option<AccessToken>> {
+        let query = "SELECT * FROM access_tokens WHERE token_hash = ?";
+
+        let row = sqlx::query(query)
+            .bind(token_hash)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn list_for_application_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<AccessToken>> {
+        let query = r#"
+            SELECT * FROM access_tokens
+            WHERE application_id = ?
+            ORDER BY created_at DESC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_mysql_row).collect()
+    }
+
+    async fn revoke_mysql(pool: &sqlx::MySqlPool, token: &AccessToken) -> Result<()> {
+        let query = "UPDATE access_tokens SET revoked_at = ? WHERE id = ?";
+
+        sqlx::query(query)
+            .bind(token.revoked_at)
+            .bind(token.id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Revoked access token: {}", token.id);
+        Ok(())
+    }
+
+    fn from_mysql_row(row: &sqlx::mysql::MySqlRow) -> Result<AccessToken> {
+        Ok(AccessToken {
+            id: AccessTokenId::from_uuid(row.get("id")),
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            name: row.get("name"),
+            token_hash: row.get("token_hash"),
+            scopes: serde_json::from_value(row.get("scopes"))?,
+            member: row.get("member"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+            revoked_at: row.get("revoked_at"),
+        })
+    }
+}
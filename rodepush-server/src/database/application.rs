@@ -1,12 +1,16 @@
 //! Application management and data models
 
 use chrono::{DateTime, Utc};
-use rodepush_core::{Result, RodePushError};
+use rodepush_core::{BulkHasher, HashAlgorithm, Result, RodePushError, secure_compare};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::database::{connection::DatabasePool, error::DatabaseError};
+use crate::database::{
+    connection::DatabasePool,
+    environment::{Environment, EnvironmentService, validate_environment_name},
+    error::DatabaseError,
+};
 use sqlx::Row;
 
 /// Application identifier
@@ -62,8 +66,8 @@ pub struct Application {
     pub id: ApplicationId,
     /// Application name
     pub name: String,
-    /// API key for authentication
-    pub api_key: String,
+    /// SHA-256 hash of the API key; the key itself is never stored
+    pub api_key_hash: String,
     /// Creation timestamp
     pub created_at: DateTime<Utc>,
     /// Last update timestamp
@@ -77,13 +81,13 @@ pub struct Application {
 }
 
 impl Application {
-    /// Create a new application
+    /// Create a new application authenticated by `api_key`
     pub fn new(name: String, api_key: String) -> Self {
         let now = Utc::now();
         Self {
             id: ApplicationId::new(),
             name,
-            api_key,
+            api_key_hash: hash_api_key(&api_key),
             created_at: now,
             updated_at: now,
             description: None,
@@ -109,6 +113,38 @@ impl Application {
         self.settings.insert(key, value);
         self
     }
+
+    /// Replace the API key of the application
+    pub fn set_api_key(&mut self, api_key: &str) {
+        self.api_key_hash = hash_api_key(api_key);
+        self.updated_at = Utc::now();
+    }
+
+    /// Check whether `api_key` is the API key of the application
+    pub fn verify_api_key(&self, api_key: &str) -> bool {
+        secure_compare(&hash_api_key(api_key), &self.api_key_hash)
+    }
+}
+
+/// Prefix of generated API keys, making leaked keys easy to recognize
+pub const API_KEY_PREFIX: &str = "rp_";
+
+/// Generate a new random API key
+pub fn generate_api_key() -> String {
+    format!(
+        "{}{}{}",
+        API_KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Hash an API key for storage and lookup
+///
+/// API keys are long random strings, so a plain SHA-256 digest is enough to
+/// keep them out of the database without a slow password hash.
+pub fn hash_api_key(api_key: &str) -> String {
+    BulkHasher::new(HashAlgorithm::Sha256).hash_data(api_key.as_bytes())
 }
 
 /// Application service for database operations
@@ -123,6 +159,25 @@ impl ApplicationService {
         }
     }
 
+    /// Create a new application together with its first environments, atomically
+    pub async fn create_with_environments(
+        pool: &DatabasePool,
+        application: &Application,
+        environments: &[Environment],
+    ) -> Result<()> {
+        for environment in environments {
+            validate_environment_name(&environment.name)?;
+        }
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::create_with_environments_postgres(pg_pool, application, environments).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::create_with_environments_mysql(mysql_pool, application, environments).await
+            }
+        }
+    }
+
     /// Get application by ID from the database
     pub async fn get_by_id(pool: &DatabasePool, id: &ApplicationId) -> Result<Option<Application>> {
         match pool {
@@ -168,22 +223,25 @@ impl ApplicationService {
     }
 
     // PostgreSQL implementations
-    async fn create_postgres(pool: &sqlx::PgPool, application: &Application) -> Result<()> {
+    async fn create_postgres(
+        executor: impl sqlx::PgExecutor<'_>,
+        application: &Application,
+    ) -> Result<()> {
         let query = r#"
-            INSERT INTO applications (id, name, api_key, created_at, updated_at, description, owner, settings)
+            INSERT INTO applications (id, name, api_key_hash, created_at, updated_at, description, owner, settings)
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         "#;
 
         sqlx::query(query)
             .bind(application.id.as_uuid())
             .bind(&application.name)
-            .bind(&application.api_key)
+            .bind(&application.api_key_hash)
             .bind(application.created_at)
             .bind(application.updated_at)
             .bind(&application.description)
             .bind(&application.owner)
             .bind(serde_json::to_value(&application.settings)?)
-            .execute(pool)
+            .execute(executor)
             .await
             .map_err(|e| DatabaseError::Query {
                 message: e.to_string(),
@@ -193,6 +251,24 @@ impl ApplicationService {
         Ok(())
     }
 
+    async fn create_with_environments_postgres(
+        pool: &sqlx::PgPool,
+        application: &Application,
+        environments: &[Environment],
+    ) -> Result<()> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        Self::create_postgres(&mut *tx, application).await?;
+        for environment in environments {
+            EnvironmentService::create_postgres(&mut *tx, environment).await?;
+        }
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        Ok(())
+    }
+
     async fn get_by_id_postgres(
         pool: &sqlx::PgPool,
         id: &ApplicationId,
@@ -212,7 +288,7 @@ impl ApplicationService {
                 let application = Application {
                     id: ApplicationId::from_uuid(row.get("id")),
                     name: row.get("name"),
-                    api_key: row.get("api_key"),
+                    api_key_hash: row.get("api_key_hash"),
                     created_at: row.get("created_at"),
                     updated_at: row.get("updated_at"),
                     description: row.get("description"),
@@ -229,10 +305,10 @@ impl ApplicationService {
         pool: &sqlx::PgPool,
         api_key: &str,
     ) -> Result<Option<Application>> {
-        let query = "SELECT * FROM applications WHERE api_key = $1";
+        let query = "SELECT * FROM applications WHERE api_key_hash = $1";
 
         let row = sqlx::query(query)
-            .bind(api_key)
+            .bind(hash_api_key(api_key))
             .fetch_optional(pool)
             .await
             .map_err(|e| DatabaseError::Query {
@@ -244,7 +320,7 @@ impl ApplicationService {
                 let application = Application {
                     id: ApplicationId::from_uuid(row.get("id")),
                     name: row.get("name"),
-                    api_key: row.get("api_key"),
+                    api_key_hash: row.get("api_key_hash"),
                     created_at: row.get("created_at"),
                     updated_at: row.get("updated_at"),
                     description: row.get("description"),
@@ -260,14 +336,14 @@ impl ApplicationService {
     async fn update_postgres(pool: &sqlx::PgPool, application: &Application) -> Result<()> {
         let query = r#"
             UPDATE applications 
-            SET name = $2, api_key = $3, updated_at = $4, description = $5, owner = $6, settings = $7
+            SET name = $2, api_key_hash = $3, updated_at = $4, description = $5, owner = $6, settings = $7
             WHERE id = $1
         "#;
 
         sqlx::query(query)
             .bind(application.id.as_uuid())
             .bind(&application.name)
-            .bind(&application.api_key)
+            .bind(&application.api_key_hash)
             .bind(application.updated_at)
             .bind(&application.description)
             .bind(&application.owner)
@@ -322,7 +398,7 @@ impl ApplicationService {
             let application = Application {
                 id: ApplicationId::from_uuid(row.get("id")),
                 name: row.get("name"),
-                api_key: row.get("api_key"),
+                api_key_hash: row.get("api_key_hash"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
                 description: row.get("description"),
@@ -336,22 +412,25 @@ impl ApplicationService {
     }
 
     // MySQL implementations
-    async fn create_mysql(pool: &sqlx::MySqlPool, application: &Application) -> Result<()> {
+    async fn create_mysql(
+        executor: impl sqlx::MySqlExecutor<'_>,
+        application: &Application,
+    ) -> Result<()> {
         let query = r#"
-            INSERT INTO applications (id, name, api_key, created_at, updated_at, description, owner, settings)
+            INSERT INTO applications (id, name, api_key_hash, created_at, updated_at, description, owner, settings)
             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         "#;
 
         sqlx::query(query)
             .bind(application.id.as_uuid())
             .bind(&application.name)
-            .bind(&application.api_key)
+            .bind(&application.api_key_hash)
             .bind(application.created_at)
             .bind(application.updated_at)
             .bind(&application.description)
             .bind(&application.owner)
             .bind(serde_json::to_value(&application.settings)?)
-            .execute(pool)
+            .execute(executor)
             .await
             .map_err(|e| DatabaseError::Query {
                 message: e.to_string(),
@@ -361,6 +440,24 @@ impl ApplicationService {
         Ok(())
     }
 
+    async fn create_with_environments_mysql(
+        pool: &sqlx::MySqlPool,
+        application: &Application,
+        environments: &[Environment],
+    ) -> Result<()> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        Self::create_mysql(&mut *tx, application).await?;
+        for environment in environments {
+            EnvironmentService::create_mysql(&mut *tx, environment).await?;
+        }
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        Ok(())
+    }
+
     async fn get_by_id_mysql(
         pool: &sqlx::MySqlPool,
         id: &ApplicationId,
@@ -380,7 +477,7 @@ impl ApplicationService {
                 let application = Application {
                     id: ApplicationId::from_uuid(row.get("id")),
                     name: row.get("name"),
-                    api_key: row.get("api_key"),
+                    api_key_hash: row.get("api_key_hash"),
                     created_at: row.get("created_at"),
                     updated_at: row.get("updated_at"),
                     description: row.get("description"),
@@ -397,10 +494,10 @@ impl ApplicationService {
         pool: &sqlx::MySqlPool,
         api_key: &str,
     ) -> Result<Option<Application>> {
-        let query = "SELECT * FROM applications WHERE api_key = ?";
+        let query = "SELECT * FROM applications WHERE api_key_hash = ?";
 
         let row = sqlx::query(query)
-            .bind(api_key)
+            .bind(hash_api_key(api_key))
             .fetch_optional(pool)
             .await
             .map_err(|e| DatabaseError::Query {
@@ -412,7 +509,7 @@ impl ApplicationService {
                 let application = Application {
                     id: ApplicationId::from_uuid(row.get("id")),
                     name: row.get("name"),
-                    api_key: row.get("api_key"),
+                    api_key_hash: row.get("api_key_hash"),
                     created_at: row.get("created_at"),
                     updated_at: row.get("updated_at"),
                     description: row.get("description"),
@@ -428,13 +525,13 @@ impl ApplicationService {
     async fn update_mysql(pool: &sqlx::MySqlPool, application: &Application) -> Result<()> {
         let query = r#"
             UPDATE applications 
-            SET name = ?, api_key = ?, updated_at = ?, description = ?, owner = ?, settings = ?
+            SET name = ?, api_key_hash = ?, updated_at = ?, description = ?, owner = ?, settings = ?
             WHERE id = ?
         "#;
 
         sqlx::query(query)
             .bind(&application.name)
-            .bind(&application.api_key)
+            .bind(&application.api_key_hash)
             .bind(application.updated_at)
             .bind(&application.description)
             .bind(&application.owner)
@@ -490,7 +587,7 @@ impl ApplicationService {
             let application = Application {
                 id: ApplicationId::from_uuid(row.get("id")),
                 name: row.get("name"),
-                api_key: row.get("api_key"),
+                api_key_hash: row.get("api_key_hash"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
                 description: row.get("description"),
@@ -1,7 +1,7 @@
 //! Bundle management and data models
 
 use chrono::{DateTime, Utc};
-use rodepush_core::{BundleId, Platform, Result};
+use rodepush_core::{BundleId, Platform, Result, SemanticVersion};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -69,6 +69,37 @@ impl Bundle {
         self.id = id;
         self
     }
+
+    /// Check whether this bundle serves some of the clients `other` serves
+    ///
+    /// See [`releases_overlap`].
+    pub fn overlaps(&self, other: &Bundle) -> bool {
+        releases_overlap(self.platform, &self.version, other.platform, &other.version)
+    }
+}
+
+/// Check whether two bundle releases serve some of the same clients
+///
+/// Releases overlap when their platforms do, `both` overlapping either
+/// platform, and their versions share a major.minor release line, the range
+/// of binary versions a bundle is offered to. A version that cannot be
+/// parsed only overlaps the identical version.
+pub fn releases_overlap(
+    platform: Platform,
+    version: &str,
+    other_platform: Platform,
+    other_version: &str,
+) -> bool {
+    if !platform.is_compatible_with(other_platform) {
+        return false;
+    }
+    match (
+        SemanticVersion::parse(version),
+        SemanticVersion::parse(other_version),
+    ) {
+        (Ok(version), Ok(other_version)) => version.is_compatible_with(&other_version),
+        _ => version == other_version,
+    }
 }
 
 /// Bundle service for database operations
@@ -1,13 +1,23 @@
 //! Deployment management and data models
 
 use chrono::{DateTime, Utc};
-use rodepush_core::{BundleId, Result, RodePushError};
+use rodepush_core::{
+    BulkHasher, BundleId, HashAlgorithm, Platform, Result, RodePushError, SemanticVersion,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
 use uuid::Uuid;
 
-use crate::database::{application::ApplicationId, connection::DatabasePool, error::DatabaseError};
+use crate::database::{
+    application::ApplicationId,
+    bundle::releases_overlap,
+    connection::DatabasePool,
+    environment::EnvironmentService,
+    error::DatabaseError,
+    rollout_plan::{RolloutPlan, RolloutPlanService},
+    targeting::{ClientProfile, TARGETING_METADATA_KEY, TargetingRules},
+};
 use sqlx::Row;
 
 /// Deployment identifier
@@ -56,8 +66,24 @@ impl std::fmt::Display for DeploymentId {
     }
 }
 
+/// Metadata key marking a deployment as a mandatory update
+pub const MANDATORY_METADATA_KEY: &str = "mandatory";
+
+/// Metadata key holding the deployment that superseded a deployment
+pub const SUPERSEDED_BY_METADATA_KEY: &str = "superseded_by";
+
 /// Deployment status
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Deployments start out pending and go live when promoted. Promoting a
+/// deployment supersedes the live (active or paused) deployments of its
+/// environment whose bundles serve some of the same clients and that have the
+/// same targeting rules, so bundles for different platforms or release lines,
+/// and targeted deployments next to the general one, stay live side by side.
+/// A deployment only replaces them while it is active and rolled out to every
+/// client: until then they keep serving the clients outside its rollout, and
+/// when it is paused, rolled back or fails, the deployments it superseded go
+/// back to the status they had. Rolled back and failed deployments are final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DeploymentStatus {
     /// Deployment is pending
     Pending,
@@ -69,6 +95,37 @@ pub enum DeploymentStatus {
     RolledBack,
     /// Deployment failed
     Failed,
+    /// Deployment was replaced by a newer one in its environment
+    Superseded,
+}
+
+impl DeploymentStatus {
+    /// Statuses a deployment in this status may move to
+    pub fn allowed_transitions(&self) -> &'static [DeploymentStatus] {
+        use DeploymentStatus::*;
+        match self {
+            Pending => &[Active, Failed],
+            Active => &[Paused, RolledBack, Failed, Superseded],
+            Paused => &[Active, RolledBack, Failed, Superseded],
+            Superseded => &[Active, Paused],
+            RolledBack | Failed => &[],
+        }
+    }
+
+    /// Check whether a deployment in this status may move to `next`
+    pub fn can_transition_to(&self, next: DeploymentStatus) -> bool {
+        self.allowed_transitions().contains(&next)
+    }
+
+    /// Check whether deployments in this status can no longer change
+    pub fn is_final(&self) -> bool {
+        self.allowed_transitions().is_empty()
+    }
+
+    /// Check whether deployments in this status occupy their environment
+    pub fn is_live(&self) -> bool {
+        matches!(self, DeploymentStatus::Active | DeploymentStatus::Paused)
+    }
 }
 
 impl std::fmt::Display for DeploymentStatus {
@@ -79,6 +136,7 @@ impl std::fmt::Display for DeploymentStatus {
             DeploymentStatus::Paused => write!(f, "paused"),
             DeploymentStatus::RolledBack => write!(f, "rolled_back"),
             DeploymentStatus::Failed => write!(f, "failed"),
+            DeploymentStatus::Superseded => write!(f, "superseded"),
         }
     }
 }
@@ -93,6 +151,7 @@ impl std::str::FromStr for DeploymentStatus {
             "paused" => Ok(DeploymentStatus::Paused),
             "rolled_back" => Ok(DeploymentStatus::RolledBack),
             "failed" => Ok(DeploymentStatus::Failed),
+            "superseded" => Ok(DeploymentStatus::Superseded),
             _ => Err(RodePushError::Validation {
                 message: format!("Invalid deployment status: {}", s),
             }),
@@ -100,6 +159,39 @@ impl std::str::FromStr for DeploymentStatus {
     }
 }
 
+/// Record of a deployment changing its status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentTransition {
+    /// Transition ID
+    pub id: Uuid,
+    /// Deployment that changed
+    pub deployment_id: DeploymentId,
+    /// Status before the transition
+    pub from_status: DeploymentStatus,
+    /// Status after the transition
+    pub to_status: DeploymentStatus,
+    /// Who caused the transition (e.g., "api_key" or "token:<id>")
+    pub actor: Option<String>,
+    /// Why the transition happened
+    pub reason: Option<String>,
+    /// Transition timestamp
+    pub created_at: DateTime<Utc>,
+}
+
+impl DeploymentTransition {
+    /// Record who caused the transition
+    pub fn with_actor(mut self, actor: Option<String>) -> Self {
+        self.actor = actor;
+        self
+    }
+
+    /// Record why the transition happened
+    pub fn with_reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason;
+        self
+    }
+}
+
 /// Deployment model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Deployment {
@@ -164,48 +256,250 @@ impl Deployment {
         self
     }
 
-    /// Mark deployment as active
-    pub fn activate(&mut self) {
-        self.status = DeploymentStatus::Active;
-        self.deployed_at = Some(Utc::now());
+    /// Mark whether clients must install this deployment
+    pub fn with_mandatory(mut self, mandatory: bool) -> Self {
+        self.metadata.insert(
+            MANDATORY_METADATA_KEY.to_string(),
+            serde_json::Value::Bool(mandatory),
+        );
+        self
+    }
+
+    /// Check whether clients must install this deployment
+    pub fn is_mandatory(&self) -> bool {
+        self.metadata
+            .get(MANDATORY_METADATA_KEY)
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    /// Rollout bucket (0-99) a client falls into for this deployment
+    ///
+    /// The bucket only depends on the deployment and the client, so a client
+    /// keeps its answer across update checks and stays included while the
+    /// rollout percentage grows.
+    pub fn rollout_bucket(&self, client_id: &str) -> u32 {
+        let digest = BulkHasher::new(HashAlgorithm::Sha256)
+            .hash_data(format!("{}:{}", self.id, client_id).as_bytes());
+        let value = u64::from_str_radix(&digest[..16], 16).unwrap_or(0);
+        (value % 100) as u32
+    }
+
+    /// Check whether the rollout of this deployment includes a client
+    pub fn includes_client(&self, client_id: &str) -> bool {
+        self.rollout_percentage >= 100 || self.rollout_bucket(client_id) < self.rollout_percentage
+    }
+
+    /// Restrict the deployment to the clients matching `rules`
+    pub fn with_targeting(mut self, rules: TargetingRules) -> Self {
+        if let Ok(value) = serde_json::to_value(&rules) {
+            self.metadata
+                .insert(TARGETING_METADATA_KEY.to_string(), value);
+        }
+        self
+    }
+
+    /// Targeting rules of the deployment, if it has any
+    pub fn targeting(&self) -> Option<TargetingRules> {
+        self.metadata
+            .get(TARGETING_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Explain why the deployment is not served to `profile`, if it is not
+    ///
+    /// Clients must match the targeting rules and fall within the rollout
+    /// percentage, unless the rules list them explicitly.
+    pub fn exclusion_reason(&self, profile: &ClientProfile) -> Option<String> {
+        if let Some(rules) = self.targeting() {
+            if let Some(reason) = rules.mismatch(profile) {
+                return Some(reason);
+            }
+            if rules.allowlists(&profile.client_id) {
+                return None;
+            }
+        }
+        if !self.includes_client(&profile.client_id) {
+            return Some(format!(
+                "client is outside the {}% rollout",
+                self.rollout_percentage
+            ));
+        }
+        None
+    }
+
+    /// Check whether the deployment is served to `profile`
+    pub fn targets_client(&self, profile: &ClientProfile) -> bool {
+        self.exclusion_reason(profile).is_none()
+    }
+
+    /// Move the deployment to `next`, returning the record of the change
+    ///
+    /// Fails with a conflict if the current status does not allow the move.
+    pub fn transition_to(&mut self, next: DeploymentStatus) -> Result<DeploymentTransition> {
+        if !self.status.can_transition_to(next) {
+            return Err(RodePushError::conflict(format!(
+                "Deployment {} cannot move from {} to {}",
+                self.id, self.status, next
+            )));
+        }
+
+        let now = Utc::now();
+        match next {
+            DeploymentStatus::Active if self.deployed_at.is_none() => self.deployed_at = Some(now),
+            DeploymentStatus::RolledBack => self.rolled_back_at = Some(now),
+            _ => {}
+        }
+        let transition = DeploymentTransition {
+            id: Uuid::new_v4(),
+            deployment_id: self.id.clone(),
+            from_status: self.status,
+            to_status: next,
+            actor: None,
+            reason: None,
+            created_at: now,
+        };
+        self.status = next;
+        Ok(transition)
+    }
+
+    /// Mark a pending deployment as active
+    pub fn activate(&mut self) -> Result<DeploymentTransition> {
+        if self.status != DeploymentStatus::Pending {
+            return Err(RodePushError::conflict(format!(
+                "Deployment {} cannot be activated while it is {}",
+                self.id, self.status
+            )));
+        }
+        self.transition_to(DeploymentStatus::Active)
     }
 
-    /// Mark deployment as rolled back
-    pub fn rollback(&mut self) {
-        self.status = DeploymentStatus::RolledBack;
-        self.rolled_back_at = Some(Utc::now());
+    /// Mark a live deployment as rolled back
+    pub fn rollback(&mut self) -> Result<DeploymentTransition> {
+        self.transition_to(DeploymentStatus::RolledBack)
     }
 
     /// Mark deployment as failed
-    pub fn fail(&mut self) {
-        self.status = DeploymentStatus::Failed;
+    pub fn fail(&mut self) -> Result<DeploymentTransition> {
+        self.transition_to(DeploymentStatus::Failed)
+    }
+
+    /// Pause an active deployment
+    pub fn pause(&mut self) -> Result<DeploymentTransition> {
+        if self.status != DeploymentStatus::Active {
+            return Err(RodePushError::conflict(format!(
+                "Deployment {} cannot be paused while it is {}",
+                self.id, self.status
+            )));
+        }
+        self.transition_to(DeploymentStatus::Paused)
+    }
+
+    /// Resume a paused deployment
+    pub fn resume(&mut self) -> Result<DeploymentTransition> {
+        if self.status != DeploymentStatus::Paused {
+            return Err(RodePushError::conflict(format!(
+                "Deployment {} cannot be resumed while it is {}",
+                self.id, self.status
+            )));
+        }
+        self.transition_to(DeploymentStatus::Active)
+    }
+
+    /// Mark a live deployment as replaced by `replacement`
+    pub fn supersede(&mut self, replacement: &DeploymentId) -> Result<DeploymentTransition> {
+        let transition = self.transition_to(DeploymentStatus::Superseded)?;
+        self.metadata.insert(
+            SUPERSEDED_BY_METADATA_KEY.to_string(),
+            serde_json::Value::String(replacement.to_string()),
+        );
+        Ok(transition)
     }
 
-    /// Pause deployment
-    pub fn pause(&mut self) {
-        self.status = DeploymentStatus::Paused;
+    /// Deployment that superseded this one, if it is superseded
+    pub fn superseded_by(&self) -> Option<DeploymentId> {
+        self.metadata
+            .get(SUPERSEDED_BY_METADATA_KEY)
+            .and_then(serde_json::Value::as_str)
+            .and_then(|id| DeploymentId::from_string(id).ok())
     }
 
-    /// Resume deployment (from paused state)
-    pub fn resume(&mut self) {
-        if self.status == DeploymentStatus::Paused {
-            self.status = DeploymentStatus::Active;
+    /// Put a superseded deployment back in the live `status` it had before
+    pub fn restore(&mut self, status: DeploymentStatus) -> Result<DeploymentTransition> {
+        if self.status != DeploymentStatus::Superseded {
+            return Err(RodePushError::conflict(format!(
+                "Deployment {} cannot be restored while it is {}",
+                self.id, self.status
+            )));
         }
+        let transition = self.transition_to(status)?;
+        self.metadata.remove(SUPERSEDED_BY_METADATA_KEY);
+        Ok(transition)
+    }
+
+    /// Check whether the deployment replaces the live deployments it overlaps
+    ///
+    /// A partial rollout leaves them live, so that clients outside it keep
+    /// receiving their previous bundle.
+    pub fn replaces_predecessors(&self) -> bool {
+        self.status == DeploymentStatus::Active && self.rollout_percentage >= 100
     }
 }
 
 /// Deployment service for database operations
+///
+/// Writes are crate-private: they go through [`DatabaseManager`], which
+/// keeps the cached update-check answers in step with them.
+///
+/// [`DatabaseManager`]: crate::database::DatabaseManager
 pub struct DeploymentService;
 
 impl DeploymentService {
-    /// Create a new deployment in the database
-    pub async fn create(pool: &DatabasePool, deployment: &Deployment) -> Result<()> {
+    /// Create a new pending deployment in the database
+    ///
+    /// Fails if the deployment is not pending, or if its environment does not
+    /// exist in its application. Deployments go live through
+    /// [`DeploymentService::promote`].
+    pub(crate) async fn create(pool: &DatabasePool, deployment: &Deployment) -> Result<()> {
+        Self::ensure_can_create(pool, deployment).await?;
         match pool {
             DatabasePool::Postgres(pg_pool) => Self::create_postgres(pg_pool, deployment).await,
             DatabasePool::MySql(mysql_pool) => Self::create_mysql(mysql_pool, deployment).await,
         }
     }
 
+    /// Create a pending deployment and promote it, atomically
+    ///
+    /// Either the deployment is stored live, with its rollout `plan` if it
+    /// has one and superseding its predecessors as
+    /// [`DeploymentService::promote`] does, or nothing is stored.
+    pub(crate) async fn deploy(
+        pool: &DatabasePool,
+        deployment: &Deployment,
+        plan: Option<&RolloutPlan>,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        Self::ensure_can_create(pool, deployment).await?;
+        if let Some(plan) = plan
+            && plan.deployment_id != deployment.id
+        {
+            return Err(RodePushError::Validation {
+                message: format!(
+                    "Rollout plan of deployment {} cannot be stored with deployment {}",
+                    plan.deployment_id, deployment.id
+                ),
+            });
+        }
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::deploy_postgres(pg_pool, deployment, plan, actor).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::deploy_mysql(mysql_pool, deployment, plan, actor).await
+            }
+        }
+    }
+
     /// Get deployment by ID from the database
     pub async fn get_by_id(pool: &DatabasePool, id: &DeploymentId) -> Result<Option<Deployment>> {
         match pool {
@@ -214,22 +508,52 @@ impl DeploymentService {
         }
     }
 
-    /// Update deployment in the database
-    pub async fn update(pool: &DatabasePool, deployment: &Deployment) -> Result<()> {
+    /// Change the rollout percentage of a live deployment
+    ///
+    /// Reaching 100% supersedes the predecessors of the deployment, and
+    /// leaving it restores them, in the same transaction. Returns `false`
+    /// without writing anything if the deployment is no longer active or
+    /// paused.
+    pub(crate) async fn set_rollout_percentage(
+        pool: &DatabasePool,
+        id: &DeploymentId,
+        rollout_percentage: u32,
+        actor: Option<String>,
+    ) -> Result<bool> {
         match pool {
-            DatabasePool::Postgres(pg_pool) => Self::update_postgres(pg_pool, deployment).await,
-            DatabasePool::MySql(mysql_pool) => Self::update_mysql(mysql_pool, deployment).await,
+            DatabasePool::Postgres(pg_pool) => {
+                Self::set_rollout_percentage_postgres(pg_pool, id, rollout_percentage, actor).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::set_rollout_percentage_mysql(mysql_pool, id, rollout_percentage, actor).await
+            }
         }
     }
 
     /// Delete deployment from the database
-    pub async fn delete(pool: &DatabasePool, id: &DeploymentId) -> Result<()> {
+    pub(crate) async fn delete(pool: &DatabasePool, id: &DeploymentId) -> Result<()> {
         match pool {
             DatabasePool::Postgres(pg_pool) => Self::delete_postgres(pg_pool, id).await,
             DatabasePool::MySql(mysql_pool) => Self::delete_mysql(mysql_pool, id).await,
         }
     }
 
+    /// Count the deployments of an application in an environment, whatever their status
+    pub async fn count_for_environment(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+        environment: &str,
+    ) -> Result<i64> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::count_for_environment_postgres(pg_pool, application_id, environment).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::count_for_environment_mysql(mysql_pool, application_id, environment).await
+            }
+        }
+    }
+
     /// Get active deployments for an application in an environment
     pub async fn get_active_for_application(
         pool: &DatabasePool,
@@ -282,8 +606,230 @@ impl DeploymentService {
         }
     }
 
+    /// Get active deployments that went live after `since`, oldest first
+    ///
+    /// `None` returns the earliest ones. Passing the `deployed_at` of the last
+    /// deployment returned pages through the rest.
+    pub async fn get_active_deployed_since(
+        pool: &DatabasePool,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<Deployment>> {
+        let since = since.unwrap_or(DateTime::UNIX_EPOCH);
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::get_active_deployed_since_postgres(pg_pool, since, limit).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::get_active_deployed_since_mysql(mysql_pool, since, limit).await
+            }
+        }
+    }
+
+    /// Replace the targeting rules of a deployment that is not withdrawn,
+    /// leaving the rest of its metadata alone
+    ///
+    /// `None` removes the rules. Returns `false` if the deployment was rolled
+    /// back, failed or superseded.
+    pub(crate) async fn set_targeting(
+        pool: &DatabasePool,
+        id: &DeploymentId,
+        rules: Option<&TargetingRules>,
+    ) -> Result<bool> {
+        let rules = rules.map(serde_json::to_value).transpose()?;
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::set_targeting_postgres(pg_pool, id, rules).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::set_targeting_mysql(mysql_pool, id, rules).await
+            }
+        }
+    }
+
+    /// Get the most recent active deployment in an environment whose bundle
+    /// targets `platform` and is compatible with the client's `binary_version`
+    pub async fn get_latest_for_target(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+        environment: &str,
+        platform: Platform,
+        binary_version: &SemanticVersion,
+    ) -> Result<Option<Deployment>> {
+        let deployments = Self::get_active_for_target(
+            pool,
+            application_id,
+            environment,
+            platform,
+            binary_version,
+        )
+        .await?;
+        Ok(deployments.into_iter().next())
+    }
+
+    /// Get the active deployments in an environment whose bundles target
+    /// `platform` and are compatible with the client's `binary_version`,
+    /// newest first
+    ///
+    /// Several deployments serve the same clients when they have different
+    /// targeting rules; a client receives the first one that serves it.
+    pub async fn get_active_for_target(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+        environment: &str,
+        platform: Platform,
+        binary_version: &SemanticVersion,
+    ) -> Result<Vec<Deployment>> {
+        let candidates = match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::get_active_with_bundle_version_postgres(
+                    pg_pool,
+                    application_id,
+                    environment,
+                    platform,
+                )
+                .await?
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::get_active_with_bundle_version_mysql(
+                    mysql_pool,
+                    application_id,
+                    environment,
+                    platform,
+                )
+                .await?
+            }
+        };
+
+        // Candidates are ordered newest first
+        let mut deployments = Vec::new();
+        for (deployment, bundle_version) in candidates {
+            match SemanticVersion::parse(&bundle_version) {
+                Ok(version) if version.is_compatible_with(binary_version) => {
+                    deployments.push(deployment);
+                }
+                Ok(_) => {}
+                Err(_) => tracing::warn!(
+                    "Skipping deployment {} with unparseable bundle version {}",
+                    deployment.id,
+                    bundle_version
+                ),
+            }
+        }
+
+        Ok(deployments)
+    }
+
+    /// Apply a status change to a deployment and record it, atomically
+    ///
+    /// `change` runs on the current state of the deployment, locked for the
+    /// duration of the transaction, and returns the transition it made.
+    /// Pending deployments only go live through [`DeploymentService::promote`],
+    /// and superseded ones when the deployment that superseded them stops
+    /// replacing them. A deployment that stops being active restores the
+    /// deployments it superseded within the same transaction, and one that
+    /// becomes active again at 100% supersedes them anew.
+    pub(crate) async fn transition<F>(
+        pool: &DatabasePool,
+        id: &DeploymentId,
+        change: F,
+    ) -> Result<Deployment>
+    where
+        F: FnOnce(&mut Deployment) -> Result<DeploymentTransition>,
+    {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::transition_postgres(pg_pool, id, change).await,
+            DatabasePool::MySql(mysql_pool) => Self::transition_mysql(mysql_pool, id, change).await,
+        }
+    }
+
+    /// Make a pending deployment live, atomically superseding the older live
+    /// deployments of its environment whose bundles overlap its own and that
+    /// have the same targeting rules
+    ///
+    /// A deployment rolled out to only some clients supersedes them once its
+    /// rollout reaches 100%; see [`Deployment::replaces_predecessors`]. See
+    /// [`releases_overlap`] for when two bundles overlap. The superseded
+    /// deployments record which deployment superseded them, so that they can
+    /// be restored if it is withdrawn.
+    pub(crate) async fn promote(
+        pool: &DatabasePool,
+        id: &DeploymentId,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::promote_postgres(pg_pool, id, actor).await,
+            DatabasePool::MySql(mysql_pool) => Self::promote_mysql(mysql_pool, id, actor).await,
+        }
+    }
+
+    /// Status changes of a deployment, oldest first
+    pub async fn history(
+        pool: &DatabasePool,
+        id: &DeploymentId,
+    ) -> Result<Vec<DeploymentTransition>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::history_postgres(pg_pool, id).await,
+            DatabasePool::MySql(mysql_pool) => Self::history_mysql(mysql_pool, id).await,
+        }
+    }
+
+    /// Reject transitions that would bypass promotion or restoration
+    fn ensure_not_activation(transition: &DeploymentTransition) -> Result<()> {
+        if !transition.to_status.is_live() {
+            return Ok(());
+        }
+        match transition.from_status {
+            DeploymentStatus::Pending => Err(RodePushError::conflict(format!(
+                "Deployment {} must be promoted to go live",
+                transition.deployment_id
+            ))),
+            DeploymentStatus::Superseded => Err(RodePushError::conflict(format!(
+                "Deployment {} only goes live again when the deployment that superseded it is withdrawn",
+                transition.deployment_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail unless `deployment` is pending and its environment exists in its application
+    async fn ensure_can_create(pool: &DatabasePool, deployment: &Deployment) -> Result<()> {
+        if deployment.status != DeploymentStatus::Pending {
+            return Err(RodePushError::Validation {
+                message: format!(
+                    "Deployment {} must be created pending, not {}",
+                    deployment.id, deployment.status
+                ),
+            });
+        }
+        if EnvironmentService::get_by_name(
+            pool,
+            &deployment.application_id,
+            &deployment.environment,
+        )
+        .await?
+        .is_none()
+        {
+            return Err(RodePushError::Validation {
+                message: format!(
+                    "Environment {} not found in application {}",
+                    deployment.environment, deployment.application_id
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Error for a deployment that does not exist
+    fn not_found(id: &DeploymentId) -> RodePushError {
+        RodePushError::not_found(format!("Deployment {} not found", id))
+    }
+
     // PostgreSQL implementations
-    async fn create_postgres(pool: &sqlx::PgPool, deployment: &Deployment) -> Result<()> {
+    async fn create_postgres(
+        executor: impl sqlx::PgExecutor<'_>,
+        deployment: &Deployment,
+    ) -> Result<()> {
         let query = r#"
             INSERT INTO deployments (id, application_id, bundle_id, environment, status, rollout_percentage, created_at, deployed_at, rolled_back_at, description, metadata)
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
@@ -301,7 +847,7 @@ impl DeploymentService {
             .bind(deployment.rolled_back_at)
             .bind(&deployment.description)
             .bind(serde_json::to_value(&deployment.metadata)?)
-            .execute(pool)
+            .execute(executor)
             .await
             .map_err(|e| DatabaseError::Query {
                 message: e.to_string(),
@@ -346,7 +892,10 @@ impl DeploymentService {
         }
     }
 
-    async fn update_postgres(pool: &sqlx::PgPool, deployment: &Deployment) -> Result<()> {
+    async fn update_postgres(
+        executor: impl sqlx::PgExecutor<'_>,
+        deployment: &Deployment,
+    ) -> Result<()> {
         let query = r#"
             UPDATE deployments 
             SET status = $2, rollout_percentage = $3, deployed_at = $4, rolled_back_at = $5, description = $6, metadata = $7
@@ -361,7 +910,7 @@ impl DeploymentService {
             .bind(deployment.rolled_back_at)
             .bind(&deployment.description)
             .bind(serde_json::to_value(&deployment.metadata)?)
-            .execute(pool)
+            .execute(executor)
             .await
             .map_err(|e| DatabaseError::Query {
                 message: e.to_string(),
@@ -371,6 +920,104 @@ impl DeploymentService {
         Ok(())
     }
 
+    async fn set_rollout_percentage_postgres(
+        pool: &sqlx::PgPool,
+        id: &DeploymentId,
+        rollout_percentage: u32,
+        actor: Option<String>,
+    ) -> Result<bool> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let row = sqlx::query("SELECT * FROM deployments WHERE id = $1 FOR UPDATE")
+            .bind(id.as_uuid())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let Some(row) = row else {
+            return Ok(false);
+        };
+        let mut deployment = Self::rows_to_deployments_postgres(vec![row])?.remove(0);
+        if !deployment.status.is_live() {
+            return Ok(false);
+        }
+        deployment.rollout_percentage = rollout_percentage;
+
+        let query = r#"
+            UPDATE deployments
+            SET rollout_percentage = $2
+            WHERE id = $1
+        "#;
+        sqlx::query(query)
+            .bind(id.as_uuid())
+            .bind(rollout_percentage as i32)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        Self::reconcile_predecessors_postgres(&mut tx, &deployment, actor).await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Set rollout of deployment {} to {}%",
+            id,
+            rollout_percentage
+        );
+        Ok(true)
+    }
+
+    async fn set_targeting_postgres(
+        pool: &sqlx::PgPool,
+        id: &DeploymentId,
+        rules: Option<serde_json::Value>,
+    ) -> Result<bool> {
+        let query = r#"
+            UPDATE deployments
+            SET metadata = CASE
+                WHEN $2::jsonb IS NULL THEN COALESCE(metadata, '{}'::jsonb) - 'targeting'
+                ELSE jsonb_set(COALESCE(metadata, '{}'::jsonb), '{targeting}', $2::jsonb)
+            END
+            WHERE id = $1 AND status IN ('pending', 'active', 'paused')
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(id.as_uuid())
+            .bind(rules)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Set targeting of deployment {}", id);
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn count_for_environment_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+        environment: &str,
+    ) -> Result<i64> {
+        let query = "SELECT COUNT(*) AS count FROM deployments WHERE application_id = $1 AND environment = $2";
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(environment)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(row.get("count"))
+    }
+
     async fn delete_postgres(pool: &sqlx::PgPool, id: &DeploymentId) -> Result<()> {
         let query = "DELETE FROM deployments WHERE id = $1";
 
@@ -461,47 +1108,441 @@ impl DeploymentService {
         Self::rows_to_deployments_postgres(rows)
     }
 
-    fn rows_to_deployments_postgres(rows: Vec<sqlx::postgres::PgRow>) -> Result<Vec<Deployment>> {
-        let mut deployments = Vec::new();
-        for row in rows {
-            let deployment = Deployment {
-                id: DeploymentId::from_uuid(row.get("id")),
-                application_id: ApplicationId::from_uuid(row.get("application_id")),
-                bundle_id: BundleId::from_uuid(row.get("bundle_id")),
-                environment: row.get("environment"),
-                status: DeploymentStatus::from_str(&row.get::<String, _>("status"))?,
-                rollout_percentage: row.get::<i32, _>("rollout_percentage") as u32,
-                created_at: row.get("created_at"),
-                deployed_at: row.get("deployed_at"),
-                rolled_back_at: row.get("rolled_back_at"),
-                description: row.get("description"),
-                metadata: serde_json::from_value(row.get("metadata"))?,
-            };
-            deployments.push(deployment);
-        }
-        Ok(deployments)
-    }
-
-    // MySQL implementations
-    async fn create_mysql(pool: &sqlx::MySqlPool, deployment: &Deployment) -> Result<()> {
+    async fn get_active_deployed_since_postgres(
+        pool: &sqlx::PgPool,
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Deployment>> {
         let query = r#"
-            INSERT INTO deployments (id, application_id, bundle_id, environment, status, rollout_percentage, created_at, deployed_at, rolled_back_at, description, metadata)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            SELECT * FROM deployments
+            WHERE status = 'active' AND deployed_at > $1
+            ORDER BY deployed_at ASC
+            LIMIT $2
         "#;
 
-        sqlx::query(query)
-            .bind(deployment.id.as_uuid())
-            .bind(deployment.application_id.as_uuid())
-            .bind(deployment.bundle_id.as_uuid())
-            .bind(&deployment.environment)
-            .bind(deployment.status.to_string())
-            .bind(deployment.rollout_percentage as i32)
-            .bind(deployment.created_at)
-            .bind(deployment.deployed_at)
+        let rows = sqlx::query(query)
+            .bind(since)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Self::rows_to_deployments_postgres(rows)
+    }
+
+    async fn get_active_with_bundle_version_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+        environment: &str,
+        platform: Platform,
+    ) -> Result<Vec<(Deployment, String)>> {
+        let query = r#"
+            SELECT d.*, b.version AS bundle_version FROM deployments d
+            JOIN bundles b ON b.id = d.bundle_id
+            WHERE d.application_id = $1 AND d.environment = $2 AND d.status = 'active'
+              AND (b.platform = $3 OR b.platform = 'both')
+            ORDER BY d.created_at DESC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(environment)
+            .bind(platform.to_string())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        let versions: Vec<String> = rows.iter().map(|row| row.get("bundle_version")).collect();
+        let deployments = Self::rows_to_deployments_postgres(rows)?;
+        Ok(deployments.into_iter().zip(versions).collect())
+    }
+
+    async fn transition_postgres<F>(
+        pool: &sqlx::PgPool,
+        id: &DeploymentId,
+        change: F,
+    ) -> Result<Deployment>
+    where
+        F: FnOnce(&mut Deployment) -> Result<DeploymentTransition>,
+    {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let row = sqlx::query("SELECT * FROM deployments WHERE id = $1 FOR UPDATE")
+            .bind(id.as_uuid())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?
+            .ok_or_else(|| Self::not_found(id))?;
+        let mut deployment = Self::rows_to_deployments_postgres(vec![row])?.remove(0);
+
+        let transition = change(&mut deployment)?;
+        Self::ensure_not_activation(&transition)?;
+        Self::update_postgres(&mut *tx, &deployment).await?;
+        Self::record_transition_postgres(&mut *tx, &transition).await?;
+        Self::reconcile_predecessors_postgres(&mut tx, &deployment, transition.actor.clone())
+            .await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Deployment {} moved from {} to {}",
+            deployment.id,
+            transition.from_status,
+            transition.to_status
+        );
+        Ok(deployment)
+    }
+
+    async fn promote_postgres(
+        pool: &sqlx::PgPool,
+        id: &DeploymentId,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let row = sqlx::query("SELECT * FROM deployments WHERE id = $1 FOR UPDATE")
+            .bind(id.as_uuid())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?
+            .ok_or_else(|| Self::not_found(id))?;
+        let deployment = Self::rows_to_deployments_postgres(vec![row])?.remove(0);
+        let deployment = Self::go_live_postgres(&mut tx, deployment, actor).await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Promoted deployment {} in {}",
+            deployment.id,
+            deployment.environment
+        );
+        Ok(deployment)
+    }
+
+    async fn deploy_postgres(
+        pool: &sqlx::PgPool,
+        deployment: &Deployment,
+        plan: Option<&RolloutPlan>,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        Self::create_postgres(&mut *tx, deployment).await?;
+        if let Some(plan) = plan {
+            RolloutPlanService::upsert_postgres(&mut *tx, plan).await?;
+        }
+        let deployment = Self::go_live_postgres(&mut tx, deployment.clone(), actor).await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Deployed deployment {} in {}",
+            deployment.id,
+            deployment.environment
+        );
+        Ok(deployment)
+    }
+
+    /// Activate a pending deployment locked by `conn` and supersede its predecessors
+    async fn go_live_postgres(
+        conn: &mut sqlx::PgConnection,
+        mut deployment: Deployment,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let promotion = deployment.activate()?.with_actor(actor.clone());
+        Self::update_postgres(&mut *conn, &deployment).await?;
+        Self::record_transition_postgres(&mut *conn, &promotion).await?;
+        Self::reconcile_predecessors_postgres(conn, &deployment, actor).await?;
+        Ok(deployment)
+    }
+
+    /// Supersede the predecessors of `deployment` while it replaces them, or
+    /// restore the ones it superseded otherwise
+    ///
+    /// Restored deployments are reconciled in turn, so that they take back
+    /// their own predecessors or give them back.
+    async fn reconcile_predecessors_postgres(
+        conn: &mut sqlx::PgConnection,
+        deployment: &Deployment,
+        actor: Option<String>,
+    ) -> Result<()> {
+        // Serialize these changes within an application, so that concurrent
+        // ones cannot both miss each other's live deployment
+        sqlx::query("SELECT id FROM applications WHERE id = $1 FOR UPDATE")
+            .bind(deployment.application_id.as_uuid())
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        let mut changed = vec![deployment.clone()];
+        while let Some(current) = changed.pop() {
+            if current.replaces_predecessors() {
+                Self::supersede_predecessors_postgres(conn, &current, actor.clone()).await?;
+            } else {
+                let restored =
+                    Self::restore_superseded_postgres(conn, &current, actor.clone()).await?;
+                changed.extend(restored);
+            }
+        }
+        Ok(())
+    }
+
+    async fn supersede_predecessors_postgres(
+        conn: &mut sqlx::PgConnection,
+        deployment: &Deployment,
+        actor: Option<String>,
+    ) -> Result<()> {
+        let row = sqlx::query("SELECT platform, version FROM bundles WHERE id = $1")
+            .bind(deployment.bundle_id.as_uuid())
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let platform: String = row.get("platform");
+        let version: String = row.get("version");
+        let platform = Platform::from_str(&platform)?;
+
+        // Only the older live deployments serving some of the same clients
+        // under the same targeting rules are replaced
+        let query = r#"
+            SELECT d.*, b.platform AS bundle_platform, b.version AS bundle_version
+            FROM deployments d
+            JOIN bundles b ON b.id = d.bundle_id
+            WHERE d.application_id = $1 AND d.environment = $2 AND d.status IN ('active', 'paused')
+              AND d.deployed_at < $3
+            FOR UPDATE OF d
+        "#;
+        let rows = sqlx::query(query)
+            .bind(deployment.application_id.as_uuid())
+            .bind(&deployment.environment)
+            .bind(deployment.deployed_at)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let releases: Vec<(String, String)> = rows
+            .iter()
+            .map(|row| (row.get("bundle_platform"), row.get("bundle_version")))
+            .collect();
+        let live = Self::rows_to_deployments_postgres(rows)?
+            .into_iter()
+            .zip(releases);
+        for (mut previous, (previous_platform, previous_version)) in live {
+            if previous.targeting() != deployment.targeting()
+                || !releases_overlap(
+                    Platform::from_str(&previous_platform)?,
+                    &previous_version,
+                    platform,
+                    &version,
+                )
+            {
+                continue;
+            }
+            let transition = previous
+                .supersede(&deployment.id)?
+                .with_actor(actor.clone())
+                .with_reason(Some(format!("Superseded by deployment {}", deployment.id)));
+            Self::update_postgres(&mut *conn, &previous).await?;
+            Self::record_transition_postgres(&mut *conn, &transition).await?;
+            tracing::info!("Deployment {} superseded by {}", previous.id, deployment.id);
+        }
+        Ok(())
+    }
+
+    /// Restore the deployments `deployment` superseded, returning them
+    async fn restore_superseded_postgres(
+        conn: &mut sqlx::PgConnection,
+        deployment: &Deployment,
+        actor: Option<String>,
+    ) -> Result<Vec<Deployment>> {
+        let query = r#"
+            SELECT * FROM deployments
+            WHERE application_id = $1 AND environment = $2 AND status = 'superseded'
+              AND metadata->>'superseded_by' = $3
+            FOR UPDATE
+        "#;
+        let rows = sqlx::query(query)
+            .bind(deployment.application_id.as_uuid())
+            .bind(&deployment.environment)
+            .bind(deployment.id.to_string())
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        let mut restored = Vec::new();
+        for mut previous in Self::rows_to_deployments_postgres(rows)? {
+            let status = Self::status_before_superseded_postgres(&mut *conn, &previous.id).await?;
+            let transition = previous
+                .restore(status)?
+                .with_actor(actor.clone())
+                .with_reason(Some(format!(
+                    "Restored because deployment {} is {}",
+                    deployment.id, deployment.status
+                )));
+            Self::update_postgres(&mut *conn, &previous).await?;
+            Self::record_transition_postgres(&mut *conn, &transition).await?;
+            tracing::info!(
+                "Deployment {} restored as {} after {} became {}",
+                previous.id,
+                previous.status,
+                deployment.id,
+                deployment.status
+            );
+            restored.push(previous);
+        }
+        Ok(restored)
+    }
+
+    /// Live status a deployment had when it was last superseded
+    async fn status_before_superseded_postgres(
+        executor: impl sqlx::PgExecutor<'_>,
+        id: &DeploymentId,
+    ) -> Result<DeploymentStatus> {
+        let query = r#"
+            SELECT from_status FROM deployment_transitions
+            WHERE deployment_id = $1 AND to_status = 'superseded'
+            ORDER BY created_at DESC
+            LIMIT 1
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        match row {
+            Some(row) => DeploymentStatus::from_str(&row.get::<String, _>("from_status")),
+            None => Ok(DeploymentStatus::Active),
+        }
+    }
+
+    async fn record_transition_postgres(
+        executor: impl sqlx::PgExecutor<'_>,
+        transition: &DeploymentTransition,
+    ) -> Result<()> {
+        let query = r#"
+            INSERT INTO deployment_transitions (id, deployment_id, from_status, to_status, actor, reason, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#;
+
+        sqlx::query(query)
+            .bind(transition.id)
+            .bind(transition.deployment_id.as_uuid())
+            .bind(transition.from_status.to_string())
+            .bind(transition.to_status.to_string())
+            .bind(&transition.actor)
+            .bind(&transition.reason)
+            .bind(transition.created_at)
+            .execute(executor)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        Ok(())
+    }
+
+    async fn history_postgres(
+        pool: &sqlx::PgPool,
+        id: &DeploymentId,
+    ) -> Result<Vec<DeploymentTransition>> {
+        let query = r#"
+            SELECT * FROM deployment_transitions
+            WHERE deployment_id = $1
+            ORDER BY created_at ASC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(DeploymentTransition {
+                    id: row.get("id"),
+                    deployment_id: DeploymentId::from_uuid(row.get("deployment_id")),
+                    from_status: DeploymentStatus::from_str(&row.get::<String, _>("from_status"))?,
+                    to_status: DeploymentStatus::from_str(&row.get::<String, _>("to_status"))?,
+                    actor: row.get("actor"),
+                    reason: row.get("reason"),
+                    created_at: row.get("created_at"),
+                })
+            })
+            .collect()
+    }
+
+    fn rows_to_deployments_postgres(rows: Vec<sqlx::postgres::PgRow>) -> Result<Vec<Deployment>> {
+        let mut deployments = Vec::new();
+        for row in rows {
+            let deployment = Deployment {
+                id: DeploymentId::from_uuid(row.get("id")),
+                application_id: ApplicationId::from_uuid(row.get("application_id")),
+                bundle_id: BundleId::from_uuid(row.get("bundle_id")),
+                environment: row.get("environment"),
+                status: DeploymentStatus::from_str(&row.get::<String, _>("status"))?,
+                rollout_percentage: row.get::<i32, _>("rollout_percentage") as u32,
+                created_at: row.get("created_at"),
+                deployed_at: row.get("deployed_at"),
+                rolled_back_at: row.get("rolled_back_at"),
+                description: row.get("description"),
+                metadata: serde_json::from_value(row.get("metadata"))?,
+            };
+            deployments.push(deployment);
+        }
+        Ok(deployments)
+    }
+
+    // MySQL implementations
+    async fn create_mysql(
+        executor: impl sqlx::MySqlExecutor<'_>,
+        deployment: &Deployment,
+    ) -> Result<()> {
+        let query = r#"
+            INSERT INTO deployments (id, application_id, bundle_id, environment, status, rollout_percentage, created_at, deployed_at, rolled_back_at, description, metadata)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(deployment.id.as_uuid())
+            .bind(deployment.application_id.as_uuid())
+            .bind(deployment.bundle_id.as_uuid())
+            .bind(&deployment.environment)
+            .bind(deployment.status.to_string())
+            .bind(deployment.rollout_percentage as i32)
+            .bind(deployment.created_at)
+            .bind(deployment.deployed_at)
             .bind(deployment.rolled_back_at)
             .bind(&deployment.description)
             .bind(serde_json::to_value(&deployment.metadata)?)
-            .execute(pool)
+            .execute(executor)
             .await
             .map_err(|e| DatabaseError::Query {
                 message: e.to_string(),
@@ -546,7 +1587,10 @@ impl DeploymentService {
         }
     }
 
-    async fn update_mysql(pool: &sqlx::MySqlPool, deployment: &Deployment) -> Result<()> {
+    async fn update_mysql(
+        executor: impl sqlx::MySqlExecutor<'_>,
+        deployment: &Deployment,
+    ) -> Result<()> {
         let query = r#"
             UPDATE deployments 
             SET status = ?, rollout_percentage = ?, deployed_at = ?, rolled_back_at = ?, description = ?, metadata = ?
@@ -561,7 +1605,7 @@ impl DeploymentService {
             .bind(&deployment.description)
             .bind(serde_json::to_value(&deployment.metadata)?)
             .bind(deployment.id.as_uuid())
-            .execute(pool)
+            .execute(executor)
             .await
             .map_err(|e| DatabaseError::Query {
                 message: e.to_string(),
@@ -571,6 +1615,115 @@ impl DeploymentService {
         Ok(())
     }
 
+    async fn set_rollout_percentage_mysql(
+        pool: &sqlx::MySqlPool,
+        id: &DeploymentId,
+        rollout_percentage: u32,
+        actor: Option<String>,
+    ) -> Result<bool> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let row = sqlx::query("SELECT * FROM deployments WHERE id = ? FOR UPDATE")
+            .bind(id.as_uuid())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let Some(row) = row else {
+            return Ok(false);
+        };
+        let mut deployment = Self::rows_to_deployments_mysql(vec![row])?.remove(0);
+        if !deployment.status.is_live() {
+            return Ok(false);
+        }
+        deployment.rollout_percentage = rollout_percentage;
+
+        let query = r#"
+            UPDATE deployments
+            SET rollout_percentage = ?
+            WHERE id = ?
+        "#;
+        sqlx::query(query)
+            .bind(rollout_percentage as i32)
+            .bind(id.as_uuid())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        Self::reconcile_predecessors_mysql(&mut tx, &deployment, actor).await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Set rollout of deployment {} to {}%",
+            id,
+            rollout_percentage
+        );
+        Ok(true)
+    }
+
+    async fn set_targeting_mysql(
+        pool: &sqlx::MySqlPool,
+        id: &DeploymentId,
+        rules: Option<serde_json::Value>,
+    ) -> Result<bool> {
+        let query = match rules {
+            Some(_) => {
+                r#"
+                UPDATE deployments
+                SET metadata = JSON_SET(COALESCE(metadata, JSON_OBJECT()), '$.targeting', CAST(? AS JSON))
+                WHERE id = ? AND status IN ('pending', 'active', 'paused')
+                "#
+            }
+            None => {
+                r#"
+                UPDATE deployments
+                SET metadata = JSON_REMOVE(COALESCE(metadata, JSON_OBJECT()), '$.targeting')
+                WHERE id = ? AND status IN ('pending', 'active', 'paused')
+                "#
+            }
+        };
+
+        let mut statement = sqlx::query(query);
+        if let Some(rules) = rules {
+            statement = statement.bind(rules.to_string());
+        }
+        let result = statement
+            .bind(id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Set targeting of deployment {}", id);
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn count_for_environment_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+        environment: &str,
+    ) -> Result<i64> {
+        let query = "SELECT COUNT(*) AS count FROM deployments WHERE application_id = ? AND environment = ?";
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(environment)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(row.get("count"))
+    }
+
     async fn delete_mysql(pool: &sqlx::MySqlPool, id: &DeploymentId) -> Result<()> {
         let query = "DELETE FROM deployments WHERE id = ?";
 
@@ -661,6 +1814,396 @@ impl DeploymentService {
         Self::rows_to_deployments_mysql(rows)
     }
 
+    async fn get_active_deployed_since_mysql(
+        pool: &sqlx::MySqlPool,
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Deployment>> {
+        let query = r#"
+            SELECT * FROM deployments
+            WHERE status = 'active' AND deployed_at > ?
+            ORDER BY deployed_at ASC
+            LIMIT ?
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(since)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Self::rows_to_deployments_mysql(rows)
+    }
+
+    async fn get_active_with_bundle_version_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+        environment: &str,
+        platform: Platform,
+    ) -> Result<Vec<(Deployment, String)>> {
+        let query = r#"
+            SELECT d.*, b.version AS bundle_version FROM deployments d
+            JOIN bundles b ON b.id = d.bundle_id
+            WHERE d.application_id = ? AND d.environment = ? AND d.status = 'active'
+              AND (b.platform = ? OR b.platform = 'both')
+            ORDER BY d.created_at DESC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(environment)
+            .bind(platform.to_string())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        let versions: Vec<String> = rows.iter().map(|row| row.get("bundle_version")).collect();
+        let deployments = Self::rows_to_deployments_mysql(rows)?;
+        Ok(deployments.into_iter().zip(versions).collect())
+    }
+
+    async fn transition_mysql<F>(
+        pool: &sqlx::MySqlPool,
+        id: &DeploymentId,
+        change: F,
+    ) -> Result<Deployment>
+    where
+        F: FnOnce(&mut Deployment) -> Result<DeploymentTransition>,
+    {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let row = sqlx::query("SELECT * FROM deployments WHERE id = ? FOR UPDATE")
+            .bind(id.as_uuid())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?
+            .ok_or_else(|| Self::not_found(id))?;
+        let mut deployment = Self::rows_to_deployments_mysql(vec![row])?.remove(0);
+
+        let transition = change(&mut deployment)?;
+        Self::ensure_not_activation(&transition)?;
+        Self::update_mysql(&mut *tx, &deployment).await?;
+        Self::record_transition_mysql(&mut *tx, &transition).await?;
+        Self::reconcile_predecessors_mysql(&mut tx, &deployment, transition.actor.clone()).await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Deployment {} moved from {} to {}",
+            deployment.id,
+            transition.from_status,
+            transition.to_status
+        );
+        Ok(deployment)
+    }
+
+    async fn promote_mysql(
+        pool: &sqlx::MySqlPool,
+        id: &DeploymentId,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let row = sqlx::query("SELECT * FROM deployments WHERE id = ? FOR UPDATE")
+            .bind(id.as_uuid())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?
+            .ok_or_else(|| Self::not_found(id))?;
+        let deployment = Self::rows_to_deployments_mysql(vec![row])?.remove(0);
+        let deployment = Self::go_live_mysql(&mut tx, deployment, actor).await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Promoted deployment {} in {}",
+            deployment.id,
+            deployment.environment
+        );
+        Ok(deployment)
+    }
+
+    async fn deploy_mysql(
+        pool: &sqlx::MySqlPool,
+        deployment: &Deployment,
+        plan: Option<&RolloutPlan>,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        Self::create_mysql(&mut *tx, deployment).await?;
+        if let Some(plan) = plan {
+            RolloutPlanService::upsert_mysql(&mut *tx, plan).await?;
+        }
+        let deployment = Self::go_live_mysql(&mut tx, deployment.clone(), actor).await?;
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::info!(
+            "Deployed deployment {} in {}",
+            deployment.id,
+            deployment.environment
+        );
+        Ok(deployment)
+    }
+
+    /// Activate a pending deployment locked by `conn` and supersede its predecessors
+    async fn go_live_mysql(
+        conn: &mut sqlx::MySqlConnection,
+        mut deployment: Deployment,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let promotion = deployment.activate()?.with_actor(actor.clone());
+        Self::update_mysql(&mut *conn, &deployment).await?;
+        Self::record_transition_mysql(&mut *conn, &promotion).await?;
+        Self::reconcile_predecessors_mysql(conn, &deployment, actor).await?;
+        Ok(deployment)
+    }
+
+    /// Supersede the predecessors of `deployment` while it replaces them, or
+    /// restore the ones it superseded otherwise
+    ///
+    /// Restored deployments are reconciled in turn, so that they take back
+    /// their own predecessors or give them back.
+    async fn reconcile_predecessors_mysql(
+        conn: &mut sqlx::MySqlConnection,
+        deployment: &Deployment,
+        actor: Option<String>,
+    ) -> Result<()> {
+        // Serialize these changes within an application, so that concurrent
+        // ones cannot both miss each other's live deployment
+        sqlx::query("SELECT id FROM applications WHERE id = ? FOR UPDATE")
+            .bind(deployment.application_id.as_uuid())
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        let mut changed = vec![deployment.clone()];
+        while let Some(current) = changed.pop() {
+            if current.replaces_predecessors() {
+                Self::supersede_predecessors_mysql(conn, &current, actor.clone()).await?;
+            } else {
+                let restored =
+                    Self::restore_superseded_mysql(conn, &current, actor.clone()).await?;
+                changed.extend(restored);
+            }
+        }
+        Ok(())
+    }
+
+    async fn supersede_predecessors_mysql(
+        conn: &mut sqlx::MySqlConnection,
+        deployment: &Deployment,
+        actor: Option<String>,
+    ) -> Result<()> {
+        let row = sqlx::query("SELECT platform, version FROM bundles WHERE id = ?")
+            .bind(deployment.bundle_id.as_uuid())
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let platform: String = row.get("platform");
+        let version: String = row.get("version");
+        let platform = Platform::from_str(&platform)?;
+
+        // Only the older live deployments serving some of the same clients
+        // under the same targeting rules are replaced
+        let query = r#"
+            SELECT d.*, b.platform AS bundle_platform, b.version AS bundle_version
+            FROM deployments d
+            JOIN bundles b ON b.id = d.bundle_id
+            WHERE d.application_id = ? AND d.environment = ? AND d.status IN ('active', 'paused')
+              AND d.deployed_at < ?
+            FOR UPDATE
+        "#;
+        let rows = sqlx::query(query)
+            .bind(deployment.application_id.as_uuid())
+            .bind(&deployment.environment)
+            .bind(deployment.deployed_at)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let releases: Vec<(String, String)> = rows
+            .iter()
+            .map(|row| (row.get("bundle_platform"), row.get("bundle_version")))
+            .collect();
+        let live = Self::rows_to_deployments_mysql(rows)?
+            .into_iter()
+            .zip(releases);
+        for (mut previous, (previous_platform, previous_version)) in live {
+            if previous.targeting() != deployment.targeting()
+                || !releases_overlap(
+                    Platform::from_str(&previous_platform)?,
+                    &previous_version,
+                    platform,
+                    &version,
+                )
+            {
+                continue;
+            }
+            let transition = previous
+                .supersede(&deployment.id)?
+                .with_actor(actor.clone())
+                .with_reason(Some(format!("Superseded by deployment {}", deployment.id)));
+            Self::update_mysql(&mut *conn, &previous).await?;
+            Self::record_transition_mysql(&mut *conn, &transition).await?;
+            tracing::info!("Deployment {} superseded by {}", previous.id, deployment.id);
+        }
+        Ok(())
+    }
+
+    /// Restore the deployments `deployment` superseded, returning them
+    async fn restore_superseded_mysql(
+        conn: &mut sqlx::MySqlConnection,
+        deployment: &Deployment,
+        actor: Option<String>,
+    ) -> Result<Vec<Deployment>> {
+        let query = r#"
+            SELECT * FROM deployments
+            WHERE application_id = ? AND environment = ? AND status = 'superseded'
+              AND JSON_UNQUOTE(JSON_EXTRACT(metadata, '$.superseded_by')) = ?
+            FOR UPDATE
+        "#;
+        let rows = sqlx::query(query)
+            .bind(deployment.application_id.as_uuid())
+            .bind(&deployment.environment)
+            .bind(deployment.id.to_string())
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        let mut restored = Vec::new();
+        for mut previous in Self::rows_to_deployments_mysql(rows)? {
+            let status = Self::status_before_superseded_mysql(&mut *conn, &previous.id).await?;
+            let transition = previous
+                .restore(status)?
+                .with_actor(actor.clone())
+                .with_reason(Some(format!(
+                    "Restored because deployment {} is {}",
+                    deployment.id, deployment.status
+                )));
+            Self::update_mysql(&mut *conn, &previous).await?;
+            Self::record_transition_mysql(&mut *conn, &transition).await?;
+            tracing::info!(
+                "Deployment {} restored as {} after {} became {}",
+                previous.id,
+                previous.status,
+                deployment.id,
+                deployment.status
+            );
+            restored.push(previous);
+        }
+        Ok(restored)
+    }
+
+    /// Live status a deployment had when it was last superseded
+    async fn status_before_superseded_mysql(
+        executor: impl sqlx::MySqlExecutor<'_>,
+        id: &DeploymentId,
+    ) -> Result<DeploymentStatus> {
+        let query = r#"
+            SELECT from_status FROM deployment_transitions
+            WHERE deployment_id = ? AND to_status = 'superseded'
+            ORDER BY created_at DESC
+            LIMIT 1
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        match row {
+            Some(row) => DeploymentStatus::from_str(&row.get::<String, _>("from_status")),
+            None => Ok(DeploymentStatus::Active),
+        }
+    }
+
+    async fn record_transition_mysql(
+        executor: impl sqlx::MySqlExecutor<'_>,
+        transition: &DeploymentTransition,
+    ) -> Result<()> {
+        let query = r#"
+            INSERT INTO deployment_transitions (id, deployment_id, from_status, to_status, actor, reason, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(transition.id)
+            .bind(transition.deployment_id.as_uuid())
+            .bind(transition.from_status.to_string())
+            .bind(transition.to_status.to_string())
+            .bind(&transition.actor)
+            .bind(&transition.reason)
+            .bind(transition.created_at)
+            .execute(executor)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        Ok(())
+    }
+
+    async fn history_mysql(
+        pool: &sqlx::MySqlPool,
+        id: &DeploymentId,
+    ) -> Result<Vec<DeploymentTransition>> {
+        let query = r#"
+            SELECT * FROM deployment_transitions
+            WHERE deployment_id = ?
+            ORDER BY created_at ASC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(DeploymentTransition {
+                    id: row.get("id"),
+                    deployment_id: DeploymentId::from_uuid(row.get("deployment_id")),
+                    from_status: DeploymentStatus::from_str(&row.get::<String, _>("from_status"))?,
+                    to_status: DeploymentStatus::from_str(&row.get::<String, _>("to_status"))?,
+                    actor: row.get("actor"),
+                    reason: row.get("reason"),
+                    created_at: row.get("created_at"),
+                })
+            })
+            .collect()
+    }
+
     fn rows_to_deployments_mysql(rows: Vec<sqlx::mysql::MySqlRow>) -> Result<Vec<Deployment>> {
         let mut deployments = Vec::new();
         for row in rows {
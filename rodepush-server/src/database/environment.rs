@@ -0,0 +1,524 @@
+//! Deployment environments and data models
+
+use chrono::{DateTime, Utc};
+use rodepush_core::{Result, RodePushError};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::database::{
+    application::ApplicationId, connection::DatabasePool, error::DatabaseError,
+    rollout_plan::RolloutStep,
+};
+
+/// Prefix of generated deployment keys
+pub const DEPLOYMENT_KEY_PREFIX: &str = "rpd_";
+
+/// Longest accepted environment name
+pub const MAX_ENVIRONMENT_NAME_LENGTH: usize = 50;
+
+/// Generate a new random deployment key
+pub fn generate_deployment_key() -> String {
+    format!("{}{}", DEPLOYMENT_KEY_PREFIX, Uuid::new_v4().simple())
+}
+
+/// Check that an environment name is 1-50 lowercase letters, digits, `-` or `_`
+pub fn validate_environment_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_ENVIRONMENT_NAME_LENGTH {
+        return Err(RodePushError::validation(format!(
+            "Environment name must be between 1 and {} characters long",
+            MAX_ENVIRONMENT_NAME_LENGTH
+        )));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        return Err(RodePushError::validation(format!(
+            "Environment name {} may only contain lowercase letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Environment identifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EnvironmentId(Uuid);
+
+impl EnvironmentId {
+    /// Generate a new environment ID
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// Create from existing UUID
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+
+    /// Get the underlying UUID
+    pub fn as_uuid(&self) -> Uuid {
+        self.0
+    }
+}
+
+impl Default for EnvironmentId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for EnvironmentId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Deployment channel of an application (e.g., "staging" or "production")
+///
+/// Clients follow one environment, identified either by name together with
+/// an application credential or by its deployment key alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Environment {
+    /// Environment ID
+    pub id: EnvironmentId,
+    /// Application the environment belongs to
+    pub application_id: ApplicationId,
+    /// Name, unique within the application
+    pub name: String,
+    /// Environment description
+    pub description: Option<String>,
+    /// Percentage of clients receiving new deployments unless a deployment says otherwise
+    pub default_rollout_percentage: u32,
+    /// Stages new deployments are rolled out in unless a deployment says otherwise
+    pub default_rollout_plan: Option<Vec<RolloutStep>>,
+    /// Stable key clients embed to check for updates in this environment
+    pub deployment_key: String,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Last update timestamp
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Environment {
+    /// Create an environment serving new deployments to every client
+    pub fn new(application_id: ApplicationId, name: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: EnvironmentId::new(),
+            application_id,
+            name,
+            description: None,
+            default_rollout_percentage: 100,
+            default_rollout_plan: None,
+            deployment_key: generate_deployment_key(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Set description
+    pub fn with_description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Set the default rollout percentage
+    pub fn with_default_rollout_percentage(mut self, percentage: u32) -> Self {
+        self.default_rollout_percentage = percentage.min(100);
+        self
+    }
+
+    /// Set the default rollout plan
+    pub fn with_default_rollout_plan(mut self, steps: Vec<RolloutStep>) -> Self {
+        self.default_rollout_plan = Some(steps);
+        self
+    }
+}
+
+/// Environment service for database operations
+pub struct EnvironmentService;
+
+impl EnvironmentService {
+    /// Create a new environment in the database
+    pub async fn create(pool: &DatabasePool, environment: &Environment) -> Result<()> {
+        validate_environment_name(&environment.name)?;
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::create_postgres(pg_pool, environment).await,
+            DatabasePool::MySql(mysql_pool) => Self::create_mysql(mysql_pool, environment).await,
+        }
+    }
+
+    /// Get an environment of an application by name
+    pub async fn get_by_name(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+        name: &str,
+    ) -> Result<Option<Environment>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::get_by_name_postgres(pg_pool, application_id, name).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::get_by_name_mysql(mysql_pool, application_id, name).await
+            }
+        }
+    }
+
+    /// Get the environment a deployment key belongs to
+    pub async fn get_by_deployment_key(
+        pool: &DatabasePool,
+        deployment_key: &str,
+    ) -> Result<Option<Environment>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::get_by_deployment_key_postgres(pg_pool, deployment_key).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::get_by_deployment_key_mysql(mysql_pool, deployment_key).await
+            }
+        }
+    }
+
+    /// List the environments of an application by name
+    pub async fn list_for_application(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<Environment>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::list_for_application_postgres(pg_pool, application_id).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::list_for_application_mysql(mysql_pool, application_id).await
+            }
+        }
+    }
+
+    /// Update the description and rollout defaults of an environment
+    pub async fn update(pool: &DatabasePool, environment: &Environment) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::update_postgres(pg_pool, environment).await,
+            DatabasePool::MySql(mysql_pool) => Self::update_mysql(mysql_pool, environment).await,
+        }
+    }
+
+    /// Delete an environment from the database
+    pub async fn delete(pool: &DatabasePool, id: &EnvironmentId) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::delete_postgres(pg_pool, id).await,
+            DatabasePool::MySql(mysql_pool) => Self::delete_mysql(mysql_pool, id).await,
+        }
+    }
+
+    // PostgreSQL implementations
+    pub(crate) async fn create_postgres(
+        executor: impl sqlx::PgExecutor<'_>,
+        environment: &Environment,
+    ) -> Result<()> {
+        let query = r#"
+            INSERT INTO environments (id, application_id, name, description, default_rollout_percentage, default_rollout_plan, deployment_key, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#;
+
+        sqlx::query(query)
+            .bind(environment.id.as_uuid())
+            .bind(environment.application_id.as_uuid())
+            .bind(&environment.name)
+            .bind(&environment.description)
+            .bind(environment.default_rollout_percentage as i32)
+            .bind(Self::plan_to_value(environment)?)
+            .bind(&environment.deployment_key)
+            .bind(environment.created_at)
+            .bind(environment.updated_at)
+            .execute(executor)
+            .await
+            .map_err(|e| match &e {
+                sqlx::Error::Database(db) if db.is_unique_violation() => {
+                    RodePushError::conflict(format!(
+                        "Environment {} already exists in application {}",
+                        environment.name, environment.application_id
+                    ))
+                }
+                _ => DatabaseError::Query {
+                    message: e.to_string(),
+                }
+                .into(),
+            })?;
+
+        tracing::info!(
+            "Created environment {} of application {}",
+            environment.name,
+            environment.application_id
+        );
+        Ok(())
+    }
+
+    async fn get_by_name_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+        name: &str,
+    ) -> Result<Option<Environment>> {
+        let query = "SELECT * FROM environments WHERE application_id = $1 AND name = $2";
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(name)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn get_by_deployment_key_postgres(
+        pool: &sqlx::PgPool,
+        deployment_key: &str,
+    ) -> Result<Option<Environment>> {
+        let query = "SELECT * FROM environments WHERE deployment_key = $1";
+
+        let row = sqlx::query(query)
+            .bind(deployment_key)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn list_for_application_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<Environment>> {
+        let query = "SELECT * FROM environments WHERE application_id = $1 ORDER BY name ASC";
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_postgres_row).collect()
+    }
+
+    async fn update_postgres(pool: &sqlx::PgPool, environment: &Environment) -> Result<()> {
+        let query = r#"
+            UPDATE environments
+            SET description = $2, default_rollout_percentage = $3, default_rollout_plan = $4, updated_at = $5
+            WHERE id = $1
+        "#;
+
+        sqlx::query(query)
+            .bind(environment.id.as_uuid())
+            .bind(&environment.description)
+            .bind(environment.default_rollout_percentage as i32)
+            .bind(Self::plan_to_value(environment)?)
+            .bind(environment.updated_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Updated environment: {}", environment.id);
+        Ok(())
+    }
+
+    async fn delete_postgres(pool: &sqlx::PgPool, id: &EnvironmentId) -> Result<()> {
+        let query = "DELETE FROM environments WHERE id = $1";
+
+        sqlx::query(query)
+            .bind(id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Deleted environment: {}", id);
+        Ok(())
+    }
+
+    fn from_postgres_row(row: &sqlx::postgres::PgRow) -> Result<Environment> {
+        let default_rollout_plan: Option<serde_json::Value> = row.get("default_rollout_plan");
+        Ok(Environment {
+            id: EnvironmentId::from_uuid(row.get("id")),
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            name: row.get("name"),
+            description: row.get("description"),
+            default_rollout_percentage: row.get::<i32, _>("default_rollout_percentage") as u32,
+            default_rollout_plan: default_rollout_plan
+                .map(serde_json::from_value)
+                .transpose()?,
+            deployment_key: row.get("deployment_key"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    // MySQL implementations
+    pub(crate) async fn create_mysql(
+        executor: impl sqlx::MySqlExecutor<'_>,
+        environment: &Environment,
+    ) -> Result<()> {
+        let query = r#"
+            INSERT INTO environments (id, application_id, name, description, default_rollout_percentage, default_rollout_plan, deployment_key, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(environment.id.as_uuid())
+            .bind(environment.application_id.as_uuid())
+            .bind(&environment.name)
+            .bind(&environment.description)
+            .bind(environment.default_rollout_percentage as i32)
+            .bind(Self::plan_to_value(environment)?)
+            .bind(&environment.deployment_key)
+            .bind(environment.created_at)
+            .bind(environment.updated_at)
+            .execute(executor)
+            .await
+            .map_err(|e| match &e {
+                sqlx::Error::Database(db) if db.is_unique_violation() => {
+                    RodePushError::conflict(format!(
+                        "Environment {} already exists in application {}",
+                        environment.name, environment.application_id
+                    ))
+                }
+                _ => DatabaseError::Query {
+                    message: e.to_string(),
+                }
+                .into(),
+            })?;
+
+        tracing::info!(
+            "Created environment {} of application {}",
+            environment.name,
+            environment.application_id
+        );
+        Ok(())
+    }
+
+    async fn get_by_name_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+        name: &str,
+    ) -> Result<Option<Environment>> {
+        let query = "SELECT * FROM environments WHERE application_id = ? AND name = ?";
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(name)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn get_by_deployment_key_mysql(
+        pool: &sqlx::MySqlPool,
+        deployment_key: &str,
+    ) -> Result<Option<Environment>> {
+        let query = "SELECT * FROM environments WHERE deployment_key = ?";
+
+        let row = sqlx::query(query)
+            .bind(deployment_key)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn list_for_application_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<Environment>> {
+        let query = "SELECT * FROM environments WHERE application_id = ? ORDER BY name ASC";
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_mysql_row).collect()
+    }
+
+    async fn update_mysql(pool: &sqlx::MySqlPool, environment: &Environment) -> Result<()> {
+        let query = r#"
+            UPDATE environments
+            SET description = ?, default_rollout_percentage = ?, default_rollout_plan = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        sqlx::query(query)
+            .bind(&environment.description)
+            .bind(environment.default_rollout_percentage as i32)
+            .bind(Self::plan_to_value(environment)?)
+            .bind(environment.updated_at)
+            .bind(environment.id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Updated environment: {}", environment.id);
+        Ok(())
+    }
+
+    async fn delete_mysql(pool: &sqlx::MySqlPool, id: &EnvironmentId) -> Result<()> {
+        let query = "DELETE FROM environments WHERE id = ?";
+
+        sqlx::query(query)
+            .bind(id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Deleted environment: {}", id);
+        Ok(())
+    }
+
+    fn from_mysql_row(row: &sqlx::mysql::MySqlRow) -> Result<Environment> {
+        let default_rollout_plan: Option<serde_json::Value> = row.get("default_rollout_plan");
+        Ok(Environment {
+            id: EnvironmentId::from_uuid(row.get("id")),
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            name: row.get("name"),
+            description: row.get("description"),
+            default_rollout_percentage: row.get::<i32, _>("default_rollout_percentage") as u32,
+            default_rollout_plan: default_rollout_plan
+                .map(serde_json::from_value)
+                .transpose()?,
+            deployment_key: row.get("deployment_key"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    fn plan_to_value(environment: &Environment) -> Result<Option<serde_json::Value>> {
+        Ok(environment
+            .default_rollout_plan
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?)
+    }
+}
@@ -1,28 +1,62 @@
 //! Database manager and coordination layer
 
+use crate::cache::{ServerCache, UpdateCheckKey, UpdateTarget};
 use crate::database::{
+    access_token::{AccessToken, AccessTokenId, AccessTokenService},
     application::{Application, ApplicationId, ApplicationService},
+    bundle::{Bundle, BundleService},
     config::DatabaseConfig,
     connection::{DatabaseConnection, DatabasePool},
-    deployment::{Deployment, DeploymentId, DeploymentService},
+    deployment::{
+        Deployment, DeploymentId, DeploymentService, DeploymentStatus, DeploymentTransition,
+    },
     diff_package::{DiffPackage, DiffPackageId, DiffPackageService},
+    environment::{Environment, EnvironmentService},
+    member::{ApplicationMember, MemberService},
+    rollout_plan::{RolloutPlan, RolloutPlanService, RolloutPlanStatus},
+    targeting::TargetingRules,
+    telemetry::{InstallReport, InstallStats, TelemetryService},
+    upload_session::{UploadSession, UploadSessionId, UploadSessionService},
 };
-use rodepush_core::{BundleId, Result};
+use chrono::{DateTime, Utc};
+use rodepush_core::{BundleId, Result, RodePushError};
+
+/// Page size used when collecting the cache entries of an application
+const CACHE_PAGE_SIZE: i64 = 500;
 
 /// Database manager for high-level coordination
 ///
 /// This is a lightweight manager that coordinates database operations
 /// by delegating to specific service modules. It doesn't contain business logic
 /// or SQL queries - those are handled by the respective service modules.
+///
+/// Every deployment write goes through the manager. When a [`ServerCache`]
+/// is attached, these writes invalidate the cached update-check answers of
+/// their environment, and bundle writes drop the cached bundle metadata.
 pub struct DatabaseManager {
     connection: DatabaseConnection,
+    cache: Option<ServerCache>,
 }
 
 impl DatabaseManager {
     /// Create a new database manager
     pub async fn new(config: &DatabaseConfig) -> Result<Self> {
         let connection = DatabaseConnection::new(config).await?;
-        Ok(Self { connection })
+        Ok(Self {
+            connection,
+            cache: None,
+        })
+    }
+
+    /// Attach a shared cache for update checks and bundle metadata
+    pub fn with_cache(mut self, cache: ServerCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Get the attached cache, if any
+    pub fn cache(&self) -> Option<&ServerCache> {
+        self.cache.as_ref()
     }
 
     /// Get the underlying connection pool
@@ -52,6 +86,15 @@ impl DatabaseManager {
         ApplicationService::create(self.pool(), application).await
     }
 
+    /// Create a new application together with its first environments, atomically
+    pub async fn create_application_with_environments(
+        &self,
+        application: &Application,
+        environments: &[Environment],
+    ) -> Result<()> {
+        ApplicationService::create_with_environments(self.pool(), application, environments).await
+    }
+
     /// Get application by ID
     pub async fn get_application(&self, id: &ApplicationId) -> Result<Option<Application>> {
         ApplicationService::get_by_id(self.pool(), id).await
@@ -68,8 +111,35 @@ impl DatabaseManager {
     }
 
     /// Delete application
+    ///
+    /// Its bundles and deployments are deleted with it, so their cached
+    /// metadata and update-check answers are dropped as well.
     pub async fn delete_application(&self, id: &ApplicationId) -> Result<()> {
-        ApplicationService::delete(self.pool(), id).await
+        if self.cache.is_none() {
+            return ApplicationService::delete(self.pool(), id).await;
+        }
+
+        let environments = self.list_environments(id).await?;
+        let mut bundle_ids = Vec::new();
+        loop {
+            let page = self
+                .list_bundles_for_application(id, CACHE_PAGE_SIZE, bundle_ids.len() as i64)
+                .await?;
+            let last_page = (page.len() as i64) < CACHE_PAGE_SIZE;
+            bundle_ids.extend(page.into_iter().map(|bundle| bundle.id));
+            if last_page {
+                break;
+            }
+        }
+
+        ApplicationService::delete(self.pool(), id).await?;
+        for environment in &environments {
+            self.invalidate_update_checks(id, &environment.name).await;
+        }
+        for bundle_id in &bundle_ids {
+            self.invalidate_bundle_cache(bundle_id).await;
+        }
+        Ok(())
     }
 
     /// List applications with pagination
@@ -79,9 +149,14 @@ impl DatabaseManager {
 
     // Deployment operations - delegate to DeploymentService
 
-    /// Create a new deployment
+    /// Create a new pending deployment
+    ///
+    /// See [`DeploymentService::create`].
     pub async fn create_deployment(&self, deployment: &Deployment) -> Result<()> {
-        DeploymentService::create(self.pool(), deployment).await
+        DeploymentService::create(self.pool(), deployment).await?;
+        self.invalidate_update_checks(&deployment.application_id, &deployment.environment)
+            .await;
+        Ok(())
     }
 
     /// Get deployment by ID
@@ -89,14 +164,18 @@ impl DatabaseManager {
         DeploymentService::get_by_id(self.pool(), id).await
     }
 
-    /// Update deployment
-    pub async fn update_deployment(&self, deployment: &Deployment) -> Result<()> {
-        DeploymentService::update(self.pool(), deployment).await
-    }
-
     /// Delete deployment
     pub async fn delete_deployment(&self, id: &DeploymentId) -> Result<()> {
-        DeploymentService::delete(self.pool(), id).await
+        let deployment = match self.cache {
+            Some(_) => self.get_deployment(id).await?,
+            None => None,
+        };
+        DeploymentService::delete(self.pool(), id).await?;
+        if let Some(deployment) = deployment {
+            self.invalidate_update_checks(&deployment.application_id, &deployment.environment)
+                .await;
+        }
+        Ok(())
     }
 
     /// Get active deployments for an application
@@ -109,6 +188,25 @@ impl DatabaseManager {
             .await
     }
 
+    /// Get deployments in a given status, newest first
+    pub async fn get_deployments_by_status(
+        &self,
+        status: &DeploymentStatus,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Deployment>> {
+        DeploymentService::get_by_status(self.pool(), status, limit, offset).await
+    }
+
+    /// Get active deployments that went live after `since`, oldest first
+    pub async fn get_deployments_activated_since(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<Deployment>> {
+        DeploymentService::get_active_deployed_since(self.pool(), since, limit).await
+    }
+
     /// List deployments for an application
     pub async fn list_deployments_for_application(
         &self,
@@ -119,6 +217,120 @@ impl DatabaseManager {
         DeploymentService::list_for_application(self.pool(), application_id, limit, offset).await
     }
 
+    // Environment operations - delegate to EnvironmentService
+
+    /// Create a new environment
+    pub async fn create_environment(&self, environment: &Environment) -> Result<()> {
+        EnvironmentService::create(self.pool(), environment).await
+    }
+
+    /// Get an environment of an application by name
+    pub async fn get_environment(
+        &self,
+        application_id: &ApplicationId,
+        name: &str,
+    ) -> Result<Option<Environment>> {
+        EnvironmentService::get_by_name(self.pool(), application_id, name).await
+    }
+
+    /// Get the environment a deployment key belongs to
+    pub async fn get_environment_by_deployment_key(
+        &self,
+        deployment_key: &str,
+    ) -> Result<Option<Environment>> {
+        EnvironmentService::get_by_deployment_key(self.pool(), deployment_key).await
+    }
+
+    /// List the environments of an application by name
+    pub async fn list_environments(
+        &self,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<Environment>> {
+        EnvironmentService::list_for_application(self.pool(), application_id).await
+    }
+
+    /// Update the description and rollout defaults of an environment
+    pub async fn update_environment(&self, environment: &Environment) -> Result<()> {
+        EnvironmentService::update(self.pool(), environment).await
+    }
+
+    /// Delete an environment that was never deployed to
+    pub async fn delete_environment(&self, environment: &Environment) -> Result<()> {
+        let deployments = DeploymentService::count_for_environment(
+            self.pool(),
+            &environment.application_id,
+            &environment.name,
+        )
+        .await?;
+        if deployments > 0 {
+            return Err(RodePushError::conflict(format!(
+                "Environment {} still has {} deployments",
+                environment.name, deployments
+            )));
+        }
+        EnvironmentService::delete(self.pool(), &environment.id).await
+    }
+
+    // Bundle operations - delegate to BundleService
+
+    /// Create a new bundle
+    pub async fn create_bundle(&self, bundle: &Bundle) -> Result<()> {
+        BundleService::create(self.pool(), bundle).await
+    }
+
+    /// List bundles of an application, newest first
+    pub async fn list_bundles_for_application(
+        &self,
+        application_id: &ApplicationId,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Bundle>> {
+        BundleService::get_by_application(self.pool(), application_id, limit, offset).await
+    }
+
+    /// Update bundle metadata
+    pub async fn update_bundle(&self, bundle: &Bundle) -> Result<()> {
+        BundleService::update(self.pool(), bundle).await?;
+        self.invalidate_bundle_cache(&bundle.id).await;
+        Ok(())
+    }
+
+    /// Delete a bundle along with the deployments serving it
+    pub async fn delete_bundle(&self, id: &BundleId) -> Result<()> {
+        let bundle = match self.cache {
+            Some(_) => BundleService::get_by_id(self.pool(), id).await?,
+            None => None,
+        };
+        BundleService::delete(self.pool(), id).await?;
+        if let Some(bundle) = bundle {
+            self.invalidate_bundle_cache(id).await;
+            for environment in self.list_environments(&bundle.application_id).await? {
+                self.invalidate_update_checks(&bundle.application_id, &environment.name)
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get bundle metadata by ID, served from the cache when possible
+    pub async fn get_bundle(&self, id: &BundleId) -> Result<Option<Bundle>> {
+        if let Some(cache) = &self.cache {
+            match cache.get_bundle(id).await {
+                Ok(Some(bundle)) => return Ok(Some(bundle)),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Bundle cache lookup failed for {}: {}", id, e),
+            }
+        }
+
+        let bundle = BundleService::get_by_id(self.pool(), id).await?;
+        if let (Some(cache), Some(bundle)) = (&self.cache, &bundle)
+            && let Err(e) = cache.set_bundle(bundle).await
+        {
+            tracing::warn!("Failed to cache bundle {}: {}", id, e);
+        }
+        Ok(bundle)
+    }
+
     // Differential package operations - delegate to DiffPackageService
 
     /// Create a new differential package
@@ -155,56 +367,411 @@ impl DatabaseManager {
         DiffPackageService::cleanup_old_packages(self.pool(), older_than_days).await
     }
 
-    // Cross-service coordination methods
+    // Upload session operations - delegate to UploadSessionService
 
-    /// Deploy a bundle - this involves multiple services working together
-    /// This is an example of where the manager provides coordination logic
-    pub async fn deploy_bundle(
+    /// Create a new upload session
+    pub async fn create_upload_session(&self, session: &UploadSession) -> Result<()> {
+        UploadSessionService::create(self.pool(), session).await
+    }
+
+    /// Get upload session by ID
+    pub async fn get_upload_session(&self, id: &UploadSessionId) -> Result<Option<UploadSession>> {
+        UploadSessionService::get_by_id(self.pool(), id).await
+    }
+
+    /// Get the newest unexpired session uploading a bundle
+    pub async fn get_active_upload_session(
         &self,
         application_id: &ApplicationId,
         bundle_id: &BundleId,
-        environment: String,
-        description: Option<String>,
-    ) -> Result<DeploymentId> {
-        // 1. Verify application exists
-        let _app = self.get_application(application_id).await?.ok_or_else(|| {
-            rodepush_core::RodePushError::Validation {
-                message: format!("Application {} not found", application_id),
+    ) -> Result<Option<UploadSession>> {
+        UploadSessionService::get_active_for_bundle(self.pool(), application_id, bundle_id).await
+    }
+
+    /// Update the expiry of an upload session
+    pub async fn update_upload_session_expiry(&self, session: &UploadSession) -> Result<()> {
+        UploadSessionService::update_expiry(self.pool(), session).await
+    }
+
+    /// Delete upload session
+    pub async fn delete_upload_session(&self, id: &UploadSessionId) -> Result<()> {
+        UploadSessionService::delete(self.pool(), id).await
+    }
+
+    /// List expired upload sessions
+    pub async fn list_expired_upload_sessions(&self, limit: i64) -> Result<Vec<UploadSession>> {
+        UploadSessionService::list_expired(self.pool(), limit).await
+    }
+
+    // Access token operations - delegate to AccessTokenService
+
+    /// Create a new access token
+    pub async fn create_access_token(&self, token: &AccessToken) -> Result<()> {
+        AccessTokenService::create(self.pool(), token).await
+    }
+
+    /// Get access token by ID
+    pub async fn get_access_token(&self, id: &AccessTokenId) -> Result<Option<AccessToken>> {
+        AccessTokenService::get_by_id(self.pool(), id).await
+    }
+
+    /// Get access token by its plaintext value
+    pub async fn get_access_token_by_token(&self, token: &str) -> Result<Option<AccessToken>> {
+        AccessTokenService::get_by_token(self.pool(), token).await
+    }
+
+    /// List the access tokens of an application
+    pub async fn list_access_tokens(
+        &self,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<AccessToken>> {
+        AccessTokenService::list_for_application(self.pool(), application_id).await
+    }
+
+    /// Record the revocation of an access token
+    pub async fn revoke_access_token(&self, token: &AccessToken) -> Result<()> {
+        AccessTokenService::revoke(self.pool(), token).await
+    }
+
+    // Membership operations - delegate to MemberService
+
+    /// Add a member to an application, or change its role
+    pub async fn upsert_member(&self, member: &ApplicationMember) -> Result<()> {
+        MemberService::upsert(self.pool(), member).await
+    }
+
+    /// Get a member of an application
+    pub async fn get_member(
+        &self,
+        application_id: &ApplicationId,
+        member: &str,
+    ) -> Result<Option<ApplicationMember>> {
+        MemberService::get(self.pool(), application_id, member).await
+    }
+
+    /// List the members of an application
+    pub async fn list_members(
+        &self,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<ApplicationMember>> {
+        MemberService::list_for_application(self.pool(), application_id).await
+    }
+
+    /// Remove a member from an application
+    pub async fn delete_member(&self, application_id: &ApplicationId, member: &str) -> Result<()> {
+        MemberService::delete(self.pool(), application_id, member).await
+    }
+
+    // Cross-service coordination methods
+
+    /// Resolve the deployments a client may receive for an update check
+    ///
+    /// Returns every live deployment serving the client's platform and
+    /// binary version, newest first; the client receives the first one whose
+    /// targeting rules and rollout include it. Answers, including "no
+    /// deployment", are cached so that repeated checks from many clients do
+    /// not reach the database.
+    pub async fn resolve_update_targets(&self, key: &UpdateCheckKey) -> Result<Vec<UpdateTarget>> {
+        // The generation is read before the database, so that an answer
+        // read before a concurrent change is never cached after it
+        let mut generation = None;
+        if let Some(cache) = &self.cache {
+            match cache.get_update_targets(key).await {
+                Ok(lookup) => match lookup.targets {
+                    Some(targets) => return Ok(targets),
+                    None => generation = Some(lookup.generation),
+                },
+                Err(e) => tracing::warn!("Update check cache lookup failed: {}", e),
             }
-        })?;
+        }
 
-        // 2. Create deployment
-        let mut deployment =
-            Deployment::new(application_id.clone(), bundle_id.clone(), environment);
-        if let Some(desc) = description {
-            deployment = deployment.with_description(desc);
+        let deployments = DeploymentService::get_active_for_target(
+            self.pool(),
+            &key.application_id,
+            &key.environment,
+            key.platform,
+            &key.binary_version,
+        )
+        .await?;
+        let mut targets = Vec::with_capacity(deployments.len());
+        for deployment in deployments {
+            if let Some(bundle) = self.get_bundle(&deployment.bundle_id).await? {
+                targets.push(UpdateTarget { deployment, bundle });
+            }
         }
 
-        let deployment_id = deployment.id.clone();
-        self.create_deployment(&deployment).await?;
+        if let Some(cache) = &self.cache
+            && let Some(generation) = generation
+            && let Err(e) = cache.set_update_targets(key, generation, &targets).await
+        {
+            tracing::warn!("Failed to cache update check answer: {}", e);
+        }
+        Ok(targets)
+    }
 
-        // 3. Could add more coordination logic here (e.g., notifications, metrics)
+    /// Drop cached update-check answers after a deployment change
+    ///
+    /// Failures are logged rather than returned: the write already succeeded
+    /// and stale entries expire on their own.
+    async fn invalidate_update_checks(&self, application_id: &ApplicationId, environment: &str) {
+        if let Some(cache) = &self.cache
+            && let Err(e) = cache
+                .invalidate_environment(application_id, environment)
+                .await
+        {
+            tracing::warn!(
+                "Failed to invalidate update check cache for {}/{}: {}",
+                application_id,
+                environment,
+                e
+            );
+        }
+    }
 
-        Ok(deployment_id)
+    /// Drop the cached metadata of a bundle, logging failures
+    async fn invalidate_bundle_cache(&self, id: &BundleId) {
+        if let Some(cache) = &self.cache
+            && let Err(e) = cache.invalidate_bundle(id).await
+        {
+            tracing::warn!("Failed to invalidate bundle cache for {}: {}", id, e);
+        }
     }
 
-    /// Rollback a deployment - another coordination example
-    pub async fn rollback_deployment(&self, deployment_id: &DeploymentId) -> Result<()> {
-        // 1. Get current deployment
-        let mut deployment = self.get_deployment(deployment_id).await?.ok_or_else(|| {
-            rodepush_core::RodePushError::Validation {
-                message: format!("Deployment {} not found", deployment_id),
+    /// Deploy a bundle: check that it belongs to the application, then
+    /// store the deployment with its rollout plan and promote it in its
+    /// environment, atomically
+    ///
+    /// See [`DeploymentService::deploy`].
+    pub async fn deploy_bundle(
+        &self,
+        deployment: Deployment,
+        plan: Option<RolloutPlan>,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let application_id = &deployment.application_id;
+        if self.get_application(application_id).await?.is_none() {
+            return Err(RodePushError::Validation {
+                message: format!("Application {} not found", application_id),
+            });
+        }
+        match self.get_bundle(&deployment.bundle_id).await? {
+            Some(bundle) if bundle.application_id == *application_id => {}
+            _ => {
+                return Err(RodePushError::Validation {
+                    message: format!(
+                        "Bundle {} not found in application {}",
+                        deployment.bundle_id, application_id
+                    ),
+                });
             }
-        })?;
+        }
+
+        let deployment =
+            DeploymentService::deploy(self.pool(), &deployment, plan.as_ref(), actor).await?;
+        self.invalidate_update_checks(&deployment.application_id, &deployment.environment)
+            .await;
+        tracing::info!(
+            "Deployed bundle {} to {} as {}",
+            deployment.bundle_id,
+            deployment.environment,
+            deployment.id
+        );
+        Ok(deployment)
+    }
+
+    /// Make a pending deployment live, superseding the live deployments of its
+    /// environment that serve the same clients
+    ///
+    /// See [`DeploymentService::promote`].
+    pub async fn promote_deployment(
+        &self,
+        deployment_id: &DeploymentId,
+        actor: Option<String>,
+    ) -> Result<Deployment> {
+        let deployment = DeploymentService::promote(self.pool(), deployment_id, actor).await?;
+        self.invalidate_update_checks(&deployment.application_id, &deployment.environment)
+            .await;
+        Ok(deployment)
+    }
 
-        // 2. Mark as rolled back
-        deployment.rollback();
+    /// Change the status of a deployment and record the transition
+    ///
+    /// See [`DeploymentService::transition`].
+    pub async fn transition_deployment<F>(
+        &self,
+        deployment_id: &DeploymentId,
+        change: F,
+    ) -> Result<Deployment>
+    where
+        F: FnOnce(&mut Deployment) -> Result<DeploymentTransition>,
+    {
+        let deployment = DeploymentService::transition(self.pool(), deployment_id, change).await?;
+        self.invalidate_update_checks(&deployment.application_id, &deployment.environment)
+            .await;
+        Ok(deployment)
+    }
 
-        // 3. Update in database
-        self.update_deployment(&deployment).await?;
+    /// Roll back a deployment, returning it in its rolled back state
+    ///
+    /// The deployments it superseded are restored.
+    pub async fn rollback_deployment(
+        &self,
+        deployment_id: &DeploymentId,
+        actor: Option<String>,
+        reason: Option<String>,
+    ) -> Result<Deployment> {
+        let deployment = self
+            .transition_deployment(deployment_id, |deployment| {
+                Ok(deployment.rollback()?.with_actor(actor).with_reason(reason))
+            })
+            .await?;
+        tracing::info!("Rolled back deployment {}", deployment.id);
+        Ok(deployment)
+    }
 
-        // 4. Could add more coordination logic here (e.g., cleanup, notifications)
+    /// Withdraw a deployment that failed, returning it in its failed state
+    ///
+    /// Like a rollback, this stops serving the deployment, but records that
+    /// it was withdrawn because it did not work.
+    pub async fn fail_deployment(
+        &self,
+        deployment_id: &DeploymentId,
+        actor: Option<String>,
+        reason: Option<String>,
+    ) -> Result<Deployment> {
+        let deployment = self
+            .transition_deployment(deployment_id, |deployment| {
+                Ok(deployment.fail()?.with_actor(actor).with_reason(reason))
+            })
+            .await?;
+        tracing::info!("Marked deployment {} as failed", deployment.id);
+        Ok(deployment)
+    }
 
-        Ok(())
+    /// Status changes of a deployment, oldest first
+    pub async fn get_deployment_history(
+        &self,
+        deployment_id: &DeploymentId,
+    ) -> Result<Vec<DeploymentTransition>> {
+        DeploymentService::history(self.pool(), deployment_id).await
+    }
+
+    /// Change the rollout percentage of a live deployment
+    ///
+    /// See [`DeploymentService::set_rollout_percentage`].
+    pub async fn set_rollout_percentage(
+        &self,
+        deployment: &Deployment,
+        rollout_percentage: u32,
+        actor: Option<String>,
+    ) -> Result<bool> {
+        let updated = DeploymentService::set_rollout_percentage(
+            self.pool(),
+            &deployment.id,
+            rollout_percentage,
+            actor,
+        )
+        .await?;
+        if updated {
+            self.invalidate_update_checks(&deployment.application_id, &deployment.environment)
+                .await;
+        }
+        Ok(updated)
+    }
+
+    /// Replace or remove the targeting rules of a deployment
+    ///
+    /// See [`DeploymentService::set_targeting`].
+    pub async fn set_deployment_targeting(
+        &self,
+        deployment: &Deployment,
+        rules: Option<&TargetingRules>,
+    ) -> Result<bool> {
+        let updated = DeploymentService::set_targeting(self.pool(), &deployment.id, rules).await?;
+        if updated {
+            self.invalidate_update_checks(&deployment.application_id, &deployment.environment)
+                .await;
+        }
+        Ok(updated)
+    }
+
+    // Rollout plan operations - delegate to RolloutPlanService
+
+    /// Store the rollout plan of a deployment, replacing any previous plan
+    pub async fn set_rollout_plan(&self, plan: &RolloutPlan) -> Result<()> {
+        RolloutPlanService::upsert(self.pool(), plan).await
+    }
+
+    /// Get the rollout plan of a deployment
+    pub async fn get_rollout_plan(
+        &self,
+        deployment_id: &DeploymentId,
+    ) -> Result<Option<RolloutPlan>> {
+        RolloutPlanService::get(self.pool(), deployment_id).await
+    }
+
+    /// Delete the rollout plan of a deployment
+    pub async fn delete_rollout_plan(&self, deployment_id: &DeploymentId) -> Result<()> {
+        RolloutPlanService::delete(self.pool(), deployment_id).await
+    }
+
+    /// List running rollout plans whose next stage is due at `now`
+    pub async fn list_due_rollout_plans(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<RolloutPlan>> {
+        RolloutPlanService::list_due(self.pool(), now, limit).await
+    }
+
+    /// Store the progress of a rollout plan that was at stage `previous_step`
+    ///
+    /// See [`RolloutPlanService::update_progress`].
+    pub async fn update_rollout_progress(
+        &self,
+        plan: &RolloutPlan,
+        previous_step: usize,
+    ) -> Result<bool> {
+        RolloutPlanService::update_progress(self.pool(), plan, previous_step).await
+    }
+
+    /// Halt the running rollout plan of a deployment, if it has one
+    ///
+    /// Returns the plan as stored afterwards. A plan that is not running is
+    /// returned unchanged.
+    pub async fn halt_rollout(
+        &self,
+        deployment_id: &DeploymentId,
+        reason: &str,
+    ) -> Result<Option<RolloutPlan>> {
+        loop {
+            let Some(mut plan) = self.get_rollout_plan(deployment_id).await? else {
+                return Ok(None);
+            };
+            if plan.status != RolloutPlanStatus::Running {
+                return Ok(Some(plan));
+            }
+            let step = plan.current_step;
+            plan.halt(reason)?;
+            // Retry if the scheduler advanced the plan in the meantime
+            if self.update_rollout_progress(&plan, step).await? {
+                tracing::info!("Halted rollout of deployment {}: {}", deployment_id, reason);
+                return Ok(Some(plan));
+            }
+        }
+    }
+
+    // Telemetry operations - delegate to TelemetryService
+
+    /// Store and count an install report
+    ///
+    /// Returns the install outcomes of the deployment including the report.
+    pub async fn record_install_report(&self, report: &InstallReport) -> Result<InstallStats> {
+        TelemetryService::record(self.pool(), report).await
+    }
+
+    /// Get the install outcomes of a deployment, counted in distinct clients
+    pub async fn get_install_stats(&self, deployment_id: &DeploymentId) -> Result<InstallStats> {
+        TelemetryService::stats(self.pool(), deployment_id).await
     }
 }
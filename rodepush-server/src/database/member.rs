@@ -0,0 +1,353 @@
+//! Application membership and data models
+
+use chrono::{DateTime, Utc};
+use rodepush_core::{Result, RodePushError};
+use serde::{Deserialize, Serialize};
+
+use crate::database::{
+    access_token::TokenScope, application::ApplicationId, connection::DatabasePool,
+    error::DatabaseError,
+};
+use sqlx::Row;
+
+/// Role of a member within an application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberRole {
+    /// May check for updates
+    Viewer,
+    /// May also upload bundles, e.g. from CI
+    Developer,
+    /// May also deploy and roll back
+    ReleaseManager,
+    /// May do everything, including managing tokens and members
+    Admin,
+}
+
+impl MemberRole {
+    /// Scopes tokens issued to a member with this role may exercise
+    pub fn scopes(self) -> &'static [TokenScope] {
+        match self {
+            MemberRole::Viewer => &[TokenScope::UpdatesRead],
+            MemberRole::Developer => &[TokenScope::UpdatesRead, TokenScope::BundlesWrite],
+            MemberRole::ReleaseManager => &[
+                TokenScope::UpdatesRead,
+                TokenScope::BundlesWrite,
+                TokenScope::DeploymentsWrite,
+            ],
+            MemberRole::Admin => &TokenScope::ALL,
+        }
+    }
+
+    /// Check whether the role allows operations requiring `scope`
+    pub fn grants(self, scope: TokenScope) -> bool {
+        self.scopes().iter().any(|granted| granted.grants(scope))
+    }
+}
+
+impl std::fmt::Display for MemberRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemberRole::Viewer => write!(f, "viewer"),
+            MemberRole::Developer => write!(f, "developer"),
+            MemberRole::ReleaseManager => write!(f, "release_manager"),
+            MemberRole::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for MemberRole {
+    type Err = RodePushError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "viewer" => Ok(MemberRole::Viewer),
+            "developer" => Ok(MemberRole::Developer),
+            "release_manager" => Ok(MemberRole::ReleaseManager),
+            "admin" => Ok(MemberRole::Admin),
+            _ => Err(RodePushError::Validation {
+                message: format!("Invalid member role: {}", s),
+            }),
+        }
+    }
+}
+
+/// Application member model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationMember {
+    /// Application the member belongs to
+    pub application_id: ApplicationId,
+    /// Member identifier (e.g., an email address)
+    pub member: String,
+    /// Role of the member
+    pub role: MemberRole,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Last update timestamp
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ApplicationMember {
+    /// Create a new membership
+    pub fn new(application_id: ApplicationId, member: String, role: MemberRole) -> Self {
+        let now = Utc::now();
+        Self {
+            application_id,
+            member,
+            role,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Membership service for database operations
+pub struct MemberService;
+
+impl MemberService {
+    /// Add a member, or change the role of an existing one
+    pub async fn upsert(pool: &DatabasePool, member: &ApplicationMember) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::upsert_postgres(pg_pool, member).await,
+            DatabasePool::MySql(mysql_pool) => Self::upsert_mysql(mysql_pool, member).await,
+        }
+    }
+
+    /// Get a member of an application from the database
+    pub async fn get(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+        member: &str,
+    ) -> Result<Option<ApplicationMember>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::get_postgres(pg_pool, application_id, member).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::get_mysql(mysql_pool, application_id, member).await
+            }
+        }
+    }
+
+    /// List the members of an application
+    pub async fn list_for_application(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<ApplicationMember>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::list_for_application_postgres(pg_pool, application_id).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::list_for_application_mysql(mysql_pool, application_id).await
+            }
+        }
+    }
+
+    /// Remove a member from an application
+    pub async fn delete(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+        member: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::delete_postgres(pg_pool, application_id, member).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::delete_mysql(mysql_pool, application_id, member).await
+            }
+        }
+    }
+
+    // PostgreSQL implementations
+    async fn upsert_postgres(pool: &sqlx::PgPool, member: &ApplicationMember) -> Result<()> {
+        let query = r#"
+            INSERT INTO application_members (application_id, member, role, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (application_id, member)
+            DO UPDATE SET role = EXCLUDED.role, updated_at = EXCLUDED.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(member.application_id.as_uuid())
+            .bind(&member.member)
+            .bind(member.role.to_string())
+            .bind(member.created_at)
+            .bind(member.updated_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!(
+            "Set role of {} in application {} to {}",
+            member.member,
+            member.application_id,
+            member.role
+        );
+        Ok(())
+    }
+
+    async fn get_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+        member: &str,
+    ) -> Result<Option<ApplicationMember>> {
+        let query = "SELECT * FROM application_members WHERE application_id = $1 AND member = $2";
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(member)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn list_for_application_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<ApplicationMember>> {
+        let query = "SELECT * FROM application_members WHERE application_id = $1 ORDER BY member";
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_postgres_row).collect()
+    }
+
+    async fn delete_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+        member: &str,
+    ) -> Result<()> {
+        let query = "DELETE FROM application_members WHERE application_id = $1 AND member = $2";
+
+        sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(member)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Removed {} from application {}", member, application_id);
+        Ok(())
+    }
+
+    fn from_postgres_row(row: &sqlx::postgres::PgRow) -> Result<ApplicationMember> {
+        Ok(ApplicationMember {
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            member: row.get("member"),
+            role: row.get::<String, _>("role").parse()?,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    // MySQL implementations
+    async fn upsert_mysql(pool: &sqlx::MySqlPool, member: &ApplicationMember) -> Result<()> {
+        let query = r#"
+            INSERT INTO application_members (application_id, member, role, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE role = VALUES(role), updated_at = VALUES(updated_at)
+        "#;
+
+        sqlx::query(query)
+            .bind(member.application_id.as_uuid())
+            .bind(&member.member)
+            .bind(member.role.to_string())
+            .bind(member.created_at)
+            .bind(member.updated_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!(
+            "Set role of {} in application {} to {}",
+            member.member,
+            member.application_id,
+            member.role
+        );
+        Ok(())
+    }
+
+    async fn get_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+        member: &str,
+    ) -> Result<Option<ApplicationMember>> {
+        let query = "SELECT * FROM application_members WHERE application_id = ? AND member = ?";
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(member)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn list_for_application_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+    ) -> Result<Vec<ApplicationMember>> {
+        let query = "SELECT * FROM application_members WHERE application_id = ? ORDER BY member";
+
+        let rows = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_mysql_row).collect()
+    }
+
+    async fn delete_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+        member: &str,
+    ) -> Result<()> {
+        let query = "DELETE FROM application_members WHERE application_id = ? AND member = ?";
+
+        sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(member)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Removed {} from application {}", member, application_id);
+        Ok(())
+    }
+
+    fn from_mysql_row(row: &sqlx::mysql::MySqlRow) -> Result<ApplicationMember> {
+        Ok(ApplicationMember {
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            member: row.get("member"),
+            role: row.get::<String, _>("role").parse()?,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
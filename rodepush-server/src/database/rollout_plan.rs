@@ -0,0 +1,554 @@
+//! Staged rollout plans and data models
+
+use chrono::{DateTime, Duration, Utc};
+use rodepush_core::{Result, RodePushError};
+use serde::{Deserialize, Serialize};
+
+use crate::database::{connection::DatabasePool, deployment::DeploymentId, error::DatabaseError};
+use sqlx::Row;
+
+/// Longest a single rollout stage may last (one year)
+pub const MAX_STEP_WAIT_SECONDS: u64 = 365 * 24 * 60 * 60;
+
+/// One stage of a rollout plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RolloutStep {
+    /// Percentage of clients receiving the deployment during this stage (1-100)
+    pub percentage: u32,
+    /// How long the stage lasts before the next one starts; ignored for the last stage
+    #[serde(default)]
+    pub wait_seconds: u64,
+}
+
+impl RolloutStep {
+    /// Create a stage serving `percentage` of clients for `wait_seconds`
+    pub fn new(percentage: u32, wait_seconds: u64) -> Self {
+        Self {
+            percentage,
+            wait_seconds,
+        }
+    }
+}
+
+/// Progress of a rollout plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutPlanStatus {
+    /// The plan advances when its current stage is over
+    Running,
+    /// The plan stays at its current stage until resumed
+    Halted,
+    /// The plan reached its last stage
+    Completed,
+}
+
+impl std::fmt::Display for RolloutPlanStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RolloutPlanStatus::Running => write!(f, "running"),
+            RolloutPlanStatus::Halted => write!(f, "halted"),
+            RolloutPlanStatus::Completed => write!(f, "completed"),
+        }
+    }
+}
+
+impl std::str::FromStr for RolloutPlanStatus {
+    type Err = RodePushError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "running" => Ok(RolloutPlanStatus::Running),
+            "halted" => Ok(RolloutPlanStatus::Halted),
+            "completed" => Ok(RolloutPlanStatus::Completed),
+            _ => Err(RodePushError::Validation {
+                message: format!("Invalid rollout plan status: {}", s),
+            }),
+        }
+    }
+}
+
+/// Staged rollout plan of a deployment
+///
+/// The plan raises the rollout percentage of its deployment stage by stage
+/// (e.g., 1% → 10% → 50% → 100%). Clients are bucketed deterministically, so
+/// every client included in one stage stays included in the later ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutPlan {
+    /// Deployment the plan rolls out
+    pub deployment_id: DeploymentId,
+    /// Stages of the plan, in order
+    pub steps: Vec<RolloutStep>,
+    /// Index of the current stage
+    pub current_step: usize,
+    /// Progress of the plan
+    pub status: RolloutPlanStatus,
+    /// When the next stage starts, while the plan is running
+    pub next_step_at: Option<DateTime<Utc>>,
+    /// Why the plan was halted
+    pub halt_reason: Option<String>,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Last update timestamp
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RolloutPlan {
+    /// Create a plan starting at its first stage now
+    pub fn new(deployment_id: DeploymentId, steps: Vec<RolloutStep>) -> Result<Self> {
+        Self::validate_steps(&steps)?;
+        let now = Utc::now();
+        let mut plan = Self {
+            deployment_id,
+            steps,
+            current_step: 0,
+            status: RolloutPlanStatus::Running,
+            next_step_at: None,
+            halt_reason: None,
+            created_at: now,
+            updated_at: now,
+        };
+        plan.schedule_next_step(now);
+        Ok(plan)
+    }
+
+    /// Check that stage percentages are between 1 and 100 and strictly
+    /// increasing, and that every stage but the last has a duration of at
+    /// most [`MAX_STEP_WAIT_SECONDS`]
+    pub fn validate_steps(steps: &[RolloutStep]) -> Result<()> {
+        if steps.is_empty() {
+            return Err(RodePushError::validation(
+                "A rollout plan needs at least one step",
+            ));
+        }
+        let mut previous = 0;
+        for (index, step) in steps.iter().enumerate() {
+            if step.percentage == 0 || step.percentage > 100 {
+                return Err(RodePushError::validation(format!(
+                    "Rollout step {} must serve between 1 and 100 percent, got {}",
+                    index + 1,
+                    step.percentage
+                )));
+            }
+            if step.percentage <= previous {
+                return Err(RodePushError::validation(format!(
+                    "Rollout step {} must serve more than the {} percent before it",
+                    index + 1,
+                    previous
+                )));
+            }
+            if step.wait_seconds == 0 && index + 1 < steps.len() {
+                return Err(RodePushError::validation(format!(
+                    "Rollout step {} needs a positive wait_seconds",
+                    index + 1
+                )));
+            }
+            if step.wait_seconds > MAX_STEP_WAIT_SECONDS {
+                return Err(RodePushError::validation(format!(
+                    "Rollout step {} cannot last longer than {} seconds",
+                    index + 1,
+                    MAX_STEP_WAIT_SECONDS
+                )));
+            }
+            previous = step.percentage;
+        }
+        Ok(())
+    }
+
+    /// Rollout percentage of the current stage
+    pub fn current_percentage(&self) -> u32 {
+        self.steps[self.current_step].percentage
+    }
+
+    /// Check whether the plan should move to its next stage at `now`
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.status == RolloutPlanStatus::Running
+            && self
+                .next_step_at
+                .is_some_and(|next_step_at| next_step_at <= now)
+    }
+
+    /// Move to the next stage if it is due, returning its rollout percentage
+    pub fn advance(&mut self, now: DateTime<Utc>) -> Option<u32> {
+        if !self.is_due(now) {
+            return None;
+        }
+        self.current_step += 1;
+        self.schedule_next_step(now);
+        self.updated_at = now;
+        Some(self.current_percentage())
+    }
+
+    /// Stop advancing the plan
+    pub fn halt(&mut self, reason: impl Into<String>) -> Result<()> {
+        if self.status != RolloutPlanStatus::Running {
+            return Err(RodePushError::conflict(format!(
+                "Rollout of deployment {} cannot be halted while it is {}",
+                self.deployment_id, self.status
+            )));
+        }
+        self.status = RolloutPlanStatus::Halted;
+        self.next_step_at = None;
+        self.halt_reason = Some(reason.into());
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Continue a halted plan, restarting its current stage now
+    pub fn resume(&mut self) -> Result<()> {
+        if self.status != RolloutPlanStatus::Halted {
+            return Err(RodePushError::conflict(format!(
+                "Rollout of deployment {} cannot be resumed while it is {}",
+                self.deployment_id, self.status
+            )));
+        }
+        let now = Utc::now();
+        self.status = RolloutPlanStatus::Running;
+        self.halt_reason = None;
+        self.schedule_next_step(now);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Schedule the end of the current stage, completing the plan at its last one
+    fn schedule_next_step(&mut self, now: DateTime<Utc>) {
+        if self.current_step + 1 >= self.steps.len() {
+            self.status = RolloutPlanStatus::Completed;
+            self.next_step_at = None;
+            return;
+        }
+        let wait = self.steps[self.current_step].wait_seconds;
+        self.next_step_at = Some(now + Duration::seconds(wait as i64));
+    }
+}
+
+/// Rollout plan service for database operations
+pub struct RolloutPlanService;
+
+impl RolloutPlanService {
+    /// Store a plan, replacing the previous plan of its deployment
+    pub async fn upsert(pool: &DatabasePool, plan: &RolloutPlan) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::upsert_postgres(pg_pool, plan).await,
+            DatabasePool::MySql(mysql_pool) => Self::upsert_mysql(mysql_pool, plan).await,
+        }
+    }
+
+    /// Get the plan of a deployment from the database
+    pub async fn get(
+        pool: &DatabasePool,
+        deployment_id: &DeploymentId,
+    ) -> Result<Option<RolloutPlan>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::get_postgres(pg_pool, deployment_id).await,
+            DatabasePool::MySql(mysql_pool) => Self::get_mysql(mysql_pool, deployment_id).await,
+        }
+    }
+
+    /// List running plans whose next stage is due at `now`
+    pub async fn list_due(
+        pool: &DatabasePool,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<RolloutPlan>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::list_due_postgres(pg_pool, now, limit).await,
+            DatabasePool::MySql(mysql_pool) => Self::list_due_mysql(mysql_pool, now, limit).await,
+        }
+    }
+
+    /// Store the progress of a plan that was at stage `previous_step`
+    ///
+    /// Returns `false` without writing anything if the stored plan is no
+    /// longer running at that stage, e.g. because another server advanced it.
+    pub async fn update_progress(
+        pool: &DatabasePool,
+        plan: &RolloutPlan,
+        previous_step: usize,
+    ) -> Result<bool> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::update_progress_postgres(pg_pool, plan, previous_step).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::update_progress_mysql(mysql_pool, plan, previous_step).await
+            }
+        }
+    }
+
+    /// Remove the plan of a deployment
+    pub async fn delete(pool: &DatabasePool, deployment_id: &DeploymentId) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::delete_postgres(pg_pool, deployment_id).await,
+            DatabasePool::MySql(mysql_pool) => Self::delete_mysql(mysql_pool, deployment_id).await,
+        }
+    }
+
+    // PostgreSQL implementations
+    pub(crate) async fn upsert_postgres(
+        executor: impl sqlx::PgExecutor<'_>,
+        plan: &RolloutPlan,
+    ) -> Result<()> {
+        let query = r#"
+            INSERT INTO rollout_plans (deployment_id, steps, current_step, status, next_step_at, halt_reason, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (deployment_id)
+            DO UPDATE SET steps = EXCLUDED.steps, current_step = EXCLUDED.current_step, status = EXCLUDED.status,
+                next_step_at = EXCLUDED.next_step_at, halt_reason = EXCLUDED.halt_reason, updated_at = EXCLUDED.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(plan.deployment_id.as_uuid())
+            .bind(serde_json::to_value(&plan.steps)?)
+            .bind(plan.current_step as i32)
+            .bind(plan.status.to_string())
+            .bind(plan.next_step_at)
+            .bind(&plan.halt_reason)
+            .bind(plan.created_at)
+            .bind(plan.updated_at)
+            .execute(executor)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!(
+            "Stored rollout plan of deployment {} ({}, step {}/{})",
+            plan.deployment_id,
+            plan.status,
+            plan.current_step + 1,
+            plan.steps.len()
+        );
+        Ok(())
+    }
+
+    async fn get_postgres(
+        pool: &sqlx::PgPool,
+        deployment_id: &DeploymentId,
+    ) -> Result<Option<RolloutPlan>> {
+        let query = "SELECT * FROM rollout_plans WHERE deployment_id = $1";
+
+        let row = sqlx::query(query)
+            .bind(deployment_id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn list_due_postgres(
+        pool: &sqlx::PgPool,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<RolloutPlan>> {
+        let query = r#"
+            SELECT * FROM rollout_plans
+            WHERE status = 'running' AND next_step_at <= $1
+            ORDER BY next_step_at ASC
+            LIMIT $2
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(now)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_postgres_row).collect()
+    }
+
+    async fn update_progress_postgres(
+        pool: &sqlx::PgPool,
+        plan: &RolloutPlan,
+        previous_step: usize,
+    ) -> Result<bool> {
+        let query = r#"
+            UPDATE rollout_plans
+            SET current_step = $2, status = $3, next_step_at = $4, halt_reason = $5, updated_at = $6
+            WHERE deployment_id = $1 AND current_step = $7 AND status = 'running'
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(plan.deployment_id.as_uuid())
+            .bind(plan.current_step as i32)
+            .bind(plan.status.to_string())
+            .bind(plan.next_step_at)
+            .bind(&plan.halt_reason)
+            .bind(plan.updated_at)
+            .bind(previous_step as i32)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_postgres(pool: &sqlx::PgPool, deployment_id: &DeploymentId) -> Result<()> {
+        let query = "DELETE FROM rollout_plans WHERE deployment_id = $1";
+
+        sqlx::query(query)
+            .bind(deployment_id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Deleted rollout plan of deployment {}", deployment_id);
+        Ok(())
+    }
+
+    fn from_postgres_row(row: &sqlx::postgres::PgRow) -> Result<RolloutPlan> {
+        Ok(RolloutPlan {
+            deployment_id: DeploymentId::from_uuid(row.get("deployment_id")),
+            steps: serde_json::from_value(row.get("steps"))?,
+            current_step: row.get::<i32, _>("current_step") as usize,
+            status: row.get::<String, _>("status").parse()?,
+            next_step_at: row.get("next_step_at"),
+            halt_reason: row.get("halt_reason"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    // MySQL implementations
+    pub(crate) async fn upsert_mysql(
+        executor: impl sqlx::MySqlExecutor<'_>,
+        plan: &RolloutPlan,
+    ) -> Result<()> {
+        let query = r#"
+            INSERT INTO rollout_plans (deployment_id, steps, current_step, status, next_step_at, halt_reason, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE steps = VALUES(steps), current_step = VALUES(current_step), status = VALUES(status),
+                next_step_at = VALUES(next_step_at), halt_reason = VALUES(halt_reason), updated_at = VALUES(updated_at)
+        "#;
+
+        sqlx::query(query)
+            .bind(plan.deployment_id.as_uuid())
+            .bind(serde_json::to_value(&plan.steps)?)
+            .bind(plan.current_step as i32)
+            .bind(plan.status.to_string())
+            .bind(plan.next_step_at)
+            .bind(&plan.halt_reason)
+            .bind(plan.created_at)
+            .bind(plan.updated_at)
+            .execute(executor)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!(
+            "Stored rollout plan of deployment {} ({}, step {}/{})",
+            plan.deployment_id,
+            plan.status,
+            plan.current_step + 1,
+            plan.steps.len()
+        );
+        Ok(())
+    }
+
+    async fn get_mysql(
+        pool: &sqlx::MySqlPool,
+        deployment_id: &DeploymentId,
+    ) -> Result<Option<RolloutPlan>> {
+        let query = "SELECT * FROM rollout_plans WHERE deployment_id = ?";
+
+        let row = sqlx::query(query)
+            .bind(deployment_id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn list_due_mysql(
+        pool: &sqlx::MySqlPool,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<RolloutPlan>> {
+        let query = r#"
+            SELECT * FROM rollout_plans
+            WHERE status = 'running' AND next_step_at <= ?
+            ORDER BY next_step_at ASC
+            LIMIT ?
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(now)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_mysql_row).collect()
+    }
+
+    async fn update_progress_mysql(
+        pool: &sqlx::MySqlPool,
+        plan: &RolloutPlan,
+        previous_step: usize,
+    ) -> Result<bool> {
+        let query = r#"
+            UPDATE rollout_plans
+            SET current_step = ?, status = ?, next_step_at = ?, halt_reason = ?, updated_at = ?
+            WHERE deployment_id = ? AND current_step = ? AND status = 'running'
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(plan.current_step as i32)
+            .bind(plan.status.to_string())
+            .bind(plan.next_step_at)
+            .bind(&plan.halt_reason)
+            .bind(plan.updated_at)
+            .bind(plan.deployment_id.as_uuid())
+            .bind(previous_step as i32)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_mysql(pool: &sqlx::MySqlPool, deployment_id: &DeploymentId) -> Result<()> {
+        let query = "DELETE FROM rollout_plans WHERE deployment_id = ?";
+
+        sqlx::query(query)
+            .bind(deployment_id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Deleted rollout plan of deployment {}", deployment_id);
+        Ok(())
+    }
+
+    fn from_mysql_row(row: &sqlx::mysql::MySqlRow) -> Result<RolloutPlan> {
+        Ok(RolloutPlan {
+            deployment_id: DeploymentId::from_uuid(row.get("deployment_id")),
+            steps: serde_json::from_value(row.get("steps"))?,
+            current_step: row.get::<i32, _>("current_step") as usize,
+            status: row.get::<String, _>("status").parse()?,
+            next_step_at: row.get("next_step_at"),
+            halt_reason: row.get("halt_reason"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
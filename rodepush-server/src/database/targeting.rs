@@ -0,0 +1,249 @@
+//! Deployment targeting rules
+//!
+//! Targeting narrows a deployment down to the clients matching every
+//! criterion it sets: a range of native app versions, a range of OS
+//! versions, device locales, custom tags and explicit client IDs. Rules are
+//! stored in the deployment metadata and evaluated on every update check.
+//! Clients listed explicitly skip the rollout percentage; every other
+//! matching client still has to fall within it.
+
+use rodepush_core::{Result, RodePushError};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Metadata key holding the targeting rules of a deployment
+pub const TARGETING_METADATA_KEY: &str = "targeting";
+
+/// Most values accepted in a single list of a rule
+pub const MAX_TARGETING_VALUES: usize = 1000;
+
+/// Inclusive range of dotted versions, open on the sides left unset
+///
+/// Versions compare component by component, missing components counting as
+/// zero, so that OS versions such as "17" or "14.4.1" compare naturally.
+/// Pre-release and build suffixes are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionRange {
+    /// Lowest matching version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<String>,
+    /// Highest matching version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<String>,
+}
+
+impl VersionRange {
+    /// Check that both bounds are versions and in order
+    pub fn validate(&self, field: &str) -> Result<()> {
+        let bound = |version: &Option<String>| match version {
+            Some(version) => parse_version(version).map(Some).ok_or_else(|| {
+                RodePushError::validation(format!("{} has an invalid version {}", field, version))
+            }),
+            None => Ok(None),
+        };
+        if let (Some(min), Some(max)) = (bound(&self.min)?, bound(&self.max)?)
+            && compare_versions(&min, &max) == Ordering::Greater
+        {
+            return Err(RodePushError::validation(format!(
+                "{} must not have a minimum above its maximum",
+                field
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check whether `version` falls within the range
+    ///
+    /// A version that cannot be parsed never matches.
+    pub fn contains(&self, version: &str) -> bool {
+        let Some(version) = parse_version(version) else {
+            return false;
+        };
+        let within = |bound: &Option<String>, rejected: Ordering| {
+            bound
+                .as_deref()
+                .and_then(parse_version)
+                .is_none_or(|bound| compare_versions(&version, &bound) != rejected)
+        };
+        within(&self.min, Ordering::Less) && within(&self.max, Ordering::Greater)
+    }
+}
+
+impl std::fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) => write!(f, "{} to {}", min, max),
+            (Some(min), None) => write!(f, "{} or later", min),
+            (None, Some(max)) => write!(f, "{} or earlier", max),
+            (None, None) => write!(f, "any version"),
+        }
+    }
+}
+
+/// Parse the numeric components of a dotted version
+fn parse_version(version: &str) -> Option<Vec<u32>> {
+    let core = version.trim().split(['-', '+']).next().unwrap_or_default();
+    core.split('.')
+        .map(|component| component.parse().ok())
+        .collect()
+}
+
+/// Compare two parsed versions, padding the shorter one with zeros
+fn compare_versions(a: &[u32], b: &[u32]) -> Ordering {
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let left = a.get(i).copied().unwrap_or(0);
+            let right = b.get(i).copied().unwrap_or(0);
+            left.cmp(&right)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Normalize a locale for comparison, e.g. "en_US" to "en-us"
+fn normalize_locale(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+/// What a client reports about itself when checking for updates
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientProfile {
+    /// Stable identifier of the client installation
+    pub client_id: String,
+    /// Version of the native binary installed on the client
+    pub binary_version: String,
+    /// Version of the operating system
+    #[serde(default)]
+    pub os_version: Option<String>,
+    /// Device locale (e.g., "en-US")
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Custom tags assigned to the client by the application
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Criteria a client must all match to receive a deployment
+///
+/// Unset criteria match every client. Within a list, matching any value is
+/// enough.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetingRules {
+    /// Native app versions that receive the deployment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_versions: Option<VersionRange>,
+    /// OS versions that receive the deployment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os_versions: Option<VersionRange>,
+    /// Device locales that receive the deployment; a language such as "en"
+    /// matches all its regional variants
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locales: Vec<String>,
+    /// Tags of which a client must carry at least one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The only clients that receive the deployment; they skip the rollout
+    /// percentage
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub client_ids: Vec<String>,
+}
+
+impl TargetingRules {
+    /// Check whether the rules match every client
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Check the rules for invalid ranges and oversized or blank lists
+    pub fn validate(&self) -> Result<()> {
+        if let Some(range) = &self.app_versions {
+            range.validate("app_versions")?;
+        }
+        if let Some(range) = &self.os_versions {
+            range.validate("os_versions")?;
+        }
+        for (field, values) in [
+            ("locales", &self.locales),
+            ("tags", &self.tags),
+            ("client_ids", &self.client_ids),
+        ] {
+            if values.len() > MAX_TARGETING_VALUES {
+                return Err(RodePushError::validation(format!(
+                    "{} must have at most {} values",
+                    field, MAX_TARGETING_VALUES
+                )));
+            }
+            if values.iter().any(|value| value.trim().is_empty()) {
+                return Err(RodePushError::validation(format!(
+                    "{} must not contain empty values",
+                    field
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether the rules list the client explicitly
+    pub fn allowlists(&self, client_id: &str) -> bool {
+        self.client_ids
+            .iter()
+            .any(|id| id.trim() == client_id.trim())
+    }
+
+    /// Explain why `profile` does not match the rules, if it does not
+    pub fn mismatch(&self, profile: &ClientProfile) -> Option<String> {
+        if !self.client_ids.is_empty() && !self.allowlists(&profile.client_id) {
+            return Some(format!(
+                "client {} is not in the allowlist",
+                profile.client_id
+            ));
+        }
+        if let Some(range) = &self.app_versions
+            && !range.contains(&profile.binary_version)
+        {
+            return Some(format!(
+                "app version {} is not within {}",
+                profile.binary_version, range
+            ));
+        }
+        if let Some(range) = &self.os_versions {
+            match profile.os_version.as_deref() {
+                Some(os_version) if range.contains(os_version) => {}
+                Some(os_version) => {
+                    return Some(format!("OS version {} is not within {}", os_version, range));
+                }
+                None => return Some("client did not report its OS version".to_string()),
+            }
+        }
+        if !self.locales.is_empty() {
+            let Some(locale) = profile.locale.as_deref().map(normalize_locale) else {
+                return Some("client did not report its locale".to_string());
+            };
+            let matches = self
+                .locales
+                .iter()
+                .map(|l| normalize_locale(l))
+                .any(|rule| {
+                    locale == rule
+                        || locale
+                            .strip_prefix(&rule)
+                            .is_some_and(|rest| rest.starts_with('-'))
+                });
+            if !matches {
+                return Some(format!("locale {} is not targeted", locale));
+            }
+        }
+        if !self.tags.is_empty()
+            && !profile
+                .tags
+                .iter()
+                .any(|tag| self.tags.iter().any(|rule| rule.trim() == tag.trim()))
+        {
+            return Some(format!(
+                "client carries none of the tags {}",
+                self.tags.join(", ")
+            ));
+        }
+        None
+    }
+}
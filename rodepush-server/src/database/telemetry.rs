@@ -0,0 +1,424 @@
+//! Install telemetry data models and operations
+
+use chrono::{DateTime, Utc};
+use rodepush_core::{Result, RodePushError};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::database::{connection::DatabasePool, deployment::DeploymentId, error::DatabaseError};
+
+/// Install outcome reported by a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallEvent {
+    /// The client downloaded the update
+    Downloaded,
+    /// The client installed the update and launched it successfully
+    Installed,
+    /// The client reverted the update on its own
+    RolledBack,
+    /// The update crashed when the client launched it
+    CrashedOnLaunch,
+}
+
+impl InstallEvent {
+    /// Check whether the event means the update failed on the client
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            InstallEvent::RolledBack | InstallEvent::CrashedOnLaunch
+        )
+    }
+
+    /// Counters of [`InstallStats`] a client reporting the event is counted in
+    pub fn counted_outcomes(&self) -> &'static [&'static str] {
+        match self {
+            InstallEvent::Downloaded => &["downloaded"],
+            InstallEvent::Installed => &["installed", "attempted"],
+            InstallEvent::RolledBack => &["rolled_back", "failed", "attempted"],
+            InstallEvent::CrashedOnLaunch => &["crashed_on_launch", "failed", "attempted"],
+        }
+    }
+}
+
+impl std::fmt::Display for InstallEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallEvent::Downloaded => write!(f, "downloaded"),
+            InstallEvent::Installed => write!(f, "installed"),
+            InstallEvent::RolledBack => write!(f, "rolled_back"),
+            InstallEvent::CrashedOnLaunch => write!(f, "crashed_on_launch"),
+        }
+    }
+}
+
+impl std::str::FromStr for InstallEvent {
+    type Err = RodePushError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "downloaded" => Ok(InstallEvent::Downloaded),
+            "installed" => Ok(InstallEvent::Installed),
+            "rolled_back" => Ok(InstallEvent::RolledBack),
+            "crashed_on_launch" => Ok(InstallEvent::CrashedOnLaunch),
+            _ => Err(RodePushError::Validation {
+                message: format!("Invalid install event: {}", s),
+            }),
+        }
+    }
+}
+
+/// One install outcome of a deployment on a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallReport {
+    /// Report ID
+    pub id: Uuid,
+    /// Deployment the client installed
+    pub deployment_id: DeploymentId,
+    /// Stable identifier of the client installation
+    pub client_id: String,
+    /// What happened
+    pub event: InstallEvent,
+    /// Details sent by the client, e.g. a crash message
+    pub message: Option<String>,
+    /// When the report was received
+    pub created_at: DateTime<Utc>,
+}
+
+impl InstallReport {
+    /// Create a report received now
+    pub fn new(deployment_id: DeploymentId, client_id: String, event: InstallEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            deployment_id,
+            client_id,
+            event,
+            message: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Attach details sent by the client
+    pub fn with_message(mut self, message: Option<String>) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+/// Install outcomes of a deployment, counted in distinct clients
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallStats {
+    /// Clients that downloaded the deployment
+    pub downloaded: u64,
+    /// Clients that installed the deployment successfully
+    pub installed: u64,
+    /// Clients that reverted the deployment on their own
+    pub rolled_back: u64,
+    /// Clients on which the deployment crashed at launch
+    pub crashed_on_launch: u64,
+    /// Clients that reported any failure
+    pub failed: u64,
+    /// Clients that reported a success or a failure
+    pub attempted: u64,
+}
+
+impl InstallStats {
+    /// Share of attempted installs that failed, between 0 and 1
+    pub fn failure_rate(&self) -> f64 {
+        if self.attempted == 0 {
+            return 0.0;
+        }
+        self.failed as f64 / self.attempted as f64
+    }
+
+    /// Count one more client in `outcome`, one of [`InstallEvent::counted_outcomes`]
+    fn count(&mut self, outcome: &str) {
+        match outcome {
+            "downloaded" => self.downloaded += 1,
+            "installed" => self.installed += 1,
+            "rolled_back" => self.rolled_back += 1,
+            "crashed_on_launch" => self.crashed_on_launch += 1,
+            "failed" => self.failed += 1,
+            "attempted" => self.attempted += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Telemetry service for database operations
+///
+/// Each deployment keeps running counters of its install outcomes, so that
+/// reading them costs the same however many clients reported. A client is
+/// counted once per outcome; repeated reports are stored but not counted
+/// again.
+pub struct TelemetryService;
+
+impl TelemetryService {
+    /// Store an install report and count it
+    ///
+    /// Returns the install outcomes of the deployment including the report.
+    pub async fn record(pool: &DatabasePool, report: &InstallReport) -> Result<InstallStats> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::record_postgres(pg_pool, report).await,
+            DatabasePool::MySql(mysql_pool) => Self::record_mysql(mysql_pool, report).await,
+        }
+    }
+
+    /// Count the install outcomes of a deployment
+    pub async fn stats(pool: &DatabasePool, deployment_id: &DeploymentId) -> Result<InstallStats> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::stats_postgres(pg_pool, deployment_id).await,
+            DatabasePool::MySql(mysql_pool) => Self::stats_mysql(mysql_pool, deployment_id).await,
+        }
+    }
+
+    const STATS_COLUMNS: &'static str =
+        "downloaded, installed, rolled_back, crashed_on_launch, failed, attempted";
+
+    // PostgreSQL implementations
+    async fn record_postgres(pool: &sqlx::PgPool, report: &InstallReport) -> Result<InstallStats> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let query = r#"
+            INSERT INTO install_events (id, deployment_id, client_id, event, message, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+        "#;
+        sqlx::query(query)
+            .bind(report.id)
+            .bind(report.deployment_id.as_uuid())
+            .bind(&report.client_id)
+            .bind(report.event.to_string())
+            .bind(&report.message)
+            .bind(report.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        // Only outcomes the client is counted in for the first time come back
+        let query = r#"
+            INSERT INTO install_counted_clients (deployment_id, outcome, client_id)
+            SELECT $1, outcome, $2 FROM UNNEST($3::VARCHAR[]) AS outcome
+            ON CONFLICT DO NOTHING
+            RETURNING outcome
+        "#;
+        let outcomes: Vec<String> = sqlx::query_scalar(query)
+            .bind(report.deployment_id.as_uuid())
+            .bind(&report.client_id)
+            .bind(report.event.counted_outcomes())
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let mut increments = InstallStats::default();
+        for outcome in &outcomes {
+            increments.count(outcome);
+        }
+
+        let query = format!(
+            r#"
+            INSERT INTO install_counters (deployment_id, {columns})
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (deployment_id)
+            DO UPDATE SET downloaded = install_counters.downloaded + EXCLUDED.downloaded,
+                installed = install_counters.installed + EXCLUDED.installed,
+                rolled_back = install_counters.rolled_back + EXCLUDED.rolled_back,
+                crashed_on_launch = install_counters.crashed_on_launch + EXCLUDED.crashed_on_launch,
+                failed = install_counters.failed + EXCLUDED.failed,
+                attempted = install_counters.attempted + EXCLUDED.attempted
+            RETURNING {columns}
+        "#,
+            columns = Self::STATS_COLUMNS
+        );
+        let row = sqlx::query(&query)
+            .bind(report.deployment_id.as_uuid())
+            .bind(increments.downloaded as i64)
+            .bind(increments.installed as i64)
+            .bind(increments.rolled_back as i64)
+            .bind(increments.crashed_on_launch as i64)
+            .bind(increments.failed as i64)
+            .bind(increments.attempted as i64)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let stats = Self::row_to_stats_postgres(&row);
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::debug!(
+            "Recorded {} of deployment {} on client {}",
+            report.event,
+            report.deployment_id,
+            report.client_id
+        );
+        Ok(stats)
+    }
+
+    async fn stats_postgres(
+        pool: &sqlx::PgPool,
+        deployment_id: &DeploymentId,
+    ) -> Result<InstallStats> {
+        let query = format!(
+            "SELECT {} FROM install_counters WHERE deployment_id = $1",
+            Self::STATS_COLUMNS
+        );
+
+        let row = sqlx::query(&query)
+            .bind(deployment_id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(row
+            .map(|row| Self::row_to_stats_postgres(&row))
+            .unwrap_or_default())
+    }
+
+    fn row_to_stats_postgres(row: &sqlx::postgres::PgRow) -> InstallStats {
+        InstallStats {
+            downloaded: row.get::<i64, _>("downloaded") as u64,
+            installed: row.get::<i64, _>("installed") as u64,
+            rolled_back: row.get::<i64, _>("rolled_back") as u64,
+            crashed_on_launch: row.get::<i64, _>("crashed_on_launch") as u64,
+            failed: row.get::<i64, _>("failed") as u64,
+            attempted: row.get::<i64, _>("attempted") as u64,
+        }
+    }
+
+    // MySQL implementations
+    async fn record_mysql(pool: &sqlx::MySqlPool, report: &InstallReport) -> Result<InstallStats> {
+        let mut tx = pool.begin().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+
+        let query = r#"
+            INSERT INTO install_events (id, deployment_id, client_id, event, message, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+        "#;
+        sqlx::query(query)
+            .bind(report.id)
+            .bind(report.deployment_id.as_uuid())
+            .bind(&report.client_id)
+            .bind(report.event.to_string())
+            .bind(&report.message)
+            .bind(report.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        // Only outcomes the client is counted in for the first time insert a row
+        let mut increments = InstallStats::default();
+        for outcome in report.event.counted_outcomes() {
+            let result = sqlx::query(
+                "INSERT IGNORE INTO install_counted_clients (deployment_id, outcome, client_id) VALUES (?, ?, ?)",
+            )
+            .bind(report.deployment_id.as_uuid())
+            .bind(outcome)
+            .bind(&report.client_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+            if result.rows_affected() > 0 {
+                increments.count(outcome);
+            }
+        }
+
+        let query = format!(
+            r#"
+            INSERT INTO install_counters (deployment_id, {})
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE downloaded = downloaded + VALUES(downloaded),
+                installed = installed + VALUES(installed),
+                rolled_back = rolled_back + VALUES(rolled_back),
+                crashed_on_launch = crashed_on_launch + VALUES(crashed_on_launch),
+                failed = failed + VALUES(failed),
+                attempted = attempted + VALUES(attempted)
+        "#,
+            Self::STATS_COLUMNS
+        );
+        sqlx::query(&query)
+            .bind(report.deployment_id.as_uuid())
+            .bind(increments.downloaded as i64)
+            .bind(increments.installed as i64)
+            .bind(increments.rolled_back as i64)
+            .bind(increments.crashed_on_launch as i64)
+            .bind(increments.failed as i64)
+            .bind(increments.attempted as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        let query = format!(
+            "SELECT {} FROM install_counters WHERE deployment_id = ?",
+            Self::STATS_COLUMNS
+        );
+        let row = sqlx::query(&query)
+            .bind(report.deployment_id.as_uuid())
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+        let stats = Self::row_to_stats_mysql(&row);
+
+        tx.commit().await.map_err(|e| DatabaseError::Transaction {
+            message: e.to_string(),
+        })?;
+        tracing::debug!(
+            "Recorded {} of deployment {} on client {}",
+            report.event,
+            report.deployment_id,
+            report.client_id
+        );
+        Ok(stats)
+    }
+
+    async fn stats_mysql(
+        pool: &sqlx::MySqlPool,
+        deployment_id: &DeploymentId,
+    ) -> Result<InstallStats> {
+        let query = format!(
+            "SELECT {} FROM install_counters WHERE deployment_id = ?",
+            Self::STATS_COLUMNS
+        );
+
+        let row = sqlx::query(&query)
+            .bind(deployment_id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(row
+            .map(|row| Self::row_to_stats_mysql(&row))
+            .unwrap_or_default())
+    }
+
+    fn row_to_stats_mysql(row: &sqlx::mysql::MySqlRow) -> InstallStats {
+        InstallStats {
+            downloaded: row.get::<i64, _>("downloaded") as u64,
+            installed: row.get::<i64, _>("installed") as u64,
+            rolled_back: row.get::<i64, _>("rolled_back") as u64,
+            crashed_on_launch: row.get::<i64, _>("crashed_on_launch") as u64,
+            failed: row.get::<i64, _>("failed") as u64,
+            attempted: row.get::<i64, _>("attempted") as u64,
+        }
+    }
+}
@@ -0,0 +1,432 @@
+//! Resumable upload sessions and data models
+
+use chrono::{DateTime, Duration, Utc};
+use rodepush_core::{BundleId, BundleMetadata, Result, RodePushError};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::{application::ApplicationId, connection::DatabasePool, error::DatabaseError};
+use sqlx::Row;
+
+/// Upload session identifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UploadSessionId(Uuid);
+
+impl UploadSessionId {
+    /// Generate a new upload session ID
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// Create from existing UUID
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+
+    /// Create from string representation
+    pub fn from_string(s: &str) -> Result<Self> {
+        let uuid = Uuid::parse_str(s).map_err(|e| RodePushError::Validation {
+            message: format!("Invalid UUID: {}", e),
+        })?;
+        Ok(Self(uuid))
+    }
+
+    /// Get the underlying UUID
+    pub fn as_uuid(&self) -> Uuid {
+        self.0
+    }
+
+    /// Get string representation
+    pub fn as_str(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl Default for UploadSessionId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for UploadSessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Upload session model
+///
+/// A session records the declared metadata of a bundle while its chunks are
+/// uploaded individually. Chunks are stored under keys derived from the
+/// session and the chunk checksum, so what is missing can always be worked
+/// out from storage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    /// Upload session ID
+    pub id: UploadSessionId,
+    /// Associated application ID
+    pub application_id: ApplicationId,
+    /// ID of the bundle being uploaded
+    pub bundle_id: BundleId,
+    /// Declared metadata of the bundle being uploaded
+    pub metadata: BundleMetadata,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Time after which the session and its chunks may be garbage-collected
+    pub expires_at: DateTime<Utc>,
+}
+
+impl UploadSession {
+    /// Create a new upload session that expires after `ttl`
+    pub fn new(application_id: ApplicationId, metadata: BundleMetadata, ttl: Duration) -> Self {
+        let created_at = Utc::now();
+        Self {
+            id: UploadSessionId::new(),
+            application_id,
+            bundle_id: metadata.id.clone(),
+            metadata,
+            created_at,
+            expires_at: created_at + ttl,
+        }
+    }
+
+    /// Check whether the session has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    /// Push the expiry back to `ttl` from now
+    pub fn extend(&mut self, ttl: Duration) {
+        self.expires_at = Utc::now() + ttl;
+    }
+
+    /// Storage key of the uploaded chunk with the given checksum
+    pub fn chunk_storage_key(&self, checksum: &str) -> String {
+        format!("uploads/{}/{}.chunk", self.id, checksum)
+    }
+
+    /// Distinct chunk checksums in upload order
+    pub fn chunk_checksums(&self) -> Vec<&str> {
+        let mut checksums: Vec<&str> = Vec::new();
+        for chunk in &self.metadata.chunks {
+            if !checksums.contains(&chunk.checksum.as_str()) {
+                checksums.push(&chunk.checksum);
+            }
+        }
+        checksums
+    }
+}
+
+/// Upload session service for database operations
+pub struct UploadSessionService;
+
+impl UploadSessionService {
+    /// Create a new upload session in the database
+    pub async fn create(pool: &DatabasePool, session: &UploadSession) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::create_postgres(pg_pool, session).await,
+            DatabasePool::MySql(mysql_pool) => Self::create_mysql(mysql_pool, session).await,
+        }
+    }
+
+    /// Get upload session by ID from the database
+    pub async fn get_by_id(
+        pool: &DatabasePool,
+        id: &UploadSessionId,
+    ) -> Result<Option<UploadSession>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::get_by_id_postgres(pg_pool, id).await,
+            DatabasePool::MySql(mysql_pool) => Self::get_by_id_mysql(mysql_pool, id).await,
+        }
+    }
+
+    /// Get the newest unexpired session uploading a bundle
+    pub async fn get_active_for_bundle(
+        pool: &DatabasePool,
+        application_id: &ApplicationId,
+        bundle_id: &BundleId,
+    ) -> Result<Option<UploadSession>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => {
+                Self::get_active_for_bundle_postgres(pg_pool, application_id, bundle_id).await
+            }
+            DatabasePool::MySql(mysql_pool) => {
+                Self::get_active_for_bundle_mysql(mysql_pool, application_id, bundle_id).await
+            }
+        }
+    }
+
+    /// Update the expiry of an upload session
+    pub async fn update_expiry(pool: &DatabasePool, session: &UploadSession) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::update_expiry_postgres(pg_pool, session).await,
+            DatabasePool::MySql(mysql_pool) => Self::update_expiry_mysql(mysql_pool, session).await,
+        }
+    }
+
+    /// Delete upload session from the database
+    pub async fn delete(pool: &DatabasePool, id: &UploadSessionId) -> Result<()> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::delete_postgres(pg_pool, id).await,
+            DatabasePool::MySql(mysql_pool) => Self::delete_mysql(mysql_pool, id).await,
+        }
+    }
+
+    /// List expired upload sessions, oldest first
+    pub async fn list_expired(pool: &DatabasePool, limit: i64) -> Result<Vec<UploadSession>> {
+        match pool {
+            DatabasePool::Postgres(pg_pool) => Self::list_expired_postgres(pg_pool, limit).await,
+            DatabasePool::MySql(mysql_pool) => Self::list_expired_mysql(mysql_pool, limit).await,
+        }
+    }
+
+    // PostgreSQL implementations
+    async fn create_postgres(pool: &sqlx::PgPool, session: &UploadSession) -> Result<()> {
+        let query = r#"
+            INSERT INTO upload_sessions (id, application_id, bundle_id, metadata, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+        "#;
+
+        sqlx::query(query)
+            .bind(session.id.as_uuid())
+            .bind(session.application_id.as_uuid())
+            .bind(session.bundle_id.as_uuid())
+            .bind(serde_json::to_value(&session.metadata)?)
+            .bind(session.created_at)
+            .bind(session.expires_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Created upload session: {}", session.id);
+        Ok(())
+    }
+
+    async fn get_by_id_postgres(
+        pool: &sqlx::PgPool,
+        id: &UploadSessionId,
+    ) -> Result<Option<UploadSession>> {
+        let query = "SELECT * FROM upload_sessions WHERE id = $1";
+
+        let row = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn get_active_for_bundle_postgres(
+        pool: &sqlx::PgPool,
+        application_id: &ApplicationId,
+        bundle_id: &BundleId,
+    ) -> Result<Option<UploadSession>> {
+        let query = r#"
+            SELECT * FROM upload_sessions
+            WHERE application_id = $1 AND bundle_id = $2 AND expires_at > NOW()
+            ORDER BY created_at DESC
+            LIMIT 1
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(bundle_id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_postgres_row(&row)).transpose()
+    }
+
+    async fn update_expiry_postgres(pool: &sqlx::PgPool, session: &UploadSession) -> Result<()> {
+        let query = "UPDATE upload_sessions SET expires_at = $2 WHERE id = $1";
+
+        sqlx::query(query)
+            .bind(session.id.as_uuid())
+            .bind(session.expires_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    async fn delete_postgres(pool: &sqlx::PgPool, id: &UploadSessionId) -> Result<()> {
+        let query = "DELETE FROM upload_sessions WHERE id = $1";
+
+        sqlx::query(query)
+            .bind(id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Deleted upload session: {}", id);
+        Ok(())
+    }
+
+    async fn list_expired_postgres(pool: &sqlx::PgPool, limit: i64) -> Result<Vec<UploadSession>> {
+        let query = r#"
+            SELECT * FROM upload_sessions
+            WHERE expires_at <= NOW()
+            ORDER BY expires_at ASC
+            LIMIT $1
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_postgres_row).collect()
+    }
+
+    fn from_postgres_row(row: &sqlx::postgres::PgRow) -> Result<UploadSession> {
+        Ok(UploadSession {
+            id: UploadSessionId::from_uuid(row.get("id")),
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            bundle_id: BundleId::from_uuid(row.get("bundle_id")),
+            metadata: serde_json::from_value(row.get("metadata"))?,
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+        })
+    }
+
+    // MySQL implementations
+    async fn create_mysql(pool: &sqlx::MySqlPool, session: &UploadSession) -> Result<()> {
+        let query = r#"
+            INSERT INTO upload_sessions (id, application_id, bundle_id, metadata, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(session.id.as_uuid())
+            .bind(session.application_id.as_uuid())
+            .bind(session.bundle_id.as_uuid())
+            .bind(serde_json::to_value(&session.metadata)?)
+            .bind(session.created_at)
+            .bind(session.expires_at)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Created upload session: {}", session.id);
+        Ok(())
+    }
+
+    async fn get_by_id_mysql(
+        pool: &sqlx::MySqlPool,
+        id: &UploadSessionId,
+    ) -> Result<Option<UploadSession>> {
+        let query = "SELECT * FROM upload_sessions WHERE id = ?";
+
+        let row = sqlx::query(query)
+            .bind(id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn get_active_for_bundle_mysql(
+        pool: &sqlx::MySqlPool,
+        application_id: &ApplicationId,
+        bundle_id: &BundleId,
+    ) -> Result<Option<UploadSession>> {
+        let query = r#"
+            SELECT * FROM upload_sessions
+            WHERE application_id = ? AND bundle_id = ? AND expires_at > NOW()
+            ORDER BY created_at DESC
+            LIMIT 1
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(application_id.as_uuid())
+            .bind(bundle_id.as_uuid())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        row.map(|row| Self::from_mysql_row(&row)).transpose()
+    }
+
+    async fn update_expiry_mysql(pool: &sqlx::MySqlPool, session: &UploadSession) -> Result<()> {
+        let query = "UPDATE upload_sessions SET expires_at = ? WHERE id = ?";
+
+        sqlx::query(query)
+            .bind(session.expires_at)
+            .bind(session.id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    async fn delete_mysql(pool: &sqlx::MySqlPool, id: &UploadSessionId) -> Result<()> {
+        let query = "DELETE FROM upload_sessions WHERE id = ?";
+
+        sqlx::query(query)
+            .bind(id.as_uuid())
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        tracing::info!("Deleted upload session: {}", id);
+        Ok(())
+    }
+
+    async fn list_expired_mysql(pool: &sqlx::MySqlPool, limit: i64) -> Result<Vec<UploadSession>> {
+        let query = r#"
+            SELECT * FROM upload_sessions
+            WHERE expires_at <= NOW()
+            ORDER BY expires_at ASC
+            LIMIT ?
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::Query {
+                message: e.to_string(),
+            })?;
+
+        rows.iter().map(Self::from_mysql_row).collect()
+    }
+
+    fn from_mysql_row(row: &sqlx::mysql::MySqlRow) -> Result<UploadSession> {
+        Ok(UploadSession {
+            id: UploadSessionId::from_uuid(row.get("id")),
+            application_id: ApplicationId::from_uuid(row.get("application_id")),
+            bundle_id: BundleId::from_uuid(row.get("bundle_id")),
+            metadata: serde_json::from_value(row.get("metadata"))?,
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+        })
+    }
+}
@@ -3,6 +3,8 @@
 //! This crate provides the server-side functionality for RodePush,
 //! including database operations, API endpoints, and business logic.
 
+pub mod api;
+pub mod cache;
 pub mod database;
 
 pub use database::*;
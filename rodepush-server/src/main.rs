@@ -3,18 +3,26 @@ use axum::{
     http::StatusCode,
     routing::{get, post},
 };
-use chrono::{DateTime, Utc};
-use rodepush_core::{AssetCollection, AssetDiff, LogContext, init_server_logging};
+use rodepush_core::storage::FilesystemStorage;
+use rodepush_core::{AssetDiff, LogContext, init_server_logging};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
 use tower_http::trace::TraceLayer;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-mod database;
+use rodepush_server::api::{self, ApiResponse, AppState, AutoRollbackPolicy};
+use rodepush_server::cache::{CacheConfig, ServerCache};
+use rodepush_server::database::{DatabaseConfig, DatabaseManager};
 
-use database::{
-    Application, ApplicationId, DatabaseManager, Deployment, DeploymentId, DiffPackage,
-    DiffPackageId,
-};
+/// How often expired upload sessions are removed
+const UPLOAD_SESSION_COLLECTION_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How often newly activated deployments get differential packages pre-generated
+const DIFF_PREGENERATION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often staged rollouts are moved to their next stage
+const ROLLOUT_SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
 
 async fn hello() -> &'static str {
     let context = LogContext::new("hello_handler", "rodepush-server");
@@ -28,84 +36,12 @@ async fn health() -> &'static str {
     "OK"
 }
 
-/// Standard API response format
-#[derive(Debug, Serialize, Deserialize)]
-struct ApiResponse<T> {
-    success: bool,
-    data: Option<T>,
-    error: Option<String>,
-    timestamp: DateTime<Utc>,
-    request_id: String,
-}
-
-impl<T> ApiResponse<T> {
-    fn success(data: T) -> Self {
-        Self {
-            success: true,
-            data: Some(data),
-            error: None,
-            timestamp: Utc::now(),
-            request_id: uuid::Uuid::new_v4().to_string(),
-        }
-    }
-
-    #[allow(dead_code)]
-    fn error(error: String) -> Self {
-        Self {
-            success: false,
-            data: None,
-            error: Some(error),
-            timestamp: Utc::now(),
-            request_id: uuid::Uuid::new_v4().to_string(),
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct AssetCollectionUpload {
-    collection: AssetCollection,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 struct AssetDiffRequest {
     old_collection_id: String,
     new_collection_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AssetCollectionResponse {
-    collection_id: String,
-    assets_count: usize,
-    total_size: u64,
-    created_at: DateTime<Utc>,
-}
-
-async fn upload_asset_collection(
-    Json(payload): Json<AssetCollectionUpload>,
-) -> Result<Json<ApiResponse<AssetCollectionResponse>>, (StatusCode, String)> {
-    let context = LogContext::new("upload_asset_collection", "rodepush-server");
-    context.log_asset_operation(
-        "upload",
-        &payload.collection.id,
-        payload.collection.len(),
-        payload.collection.total_size,
-    );
-
-    // In a real implementation, we would:
-    // 1. Validate the collection
-    // 2. Store it in the database
-    // 3. Return the collection ID
-
-    let response = AssetCollectionResponse {
-        collection_id: payload.collection.id.0.clone(),
-        assets_count: payload.collection.len(),
-        total_size: payload.collection.total_size,
-        created_at: Utc::now(),
-    };
-
-    Ok(Json(ApiResponse::success(response)))
-}
-
 async fn get_asset_diff(
     Json(payload): Json<AssetDiffRequest>,
 ) -> Result<Json<ApiResponse<AssetDiff>>, (StatusCode, String)> {
@@ -126,23 +62,6 @@ async fn get_asset_diff(
     Ok(Json(ApiResponse::success(diff)))
 }
 
-async fn get_compressed_assets(
-    collection_id: String,
-) -> Result<Vec<u8>, (axum::http::StatusCode, String)> {
-    let context = LogContext::new("get_compressed_assets", "rodepush-server");
-    context.info(&format!(
-        "Retrieving compressed assets for collection {}",
-        collection_id
-    ));
-
-    // In a real implementation, we would:
-    // 1. Retrieve the compressed asset collection from storage
-    // 2. Return the binary data
-
-    // For now, we'll just return empty data
-    Ok(vec![])
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
@@ -154,15 +73,75 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let context = LogContext::new("server_startup", "rodepush-server");
     context.info("Starting RodePush server");
 
+    let mut database_config = DatabaseConfig::default();
+    if let Ok(url) = std::env::var("DATABASE_URL") {
+        database_config.url = url;
+    }
+    let mut cache_config = CacheConfig::default();
+    if let Ok(url) = std::env::var("REDIS_URL") {
+        cache_config.redis_url = Some(url);
+    }
+    let cache = ServerCache::connect(cache_config).await?;
+    let database = DatabaseManager::new(&database_config)
+        .await?
+        .with_cache(cache);
+    database.run_migrations().await?;
+
+    let storage_dir =
+        std::env::var("RODEPUSH_STORAGE_DIR").unwrap_or_else(|_| "./storage".to_string());
+    let storage = FilesystemStorage::new(&storage_dir)?;
+    info!("Storing bundle data in {}", storage_dir);
+
+    let mut state = AppState::new(Arc::new(database), Arc::new(storage));
+    if let Ok(public_url) = std::env::var("RODEPUSH_PUBLIC_URL") {
+        state = state.with_public_url(public_url);
+    }
+    match std::env::var("RODEPUSH_ADMIN_KEY") {
+        Ok(admin_key) if !admin_key.trim().is_empty() => {
+            state = state.with_admin_key(admin_key.trim());
+        }
+        _ => info!("RODEPUSH_ADMIN_KEY is not set; applications cannot be created over the API"),
+    }
+    let mut auto_rollback = AutoRollbackPolicy::default();
+    if let Ok(value) = std::env::var("RODEPUSH_AUTO_ROLLBACK_FAILURE_RATE") {
+        match value.parse::<f64>() {
+            Ok(rate) if (0.0..=1.0).contains(&rate) => auto_rollback.failure_rate_threshold = rate,
+            _ => warn!(
+                "Ignoring invalid RODEPUSH_AUTO_ROLLBACK_FAILURE_RATE: {}",
+                value
+            ),
+        }
+    }
+    if let Ok(value) = std::env::var("RODEPUSH_AUTO_ROLLBACK_MIN_INSTALLS") {
+        match value.parse::<u64>() {
+            Ok(min_installs) => auto_rollback.min_installs = min_installs,
+            Err(_) => warn!(
+                "Ignoring invalid RODEPUSH_AUTO_ROLLBACK_MIN_INSTALLS: {}",
+                value
+            ),
+        }
+    }
+    if std::env::var("RODEPUSH_AUTO_ROLLBACK").is_ok_and(|value| value == "false") {
+        auto_rollback.enabled = false;
+    }
+    state = state.with_auto_rollback(auto_rollback);
+    api::uploads::spawn_session_collector(
+        state.database.clone(),
+        state.storage.clone(),
+        UPLOAD_SESSION_COLLECTION_INTERVAL,
+    );
+    api::diffs::spawn_diff_pregeneration(
+        state.diffs.clone(),
+        api::diffs::DEFAULT_PREGENERATED_DIFFS,
+        DIFF_PREGENERATION_INTERVAL,
+    );
+    api::rollouts::spawn_rollout_scheduler(state.database.clone(), ROLLOUT_SCHEDULER_INTERVAL);
+
     let app = Router::new()
         .route("/", get(hello))
         .route("/health", get(health))
-        .route("/api/v1/assets/collections", post(upload_asset_collection))
         .route("/api/v1/assets/diff", post(get_asset_diff))
-        .route(
-            "/api/v1/assets/compressed/:collection_id",
-            get(get_compressed_assets),
-        )
+        .nest("/api/v1", api::router(state))
         .layer(TraceLayer::new_for_http());
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
@@ -0,0 +1,94 @@
+//! Application endpoint tests
+//!
+//! These tests cover request validation, the update semantics and the
+//! response body of the application endpoints; they need no database.
+
+use axum::http::StatusCode;
+use rodepush_server::api::{
+    ApplicationInfo, CreateApplicationRequest, CreatedApplicationResponse, UpdateApplicationRequest,
+};
+use rodepush_server::database::{Application, generate_api_key};
+
+#[test]
+fn test_create_application_request() {
+    let request: CreateApplicationRequest = serde_json::from_value(serde_json::json!({
+        "name": " My App ",
+        "description": "Mobile storefront",
+        "owner": " ",
+        "settings": {"channel": "beta"}
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+
+    let api_key = generate_api_key();
+    let application = request.into_application(api_key.clone());
+    assert_eq!(application.name, "My App");
+    assert_eq!(
+        application.description.as_deref(),
+        Some("Mobile storefront")
+    );
+    assert_eq!(application.owner, None);
+    assert_eq!(application.settings["channel"], "beta");
+    assert!(application.verify_api_key(&api_key));
+
+    let request: CreateApplicationRequest =
+        serde_json::from_value(serde_json::json!({"name": "  "})).unwrap();
+    assert_eq!(
+        request.validate().unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_update_application_request() {
+    let mut application = Application::new("My App".to_string(), generate_api_key())
+        .with_description("Old description".to_string())
+        .with_owner("team@example.com".to_string())
+        .with_setting("channel".to_string(), serde_json::json!("beta"))
+        .with_setting("region".to_string(), serde_json::json!("eu"));
+    let updated_at = application.updated_at;
+
+    let request: UpdateApplicationRequest = serde_json::from_value(serde_json::json!({
+        "description": "",
+        "settings": {"channel": null, "region": "us", "retries": 3}
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+    request.apply(&mut application);
+
+    // Omitted fields are kept, empty ones cleared
+    assert_eq!(application.name, "My App");
+    assert_eq!(application.description, None);
+    assert_eq!(application.owner.as_deref(), Some("team@example.com"));
+    // Settings are merged, and null removes a key
+    assert!(!application.settings.contains_key("channel"));
+    assert_eq!(application.settings["region"], "us");
+    assert_eq!(application.settings["retries"], 3);
+    assert!(application.updated_at >= updated_at);
+
+    let invalid = UpdateApplicationRequest {
+        name: Some(String::new()),
+        ..Default::default()
+    };
+    assert!(invalid.validate().is_err());
+}
+
+#[test]
+fn test_application_info_hides_api_key_hash() {
+    let api_key = generate_api_key();
+    let application = Application::new("My App".to_string(), api_key.clone());
+
+    let response = CreatedApplicationResponse {
+        api_key: api_key.clone(),
+        application: ApplicationInfo::from(&application),
+    };
+    let body = serde_json::to_value(&response).unwrap();
+    assert_eq!(body["api_key"], api_key);
+    assert_eq!(body["id"], application.id.to_string());
+    assert_eq!(body["name"], "My App");
+    assert!(body.get("api_key_hash").is_none());
+
+    let info = serde_json::to_value(ApplicationInfo::from(&application)).unwrap();
+    assert!(info.get("api_key").is_none());
+    assert!(info.get("api_key_hash").is_none());
+}
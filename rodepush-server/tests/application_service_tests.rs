@@ -5,7 +5,7 @@
 
 use rodepush_server::database::{
     Application, ApplicationId, ApplicationService, DatabaseConfig, DatabaseConnection,
-    DatabaseType,
+    DatabaseType, Environment, EnvironmentService,
 };
 use serial_test::serial;
 use std::collections::HashMap;
@@ -81,7 +81,8 @@ async fn test_application_create() -> Result<(), Box<dyn std::error::Error>> {
     let retrieved = retrieved.unwrap();
     assert_eq!(retrieved.id, app.id);
     assert_eq!(retrieved.name, "Test App");
-    assert_eq!(retrieved.api_key, "test-api-key-123");
+    assert!(retrieved.verify_api_key("test-api-key-123"));
+    assert_ne!(retrieved.api_key_hash, "test-api-key-123");
     assert_eq!(retrieved.description, Some("A test application".to_string()));
     assert_eq!(retrieved.owner, Some("test@example.com".to_string()));
 
@@ -208,3 +209,38 @@ async fn test_application_list_pagination() -> Result<(), Box<dyn std::error::Er
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_application_create_with_environments() -> Result<(), Box<dyn std::error::Error>> {
+    wait_for_database().await?;
+    let connection = setup_test_db().await?;
+
+    let app = Application::new("App".to_string(), "api-key-environments".to_string());
+    let environments = vec![
+        Environment::new(app.id.clone(), "staging".to_string()),
+        Environment::new(app.id.clone(), "production".to_string()),
+    ];
+    ApplicationService::create_with_environments(connection.pool(), &app, &environments).await?;
+    let stored = EnvironmentService::list_for_application(connection.pool(), &app.id).await?;
+    assert_eq!(stored.len(), 2);
+
+    // A failing environment leaves no application behind
+    let app = Application::new("Other App".to_string(), "api-key-duplicate".to_string());
+    let environments = vec![
+        Environment::new(app.id.clone(), "production".to_string()),
+        Environment::new(app.id.clone(), "production".to_string()),
+    ];
+    assert!(
+        ApplicationService::create_with_environments(connection.pool(), &app, &environments)
+            .await
+            .is_err()
+    );
+    assert!(
+        ApplicationService::get_by_id(connection.pool(), &app.id)
+            .await?
+            .is_none()
+    );
+
+    Ok(())
+}
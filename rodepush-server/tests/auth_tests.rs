@@ -0,0 +1,259 @@
+//! Authentication and authorization tests
+//!
+//! These tests cover API key extraction, hashing, token scopes, member roles
+//! and the per-application authorization check; they need no database.
+
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use chrono::{Duration, Utc};
+use rodepush_core::AuthError;
+use rodepush_server::api::auth::{API_KEY_HEADER, check_token, effective_scopes, extract_api_key};
+use rodepush_server::api::{ApiError, AuthenticatedApplication, CreateTokenRequest};
+use rodepush_server::database::{
+    ACCESS_TOKEN_PREFIX, API_KEY_PREFIX, AccessToken, Application, MemberRole, TokenScope,
+    generate_access_token, generate_api_key, hash_api_key,
+};
+
+fn headers(entries: &[(&str, &str)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in entries {
+        map.insert(
+            header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            HeaderValue::from_str(value).unwrap(),
+        );
+    }
+    map
+}
+
+#[test]
+fn test_extract_api_key() {
+    let bearer = headers(&[("authorization", "Bearer rp_secret")]);
+    assert_eq!(extract_api_key(&bearer).unwrap(), "rp_secret");
+
+    let lowercase = headers(&[("authorization", "bearer rp_secret")]);
+    assert_eq!(extract_api_key(&lowercase).unwrap(), "rp_secret");
+
+    let api_key = headers(&[(API_KEY_HEADER, "rp_secret")]);
+    assert_eq!(extract_api_key(&api_key).unwrap(), "rp_secret");
+
+    // Authorization wins over X-API-Key
+    let both = headers(&[
+        ("authorization", "Bearer rp_bearer"),
+        (API_KEY_HEADER, "rp_header"),
+    ]);
+    assert_eq!(extract_api_key(&both).unwrap(), "rp_bearer");
+}
+
+#[test]
+fn test_extract_api_key_rejects_bad_credentials() {
+    assert!(matches!(
+        extract_api_key(&HeaderMap::new()),
+        Err(AuthError::MissingAuth)
+    ));
+
+    for value in ["Basic dXNlcjpwYXNz", "Bearer", "Bearer   "] {
+        assert!(matches!(
+            extract_api_key(&headers(&[("authorization", value)])),
+            Err(AuthError::InvalidApiKey)
+        ));
+    }
+    assert!(matches!(
+        extract_api_key(&headers(&[(API_KEY_HEADER, "")])),
+        Err(AuthError::InvalidApiKey)
+    ));
+}
+
+#[test]
+fn test_api_keys_are_stored_hashed() {
+    let api_key = generate_api_key();
+    assert!(api_key.starts_with(API_KEY_PREFIX));
+    assert_ne!(api_key, generate_api_key());
+
+    let mut application = Application::new("Test App".to_string(), api_key.clone());
+    assert_eq!(application.api_key_hash, hash_api_key(&api_key));
+    assert_ne!(application.api_key_hash, api_key);
+    assert!(application.verify_api_key(&api_key));
+    assert!(!application.verify_api_key("rp_wrong"));
+
+    let rotated = generate_api_key();
+    application.set_api_key(&rotated);
+    assert!(application.verify_api_key(&rotated));
+    assert!(!application.verify_api_key(&api_key));
+}
+
+#[test]
+fn test_authorize_application() {
+    let application = Application::new("Test App".to_string(), generate_api_key());
+    let other = Application::new("Other App".to_string(), generate_api_key());
+    let auth = AuthenticatedApplication::with_api_key(application.clone());
+
+    assert_eq!(
+        auth.authorize(&application.id.to_string()).unwrap(),
+        application.id
+    );
+    assert_eq!(
+        auth.authorize(&other.id.to_string()).unwrap_err().status(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        auth.authorize("not-a-uuid").unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_auth_error_status_codes() {
+    assert_eq!(
+        ApiError::from(AuthError::MissingAuth).status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        ApiError::from(AuthError::InvalidApiKey).status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        ApiError::from(AuthError::InsufficientPermissions {
+            operation: "upload".to_string()
+        })
+        .status(),
+        StatusCode::FORBIDDEN
+    );
+}
+
+fn test_token(scopes: Vec<TokenScope>) -> (Application, AccessToken) {
+    let application = Application::new("Test App".to_string(), generate_api_key());
+    let token = AccessToken::new(
+        application.id.clone(),
+        "CI uploads".to_string(),
+        &generate_access_token(),
+        scopes,
+    );
+    (application, token)
+}
+
+#[test]
+fn test_token_scopes() {
+    for scope in TokenScope::ALL {
+        assert_eq!(scope.to_string().parse::<TokenScope>().unwrap(), scope);
+        assert_eq!(
+            serde_json::to_value(scope).unwrap(),
+            serde_json::json!(scope.to_string())
+        );
+        assert!(TokenScope::Admin.grants(scope));
+    }
+    assert!("bundles:read".parse::<TokenScope>().is_err());
+    assert!(!TokenScope::BundlesWrite.grants(TokenScope::DeploymentsWrite));
+    assert!(!TokenScope::UpdatesRead.grants(TokenScope::Admin));
+
+    let secret = generate_access_token();
+    assert!(secret.starts_with(ACCESS_TOKEN_PREFIX));
+    assert!(!secret.starts_with(API_KEY_PREFIX));
+}
+
+#[test]
+fn test_member_roles() {
+    assert!(MemberRole::Viewer.grants(TokenScope::UpdatesRead));
+    assert!(!MemberRole::Viewer.grants(TokenScope::BundlesWrite));
+    assert!(MemberRole::Developer.grants(TokenScope::BundlesWrite));
+    assert!(!MemberRole::Developer.grants(TokenScope::DeploymentsWrite));
+    assert!(MemberRole::ReleaseManager.grants(TokenScope::DeploymentsWrite));
+    assert!(!MemberRole::ReleaseManager.grants(TokenScope::Admin));
+    assert!(MemberRole::Admin.grants(TokenScope::Admin));
+
+    assert_eq!(
+        "release_manager".parse::<MemberRole>().unwrap(),
+        MemberRole::ReleaseManager
+    );
+    assert_eq!(
+        serde_json::to_value(MemberRole::ReleaseManager).unwrap(),
+        serde_json::json!("release_manager")
+    );
+}
+
+#[test]
+fn test_effective_scopes() {
+    let (application, token) = test_token(vec![TokenScope::BundlesWrite]);
+    let auth = AuthenticatedApplication::with_token(application, &token, None);
+    assert!(auth.has_scope(TokenScope::BundlesWrite));
+    assert!(!auth.has_scope(TokenScope::DeploymentsWrite));
+    assert_eq!(
+        auth.require_scope(TokenScope::Admin).unwrap_err().status(),
+        StatusCode::FORBIDDEN
+    );
+
+    // A member's role caps what its tokens grant
+    let (_, admin_token) = test_token(vec![TokenScope::Admin]);
+    let admin_token = admin_token.with_member("dev@example.com".to_string());
+    assert_eq!(
+        effective_scopes(&admin_token, Some(MemberRole::Developer)),
+        vec![TokenScope::BundlesWrite, TokenScope::UpdatesRead]
+    );
+    assert_eq!(
+        effective_scopes(&admin_token, Some(MemberRole::Admin)),
+        TokenScope::ALL.to_vec()
+    );
+    // Tokens of removed members grant nothing
+    assert!(effective_scopes(&admin_token, None).is_empty());
+
+    let (_, viewer_token) = test_token(vec![TokenScope::UpdatesRead]);
+    let viewer_token = viewer_token.with_member("dev@example.com".to_string());
+    assert_eq!(
+        effective_scopes(&viewer_token, Some(MemberRole::Admin)),
+        vec![TokenScope::UpdatesRead]
+    );
+}
+
+#[test]
+fn test_expired_and_revoked_tokens() {
+    let (_, token) = test_token(vec![TokenScope::UpdatesRead]);
+    assert!(check_token(&token).is_ok());
+
+    let unexpired = token.clone().with_expiry(Utc::now() + Duration::hours(1));
+    assert!(check_token(&unexpired).is_ok());
+
+    let expired = token.clone().with_expiry(Utc::now() - Duration::hours(1));
+    assert!(expired.is_expired());
+    assert!(matches!(
+        check_token(&expired),
+        Err(AuthError::TokenExpired { .. })
+    ));
+
+    let mut revoked = token;
+    revoked.revoked_at = Some(Utc::now());
+    assert!(revoked.is_revoked());
+    let error = check_token(&revoked).unwrap_err();
+    assert!(matches!(error, AuthError::TokenRevoked { .. }));
+    assert_eq!(ApiError::from(error).status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn test_create_token_request_validation() {
+    let request: CreateTokenRequest = serde_json::from_value(serde_json::json!({
+        "name": "CI uploads",
+        "scopes": ["bundles:write"],
+        "expires_in_seconds": 3600
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+    assert_eq!(request.scopes, vec![TokenScope::BundlesWrite]);
+    assert_eq!(request.member, None);
+
+    let mut invalid = request.clone();
+    invalid.scopes.clear();
+    assert!(invalid.validate().is_err());
+
+    let mut invalid = request.clone();
+    invalid.name = " ".to_string();
+    assert!(invalid.validate().is_err());
+
+    let mut invalid = request;
+    invalid.expires_in_seconds = Some(0);
+    assert!(invalid.validate().is_err());
+
+    assert!(
+        serde_json::from_value::<CreateTokenRequest>(serde_json::json!({
+            "name": "CI uploads",
+            "scopes": ["bundles:delete"]
+        }))
+        .is_err()
+    );
+}
@@ -0,0 +1,265 @@
+//! Bundle upload tests
+//!
+//! These tests drive the multipart parsing and chunk streaming of the bundle
+//! upload endpoint against filesystem storage; they need no database.
+
+use axum::body::Body;
+use axum::extract::{FromRequest, Multipart};
+use axum::http::{Request, StatusCode};
+use rodepush_core::storage::{FilesystemStorage, Storage};
+use rodepush_core::{Bundle, BundleBuilder, CompressionType, Platform, SemanticVersion};
+use rodepush_server::api::UploadLimits;
+use rodepush_server::api::bundles::{read_metadata_part, receive_bundle};
+use rodepush_server::database::ApplicationId;
+use tempfile::TempDir;
+use tokio::io::AsyncReadExt;
+
+const BOUNDARY: &str = "rodepush-test-boundary";
+
+/// Build a bundle with two uncompressed chunks
+fn test_bundle() -> Bundle {
+    let mut builder = BundleBuilder::new(
+        SemanticVersion::new(1, 2, 0),
+        Platform::Android,
+        "index.android.bundle".to_string(),
+    )
+    .with_compression(CompressionType::None);
+    builder
+        .add_chunk_from_data(b"var first = 'chunk';", "chunk-0".to_string())
+        .unwrap();
+    builder
+        .add_chunk_from_data(b"var second = 'chunk';", "chunk-1".to_string())
+        .unwrap();
+    builder.build().unwrap()
+}
+
+/// Encode `(name, file_name, data)` parts as a multipart request
+async fn multipart(parts: &[(&str, Option<&str>, Vec<u8>)]) -> Multipart {
+    let mut body = Vec::new();
+    for (name, file_name, data) in parts {
+        body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+        match file_name {
+            Some(file_name) => body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n",
+                    name, file_name
+                )
+                .as_bytes(),
+            ),
+            None => body.extend_from_slice(
+                format!("Content-Disposition: form-data; name=\"{}\"\r\n", name).as_bytes(),
+            ),
+        }
+        body.extend_from_slice(b"\r\n");
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+
+    let request = Request::builder()
+        .method("POST")
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    Multipart::from_request(request, &()).await.unwrap()
+}
+
+/// Multipart parts uploading `bundle` as built
+fn upload_parts(bundle: &Bundle) -> Vec<(&'static str, Option<&str>, Vec<u8>)> {
+    let mut parts = vec![(
+        "metadata",
+        None,
+        serde_json::to_vec(&bundle.metadata).unwrap(),
+    )];
+    for chunk in &bundle.chunks {
+        parts.push(("chunk", Some(chunk.id()), chunk.data.clone()));
+    }
+    parts
+}
+
+/// Number of files left anywhere below `dir`
+fn count_files(dir: &std::path::Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() { count_files(&path) } else { 1 }
+        })
+        .sum()
+}
+
+#[tokio::test]
+async fn test_upload_streams_chunks_into_storage() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let app_id = ApplicationId::new();
+    let bundle = test_bundle();
+
+    let mut form = multipart(&upload_parts(&bundle)).await;
+    let metadata = read_metadata_part(&mut form, &UploadLimits::default())
+        .await
+        .unwrap();
+    assert_eq!(metadata.id, bundle.metadata.id);
+
+    let received = receive_bundle(&mut form, &app_id, metadata, &storage)
+        .await
+        .unwrap();
+    assert_eq!(received.size_bytes, bundle.size());
+    assert!(received.storage_key.as_str().starts_with(&format!(
+        "bundles/{}/{}/",
+        app_id,
+        bundle.id()
+    )));
+
+    let mut stored = Vec::new();
+    storage
+        .open_object(&received.storage_key)
+        .await
+        .unwrap()
+        .read_to_end(&mut stored)
+        .await
+        .unwrap();
+    let expected: Vec<u8> = bundle.chunks.iter().flat_map(|c| c.data.clone()).collect();
+    assert_eq!(stored, expected);
+    assert_eq!(count_files(temp_dir.path()), 1);
+}
+
+#[tokio::test]
+async fn test_upload_rejects_corrupt_chunk() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let bundle = test_bundle();
+
+    // Same size as the original, different content
+    let mut parts = upload_parts(&bundle);
+    parts[2].2 = vec![b'x'; parts[2].2.len()];
+
+    let mut form = multipart(&parts).await;
+    let metadata = read_metadata_part(&mut form, &UploadLimits::default())
+        .await
+        .unwrap();
+    let error = receive_bundle(&mut form, &ApplicationId::new(), metadata, &storage)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(count_files(temp_dir.path()), 0);
+}
+
+#[tokio::test]
+async fn test_upload_rejects_chunk_size_mismatch() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let bundle = test_bundle();
+
+    let mut parts = upload_parts(&bundle);
+    parts[1].2.extend_from_slice(b"trailing");
+
+    let mut form = multipart(&parts).await;
+    let metadata = read_metadata_part(&mut form, &UploadLimits::default())
+        .await
+        .unwrap();
+    let error = receive_bundle(&mut form, &ApplicationId::new(), metadata, &storage)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(count_files(temp_dir.path()), 0);
+}
+
+#[tokio::test]
+async fn test_upload_rejects_unexpected_parts() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let bundle = test_bundle();
+
+    // Chunk data before the metadata
+    let mut parts = upload_parts(&bundle);
+    parts.swap(0, 1);
+    let mut form = multipart(&parts).await;
+    let error = read_metadata_part(&mut form, &UploadLimits::default())
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+    // Chunk delivered under the wrong id
+    let mut parts = upload_parts(&bundle);
+    parts[1].1 = Some("chunk-1");
+    let mut form = multipart(&parts).await;
+    let metadata = read_metadata_part(&mut form, &UploadLimits::default())
+        .await
+        .unwrap();
+    let error = receive_bundle(&mut form, &ApplicationId::new(), metadata, &storage)
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+    // Extra part after the last chunk
+    let mut parts = upload_parts(&bundle);
+    parts.push(("chunk", None, b"extra".to_vec()));
+    let mut form = multipart(&parts).await;
+    let metadata = read_metadata_part(&mut form, &UploadLimits::default())
+        .await
+        .unwrap();
+    let error = receive_bundle(&mut form, &ApplicationId::new(), metadata, &storage)
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(count_files(temp_dir.path()), 0);
+}
+
+#[tokio::test]
+async fn test_upload_enforces_limits() {
+    let bundle = test_bundle();
+
+    let limits = UploadLimits {
+        max_chunk_size: 8,
+        ..UploadLimits::default()
+    };
+    let mut form = multipart(&upload_parts(&bundle)).await;
+    let error = read_metadata_part(&mut form, &limits).await.unwrap_err();
+    assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let limits = UploadLimits {
+        max_bundle_size: bundle.size() - 1,
+        ..UploadLimits::default()
+    };
+    let mut form = multipart(&upload_parts(&bundle)).await;
+    let error = read_metadata_part(&mut form, &limits).await.unwrap_err();
+    assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let limits = UploadLimits {
+        max_chunks: 1,
+        ..UploadLimits::default()
+    };
+    let mut form = multipart(&upload_parts(&bundle)).await;
+    let error = read_metadata_part(&mut form, &limits).await.unwrap_err();
+    assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let limits = UploadLimits {
+        max_metadata_size: 16,
+        ..UploadLimits::default()
+    };
+    let mut form = multipart(&upload_parts(&bundle)).await;
+    let error = read_metadata_part(&mut form, &limits).await.unwrap_err();
+    assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_upload_rejects_inconsistent_metadata() {
+    let mut bundle = test_bundle();
+    bundle.metadata.chunks[1].offset = 0;
+
+    let mut form = multipart(&upload_parts(&bundle)).await;
+    let error = read_metadata_part(&mut form, &UploadLimits::default())
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,238 @@
+//! Server cache tests
+//!
+//! These tests exercise the update-check cache on top of the in-memory store,
+//! so they do not need a running Redis or PostgreSQL instance.
+
+use rodepush_core::{BundleId, Platform, SemanticVersion};
+use rodepush_server::cache::{
+    CacheConfig, CacheStore, MemoryCacheStore, ServerCache, UpdateCheckKey, UpdateTarget,
+};
+use rodepush_server::database::{ApplicationId, Bundle, Deployment};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn test_target(application_id: &ApplicationId, environment: &str) -> UpdateTarget {
+    let bundle = Bundle::new(
+        application_id.clone(),
+        "1.2.3".to_string(),
+        Platform::Ios,
+        "bundles/test.json".to_string(),
+        1024,
+        "checksum".to_string(),
+    );
+    let deployment = Deployment::new(
+        application_id.clone(),
+        bundle.id.clone(),
+        environment.to_string(),
+    );
+    UpdateTarget { deployment, bundle }
+}
+
+fn test_key(application_id: &ApplicationId, environment: &str) -> UpdateCheckKey {
+    UpdateCheckKey::new(
+        application_id.clone(),
+        environment,
+        Platform::Ios,
+        SemanticVersion::new(1, 2, 0),
+    )
+}
+
+#[tokio::test]
+async fn test_update_target_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = ServerCache::in_memory();
+    let app_id = ApplicationId::new();
+    let key = test_key(&app_id, "production");
+
+    let lookup = cache.get_update_targets(&key).await?;
+    assert!(lookup.targets.is_none());
+
+    let target = test_target(&app_id, "production");
+    let fallback = test_target(&app_id, "production");
+    cache
+        .set_update_targets(&key, lookup.generation, &[target.clone(), fallback.clone()])
+        .await?;
+
+    let cached = cache
+        .get_update_targets(&key)
+        .await?
+        .targets
+        .expect("cache hit");
+    assert_eq!(cached.len(), 2);
+    assert_eq!(cached[0].deployment.id, target.deployment.id);
+    assert_eq!(cached[0].bundle.id, target.bundle.id);
+    // Targets keep their order
+    assert_eq!(cached[1].deployment.id, fallback.deployment.id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_negative_answers_are_cached() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = ServerCache::in_memory();
+    let key = test_key(&ApplicationId::new(), "production");
+
+    cache.set_update_targets(&key, 0, &[]).await?;
+    assert!(
+        cache
+            .get_update_targets(&key)
+            .await?
+            .targets
+            .unwrap()
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalidate_environment() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = ServerCache::in_memory();
+    let app_id = ApplicationId::new();
+    let other_app_id = ApplicationId::new();
+
+    let production = test_key(&app_id, "production");
+    let production_android = UpdateCheckKey::new(
+        app_id.clone(),
+        "production",
+        Platform::Android,
+        SemanticVersion::new(1, 2, 0),
+    );
+    let prod = test_key(&app_id, "prod");
+    let other_app = test_key(&other_app_id, "production");
+
+    for key in [&production, &production_android, &prod, &other_app] {
+        cache.set_update_targets(key, 0, &[]).await?;
+    }
+
+    cache.invalidate_environment(&app_id, "production").await?;
+
+    let lookup = cache.get_update_targets(&production).await?;
+    assert!(lookup.targets.is_none());
+    assert_eq!(lookup.generation, 1);
+    assert!(
+        cache
+            .get_update_targets(&production_android)
+            .await?
+            .targets
+            .is_none()
+    );
+    // An environment whose name is a prefix of another must not be affected
+    assert!(cache.get_update_targets(&prod).await?.targets.is_some());
+    assert!(
+        cache
+            .get_update_targets(&other_app)
+            .await?
+            .targets
+            .is_some()
+    );
+
+    // Answers cached after an invalidation are served until the next one
+    cache
+        .set_update_targets(&production, lookup.generation, &[])
+        .await?;
+    assert!(
+        cache
+            .get_update_targets(&production)
+            .await?
+            .targets
+            .is_some()
+    );
+    cache.invalidate_environment(&app_id, "production").await?;
+    assert!(
+        cache
+            .get_update_targets(&production)
+            .await?
+            .targets
+            .is_none()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_answer_is_not_cached_after_invalidation()
+-> Result<(), Box<dyn std::error::Error>> {
+    let cache = ServerCache::in_memory();
+    let app_id = ApplicationId::new();
+    let key = test_key(&app_id, "production");
+
+    // A check misses and reads the database while a promotion is stored
+    let lookup = cache.get_update_targets(&key).await?;
+    assert!(lookup.targets.is_none());
+    let stale = test_target(&app_id, "production");
+    cache.invalidate_environment(&app_id, "production").await?;
+
+    // Its answer predates the promotion and must not be served after it
+    cache
+        .set_update_targets(&key, lookup.generation, &[stale])
+        .await?;
+    let lookup = cache.get_update_targets(&key).await?;
+    assert!(lookup.targets.is_none());
+
+    // The next check caches its fresh answer
+    let fresh = test_target(&app_id, "production");
+    cache
+        .set_update_targets(&key, lookup.generation, std::slice::from_ref(&fresh))
+        .await?;
+    let cached = cache
+        .get_update_targets(&key)
+        .await?
+        .targets
+        .expect("cache hit");
+    assert_eq!(cached[0].deployment.id, fresh.deployment.id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bundle_metadata_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = ServerCache::in_memory();
+    let target = test_target(&ApplicationId::new(), "staging");
+
+    assert!(cache.get_bundle(&target.bundle.id).await?.is_none());
+    cache.set_bundle(&target.bundle).await?;
+
+    let cached = cache
+        .get_bundle(&target.bundle.id)
+        .await?
+        .expect("cache hit");
+    assert_eq!(cached.checksum, target.bundle.checksum);
+
+    cache.invalidate_bundle(&target.bundle.id).await?;
+    assert!(cache.get_bundle(&target.bundle.id).await?.is_none());
+    assert!(cache.get_bundle(&BundleId::new()).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_store_expiry() -> Result<(), Box<dyn std::error::Error>> {
+    let store = Arc::new(MemoryCacheStore::new());
+    store
+        .set("short", b"value".to_vec(), Duration::from_millis(20))
+        .await?;
+    store
+        .set("long", b"value".to_vec(), Duration::from_secs(60))
+        .await?;
+    assert_eq!(store.len(), 2);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert!(store.get("short").await?.is_none());
+    assert_eq!(store.get("long").await?, Some(b"value".to_vec()));
+
+    let cache = ServerCache::new(store.clone(), CacheConfig::default());
+    assert_eq!(cache.config().key_prefix, "rodepush");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_store_counter() -> Result<(), Box<dyn std::error::Error>> {
+    let store = MemoryCacheStore::new();
+    assert_eq!(store.increment("counter").await?, 1);
+    assert_eq!(store.increment("counter").await?, 2);
+    assert_eq!(store.get("counter").await?, Some(b"2".to_vec()));
+    assert_eq!(store.increment("other").await?, 1);
+
+    Ok(())
+}
@@ -123,7 +123,7 @@ async fn test_application_crud() -> Result<(), Box<dyn std::error::Error>> {
     let api_key = format!("test-api-key-{}", uuid::Uuid::new_v4());
     println!("Creating application with API key: {}", api_key);
 
-    let application = Application::new("Test App".to_string(), api_key)
+    let application = Application::new("Test App".to_string(), api_key.clone())
         .with_description("Test application for integration tests".to_string())
         .with_owner("test@example.com".to_string());
 
@@ -134,7 +134,8 @@ async fn test_application_crud() -> Result<(), Box<dyn std::error::Error>> {
     assert!(retrieved.is_some());
     let retrieved = retrieved.unwrap();
     assert_eq!(retrieved.name, "Test App");
-    assert_eq!(retrieved.api_key, application.api_key);
+    assert_eq!(retrieved.api_key_hash, application.api_key_hash);
+    assert!(retrieved.verify_api_key(&api_key));
     assert_eq!(
         retrieved.description,
         Some("Test application for integration tests".to_string())
@@ -142,9 +143,7 @@ async fn test_application_crud() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(retrieved.owner, Some("test@example.com".to_string()));
 
     // Get application by API key
-    let retrieved_by_key = manager
-        .get_application_by_api_key(&application.api_key)
-        .await?;
+    let retrieved_by_key = manager.get_application_by_api_key(&api_key).await?;
     assert!(retrieved_by_key.is_some());
     let retrieved_by_key = retrieved_by_key.unwrap();
     assert_eq!(retrieved_by_key.id, application.id);
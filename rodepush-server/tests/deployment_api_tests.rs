@@ -0,0 +1,386 @@
+//! Deployment endpoint tests
+//!
+//! These tests cover request validation, the deployment state machine,
+//! promotion checks and pagination of the deployment endpoints; they need no
+//! database.
+
+use axum::http::StatusCode;
+use rodepush_core::{BundleId, Platform, RodePushError};
+use rodepush_server::api::deployments::{
+    check_promotion, ensure_not_final, validate_rollout_percentage,
+};
+use rodepush_server::api::response::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use rodepush_server::api::{
+    ApiError, CreateDeploymentRequest, Page, Pagination, PromoteDeploymentRequest,
+};
+use rodepush_server::database::{
+    ApplicationId, Bundle, Deployment, DeploymentId, DeploymentStatus, TargetingRules,
+    releases_overlap,
+};
+
+fn bundle(application_id: &ApplicationId, version: &str, platform: Platform) -> Bundle {
+    Bundle::new(
+        application_id.clone(),
+        version.to_string(),
+        platform,
+        format!("bundles/{}", version),
+        1024,
+        "checksum".to_string(),
+    )
+}
+
+#[test]
+fn test_create_deployment_request() {
+    let bundle_id = BundleId::new();
+    let request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": bundle_id,
+        "environment": " production ",
+        "description": "Fixes checkout crash",
+        "rollout_percentage": 25,
+        "mandatory": true
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+
+    let application_id = ApplicationId::new();
+    let deployment = request.into_deployment(application_id.clone());
+    assert_eq!(deployment.application_id, application_id);
+    assert_eq!(deployment.bundle_id, bundle_id);
+    assert_eq!(deployment.environment, "production");
+    assert_eq!(deployment.rollout_percentage, 25);
+    assert_eq!(
+        deployment.description.as_deref(),
+        Some("Fixes checkout crash")
+    );
+    assert!(deployment.is_mandatory());
+
+    // Everyone receives a deployment without a rollout percentage
+    let request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": bundle_id,
+        "environment": "staging"
+    }))
+    .unwrap();
+    let deployment = request.into_deployment(application_id);
+    assert_eq!(deployment.rollout_percentage, 100);
+    assert!(!deployment.is_mandatory());
+}
+
+#[test]
+fn test_create_deployment_request_validation() {
+    let request = CreateDeploymentRequest {
+        bundle_id: BundleId::new(),
+        environment: "production".to_string(),
+        description: None,
+        rollout_percentage: Some(101),
+        rollout_plan: None,
+        targeting: None,
+        mandatory: false,
+    };
+    assert_eq!(
+        request.validate().unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let request = CreateDeploymentRequest {
+        environment: " ".to_string(),
+        rollout_percentage: None,
+        rollout_plan: None,
+        ..request
+    };
+    assert!(request.validate().is_err());
+
+    assert!(validate_rollout_percentage(0).is_ok());
+    assert!(validate_rollout_percentage(100).is_ok());
+    assert!(validate_rollout_percentage(150).is_err());
+}
+
+fn test_deployment() -> Deployment {
+    Deployment::new(
+        ApplicationId::new(),
+        BundleId::new(),
+        "production".to_string(),
+    )
+}
+
+#[test]
+fn test_status_transitions() {
+    let mut deployment = test_deployment();
+    assert_eq!(deployment.status, DeploymentStatus::Pending);
+
+    let transition = deployment.activate().unwrap();
+    assert_eq!(transition.deployment_id, deployment.id);
+    assert_eq!(transition.from_status, DeploymentStatus::Pending);
+    assert_eq!(transition.to_status, DeploymentStatus::Active);
+    assert!(deployment.deployed_at.is_some());
+
+    deployment.pause().unwrap();
+    assert_eq!(deployment.status, DeploymentStatus::Paused);
+    let transition = deployment
+        .resume()
+        .unwrap()
+        .with_actor(Some("api_key".to_string()));
+    assert_eq!(transition.from_status, DeploymentStatus::Paused);
+    assert_eq!(transition.actor.as_deref(), Some("api_key"));
+
+    deployment.rollback().unwrap();
+    assert_eq!(deployment.status, DeploymentStatus::RolledBack);
+    assert!(deployment.rolled_back_at.is_some());
+    assert!(deployment.status.is_final());
+}
+
+#[test]
+fn test_invalid_transitions() {
+    // A pending deployment cannot be paused, resumed or rolled back
+    let mut deployment = test_deployment();
+    assert!(deployment.pause().is_err());
+    assert!(deployment.resume().is_err());
+    assert!(deployment.rollback().is_err());
+    assert_eq!(deployment.status, DeploymentStatus::Pending);
+
+    // Only a paused deployment can be resumed
+    deployment.activate().unwrap();
+    assert!(deployment.resume().is_err());
+    assert!(deployment.activate().is_err());
+
+    // Superseded deployments only come back by being restored
+    let replacement = DeploymentId::new();
+    deployment.supersede(&replacement).unwrap();
+    assert_eq!(deployment.superseded_by(), Some(replacement));
+    let error = deployment.activate().unwrap_err();
+    assert_eq!(ApiError::from(error).status(), StatusCode::CONFLICT);
+    assert!(deployment.pause().is_err());
+    assert!(deployment.rollback().is_err());
+    assert!(deployment.fail().is_err());
+    assert_eq!(deployment.status, DeploymentStatus::Superseded);
+
+    let transition = deployment.restore(DeploymentStatus::Paused).unwrap();
+    assert_eq!(transition.from_status, DeploymentStatus::Superseded);
+    assert_eq!(deployment.status, DeploymentStatus::Paused);
+    assert_eq!(deployment.superseded_by(), None);
+    assert!(deployment.restore(DeploymentStatus::Active).is_err());
+
+    // Final statuses stay final
+    deployment.rollback().unwrap();
+    assert!(deployment.resume().is_err());
+    assert!(deployment.restore(DeploymentStatus::Active).is_err());
+    assert!(deployment.fail().is_err());
+    assert_eq!(deployment.status, DeploymentStatus::RolledBack);
+}
+
+#[test]
+fn test_status_table() {
+    for status in [
+        DeploymentStatus::Pending,
+        DeploymentStatus::Active,
+        DeploymentStatus::Paused,
+        DeploymentStatus::RolledBack,
+        DeploymentStatus::Failed,
+        DeploymentStatus::Superseded,
+    ] {
+        assert_eq!(
+            status.to_string().parse::<DeploymentStatus>().unwrap(),
+            status
+        );
+        assert!(!status.can_transition_to(status));
+        assert!(!status.can_transition_to(DeploymentStatus::Pending));
+    }
+    assert!(DeploymentStatus::Active.is_live());
+    assert!(DeploymentStatus::Paused.is_live());
+    assert!(!DeploymentStatus::Pending.is_live());
+    assert!(!DeploymentStatus::Superseded.is_live());
+}
+
+#[test]
+fn test_status_checks() {
+    let mut deployment = test_deployment();
+    assert!(ensure_not_final(&deployment, "change the rollout of").is_ok());
+
+    deployment.activate().unwrap();
+    deployment.rollback().unwrap();
+    let error = ensure_not_final(&deployment, "change the rollout of").unwrap_err();
+    assert_eq!(error.status(), StatusCode::CONFLICT);
+    assert!(error.message().contains("rolled_back"));
+
+    // Superseded deployments cannot be changed by hand either
+    let mut deployment = test_deployment();
+    deployment.activate().unwrap();
+    deployment.supersede(&DeploymentId::new()).unwrap();
+    let error = ensure_not_final(&deployment, "change the rollout of").unwrap_err();
+    assert_eq!(error.status(), StatusCode::CONFLICT);
+
+    // Missing records are answered with 404
+    let error = ApiError::from(RodePushError::not_found("Deployment not found"));
+    assert_eq!(error.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn test_promote_request() {
+    let application_id = ApplicationId::new();
+    let source = Deployment::new(
+        application_id.clone(),
+        BundleId::new(),
+        "staging".to_string(),
+    )
+    .with_rollout_percentage(40)
+    .with_description("Fixes checkout crash".to_string())
+    .with_mandatory(true);
+
+    let request: PromoteDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "from_environment": "staging",
+        "to_environment": " production "
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+
+    // The new deployment serves the same bundle as the promoted one
+    let deployment = request.into_deployment(&source);
+    assert_ne!(deployment.id, source.id);
+    assert_eq!(deployment.application_id, application_id);
+    assert_eq!(deployment.bundle_id, source.bundle_id);
+    assert_eq!(deployment.environment, "production");
+    assert_eq!(deployment.status, DeploymentStatus::Pending);
+    assert_eq!(deployment.rollout_percentage, 100);
+    assert_eq!(deployment.targeting(), None);
+    assert_eq!(
+        deployment.description.as_deref(),
+        Some("Fixes checkout crash")
+    );
+    assert!(deployment.is_mandatory());
+    assert_eq!(
+        deployment.metadata.get("promoted_from"),
+        Some(&serde_json::Value::String(source.id.to_string()))
+    );
+
+    let request: PromoteDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "from_environment": "staging",
+        "to_environment": "production",
+        "rollout_percentage": 10,
+        "description": "Careful rollout",
+        "mandatory": false
+    }))
+    .unwrap();
+    let deployment = request.into_deployment(&source);
+    assert_eq!(deployment.rollout_percentage, 10);
+    assert_eq!(deployment.description.as_deref(), Some("Careful rollout"));
+    assert!(!deployment.is_mandatory());
+
+    // The new deployment reaches the same clients as the promoted one
+    let rules = TargetingRules {
+        locales: vec!["de".to_string()],
+        ..Default::default()
+    };
+    let source = source.with_targeting(rules.clone());
+    let request: PromoteDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "from_environment": "staging",
+        "to_environment": "production"
+    }))
+    .unwrap();
+    let deployment = request.into_deployment(&source);
+    assert_eq!(deployment.targeting(), Some(rules));
+}
+
+#[test]
+fn test_promote_request_validation() {
+    let invalid = [
+        serde_json::json!({ "from_environment": "staging", "to_environment": " " }),
+        serde_json::json!({ "from_environment": "staging", "to_environment": " staging" }),
+        serde_json::json!({
+            "from_environment": "staging",
+            "to_environment": "production",
+            "rollout_percentage": 150
+        }),
+    ];
+    for body in invalid {
+        let request: PromoteDeploymentRequest = serde_json::from_value(body).unwrap();
+        assert_eq!(
+            request.validate().unwrap_err().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
+
+#[test]
+fn test_promotion_checks() {
+    let application_id = ApplicationId::new();
+    let promoted = bundle(&application_id, "1.4.2", Platform::Ios);
+
+    // Anything goes in an environment serving nothing yet
+    assert!(check_promotion(&promoted, &[], "production").is_ok());
+
+    let live = bundle(&application_id, "1.4.0", Platform::Ios);
+    assert!(check_promotion(&promoted, &[live], "production").is_ok());
+    let universal = bundle(&application_id, "1.4.0", Platform::Both);
+    assert!(check_promotion(&promoted, &[universal], "production").is_ok());
+
+    // Other platforms and release lines are served next to the live bundles
+    let android = bundle(&application_id, "1.4.5", Platform::Android);
+    assert!(check_promotion(&promoted, std::slice::from_ref(&android), "production").is_ok());
+    let previous_line = bundle(&application_id, "1.3.9", Platform::Ios);
+    assert!(check_promotion(&promoted, &[previous_line, android], "production").is_ok());
+
+    // Replacing a newer bundle of the same release line would downgrade clients
+    let newer = bundle(&application_id, "1.4.5", Platform::Ios);
+    let error = check_promotion(&promoted, &[newer], "production").unwrap_err();
+    assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let newer_universal = bundle(&application_id, "1.4.3", Platform::Both);
+    let error = check_promotion(&promoted, &[newer_universal], "production").unwrap_err();
+    assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let error =
+        check_promotion(&promoted, std::slice::from_ref(&promoted), "production").unwrap_err();
+    assert_eq!(error.status(), StatusCode::CONFLICT);
+
+    let unversioned = bundle(&application_id, "latest", Platform::Ios);
+    let error = check_promotion(&unversioned, &[], "production").unwrap_err();
+    assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[test]
+fn test_releases_overlap() {
+    let application_id = ApplicationId::new();
+    let ios = bundle(&application_id, "1.4.2", Platform::Ios);
+
+    assert!(ios.overlaps(&bundle(&application_id, "1.4.0", Platform::Ios)));
+    assert!(ios.overlaps(&bundle(&application_id, "1.4.0", Platform::Both)));
+    assert!(!ios.overlaps(&bundle(&application_id, "1.4.2", Platform::Android)));
+    assert!(!ios.overlaps(&bundle(&application_id, "1.5.0", Platform::Ios)));
+    assert!(!ios.overlaps(&bundle(&application_id, "2.4.2", Platform::Both)));
+
+    assert!(releases_overlap(
+        Platform::Both,
+        "latest",
+        Platform::Android,
+        "latest"
+    ));
+    assert!(!releases_overlap(
+        Platform::Ios,
+        "latest",
+        Platform::Ios,
+        "1.4.0"
+    ));
+}
+
+#[test]
+fn test_pagination() {
+    let default = Pagination::default();
+    assert_eq!(default.limit(), DEFAULT_PAGE_SIZE as i64);
+    assert_eq!(default.offset(), 0);
+
+    let large = Pagination {
+        limit: Some(10_000),
+        offset: Some(40),
+    };
+    assert_eq!(large.limit(), MAX_PAGE_SIZE as i64);
+    assert_eq!(large.offset(), 40);
+
+    let empty = Pagination {
+        limit: Some(0),
+        offset: None,
+    };
+    assert_eq!(empty.limit(), 1);
+
+    let page = Page::new(vec![1, 2, 3], &large);
+    assert_eq!(page.items, vec![1, 2, 3]);
+    assert_eq!(page.limit, MAX_PAGE_SIZE as i64);
+    assert_eq!(page.offset, 40);
+}
@@ -1,13 +1,14 @@
 //! DeploymentService integration tests
 //!
 //! These tests verify all DeploymentService methods and business logic
-//! by calling the service methods rather than writing raw SQL.
+//! by calling the service methods rather than writing raw SQL. Writes go
+//! through the DatabaseManager, which owns them.
 
-use rodepush_core::{BundleId, Platform};
+use rodepush_core::{BundleId, Platform, SemanticVersion};
 use rodepush_server::database::{
-    Application, ApplicationId, ApplicationService, Bundle, BundleService, DatabaseConfig,
-    DatabaseConnection, DatabaseType, Deployment, DeploymentId, DeploymentService,
-    DeploymentStatus,
+    Application, ApplicationId, ApplicationService, Bundle, BundleService, ClientProfile,
+    DatabaseConfig, DatabaseConnection, DatabaseManager, DatabaseType, Deployment, DeploymentId,
+    DeploymentService, DeploymentStatus, Environment, EnvironmentService, TargetingRules,
 };
 use serial_test::serial;
 use std::time::Duration;
@@ -24,13 +25,13 @@ fn test_postgres_config() -> DatabaseConfig {
     }
 }
 
-/// Setup test database connection and clean tables
-async fn setup_test_db() -> Result<DatabaseConnection, Box<dyn std::error::Error>> {
+/// Setup test database manager and clean tables
+async fn setup_test_db() -> Result<DatabaseManager, Box<dyn std::error::Error>> {
     let config = test_postgres_config();
-    let connection = DatabaseConnection::new(&config).await?;
+    let manager = DatabaseManager::new(&config).await?;
 
     // Clean tables in correct order (due to foreign key constraints)
-    if let Some(pool) = connection.pool().as_postgres() {
+    if let Some(pool) = manager.pool().as_postgres() {
         sqlx::query("TRUNCATE TABLE deployments CASCADE")
             .execute(pool)
             .await?;
@@ -39,25 +40,30 @@ async fn setup_test_db() -> Result<DatabaseConnection, Box<dyn std::error::Error
             .await?;
     }
 
-    Ok(connection)
+    Ok(manager)
 }
 
 /// Create a test application for deployment tests
 async fn create_test_application(
-    connection: &DatabaseConnection,
+    manager: &DatabaseManager,
 ) -> Result<Application, Box<dyn std::error::Error>> {
     let app = Application::new(
         "Test App for Deployments".to_string(),
         format!("test-key-{}", uuid::Uuid::new_v4()),
     );
 
-    ApplicationService::create(connection.pool(), &app).await?;
+    ApplicationService::create(manager.pool(), &app).await?;
+    // Deployments can only target existing environments
+    for name in ["production", "staging", "development", "test"] {
+        let environment = Environment::new(app.id.clone(), name.to_string());
+        EnvironmentService::create(manager.pool(), &environment).await?;
+    }
     Ok(app)
 }
 
 /// Create dummy bundles for testing
 async fn create_dummy_bundles(
-    connection: &DatabaseConnection,
+    manager: &DatabaseManager,
     app_id: &ApplicationId,
     bundle_ids: &[BundleId],
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -72,26 +78,26 @@ async fn create_dummy_bundles(
         )
         .with_id(bundle_id.clone());
 
-        BundleService::create(connection.pool(), &bundle).await?;
+        BundleService::create(manager.pool(), &bundle).await?;
     }
     Ok(())
 }
 
 /// Setup test environment with clean database and test application
-async fn setup_test_env() -> Result<(DatabaseConnection, Application), Box<dyn std::error::Error>> {
+async fn setup_test_env() -> Result<(DatabaseManager, Application), Box<dyn std::error::Error>> {
     wait_for_database().await?;
-    let connection = setup_test_db().await?;
-    let app = create_test_application(&connection).await?;
-    Ok((connection, app))
+    let manager = setup_test_db().await?;
+    let app = create_test_application(&manager).await?;
+    Ok((manager, app))
 }
 
 /// Setup test environment and create dummy bundle for testing
 async fn setup_test_env_with_bundle()
--> Result<(DatabaseConnection, Application, BundleId), Box<dyn std::error::Error>> {
-    let (connection, app) = setup_test_env().await?;
+-> Result<(DatabaseManager, Application, BundleId), Box<dyn std::error::Error>> {
+    let (manager, app) = setup_test_env().await?;
     let bundle_id = BundleId::new();
-    create_dummy_bundles(&connection, &app.id, &[bundle_id.clone()]).await?;
-    Ok((connection, app, bundle_id))
+    create_dummy_bundles(&manager, &app.id, &[bundle_id.clone()]).await?;
+    Ok((manager, app, bundle_id))
 }
 
 /// Wait for database to be ready
@@ -115,7 +121,7 @@ async fn wait_for_database() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 #[serial]
 async fn test_deployment_create() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
 
     // Create a new deployment
     let deployment = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string())
@@ -127,10 +133,10 @@ async fn test_deployment_create() -> Result<(), Box<dyn std::error::Error>> {
         );
 
     // Test create operation
-    DeploymentService::create(connection.pool(), &deployment).await?;
+    manager.create_deployment(&deployment).await?;
 
     // Verify creation by retrieving
-    let retrieved = DeploymentService::get_by_id(connection.pool(), &deployment.id).await?;
+    let retrieved = DeploymentService::get_by_id(manager.pool(), &deployment.id).await?;
     assert!(retrieved.is_some());
 
     let retrieved = retrieved.unwrap();
@@ -148,25 +154,26 @@ async fn test_deployment_create() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 #[serial]
 async fn test_deployment_update() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
-
-    // Create original deployment
-    let mut deployment = Deployment::new(app.id.clone(), bundle_id, "staging".to_string());
-
-    DeploymentService::create(connection.pool(), &deployment).await?;
-
-    // Update deployment
-    deployment.activate(); // Change status to Active
-    deployment.rollout_percentage = 100;
-    deployment.description = Some("Updated deployment".to_string());
-    deployment
-        .metadata
-        .insert("updated".to_string(), serde_json::Value::Bool(true));
-
-    DeploymentService::update(connection.pool(), &deployment).await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
+
+    // Deployments are stored pending and only go live through promotion
+    let mut live = Deployment::new(app.id.clone(), bundle_id.clone(), "staging".to_string());
+    live.activate()?;
+    assert!(manager.create_deployment(&live).await.is_err());
+
+    let deployment = Deployment::new(app.id.clone(), bundle_id, "staging".to_string())
+        .with_rollout_percentage(10)
+        .with_description("Updated deployment".to_string());
+    manager.create_deployment(&deployment).await?;
+    let deployment = manager.promote_deployment(&deployment.id, None).await?;
+    assert!(
+        manager
+            .set_rollout_percentage(&deployment, 100, None)
+            .await?
+    );
 
     // Verify update
-    let retrieved = DeploymentService::get_by_id(connection.pool(), &deployment.id).await?;
+    let retrieved = DeploymentService::get_by_id(manager.pool(), &deployment.id).await?;
     assert!(retrieved.is_some());
 
     let retrieved = retrieved.unwrap();
@@ -184,43 +191,64 @@ async fn test_deployment_update() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 #[serial]
 async fn test_deployment_status_transitions() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
 
     // Create deployment
-    let mut deployment =
-        Deployment::new(app.id.clone(), bundle_id.clone(), "development".to_string());
+    let deployment = Deployment::new(app.id.clone(), bundle_id.clone(), "development".to_string());
 
-    DeploymentService::create(connection.pool(), &deployment).await?;
+    manager.create_deployment(&deployment).await?;
 
     // Test status transitions
     assert_eq!(deployment.status, DeploymentStatus::Pending);
 
+    // Pending deployments go live through promotion only
+    assert!(
+        manager
+            .transition_deployment(&deployment.id, |deployment| deployment
+                .transition_to(DeploymentStatus::Active))
+            .await
+            .is_err()
+    );
+
     // Activate deployment
-    deployment.activate();
-    DeploymentService::update(connection.pool(), &deployment).await?;
+    let deployment = manager.promote_deployment(&deployment.id, None).await?;
     assert_eq!(deployment.status, DeploymentStatus::Active);
     assert!(deployment.deployed_at.is_some());
 
     // Pause deployment
-    deployment.pause();
-    DeploymentService::update(connection.pool(), &deployment).await?;
+    let deployment = manager
+        .transition_deployment(&deployment.id, |deployment| deployment.pause())
+        .await?;
     assert_eq!(deployment.status, DeploymentStatus::Paused);
 
     // Resume deployment
-    deployment.resume();
-    DeploymentService::update(connection.pool(), &deployment).await?;
+    let deployment = manager
+        .transition_deployment(&deployment.id, |deployment| deployment.resume())
+        .await?;
     assert_eq!(deployment.status, DeploymentStatus::Active);
 
     // Rollback deployment
-    deployment.rollback();
-    DeploymentService::update(connection.pool(), &deployment).await?;
+    let deployment = manager
+        .rollback_deployment(&deployment.id, None, None)
+        .await?;
     assert_eq!(deployment.status, DeploymentStatus::RolledBack);
     assert!(deployment.rolled_back_at.is_some());
 
-    // Fail deployment
-    deployment.fail();
-    DeploymentService::update(connection.pool(), &deployment).await?;
-    assert_eq!(deployment.status, DeploymentStatus::Failed);
+    // A rolled back deployment cannot fail anymore
+    assert!(
+        manager
+            .fail_deployment(&deployment.id, None, None)
+            .await
+            .is_err()
+    );
+    let stored = DeploymentService::get_by_id(manager.pool(), &deployment.id)
+        .await?
+        .unwrap();
+    assert_eq!(stored.status, DeploymentStatus::RolledBack);
+
+    // Every status change is recorded
+    let history = DeploymentService::history(manager.pool(), &deployment.id).await?;
+    assert_eq!(history.len(), 4);
 
     Ok(())
 }
@@ -228,22 +256,22 @@ async fn test_deployment_status_transitions() -> Result<(), Box<dyn std::error::
 #[tokio::test]
 #[serial]
 async fn test_deployment_delete() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
 
     // Create deployment to delete
-    let deployment = Deployment::new(app.id.clone(), bundle_id.clone(), "test-delete".to_string());
+    let deployment = Deployment::new(app.id.clone(), bundle_id.clone(), "test".to_string());
 
-    DeploymentService::create(connection.pool(), &deployment).await?;
+    manager.create_deployment(&deployment).await?;
 
     // Verify it exists
-    let before_delete = DeploymentService::get_by_id(connection.pool(), &deployment.id).await?;
+    let before_delete = DeploymentService::get_by_id(manager.pool(), &deployment.id).await?;
     assert!(before_delete.is_some());
 
     // Delete deployment
-    DeploymentService::delete(connection.pool(), &deployment.id).await?;
+    manager.delete_deployment(&deployment.id).await?;
 
     // Verify deletion
-    let after_delete = DeploymentService::get_by_id(connection.pool(), &deployment.id).await?;
+    let after_delete = DeploymentService::get_by_id(manager.pool(), &deployment.id).await?;
     assert!(after_delete.is_none());
 
     Ok(())
@@ -252,26 +280,28 @@ async fn test_deployment_delete() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 #[serial]
 async fn test_deployment_get_active_for_application() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
 
     // Create multiple deployments with different statuses
-    let mut active_deployment =
+    let active_deployment =
         Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
-    active_deployment.activate();
-    DeploymentService::create(connection.pool(), &active_deployment).await?;
+    let active_deployment = manager.deploy_bundle(active_deployment, None, None).await?;
 
     let pending_deployment =
         Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
-    DeploymentService::create(connection.pool(), &pending_deployment).await?;
+    manager.create_deployment(&pending_deployment).await?;
 
-    let mut failed_deployment =
+    // Failing the newer deployment restores the one it superseded
+    let failed_deployment =
         Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
-    failed_deployment.fail();
-    DeploymentService::create(connection.pool(), &failed_deployment).await?;
+    let failed_deployment = manager.deploy_bundle(failed_deployment, None, None).await?;
+    manager
+        .fail_deployment(&failed_deployment.id, None, None)
+        .await?;
 
     // Get active deployments
     let active_deployments =
-        DeploymentService::get_active_for_application(connection.pool(), &app.id, "production")
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
             .await?;
 
     // Should only return the active deployment
@@ -282,29 +312,75 @@ async fn test_deployment_get_active_for_application() -> Result<(), Box<dyn std:
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn test_deployment_get_latest_for_target() -> Result<(), Box<dyn std::error::Error>> {
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
+
+    let deployment = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    let deployment = manager.deploy_bundle(deployment, None, None).await?;
+
+    // Dummy bundles are iOS 1.0.0 bundles
+    let latest = DeploymentService::get_latest_for_target(
+        manager.pool(),
+        &app.id,
+        "production",
+        Platform::Ios,
+        &SemanticVersion::new(1, 0, 5),
+    )
+    .await?;
+    assert_eq!(latest.map(|d| d.id), Some(deployment.id.clone()));
+
+    // Incompatible binary version
+    let latest = DeploymentService::get_latest_for_target(
+        manager.pool(),
+        &app.id,
+        "production",
+        Platform::Ios,
+        &SemanticVersion::new(2, 0, 0),
+    )
+    .await?;
+    assert!(latest.is_none());
+
+    // Other platform
+    let latest = DeploymentService::get_latest_for_target(
+        manager.pool(),
+        &app.id,
+        "production",
+        Platform::Android,
+        &SemanticVersion::new(1, 0, 0),
+    )
+    .await?;
+    assert!(latest.is_none());
+
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 async fn test_deployment_list_for_application() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
 
-    // Create multiple deployments for the application
+    // Create multiple deployments for the application, each in its own environment
     for i in 1..=5 {
+        let environment = Environment::new(app.id.clone(), format!("env-{}", i));
+        EnvironmentService::create(manager.pool(), &environment).await?;
         let deployment = Deployment::new(app.id.clone(), bundle_id.clone(), format!("env-{}", i));
-        DeploymentService::create(connection.pool(), &deployment).await?;
+        manager.create_deployment(&deployment).await?;
         sleep(Duration::from_millis(10)).await; // Ensure different created_at times
     }
 
     // Test pagination - first page
-    let page1 = DeploymentService::list_for_application(connection.pool(), &app.id, 3, 0).await?;
+    let page1 = DeploymentService::list_for_application(manager.pool(), &app.id, 3, 0).await?;
     assert_eq!(page1.len(), 3);
 
     // Test pagination - second page
-    let page2 = DeploymentService::list_for_application(connection.pool(), &app.id, 3, 3).await?;
+    let page2 = DeploymentService::list_for_application(manager.pool(), &app.id, 3, 3).await?;
     assert_eq!(page2.len(), 2);
 
     // Test limit larger than total
     let all_deployments =
-        DeploymentService::list_for_application(connection.pool(), &app.id, 10, 0).await?;
+        DeploymentService::list_for_application(manager.pool(), &app.id, 10, 0).await?;
     assert_eq!(all_deployments.len(), 5);
 
     // Verify ordering (should be by created_at DESC)
@@ -318,41 +394,38 @@ async fn test_deployment_list_for_application() -> Result<(), Box<dyn std::error
 #[tokio::test]
 #[serial]
 async fn test_deployment_get_by_status() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
 
     // Create deployments with different statuses
-    let mut active_deployment =
-        Deployment::new(app.id.clone(), bundle_id.clone(), "prod".to_string());
-    active_deployment.activate();
-    DeploymentService::create(connection.pool(), &active_deployment).await?;
+    let active_deployment =
+        Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    manager.deploy_bundle(active_deployment, None, None).await?;
 
     let pending_deployment =
         Deployment::new(app.id.clone(), bundle_id.clone(), "staging".to_string());
-    DeploymentService::create(connection.pool(), &pending_deployment).await?;
+    manager.create_deployment(&pending_deployment).await?;
 
-    let mut failed_deployment =
-        Deployment::new(app.id.clone(), bundle_id.clone(), "test".to_string());
-    failed_deployment.fail();
-    DeploymentService::create(connection.pool(), &failed_deployment).await?;
+    let failed_deployment = Deployment::new(app.id.clone(), bundle_id.clone(), "test".to_string());
+    let failed_deployment = manager.deploy_bundle(failed_deployment, None, None).await?;
+    manager
+        .fail_deployment(&failed_deployment.id, None, None)
+        .await?;
 
     // Get pending deployments
     let pending_deployments =
-        DeploymentService::get_by_status(connection.pool(), &DeploymentStatus::Pending, 10, 0)
-            .await?;
+        DeploymentService::get_by_status(manager.pool(), &DeploymentStatus::Pending, 10, 0).await?;
     assert_eq!(pending_deployments.len(), 1);
     assert_eq!(pending_deployments[0].status, DeploymentStatus::Pending);
 
     // Get active deployments
     let active_deployments =
-        DeploymentService::get_by_status(connection.pool(), &DeploymentStatus::Active, 10, 0)
-            .await?;
+        DeploymentService::get_by_status(manager.pool(), &DeploymentStatus::Active, 10, 0).await?;
     assert_eq!(active_deployments.len(), 1);
     assert_eq!(active_deployments[0].status, DeploymentStatus::Active);
 
     // Get failed deployments
     let failed_deployments =
-        DeploymentService::get_by_status(connection.pool(), &DeploymentStatus::Failed, 10, 0)
-            .await?;
+        DeploymentService::get_by_status(manager.pool(), &DeploymentStatus::Failed, 10, 0).await?;
     assert_eq!(failed_deployments.len(), 1);
     assert_eq!(failed_deployments[0].status, DeploymentStatus::Failed);
 
@@ -363,11 +436,11 @@ async fn test_deployment_get_by_status() -> Result<(), Box<dyn std::error::Error
 #[serial]
 async fn test_deployment_get_nonexistent() -> Result<(), Box<dyn std::error::Error>> {
     wait_for_database().await?;
-    let connection = setup_test_db().await?;
+    let manager = setup_test_db().await?;
 
     // Test get non-existent deployment by ID
     let non_existent_id = DeploymentId::new();
-    let result = DeploymentService::get_by_id(connection.pool(), &non_existent_id).await?;
+    let result = DeploymentService::get_by_id(manager.pool(), &non_existent_id).await?;
     assert!(result.is_none());
 
     Ok(())
@@ -376,7 +449,7 @@ async fn test_deployment_get_nonexistent() -> Result<(), Box<dyn std::error::Err
 #[tokio::test]
 #[serial]
 async fn test_deployment_complex_metadata() -> Result<(), Box<dyn std::error::Error>> {
-    let (connection, app, bundle_id) = setup_test_env_with_bundle().await?;
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
 
     // Create deployment with complex metadata
     let deployment = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string())
@@ -397,10 +470,10 @@ async fn test_deployment_complex_metadata() -> Result<(), Box<dyn std::error::Er
             }),
         );
 
-    DeploymentService::create(connection.pool(), &deployment).await?;
+    manager.create_deployment(&deployment).await?;
 
     // Retrieve and verify complex metadata
-    let retrieved = DeploymentService::get_by_id(connection.pool(), &deployment.id).await?;
+    let retrieved = DeploymentService::get_by_id(manager.pool(), &deployment.id).await?;
     assert!(retrieved.is_some());
 
     let retrieved = retrieved.unwrap();
@@ -419,3 +492,347 @@ async fn test_deployment_complex_metadata() -> Result<(), Box<dyn std::error::Er
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_deployment_promote_supersedes_live_deployment()
+-> Result<(), Box<dyn std::error::Error>> {
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
+
+    let first = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    manager.create_deployment(&first).await?;
+    manager
+        .promote_deployment(&first.id, Some("api_key".to_string()))
+        .await?;
+
+    let second = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    manager.create_deployment(&second).await?;
+    let promoted = manager.promote_deployment(&second.id, None).await?;
+    assert_eq!(promoted.status, DeploymentStatus::Active);
+
+    // Only the newest deployment is live
+    let active =
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
+            .await?;
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].id, second.id);
+
+    let first = DeploymentService::get_by_id(manager.pool(), &first.id)
+        .await?
+        .unwrap();
+    assert_eq!(first.status, DeploymentStatus::Superseded);
+
+    // Every status change is recorded
+    let history = DeploymentService::history(manager.pool(), &first.id).await?;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].to_status, DeploymentStatus::Active);
+    assert_eq!(history[0].actor.as_deref(), Some("api_key"));
+    assert_eq!(history[1].to_status, DeploymentStatus::Superseded);
+    assert!(
+        history[1]
+            .reason
+            .as_deref()
+            .unwrap()
+            .contains(&second.id.to_string())
+    );
+
+    // Superseded deployments cannot be promoted again
+    assert!(manager.promote_deployment(&first.id, None).await.is_err());
+
+    // Transitions that the state machine forbids are rejected
+    assert!(
+        manager
+            .transition_deployment(&second.id, |deployment| { deployment.resume() })
+            .await
+            .is_err()
+    );
+    let paused = manager
+        .transition_deployment(&second.id, |deployment| deployment.pause())
+        .await?;
+    assert_eq!(paused.status, DeploymentStatus::Paused);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deployment_withdrawal_restores_superseded_deployment()
+-> Result<(), Box<dyn std::error::Error>> {
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
+
+    let first = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    let first = manager.deploy_bundle(first, None, None).await?;
+    let second = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    let second = manager.deploy_bundle(second, None, None).await?;
+
+    let stored = DeploymentService::get_by_id(manager.pool(), &first.id)
+        .await?
+        .unwrap();
+    assert_eq!(stored.status, DeploymentStatus::Superseded);
+    assert_eq!(stored.superseded_by(), Some(second.id.clone()));
+
+    // Superseded deployments cannot be brought back by hand
+    assert!(
+        manager
+            .transition_deployment(&first.id, |deployment| deployment
+                .transition_to(DeploymentStatus::Active))
+            .await
+            .is_err()
+    );
+
+    // Pausing the replacement serves the superseded deployment again, and
+    // resuming it supersedes that one anew
+    manager
+        .transition_deployment(&second.id, |deployment| deployment.pause())
+        .await?;
+    let stored = DeploymentService::get_by_id(manager.pool(), &first.id)
+        .await?
+        .unwrap();
+    assert_eq!(stored.status, DeploymentStatus::Active);
+    manager
+        .transition_deployment(&second.id, |deployment| deployment.resume())
+        .await?;
+    let stored = DeploymentService::get_by_id(manager.pool(), &first.id)
+        .await?
+        .unwrap();
+    assert_eq!(stored.status, DeploymentStatus::Superseded);
+
+    // Rolling back the replacement restores it in the same transaction
+    manager
+        .rollback_deployment(&second.id, Some("api_key".to_string()), None)
+        .await?;
+    let active =
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
+            .await?;
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].id, first.id);
+    assert_eq!(active[0].superseded_by(), None);
+
+    let history = DeploymentService::history(manager.pool(), &first.id).await?;
+    let restoration = history.last().unwrap();
+    assert_eq!(restoration.from_status, DeploymentStatus::Superseded);
+    assert_eq!(restoration.to_status, DeploymentStatus::Active);
+    assert_eq!(restoration.actor.as_deref(), Some("api_key"));
+    assert!(
+        restoration
+            .reason
+            .as_deref()
+            .unwrap()
+            .contains(&second.id.to_string())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deployment_partial_rollout_keeps_previous_deployment_live()
+-> Result<(), Box<dyn std::error::Error>> {
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
+
+    let general = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    let general = manager.deploy_bundle(general, None, None).await?;
+
+    // A 10% deployment of a newer bundle replaces it for some clients only
+    let next_bundle = Bundle::new(
+        app.id.clone(),
+        "1.0.1".to_string(),
+        Platform::Ios,
+        "storage/next.bin".to_string(),
+        1024,
+        "dummy_checksum".to_string(),
+    );
+    BundleService::create(manager.pool(), &next_bundle).await?;
+    let next = Deployment::new(app.id.clone(), next_bundle.id, "production".to_string())
+        .with_rollout_percentage(10);
+    let next = manager.deploy_bundle(next, None, None).await?;
+
+    let candidates = DeploymentService::get_active_for_target(
+        manager.pool(),
+        &app.id,
+        "production",
+        Platform::Ios,
+        &SemanticVersion::new(1, 0, 0),
+    )
+    .await?;
+    let ids: Vec<&DeploymentId> = candidates.iter().map(|live| &live.id).collect();
+    assert_eq!(ids, [&next.id, &general.id]);
+
+    // A client outside the rollout still receives the previous bundle
+    let outside = (0..)
+        .map(|n| ClientProfile {
+            client_id: format!("client-{}", n),
+            binary_version: "1.0.0".to_string(),
+            ..Default::default()
+        })
+        .find(|profile| !next.includes_client(&profile.client_id))
+        .unwrap();
+    let served = candidates
+        .iter()
+        .find(|live| live.targets_client(&outside))
+        .unwrap();
+    assert_eq!(served.bundle_id, bundle_id);
+
+    // Reaching 100% supersedes the previous deployment, and going back
+    // below it restores that one
+    assert!(manager.set_rollout_percentage(&next, 100, None).await?);
+    let active =
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
+            .await?;
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].id, next.id);
+
+    assert!(manager.set_rollout_percentage(&next, 50, None).await?);
+    let active =
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
+            .await?;
+    assert_eq!(active.len(), 2);
+    assert!(active.iter().any(|live| live.id == general.id));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deployment_promote_keeps_other_releases_live()
+-> Result<(), Box<dyn std::error::Error>> {
+    let (manager, app, ios_bundle_id) = setup_test_env_with_bundle().await?;
+
+    let ios = Deployment::new(
+        app.id.clone(),
+        ios_bundle_id.clone(),
+        "production".to_string(),
+    );
+    manager.create_deployment(&ios).await?;
+    manager.promote_deployment(&ios.id, None).await?;
+
+    // Bundles for another platform or release line do not replace it
+    for (version, platform) in [("1.0.3", Platform::Android), ("1.1.0", Platform::Ios)] {
+        let bundle = Bundle::new(
+            app.id.clone(),
+            version.to_string(),
+            platform,
+            format!("storage/{}.bin", version),
+            1024,
+            "dummy_checksum".to_string(),
+        );
+        BundleService::create(manager.pool(), &bundle).await?;
+        let deployment = Deployment::new(app.id.clone(), bundle.id, "production".to_string());
+        manager.create_deployment(&deployment).await?;
+        manager.promote_deployment(&deployment.id, None).await?;
+    }
+    let active =
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
+            .await?;
+    assert_eq!(active.len(), 3);
+
+    // A bundle for both platforms replaces the 1.0 deployments of either
+    let universal = Bundle::new(
+        app.id.clone(),
+        "1.0.4".to_string(),
+        Platform::Both,
+        "storage/universal.bin".to_string(),
+        1024,
+        "dummy_checksum".to_string(),
+    );
+    BundleService::create(manager.pool(), &universal).await?;
+    let deployment = Deployment::new(app.id.clone(), universal.id, "production".to_string());
+    manager.create_deployment(&deployment).await?;
+    manager.promote_deployment(&deployment.id, None).await?;
+
+    let active =
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
+            .await?;
+    assert_eq!(active.len(), 2);
+    assert!(active.iter().all(|live| live.id != ios.id));
+    assert!(active.iter().any(|live| live.id == deployment.id));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deployment_promote_keeps_general_deployment_live()
+-> Result<(), Box<dyn std::error::Error>> {
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
+
+    let general = Deployment::new(app.id.clone(), bundle_id.clone(), "production".to_string());
+    manager.create_deployment(&general).await?;
+    manager.promote_deployment(&general.id, None).await?;
+
+    // A targeted deployment of the same release is served next to it
+    let beta_bundle = Bundle::new(
+        app.id.clone(),
+        "1.0.1".to_string(),
+        Platform::Ios,
+        "storage/beta.bin".to_string(),
+        1024,
+        "dummy_checksum".to_string(),
+    );
+    BundleService::create(manager.pool(), &beta_bundle).await?;
+    let beta = Deployment::new(app.id.clone(), beta_bundle.id, "production".to_string())
+        .with_targeting(TargetingRules {
+            tags: vec!["beta-testers".to_string()],
+            ..Default::default()
+        });
+    manager.create_deployment(&beta).await?;
+    manager.promote_deployment(&beta.id, None).await?;
+
+    let candidates = DeploymentService::get_active_for_target(
+        manager.pool(),
+        &app.id,
+        "production",
+        Platform::Ios,
+        &SemanticVersion::new(1, 0, 0),
+    )
+    .await?;
+    let ids: Vec<&DeploymentId> = candidates.iter().map(|live| &live.id).collect();
+    assert_eq!(ids, [&beta.id, &general.id]);
+
+    // A new general deployment only replaces the general one
+    let next = Deployment::new(app.id.clone(), bundle_id, "production".to_string());
+    manager.create_deployment(&next).await?;
+    manager.promote_deployment(&next.id, None).await?;
+
+    let active =
+        DeploymentService::get_active_for_application(manager.pool(), &app.id, "production")
+            .await?;
+    assert_eq!(active.len(), 2);
+    assert!(active.iter().any(|live| live.id == beta.id));
+    assert!(active.iter().any(|live| live.id == next.id));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deployment_get_active_deployed_since() -> Result<(), Box<dyn std::error::Error>> {
+    let (manager, app, bundle_id) = setup_test_env_with_bundle().await?;
+
+    // Created first but promoted last
+    let late = Deployment::new(app.id.clone(), bundle_id.clone(), "staging".to_string());
+    manager.create_deployment(&late).await?;
+    sleep(Duration::from_millis(10)).await;
+    let early = Deployment::new(app.id.clone(), bundle_id, "production".to_string());
+    manager.create_deployment(&early).await?;
+    let early = manager.promote_deployment(&early.id, None).await?;
+    sleep(Duration::from_millis(10)).await;
+    let late = manager.promote_deployment(&late.id, None).await?;
+
+    let activated = manager.get_deployments_activated_since(None, 10).await?;
+    let ids: Vec<&DeploymentId> = activated.iter().map(|live| &live.id).collect();
+    assert_eq!(ids, [&early.id, &late.id]);
+
+    let activated = manager
+        .get_deployments_activated_since(early.deployed_at, 10)
+        .await?;
+    assert_eq!(activated.len(), 1);
+    assert_eq!(activated[0].id, late.id);
+
+    let activated = manager
+        .get_deployments_activated_since(late.deployed_at, 10)
+        .await?;
+    assert!(activated.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+//! Differential package generation tests
+//!
+//! These tests build differential packages from bundles kept in filesystem
+//! storage; they need no database.
+
+use rodepush_core::storage::{FilesystemStorage, Storage, StorageKey};
+use rodepush_core::{
+    BulkHasher, Bundle as CoreBundle, BundleBuilder, BundleChunk, BundleId, CompressionType,
+    DiffEngine, HashAlgorithm, Platform, SemanticVersion,
+};
+use rodepush_server::api::DiffPackageManifest;
+use rodepush_server::api::bundles::BUNDLE_METADATA_KEY;
+use rodepush_server::api::diffs::{build_diff_package, can_diff, load_bundle};
+use rodepush_server::database::{ApplicationId, Bundle};
+use tempfile::TempDir;
+use tokio::io::AsyncReadExt;
+
+/// Build an uncompressed bundle from `(chunk id, data)` pairs
+fn build_bundle(version: SemanticVersion, chunks: &[(&str, &str)]) -> CoreBundle {
+    let mut builder = BundleBuilder::new(
+        version,
+        Platform::Android,
+        "index.android.bundle".to_string(),
+    )
+    .with_compression(CompressionType::None);
+    for (id, data) in chunks {
+        builder
+            .add_chunk_from_data(data.as_bytes(), id.to_string())
+            .unwrap();
+    }
+    builder.build().unwrap()
+}
+
+/// Store the chunk data of `bundle` the way an upload does and record it
+async fn store_bundle(
+    storage: &dyn Storage,
+    application_id: &ApplicationId,
+    bundle: &CoreBundle,
+) -> Bundle {
+    let key = StorageKey::new(format!("bundles/{}/{}.bundle", application_id, bundle.id()));
+    let mut writer = storage.create_object(&key).await.unwrap();
+    for chunk in &bundle.chunks {
+        writer.write(chunk.as_bytes()).await.unwrap();
+    }
+    let size_bytes = writer.commit().await.unwrap();
+
+    Bundle::new(
+        application_id.clone(),
+        bundle.version().to_string(),
+        bundle.platform(),
+        key.as_str().to_string(),
+        size_bytes,
+        bundle.metadata.checksum.clone(),
+    )
+    .with_id(bundle.id().clone())
+    .with_metadata(
+        BUNDLE_METADATA_KEY.to_string(),
+        serde_json::to_value(&bundle.metadata).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_diff_package_contains_only_changed_chunks() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let app_id = ApplicationId::new();
+
+    let old = build_bundle(
+        SemanticVersion::new(1, 2, 0),
+        &[("a", "apple"), ("b", "banana"), ("c", "cherry")],
+    );
+    let new = build_bundle(
+        SemanticVersion::new(1, 2, 1),
+        &[("a", "apple"), ("b", "blueberry"), ("d", "date")],
+    );
+    let source = store_bundle(&storage, &app_id, &old).await;
+    let target = store_bundle(&storage, &app_id, &new).await;
+
+    let loaded = load_bundle(&target, &storage).await.unwrap();
+    assert_eq!(loaded, new);
+
+    let diff = build_diff_package(&source, &target, &storage)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(diff.is_applicable(&source.id, &target.id));
+    assert_eq!(diff.platform, Platform::Android);
+    assert!(
+        diff.storage_key
+            .starts_with(&format!("diffs/{}/{}/{}/", app_id, source.id, target.id))
+    );
+
+    let mut package = Vec::new();
+    storage
+        .open_object(&StorageKey::new(diff.storage_key.clone()))
+        .await
+        .unwrap()
+        .read_to_end(&mut package)
+        .await
+        .unwrap();
+    assert_eq!(package.len() as u64, diff.size_bytes);
+    assert_eq!(
+        BulkHasher::new(new.metadata.hash_algorithm.unwrap_or(HashAlgorithm::Sha256))
+            .hash_data(&package),
+        diff.checksum
+    );
+
+    let (manifest, chunks) = DiffPackageManifest::decode(&package).unwrap();
+    assert_eq!(manifest.source_bundle_id, source.id);
+    assert_eq!(manifest.target, new.metadata);
+    assert_eq!(manifest.chunks, vec!["b".to_string(), "d".to_string()]);
+    assert_eq!(manifest.removed_chunk_ids, vec!["c".to_string()]);
+    assert_eq!(chunks[0].as_bytes(), b"blueberry");
+    assert_eq!(chunks[1].as_bytes(), b"date");
+
+    // Unchanged chunks come from the source bundle
+    let rebuilt: Vec<BundleChunk> = manifest
+        .target
+        .chunks
+        .iter()
+        .map(|metadata| {
+            chunks
+                .iter()
+                .chain(old.chunks.iter())
+                .find(|chunk| chunk.id() == metadata.id)
+                .unwrap()
+                .clone()
+        })
+        .collect();
+    assert_eq!(rebuilt, new.chunks);
+}
+
+#[tokio::test]
+async fn test_diff_package_skips_incompatible_bundles() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let app_id = ApplicationId::new();
+
+    let old = build_bundle(SemanticVersion::new(1, 2, 0), &[("a", "apple")]);
+    let source = store_bundle(&storage, &app_id, &old).await;
+
+    let major = build_bundle(SemanticVersion::new(2, 0, 0), &[("a", "apricot")]);
+    let major = store_bundle(&storage, &app_id, &major).await;
+    assert!(!can_diff(&source, &major));
+    assert!(
+        build_diff_package(&source, &major, &storage)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let other_app = build_bundle(SemanticVersion::new(1, 2, 1), &[("a", "avocado")]);
+    let other_app = store_bundle(&storage, &ApplicationId::new(), &other_app).await;
+    assert!(!can_diff(&source, &other_app));
+
+    let mut ios = source.clone().with_id(BundleId::new());
+    ios.platform = Platform::Ios;
+    assert!(!can_diff(&source, &ios));
+    assert!(!can_diff(&source, &source));
+}
+
+#[tokio::test]
+async fn test_load_bundle_detects_truncated_data() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let app_id = ApplicationId::new();
+
+    let bundle = build_bundle(
+        SemanticVersion::new(1, 0, 0),
+        &[("a", "apple"), ("b", "banana")],
+    );
+    let stored = store_bundle(&storage, &app_id, &bundle).await;
+
+    let key = StorageKey::new(stored.storage_key.clone());
+    let mut writer = storage.create_object(&key).await.unwrap();
+    writer.write(b"apple").await.unwrap();
+    writer.commit().await.unwrap();
+
+    assert!(load_bundle(&stored, &storage).await.is_err());
+}
+
+#[test]
+fn test_diff_package_manifest_rejects_corrupt_packages() {
+    let old = build_bundle(SemanticVersion::new(1, 0, 0), &[("a", "apple")]);
+    let new = build_bundle(SemanticVersion::new(1, 0, 1), &[("a", "apricot")]);
+    let diff = DiffEngine::new().compare_bundles(&old, &new).unwrap();
+
+    let manifest = DiffPackageManifest {
+        source_bundle_id: old.id().clone(),
+        target: new.metadata.clone(),
+        chunks: vec!["a".to_string()],
+        removed_chunk_ids: diff.removed_chunk_ids,
+    };
+    let package = manifest.encode(&new.chunks).unwrap();
+    let (decoded, chunks) = DiffPackageManifest::decode(&package).unwrap();
+    assert_eq!(decoded, manifest);
+    assert_eq!(chunks, new.chunks);
+
+    assert!(DiffPackageManifest::decode(&package[..2]).is_err());
+    assert!(DiffPackageManifest::decode(&package[..package.len() - 1]).is_err());
+    let mut trailing = package.clone();
+    trailing.push(0);
+    assert!(DiffPackageManifest::decode(&trailing).is_err());
+}
@@ -0,0 +1,211 @@
+//! Download endpoint tests
+//!
+//! These tests cover range and conditional request handling of downloads
+//! streamed from filesystem storage, and the verification of uploaded asset
+//! packages; they need no database.
+
+use axum::body::to_bytes;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::Response;
+use rodepush_core::storage::{FilesystemStorage, Storage, StorageKey};
+use rodepush_core::{AssetCollection, AssetCompressor};
+use rodepush_server::api::assets::verify_package;
+use rodepush_server::api::downloads::{OCTET_STREAM, etag_matches_any, serve_download};
+use rodepush_server::api::{ByteRange, DownloadObject, RangeRequest};
+use tempfile::TempDir;
+
+const DATA: &[u8] = b"0123456789abcdefghij";
+
+/// Store `DATA` and describe it as a download
+async fn stored_object(storage: &dyn Storage) -> DownloadObject {
+    let key = StorageKey::new("bundles/app/bundle.bin".to_string());
+    let mut writer = storage.create_object(&key).await.unwrap();
+    writer.write(DATA).await.unwrap();
+    let size = writer.commit().await.unwrap();
+    DownloadObject::whole(key, size, "ab".repeat(32), OCTET_STREAM)
+}
+
+fn request_headers(headers: &[(header::HeaderName, &str)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        map.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+    }
+    map
+}
+
+async fn body(response: Response) -> Vec<u8> {
+    to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+fn header_value(response: &Response, name: header::HeaderName) -> &str {
+    response.headers().get(name).unwrap().to_str().unwrap()
+}
+
+#[test]
+fn test_range_parsing() {
+    let partial = |start, end| RangeRequest::Partial(ByteRange { start, end });
+
+    assert_eq!(RangeRequest::parse(None, 20), RangeRequest::Full);
+    assert_eq!(RangeRequest::parse(Some("bytes=0-4"), 20), partial(0, 4));
+    assert_eq!(RangeRequest::parse(Some("bytes=15-"), 20), partial(15, 19));
+    assert_eq!(RangeRequest::parse(Some("bytes=-5"), 20), partial(15, 19));
+    assert_eq!(RangeRequest::parse(Some("bytes=-50"), 20), partial(0, 19));
+    assert_eq!(
+        RangeRequest::parse(Some("bytes=10-99"), 20),
+        partial(10, 19)
+    );
+    assert_eq!(ByteRange { start: 10, end: 19 }.size(), 10);
+
+    // Ignored: malformed, other units and multiple ranges
+    for header in [
+        "bytes=5-1",
+        "bytes=a-b",
+        "items=0-4",
+        "bytes=0-1,5-6",
+        "bytes=-",
+    ] {
+        assert_eq!(RangeRequest::parse(Some(header), 20), RangeRequest::Full);
+    }
+
+    for header in ["bytes=20-", "bytes=-0", "bytes=25-30"] {
+        assert_eq!(
+            RangeRequest::parse(Some(header), 20),
+            RangeRequest::Unsatisfiable
+        );
+    }
+    assert_eq!(
+        RangeRequest::parse(Some("bytes=0-"), 0),
+        RangeRequest::Unsatisfiable
+    );
+}
+
+#[test]
+fn test_etag_matching() {
+    assert!(etag_matches_any("\"abc\"", "\"abc\""));
+    assert!(etag_matches_any("\"old\", W/\"abc\"", "\"abc\""));
+    assert!(etag_matches_any("*", "\"abc\""));
+    assert!(!etag_matches_any("\"abcd\"", "\"abc\""));
+}
+
+#[tokio::test]
+async fn test_full_download() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let object = stored_object(&storage).await;
+
+    let response = serve_download(&storage, &object, &HeaderMap::new())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, header::ETAG), object.etag());
+    assert_eq!(header_value(&response, header::CONTENT_LENGTH), "20");
+    assert_eq!(header_value(&response, header::CONTENT_TYPE), OCTET_STREAM);
+    assert_eq!(header_value(&response, header::ACCEPT_RANGES), "bytes");
+    assert!(response.headers().get(header::CONTENT_RANGE).is_none());
+    assert_eq!(body(response).await, DATA);
+}
+
+#[tokio::test]
+async fn test_ranged_download_of_object_slice() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let mut object = stored_object(&storage).await;
+
+    // A chunk of the stored object, resumed after its first three bytes
+    object.offset = 10;
+    object.size = 6;
+    let headers = request_headers(&[(header::RANGE, "bytes=3-")]);
+    let response = serve_download(&storage, &object, &headers).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        header_value(&response, header::CONTENT_RANGE),
+        "bytes 3-5/6"
+    );
+    assert_eq!(header_value(&response, header::CONTENT_LENGTH), "3");
+    assert_eq!(body(response).await, b"def");
+}
+
+#[tokio::test]
+async fn test_conditional_downloads() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let object = stored_object(&storage).await;
+
+    let headers = request_headers(&[(header::IF_NONE_MATCH, &object.etag())]);
+    let response = serve_download(&storage, &object, &headers).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(header_value(&response, header::ETAG), object.etag());
+    assert!(body(response).await.is_empty());
+
+    // A range is only served while If-Range still matches
+    let headers = request_headers(&[
+        (header::RANGE, "bytes=0-3"),
+        (header::IF_RANGE, &object.etag()),
+    ]);
+    let response = serve_download(&storage, &object, &headers).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body(response).await, b"0123");
+
+    let headers = request_headers(&[(header::RANGE, "bytes=0-3"), (header::IF_RANGE, "\"old\"")]);
+    let response = serve_download(&storage, &object, &headers).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body(response).await, DATA);
+
+    let headers = request_headers(&[(header::RANGE, "bytes=40-")]);
+    let response = serve_download(&storage, &object, &headers).await.unwrap();
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(header_value(&response, header::CONTENT_RANGE), "bytes */20");
+}
+
+#[tokio::test]
+async fn test_missing_object_is_not_found() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let object = DownloadObject::whole(
+        StorageKey::new("diffs/missing.diff".to_string()),
+        10,
+        "cd".repeat(32),
+        OCTET_STREAM,
+    );
+
+    let error = serve_download(&storage, &object, &HeaderMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_asset_package_verification() {
+    let source_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(source_dir.path().join("images")).unwrap();
+    std::fs::write(source_dir.path().join("images/logo.png"), b"logo").unwrap();
+    std::fs::write(source_dir.path().join("data.json"), b"{}").unwrap();
+    let collection = AssetCollection::from_directory(source_dir.path())
+        .unwrap()
+        .with_content_id();
+    let package = AssetCompressor::compress_collection(&collection, source_dir.path()).unwrap();
+
+    let verified = verify_package(package.data.clone(), collection.clone())
+        .await
+        .unwrap();
+    assert_eq!(verified.data, package.data);
+
+    // A package missing a described file is rejected
+    let mut extra = collection.clone();
+    let mut missing = extra.assets["data.json"].clone();
+    missing.path = "missing.json".to_string();
+    extra.assets.insert(missing.path.clone(), missing);
+    let error = verify_package(package.data.clone(), extra)
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+    // So is data that is not a package at all
+    let error = verify_package(b"not a package".to_vec(), collection)
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+}
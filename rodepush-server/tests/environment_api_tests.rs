@@ -0,0 +1,170 @@
+//! Environment endpoint tests
+//!
+//! These tests cover name validation, request validation, the update
+//! semantics of the environment endpoints and how deployments pick up the
+//! default rollout of their environment; they need no database.
+
+use axum::http::StatusCode;
+use rodepush_core::BundleId;
+use rodepush_server::api::{
+    CreateDeploymentRequest, CreateEnvironmentRequest, PromoteDeploymentRequest,
+    UpdateEnvironmentRequest,
+};
+use rodepush_server::database::{
+    ApplicationId, DEPLOYMENT_KEY_PREFIX, Environment, RolloutStep, validate_environment_name,
+};
+
+fn test_plan() -> Vec<RolloutStep> {
+    vec![RolloutStep::new(10, 3600), RolloutStep::new(100, 0)]
+}
+
+#[test]
+fn test_environment_name_validation() {
+    assert!(validate_environment_name("production").is_ok());
+    assert!(validate_environment_name("beta-2_eu").is_ok());
+    assert!(validate_environment_name("").is_err());
+    assert!(validate_environment_name("Production").is_err());
+    assert!(validate_environment_name("qa env").is_err());
+    assert!(validate_environment_name(&"a".repeat(51)).is_err());
+}
+
+#[test]
+fn test_new_environment_has_deployment_key() {
+    let application_id = ApplicationId::new();
+    let first = Environment::new(application_id.clone(), "staging".to_string());
+    let second = Environment::new(application_id, "staging".to_string());
+    assert!(first.deployment_key.starts_with(DEPLOYMENT_KEY_PREFIX));
+    assert_ne!(first.deployment_key, second.deployment_key);
+    assert_eq!(first.default_rollout_percentage, 100);
+    assert!(first.default_rollout_plan.is_none());
+}
+
+#[test]
+fn test_create_environment_request() {
+    let request: CreateEnvironmentRequest = serde_json::from_value(serde_json::json!({
+        "name": " beta ",
+        "description": "Early adopters",
+        "default_rollout_percentage": 25
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+    let environment = request.into_environment(ApplicationId::new());
+    assert_eq!(environment.name, "beta");
+    assert_eq!(environment.description.as_deref(), Some("Early adopters"));
+    assert_eq!(environment.default_rollout_percentage, 25);
+
+    let request = CreateEnvironmentRequest {
+        name: "canary".to_string(),
+        description: None,
+        default_rollout_percentage: None,
+        default_rollout_plan: Some(test_plan()),
+    };
+    assert!(request.validate().is_ok());
+    let environment = request.into_environment(ApplicationId::new());
+    assert_eq!(environment.default_rollout_percentage, 10);
+    assert_eq!(environment.default_rollout_plan, Some(test_plan()));
+
+    for body in [
+        serde_json::json!({"name": "Beta"}),
+        serde_json::json!({"name": "beta", "default_rollout_percentage": 101}),
+        serde_json::json!({
+            "name": "beta",
+            "default_rollout_percentage": 10,
+            "default_rollout_plan": [{"percentage": 100, "wait_seconds": 0}]
+        }),
+    ] {
+        let request: CreateEnvironmentRequest = serde_json::from_value(body).unwrap();
+        assert_eq!(
+            request.validate().unwrap_err().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
+
+#[test]
+fn test_update_environment_request() {
+    let mut environment = Environment::new(ApplicationId::new(), "production".to_string())
+        .with_description("Live".to_string())
+        .with_default_rollout_plan(test_plan());
+
+    let request = UpdateEnvironmentRequest {
+        default_rollout_percentage: Some(50),
+        ..Default::default()
+    };
+    assert!(request.validate().is_ok());
+    request.apply(&mut environment);
+    assert_eq!(environment.default_rollout_percentage, 50);
+    assert!(environment.default_rollout_plan.is_none());
+    assert_eq!(environment.description.as_deref(), Some("Live"));
+
+    let request = UpdateEnvironmentRequest {
+        description: Some(" ".to_string()),
+        default_rollout_plan: Some(test_plan()),
+        ..Default::default()
+    };
+    assert!(request.validate().is_ok());
+    request.apply(&mut environment);
+    assert_eq!(environment.description, None);
+    assert_eq!(environment.default_rollout_percentage, 10);
+    assert_eq!(environment.default_rollout_plan, Some(test_plan()));
+
+    let request = UpdateEnvironmentRequest {
+        default_rollout_plan: Some(Vec::new()),
+        ..Default::default()
+    };
+    assert!(request.validate().is_ok());
+    request.apply(&mut environment);
+    assert!(environment.default_rollout_plan.is_none());
+
+    let request = UpdateEnvironmentRequest {
+        default_rollout_percentage: Some(0),
+        default_rollout_plan: Some(test_plan()),
+        ..Default::default()
+    };
+    assert_eq!(
+        request.validate().unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_deployment_uses_environment_rollout_defaults() {
+    let application_id = ApplicationId::new();
+    let environment = Environment::new(application_id.clone(), "production".to_string())
+        .with_default_rollout_plan(test_plan());
+
+    let mut request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": BundleId::new(),
+        "environment": "production"
+    }))
+    .unwrap();
+    request.apply_environment_defaults(&environment);
+    assert_eq!(request.rollout_plan, Some(test_plan()));
+    let deployment = request.into_deployment(application_id.clone());
+    assert_eq!(deployment.rollout_percentage, 10);
+
+    // A rollout chosen by the request wins over the environment's
+    let mut request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": BundleId::new(),
+        "environment": "production",
+        "rollout_percentage": 100
+    }))
+    .unwrap();
+    request.apply_environment_defaults(&environment);
+    assert!(request.rollout_plan.is_none());
+    assert_eq!(
+        request.into_deployment(application_id).rollout_percentage,
+        100
+    );
+
+    let environment = Environment::new(ApplicationId::new(), "staging".to_string())
+        .with_default_rollout_percentage(30);
+    let mut request: PromoteDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "from_environment": "production",
+        "to_environment": "staging"
+    }))
+    .unwrap();
+    request.apply_environment_defaults(&environment);
+    assert_eq!(request.rollout_percentage, Some(30));
+    assert!(request.rollout_plan.is_none());
+}
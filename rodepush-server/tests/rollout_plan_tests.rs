@@ -0,0 +1,171 @@
+//! Staged rollout tests
+//!
+//! These tests cover rollout plan validation, stage progression and the
+//! deployment requests carrying a plan; they need no database.
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use rodepush_core::BundleId;
+use rodepush_server::api::{ApiError, CreateDeploymentRequest};
+use rodepush_server::database::{
+    ApplicationId, Deployment, DeploymentId, RolloutPlan, RolloutPlanStatus, RolloutStep,
+};
+
+fn staged_steps() -> Vec<RolloutStep> {
+    vec![
+        RolloutStep::new(1, 3600),
+        RolloutStep::new(10, 3600),
+        RolloutStep::new(50, 7200),
+        RolloutStep::new(100, 0),
+    ]
+}
+
+#[test]
+fn test_step_validation() {
+    assert!(RolloutPlan::validate_steps(&staged_steps()).is_ok());
+    assert!(RolloutPlan::validate_steps(&[RolloutStep::new(100, 0)]).is_ok());
+
+    let invalid = [
+        vec![],
+        vec![RolloutStep::new(0, 60), RolloutStep::new(100, 0)],
+        vec![RolloutStep::new(10, 60), RolloutStep::new(101, 0)],
+        vec![RolloutStep::new(50, 60), RolloutStep::new(10, 0)],
+        vec![RolloutStep::new(50, 60), RolloutStep::new(50, 0)],
+        vec![RolloutStep::new(10, 0), RolloutStep::new(100, 0)],
+        vec![RolloutStep::new(10, u64::MAX), RolloutStep::new(100, 0)],
+    ];
+    for steps in invalid {
+        let error = RolloutPlan::new(DeploymentId::new(), steps).unwrap_err();
+        assert_eq!(ApiError::from(error).status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[test]
+fn test_plan_progression() {
+    let mut plan = RolloutPlan::new(DeploymentId::new(), staged_steps()).unwrap();
+    assert_eq!(plan.status, RolloutPlanStatus::Running);
+    assert_eq!(plan.current_percentage(), 1);
+
+    // Nothing happens before the current stage is over
+    let now = Utc::now();
+    assert!(!plan.is_due(now));
+    assert_eq!(plan.advance(now), None);
+
+    let later = plan.next_step_at.unwrap();
+    assert_eq!(plan.advance(later), Some(10));
+    assert_eq!(plan.next_step_at, Some(later + Duration::seconds(3600)));
+
+    let later = plan.next_step_at.unwrap();
+    assert_eq!(plan.advance(later), Some(50));
+    let later = plan.next_step_at.unwrap();
+    assert_eq!(plan.advance(later + Duration::days(1)), Some(100));
+
+    // The last stage completes the plan
+    assert_eq!(plan.status, RolloutPlanStatus::Completed);
+    assert_eq!(plan.next_step_at, None);
+    assert_eq!(plan.advance(later + Duration::days(2)), None);
+
+    // A single-stage plan is complete right away
+    let plan = RolloutPlan::new(DeploymentId::new(), vec![RolloutStep::new(100, 0)]).unwrap();
+    assert_eq!(plan.status, RolloutPlanStatus::Completed);
+}
+
+#[test]
+fn test_halt_and_resume() {
+    let mut plan = RolloutPlan::new(DeploymentId::new(), staged_steps()).unwrap();
+    plan.halt("Too many crashes").unwrap();
+    assert_eq!(plan.status, RolloutPlanStatus::Halted);
+    assert_eq!(plan.halt_reason.as_deref(), Some("Too many crashes"));
+    assert!(!plan.is_due(Utc::now() + Duration::days(1)));
+
+    let error = plan.halt("Again").unwrap_err();
+    assert_eq!(ApiError::from(error).status(), StatusCode::CONFLICT);
+
+    // Resuming restarts the current stage
+    plan.resume().unwrap();
+    assert_eq!(plan.status, RolloutPlanStatus::Running);
+    assert_eq!(plan.halt_reason, None);
+    assert_eq!(plan.current_percentage(), 1);
+    assert!(plan.next_step_at.unwrap() > Utc::now() + Duration::seconds(3000));
+
+    let error = plan.resume().unwrap_err();
+    assert_eq!(ApiError::from(error).status(), StatusCode::CONFLICT);
+
+    let mut completed =
+        RolloutPlan::new(DeploymentId::new(), vec![RolloutStep::new(100, 0)]).unwrap();
+    assert!(completed.halt("Too late").is_err());
+}
+
+#[test]
+fn test_status_strings() {
+    for status in [
+        RolloutPlanStatus::Running,
+        RolloutPlanStatus::Halted,
+        RolloutPlanStatus::Completed,
+    ] {
+        assert_eq!(
+            status.to_string().parse::<RolloutPlanStatus>().unwrap(),
+            status
+        );
+    }
+    assert!("paused".parse::<RolloutPlanStatus>().is_err());
+}
+
+#[test]
+fn test_create_deployment_with_plan() {
+    let request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": BundleId::new(),
+        "environment": "production",
+        "rollout_plan": [
+            { "percentage": 5, "wait_seconds": 600 },
+            { "percentage": 100 }
+        ]
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+
+    // The deployment starts at the first stage
+    let deployment = request.into_deployment(ApplicationId::new());
+    assert_eq!(deployment.rollout_percentage, 5);
+
+    let mut request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": BundleId::new(),
+        "environment": "production",
+        "rollout_percentage": 20,
+        "rollout_plan": [{ "percentage": 100 }]
+    }))
+    .unwrap();
+    assert_eq!(
+        request.validate().unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    request.rollout_percentage = None;
+    request.rollout_plan = Some(vec![RolloutStep::new(50, 0), RolloutStep::new(100, 0)]);
+    assert_eq!(
+        request.validate().unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_later_stages_keep_earlier_clients() {
+    let mut deployment = Deployment::new(
+        ApplicationId::new(),
+        BundleId::new(),
+        "production".to_string(),
+    );
+    let clients: Vec<String> = (0..500).map(|i| format!("client-{}", i)).collect();
+
+    let mut previous: Vec<&String> = Vec::new();
+    for step in staged_steps() {
+        deployment = deployment.with_rollout_percentage(step.percentage);
+        let included: Vec<&String> = clients
+            .iter()
+            .filter(|client| deployment.includes_client(client))
+            .collect();
+        assert!(previous.iter().all(|client| included.contains(client)));
+        previous = included;
+    }
+    assert_eq!(previous.len(), clients.len());
+}
@@ -0,0 +1,316 @@
+//! Deployment targeting tests
+//!
+//! These tests cover how targeting rules match client profiles, how they
+//! combine with the rollout percentage, and the answers of the update-check
+//! dry run; they need no database.
+
+use rodepush_core::{BundleId, Platform};
+use rodepush_server::api::update_check::UpdateCheckRequest;
+use rodepush_server::api::{CreateDeploymentRequest, DryRunResponse};
+use rodepush_server::cache::UpdateTarget;
+use rodepush_server::database::{
+    ApplicationId, Bundle, ClientProfile, Deployment, TARGETING_METADATA_KEY, TargetingRules,
+    VersionRange,
+};
+
+fn profile(client_id: &str) -> ClientProfile {
+    ClientProfile {
+        client_id: client_id.to_string(),
+        binary_version: "2.3.1".to_string(),
+        os_version: Some("17.4".to_string()),
+        locale: Some("en_GB".to_string()),
+        tags: vec!["beta-testers".to_string()],
+    }
+}
+
+fn range(min: Option<&str>, max: Option<&str>) -> VersionRange {
+    VersionRange {
+        min: min.map(str::to_string),
+        max: max.map(str::to_string),
+    }
+}
+
+fn test_target(rules: TargetingRules, rollout_percentage: u32) -> UpdateTarget {
+    let application_id = ApplicationId::new();
+    let bundle = Bundle::new(
+        application_id.clone(),
+        "2.3.4".to_string(),
+        Platform::Ios,
+        "bundles/app/target.bundle".to_string(),
+        4096,
+        "cd".repeat(32),
+    );
+    let deployment = Deployment::new(application_id, bundle.id.clone(), "production".to_string())
+        .with_rollout_percentage(rollout_percentage)
+        .with_targeting(rules);
+    UpdateTarget { deployment, bundle }
+}
+
+fn test_request(client_id: &str) -> UpdateCheckRequest {
+    UpdateCheckRequest {
+        deployment_key: None,
+        app_key: String::new(),
+        environment: "production".to_string(),
+        platform: Platform::Ios,
+        binary_version: "2.3.1".to_string(),
+        current_bundle_id: None,
+        current_bundle_hash: None,
+        client_id: client_id.to_string(),
+        os_version: Some("16.2".to_string()),
+        locale: Some("de-DE".to_string()),
+        tags: Vec::new(),
+    }
+}
+
+#[test]
+fn test_version_range() {
+    let within = range(Some("2.0"), Some("2.3.1"));
+    assert!(within.contains("2.0.0"));
+    assert!(within.contains("2.3.1"));
+    assert!(within.contains("2.1.7-beta.1"));
+    assert!(!within.contains("1.9.9"));
+    assert!(!within.contains("2.3.2"));
+    assert!(!within.contains("not-a-version"));
+
+    assert!(range(Some("17"), None).contains("17.0.1"));
+    assert!(!range(None, Some("16")).contains("16.0.1"));
+    assert!(range(None, None).contains("1.0.0"));
+
+    assert!(within.validate("app_versions").is_ok());
+    assert!(
+        range(Some("3.0.0"), Some("2.0.0"))
+            .validate("app_versions")
+            .is_err()
+    );
+    assert!(range(Some("x.1"), None).validate("app_versions").is_err());
+}
+
+#[test]
+fn test_rules_match_client_attributes() {
+    assert!(TargetingRules::default().is_empty());
+    assert_eq!(
+        TargetingRules::default().mismatch(&profile("client-1")),
+        None
+    );
+
+    let rules = TargetingRules {
+        app_versions: Some(range(Some("2.3.0"), None)),
+        os_versions: Some(range(Some("17.0"), None)),
+        locales: vec!["en".to_string()],
+        tags: vec!["beta-testers".to_string(), "staff".to_string()],
+        client_ids: Vec::new(),
+    };
+    assert!(rules.validate().is_ok());
+    assert_eq!(rules.mismatch(&profile("client-1")), None);
+
+    let mut client = profile("client-1");
+    client.binary_version = "2.2.9".to_string();
+    assert!(
+        rules
+            .mismatch(&client)
+            .unwrap()
+            .contains("app version 2.2.9")
+    );
+
+    let mut client = profile("client-1");
+    client.os_version = None;
+    assert!(rules.mismatch(&client).unwrap().contains("OS version"));
+
+    // A language matches its regional variants, not other languages
+    let mut client = profile("client-1");
+    client.locale = Some("eng".to_string());
+    assert!(rules.mismatch(&client).unwrap().contains("locale"));
+    client.locale = Some("EN".to_string());
+    assert_eq!(rules.mismatch(&client), None);
+
+    let mut client = profile("client-1");
+    client.tags = vec!["internal".to_string()];
+    assert!(rules.mismatch(&client).unwrap().contains("tags"));
+}
+
+#[test]
+fn test_rules_validation() {
+    let rules = TargetingRules {
+        tags: vec![" ".to_string()],
+        ..Default::default()
+    };
+    assert!(rules.validate().is_err());
+
+    let rules = TargetingRules {
+        client_ids: vec!["client".to_string(); 1001],
+        ..Default::default()
+    };
+    assert!(rules.validate().is_err());
+
+    let request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": BundleId::new(),
+        "environment": "production",
+        "targeting": {"app_versions": {"min": "2.0.0", "max": "1.0.0"}}
+    }))
+    .unwrap();
+    assert!(request.validate().is_err());
+}
+
+#[test]
+fn test_deployment_stores_rules_in_metadata() {
+    let request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": BundleId::new(),
+        "environment": "production",
+        "targeting": {"tags": ["beta-testers"]}
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+    let deployment = request.into_deployment(ApplicationId::new());
+    assert_eq!(
+        deployment.metadata[TARGETING_METADATA_KEY],
+        serde_json::json!({"tags": ["beta-testers"]})
+    );
+    assert_eq!(
+        deployment.targeting().unwrap().tags,
+        vec!["beta-testers".to_string()]
+    );
+
+    // Empty rules are not stored
+    let request: CreateDeploymentRequest = serde_json::from_value(serde_json::json!({
+        "bundle_id": BundleId::new(),
+        "environment": "production",
+        "targeting": {}
+    }))
+    .unwrap();
+    let deployment = request.into_deployment(ApplicationId::new());
+    assert!(deployment.targeting().is_none());
+}
+
+#[test]
+fn test_allowlisted_clients_skip_rollout() {
+    let rules = TargetingRules {
+        client_ids: vec!["qa-device".to_string()],
+        ..Default::default()
+    };
+    let target = test_target(rules, 0);
+    assert_eq!(
+        target.deployment.exclusion_reason(&profile("qa-device")),
+        None
+    );
+    assert!(
+        target
+            .deployment
+            .exclusion_reason(&profile("other-device"))
+            .unwrap()
+            .contains("allowlist")
+    );
+
+    // Matching clients that are not listed still need to be in the rollout
+    let rules = TargetingRules {
+        tags: vec!["beta-testers".to_string()],
+        ..Default::default()
+    };
+    let target = test_target(rules, 0);
+    assert!(
+        target
+            .deployment
+            .exclusion_reason(&profile("client-1"))
+            .unwrap()
+            .contains("0% rollout")
+    );
+}
+
+#[test]
+fn test_update_check_evaluates_targeting() {
+    let rules = TargetingRules {
+        os_versions: Some(range(Some("17"), None)),
+        ..Default::default()
+    };
+    let target = test_target(rules, 100);
+    let mut request = test_request("client-1");
+    assert!(!request.should_update_to(&target));
+
+    request.os_version = Some("17.1".to_string());
+    assert!(request.should_update_to(&target));
+
+    let request: UpdateCheckRequest = serde_json::from_value(serde_json::json!({
+        "app_key": "app-key",
+        "environment": "production",
+        "platform": "ios",
+        "binary_version": "2.3.1",
+        "client_id": "device-1",
+        "locale": "fr-FR",
+        "tags": ["staff"]
+    }))
+    .unwrap();
+    assert_eq!(request.profile().locale.as_deref(), Some("fr-FR"));
+    assert_eq!(request.profile().tags, vec!["staff".to_string()]);
+    assert_eq!(request.profile().os_version, None);
+}
+
+#[test]
+fn test_dry_run_response() {
+    let request = test_request("client-1");
+    let response = DryRunResponse::new(&request, Vec::new());
+    assert!(!response.update_available);
+    assert!(response.deployment.is_none());
+    assert!(response.reason.unwrap().contains("no active deployment"));
+
+    let rules = TargetingRules {
+        locales: vec!["de".to_string()],
+        ..Default::default()
+    };
+    let target = test_target(rules.clone(), 100);
+    let deployment_id = target.deployment.id.clone();
+    let bundle_id = target.bundle.id.clone();
+    let response = DryRunResponse::new(&request, vec![target]);
+    assert!(response.update_available);
+    assert_eq!(response.deployment.unwrap().id, deployment_id);
+    assert_eq!(response.bundle_id, Some(bundle_id));
+    assert_eq!(response.reason, None);
+
+    // The deployment is reported even when the client would not receive it
+    let mut request = test_request("client-1");
+    request.locale = Some("fr-FR".to_string());
+    let response = DryRunResponse::new(&request, vec![test_target(rules, 100)]);
+    assert!(!response.update_available);
+    assert!(response.deployment.is_some());
+    assert_eq!(response.bundle_id, None);
+    assert!(response.reason.unwrap().contains("locale fr-fr"));
+}
+
+#[test]
+fn test_targeted_deployments_fall_back_to_general() {
+    let beta = test_target(
+        TargetingRules {
+            tags: vec!["beta-testers".to_string()],
+            ..Default::default()
+        },
+        100,
+    );
+    let general = test_target(TargetingRules::default(), 100);
+    let beta_id = beta.deployment.id.clone();
+    let general_id = general.deployment.id.clone();
+    let targets = vec![beta, general];
+
+    let mut request = test_request("client-1");
+    request.tags = vec!["beta-testers".to_string()];
+    let selected = request.select_target(targets.clone()).unwrap();
+    assert_eq!(selected.deployment.id, beta_id);
+
+    // Clients the targeted deployment leaves out receive the general one
+    let request = test_request("client-1");
+    let selected = request.select_target(targets.clone()).unwrap();
+    assert_eq!(selected.deployment.id, general_id);
+    assert!(request.should_update_to(&selected));
+
+    let response = DryRunResponse::new(&request, targets.clone());
+    assert!(response.update_available);
+    assert_eq!(response.deployment.unwrap().id, general_id);
+
+    // Only a client left out by every deployment gets nothing
+    let partial = test_target(TargetingRules::default(), 0);
+    let targets = vec![targets[0].clone(), partial];
+    assert!(request.select_target(targets.clone()).is_none());
+    let response = DryRunResponse::new(&request, targets);
+    assert!(!response.update_available);
+    assert_eq!(response.deployment.unwrap().id, beta_id);
+    let reason = response.reason.unwrap();
+    assert!(reason.contains("tags"));
+    assert!(reason.contains("0% rollout"));
+}
@@ -0,0 +1,151 @@
+//! Install telemetry tests
+//!
+//! These tests cover telemetry report validation, install statistics and the
+//! automatic rollback policy; they need no database.
+
+use axum::http::StatusCode;
+use rodepush_server::api::{AutoRollbackPolicy, TelemetryReportRequest};
+use rodepush_server::database::{DeploymentId, InstallEvent, InstallStats};
+
+fn stats(attempted: u64, failed: u64) -> InstallStats {
+    InstallStats {
+        downloaded: attempted,
+        installed: attempted - failed,
+        crashed_on_launch: failed,
+        failed,
+        attempted,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_install_events() {
+    for (event, name) in [
+        (InstallEvent::Downloaded, "downloaded"),
+        (InstallEvent::Installed, "installed"),
+        (InstallEvent::RolledBack, "rolled_back"),
+        (InstallEvent::CrashedOnLaunch, "crashed_on_launch"),
+    ] {
+        assert_eq!(event.to_string(), name);
+        assert_eq!(name.parse::<InstallEvent>().unwrap(), event);
+        assert_eq!(serde_json::to_value(event).unwrap(), name);
+    }
+    assert!("crashed".parse::<InstallEvent>().is_err());
+
+    assert!(!InstallEvent::Downloaded.is_failure());
+    assert!(!InstallEvent::Installed.is_failure());
+    assert!(InstallEvent::RolledBack.is_failure());
+    assert!(InstallEvent::CrashedOnLaunch.is_failure());
+
+    // Every failure also counts as a failed and an attempted install
+    assert_eq!(InstallEvent::Downloaded.counted_outcomes(), ["downloaded"]);
+    assert_eq!(
+        InstallEvent::RolledBack.counted_outcomes(),
+        ["rolled_back", "failed", "attempted"]
+    );
+}
+
+#[test]
+fn test_report_request() {
+    let deployment_id = DeploymentId::new();
+    let request: TelemetryReportRequest = serde_json::from_value(serde_json::json!({
+        "app_key": "rp_test",
+        "deployment_id": deployment_id,
+        "client_id": " device-1 ",
+        "event": "crashed_on_launch",
+        "message": "TypeError: undefined is not a function"
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+
+    let report = request.into_report();
+    assert_eq!(report.deployment_id, deployment_id);
+    assert_eq!(report.client_id, "device-1");
+    assert_eq!(report.event, InstallEvent::CrashedOnLaunch);
+    assert!(report.message.is_some());
+
+    // Clients following an environment report with its deployment key
+    let request: TelemetryReportRequest = serde_json::from_value(serde_json::json!({
+        "deployment_key": "rpd_test",
+        "deployment_id": deployment_id,
+        "client_id": "device-1",
+        "event": "installed"
+    }))
+    .unwrap();
+    assert!(request.validate().is_ok());
+    assert_eq!(request.deployment_key.as_deref(), Some("rpd_test"));
+    assert!(request.app_key.is_empty());
+
+    let mut request: TelemetryReportRequest = serde_json::from_value(serde_json::json!({
+        "app_key": "rp_test",
+        "deployment_id": deployment_id,
+        "client_id": " ",
+        "event": "installed"
+    }))
+    .unwrap();
+    assert_eq!(
+        request.validate().unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    request.client_id = "device-1".to_string();
+    request.message = Some("x".repeat(10_000));
+    assert_eq!(
+        request.validate().unwrap_err().status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    // Unknown events are rejected when the body is parsed
+    assert!(
+        serde_json::from_value::<TelemetryReportRequest>(serde_json::json!({
+            "app_key": "rp_test",
+            "deployment_id": deployment_id,
+            "client_id": "device-1",
+            "event": "uninstalled"
+        }))
+        .is_err()
+    );
+}
+
+#[test]
+fn test_failure_rate() {
+    assert_eq!(InstallStats::default().failure_rate(), 0.0);
+    assert_eq!(stats(200, 50).failure_rate(), 0.25);
+    assert_eq!(stats(10, 10).failure_rate(), 1.0);
+}
+
+#[test]
+fn test_auto_rollback_policy() {
+    let policy = AutoRollbackPolicy {
+        enabled: true,
+        failure_rate_threshold: 0.1,
+        min_installs: 100,
+    };
+
+    // Too few installs to judge, however bad they look
+    assert_eq!(policy.evaluate(&stats(99, 99)), None);
+    // At or below the threshold
+    assert_eq!(policy.evaluate(&stats(100, 10)), None);
+    assert_eq!(policy.evaluate(&stats(1000, 5)), None);
+
+    let reason = policy.evaluate(&stats(100, 11)).unwrap();
+    assert_eq!(
+        reason,
+        "Failure rate 11.0% over 100 installs exceeds the 10.0% threshold"
+    );
+
+    let disabled = AutoRollbackPolicy {
+        enabled: false,
+        ..policy
+    };
+    assert_eq!(disabled.evaluate(&stats(1000, 1000)), None);
+
+    // A deployment is never judged on an empty sample
+    let eager = AutoRollbackPolicy {
+        min_installs: 0,
+        failure_rate_threshold: 0.0,
+        ..AutoRollbackPolicy::default()
+    };
+    assert_eq!(eager.evaluate(&InstallStats::default()), None);
+    assert!(eager.evaluate(&stats(1, 1)).is_some());
+}
@@ -0,0 +1,176 @@
+//! Update-check decision tests
+//!
+//! These tests cover rollout bucketing and how update-check answers are built
+//! from a resolved deployment; they need no database.
+
+use rodepush_core::{BundleId, Platform};
+use rodepush_server::api::UpdateInfo;
+use rodepush_server::api::update_check::{PackageType, UpdateCheckRequest, UpdateCheckResponse};
+use rodepush_server::cache::UpdateTarget;
+use rodepush_server::database::{ApplicationId, Bundle, Deployment, DiffPackage};
+
+fn test_target(rollout_percentage: u32) -> UpdateTarget {
+    let application_id = ApplicationId::new();
+    let bundle = Bundle::new(
+        application_id.clone(),
+        "1.3.0".to_string(),
+        Platform::Android,
+        "bundles/app/target.bundle".to_string(),
+        4096,
+        "ab".repeat(32),
+    );
+    let deployment = Deployment::new(application_id, bundle.id.clone(), "production".to_string())
+        .with_rollout_percentage(rollout_percentage)
+        .with_description("Fixes checkout crash".to_string());
+    UpdateTarget { deployment, bundle }
+}
+
+fn test_request(client_id: &str) -> UpdateCheckRequest {
+    UpdateCheckRequest {
+        deployment_key: None,
+        app_key: "app-key".to_string(),
+        environment: "production".to_string(),
+        platform: Platform::Android,
+        binary_version: "1.0.0".to_string(),
+        current_bundle_id: None,
+        current_bundle_hash: None,
+        client_id: client_id.to_string(),
+        os_version: None,
+        locale: None,
+        tags: Vec::new(),
+    }
+}
+
+#[test]
+fn test_rollout_is_deterministic_per_client() {
+    let target = test_target(30);
+    for i in 0..50 {
+        let client_id = format!("client-{}", i);
+        let bucket = target.deployment.rollout_bucket(&client_id);
+        assert!(bucket < 100);
+        assert_eq!(bucket, target.deployment.rollout_bucket(&client_id));
+        assert_eq!(target.deployment.includes_client(&client_id), bucket < 30);
+    }
+
+    // Roughly the requested share of clients is included
+    let included = (0..2000)
+        .filter(|i| target.deployment.includes_client(&format!("client-{}", i)))
+        .count();
+    assert!((450..750).contains(&included), "included {}", included);
+}
+
+#[test]
+fn test_rollout_grows_monotonically() {
+    let mut deployment = test_target(10).deployment;
+    let early: Vec<String> = (0..500)
+        .map(|i| format!("client-{}", i))
+        .filter(|client_id| deployment.includes_client(client_id))
+        .collect();
+
+    deployment = deployment.with_rollout_percentage(60);
+    assert!(
+        early
+            .iter()
+            .all(|client_id| deployment.includes_client(client_id))
+    );
+
+    deployment = deployment.with_rollout_percentage(100);
+    assert!(deployment.includes_client("any-client"));
+    deployment = deployment.with_rollout_percentage(0);
+    assert!(!deployment.includes_client("any-client"));
+}
+
+#[test]
+fn test_client_running_target_is_up_to_date() {
+    let target = test_target(100);
+    let mut request = test_request("client-1");
+    assert!(request.should_update_to(&target));
+
+    request.current_bundle_id = Some(target.bundle.id.clone());
+    assert!(!request.should_update_to(&target));
+
+    request.current_bundle_id = Some(BundleId::new());
+    request.current_bundle_hash = Some(target.bundle.checksum.to_uppercase());
+    assert!(!request.should_update_to(&target));
+}
+
+#[test]
+fn test_update_info_prefers_diff_package() {
+    let target = test_target(100);
+    let full = UpdateInfo::new(&target, None, "https://updates.example.com");
+    assert_eq!(full.package_type, PackageType::Full);
+    assert_eq!(
+        full.download_url,
+        format!(
+            "https://updates.example.com/api/v1/bundles/{}/download",
+            target.bundle.id
+        )
+    );
+    assert_eq!(full.package_size, 4096);
+    assert_eq!(full.package_checksum, target.bundle.checksum);
+    assert!(!full.is_mandatory);
+    assert_eq!(full.description.as_deref(), Some("Fixes checkout crash"));
+
+    let diff = DiffPackage::new(
+        BundleId::new(),
+        target.bundle.id.clone(),
+        "diffs/source-target.diff".to_string(),
+        512,
+        0.125,
+        "cd".repeat(32),
+        Platform::Android,
+    );
+    let mut mandatory = target.clone();
+    mandatory.deployment = mandatory.deployment.with_mandatory(true);
+    let info = UpdateInfo::new(&mandatory, Some(&diff), "");
+    assert_eq!(info.package_type, PackageType::Diff);
+    assert_eq!(
+        info.download_url,
+        format!("/api/v1/diffs/{}/download", diff.id)
+    );
+    assert_eq!(info.package_size, 512);
+    assert_eq!(info.package_checksum, diff.checksum);
+    assert_eq!(info.bundle_checksum, target.bundle.checksum);
+    assert!(info.is_mandatory);
+}
+
+#[test]
+fn test_update_check_response_format() {
+    let json = serde_json::to_value(UpdateCheckResponse::up_to_date()).unwrap();
+    assert_eq!(json, serde_json::json!({ "update_available": false }));
+
+    let target = test_target(100);
+    let response = UpdateCheckResponse::available(UpdateInfo::new(&target, None, ""));
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(json["update_available"], true);
+    assert_eq!(json["update"]["package_type"], "full");
+    assert_eq!(json["update"]["bundle_version"], "1.3.0");
+
+    let request: UpdateCheckRequest = serde_json::from_value(serde_json::json!({
+        "app_key": "app-key",
+        "environment": "staging",
+        "platform": "ios",
+        "binary_version": "2.1.0",
+        "client_id": "device-1"
+    }))
+    .unwrap();
+    assert!(request.current_bundle_id.is_none());
+    assert_eq!(request.platform, Platform::Ios);
+}
+
+#[test]
+fn test_update_check_request_with_deployment_key() {
+    let request: UpdateCheckRequest = serde_json::from_value(serde_json::json!({
+        "deployment_key": "rpd_0123456789abcdef",
+        "platform": "android",
+        "binary_version": "1.0.0",
+        "client_id": "device-1"
+    }))
+    .unwrap();
+    assert_eq!(
+        request.deployment_key.as_deref(),
+        Some("rpd_0123456789abcdef")
+    );
+    assert!(request.app_key.is_empty());
+    assert!(request.environment.is_empty());
+}
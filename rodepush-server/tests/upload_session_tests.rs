@@ -0,0 +1,199 @@
+//! Resumable upload session tests
+//!
+//! These tests drive chunk uploads, resumption and assembly of upload
+//! sessions against filesystem storage; they need no database.
+
+use axum::body::Bytes;
+use axum::http::StatusCode;
+use chrono::Duration;
+use futures::stream;
+use rodepush_core::storage::{FilesystemStorage, Storage};
+use rodepush_core::{Bundle, BundleBuilder, CompressionType, Platform, SemanticVersion};
+use rodepush_server::api::ApiError;
+use rodepush_server::api::uploads::{
+    assemble_bundle, discard_chunks, missing_chunks, receive_chunk,
+};
+use rodepush_server::database::{ApplicationId, UploadSession};
+use tempfile::TempDir;
+use tokio::io::AsyncReadExt;
+
+/// Build a bundle with three uncompressed chunks, two of them identical
+fn test_bundle() -> Bundle {
+    let mut builder = BundleBuilder::new(
+        SemanticVersion::new(2, 0, 1),
+        Platform::Ios,
+        "main.jsbundle".to_string(),
+    )
+    .with_compression(CompressionType::None);
+    builder
+        .add_chunk_from_data(b"var header = 1;", "chunk-0".to_string())
+        .unwrap();
+    builder
+        .add_chunk_from_data(b"var repeated = 2;", "chunk-1".to_string())
+        .unwrap();
+    builder
+        .add_chunk_from_data(b"var repeated = 2;", "chunk-2".to_string())
+        .unwrap();
+    builder.build().unwrap()
+}
+
+fn test_session(bundle: &Bundle) -> UploadSession {
+    UploadSession::new(
+        ApplicationId::new(),
+        bundle.metadata.clone(),
+        Duration::hours(1),
+    )
+}
+
+/// Body stream delivering `data` in small pieces
+fn body(data: &[u8]) -> impl futures::Stream<Item = Result<Bytes, ApiError>> + Unpin {
+    let pieces: Vec<Result<Bytes, ApiError>> = data
+        .chunks(4)
+        .map(|piece| Ok(Bytes::copy_from_slice(piece)))
+        .collect();
+    stream::iter(pieces)
+}
+
+async fn upload(session: &UploadSession, bundle: &Bundle, index: usize, storage: &dyn Storage) {
+    let chunk = &bundle.chunks[index];
+    let size = receive_chunk(
+        session,
+        &chunk.metadata.checksum,
+        body(&chunk.data),
+        storage,
+    )
+    .await
+    .unwrap();
+    assert_eq!(size, chunk.metadata.size);
+}
+
+#[tokio::test]
+async fn test_session_tracks_missing_chunks() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let bundle = test_bundle();
+    let session = test_session(&bundle);
+
+    // Identical chunks are only asked for once
+    let missing = missing_chunks(&session, &storage).await.unwrap();
+    assert_eq!(
+        missing,
+        vec![
+            bundle.chunks[0].metadata.checksum.clone(),
+            bundle.chunks[1].metadata.checksum.clone(),
+        ]
+    );
+
+    upload(&session, &bundle, 1, &storage).await;
+    let missing = missing_chunks(&session, &storage).await.unwrap();
+    assert_eq!(missing, vec![bundle.chunks[0].metadata.checksum.clone()]);
+
+    // Retrying a stored chunk is harmless
+    upload(&session, &bundle, 1, &storage).await;
+    upload(&session, &bundle, 0, &storage).await;
+    assert!(missing_chunks(&session, &storage).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_session_assembles_bundle() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let bundle = test_bundle();
+    let session = test_session(&bundle);
+
+    upload(&session, &bundle, 0, &storage).await;
+    let error = assemble_bundle(&session, &storage).await.unwrap_err();
+    assert_eq!(error.status(), StatusCode::CONFLICT);
+
+    upload(&session, &bundle, 1, &storage).await;
+    let received = assemble_bundle(&session, &storage).await.unwrap();
+    assert_eq!(received.size_bytes, bundle.size());
+    assert_eq!(received.metadata.id, bundle.metadata.id);
+
+    let mut stored = Vec::new();
+    storage
+        .open_object(&received.storage_key)
+        .await
+        .unwrap()
+        .read_to_end(&mut stored)
+        .await
+        .unwrap();
+    let expected: Vec<u8> = bundle.chunks.iter().flat_map(|c| c.data.clone()).collect();
+    assert_eq!(stored, expected);
+
+    discard_chunks(&session, &storage).await.unwrap();
+    assert_eq!(missing_chunks(&session, &storage).await.unwrap().len(), 2);
+    assert!(storage.exists(&received.storage_key).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_session_rejects_bad_chunks() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = FilesystemStorage::new(temp_dir.path()).unwrap();
+    let bundle = test_bundle();
+    let session = test_session(&bundle);
+    let chunk = &bundle.chunks[0];
+
+    // Same size, different content
+    let corrupt = vec![b'x'; chunk.data.len()];
+    let error = receive_chunk(&session, &chunk.metadata.checksum, body(&corrupt), &storage)
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let mut oversized = chunk.data.clone();
+    oversized.extend_from_slice(b"extra");
+    let error = receive_chunk(
+        &session,
+        &chunk.metadata.checksum,
+        body(&oversized),
+        &storage,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+    let truncated = &chunk.data[..chunk.data.len() - 1];
+    let error = receive_chunk(
+        &session,
+        &chunk.metadata.checksum,
+        body(truncated),
+        &storage,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+    let unknown = "0".repeat(64);
+    let error = receive_chunk(&session, &unknown, body(&chunk.data), &storage)
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+    let error = receive_chunk(&session, "not-a-checksum", body(&chunk.data), &storage)
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(missing_chunks(&session, &storage).await.unwrap().len(), 2);
+}
+
+#[test]
+fn test_session_expiry() {
+    let bundle = test_bundle();
+    let mut session = UploadSession::new(
+        ApplicationId::new(),
+        bundle.metadata.clone(),
+        Duration::seconds(-1),
+    );
+    assert!(session.is_expired());
+    assert_eq!(session.bundle_id, bundle.metadata.id);
+
+    session.extend(Duration::hours(1));
+    assert!(!session.is_expired());
+    assert!(
+        session
+            .chunk_storage_key(&bundle.chunks[0].metadata.checksum)
+            .starts_with(&format!("uploads/{}/", session.id))
+    );
+}